@@ -1,4 +1,9 @@
 //! `tower-sessions` store backed by the cache connection manager.
+//!
+//! Sessions carrying a `user_id` value are additionally indexed in a per-user
+//! set (`{prefix}user:{user_id}`) so a user's sessions can be listed and
+//! revoked without scanning the keyspace. Index members are pruned lazily when
+//! their session has expired or no longer belongs to the user.
 
 use async_trait::async_trait;
 use redis::{AsyncCommands, ExistenceCheck, SetExpiry, SetOptions, aio::ConnectionManager};
@@ -11,6 +16,18 @@ use tower_sessions_core::{
 
 use crate::Cache;
 
+/// Session data key holding the signed-in user's id.
+pub const USER_ID_KEY: &str = "user_id";
+
+const INDEX_SCRIPT: &str = r#"
+redis.call('SADD', KEYS[1], ARGV[1])
+local expire_at = tonumber(ARGV[2])
+if redis.call('EXPIRETIME', KEYS[1]) < expire_at then
+  redis.call('EXPIREAT', KEYS[1], expire_at)
+end
+return 1
+"#;
+
 #[derive(Debug, thiserror::Error)]
 pub enum RedisStoreError {
     #[error(transparent)]
@@ -60,6 +77,93 @@ impl RedisStore {
         format!("{}{}", self.prefix, id)
     }
 
+    fn user_key(&self, user_id: i64) -> String {
+        format!("{}user:{}", self.prefix, user_id)
+    }
+
+    /// Adds the session to its user's index, extending the index lifetime to
+    /// the session expiry when needed.
+    async fn index(&self, user_id: i64, record: &Record) -> Result<(), RedisStoreError> {
+        let timestamp = OffsetDateTime::unix_timestamp(record.expiry_date);
+        let mut connection = self.connection.clone();
+        let _: i64 = redis::Script::new(INDEX_SCRIPT)
+            .key(self.user_key(user_id))
+            .arg(record.id.to_string())
+            .arg(timestamp)
+            .invoke_async(&mut connection)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the live sessions of a user, pruning stale index members.
+    pub async fn list_by_user(&self, user_id: i64) -> Result<Vec<Record>, RedisStoreError> {
+        let mut connection = self.connection.clone();
+        let members: Vec<String> = connection.smembers(self.user_key(user_id)).await?;
+        if members.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let keys = members
+            .iter()
+            .map(|member| format!("{}{}", self.prefix, member))
+            .collect::<Vec<_>>();
+        let values: Vec<Option<Vec<u8>>> = connection.mget(&keys).await?;
+
+        let mut records = Vec::new();
+        let mut stale = Vec::new();
+        for (member, value) in members.into_iter().zip(values) {
+            let record = value
+                .map(|data| rmp_serde::from_slice::<Record>(&data))
+                .transpose()?
+                .filter(|record| record_user_id(record) == Some(user_id));
+            match record {
+                Some(record) => records.push(record),
+                None => stale.push(member),
+            }
+        }
+
+        if !stale.is_empty() {
+            connection
+                .srem::<_, _, usize>(self.user_key(user_id), stale)
+                .await?;
+        }
+
+        Ok(records)
+    }
+
+    /// Deletes one session of a user. Returns `false` when the session is not
+    /// indexed under that user.
+    pub async fn revoke(&self, user_id: i64, session_id: &Id) -> Result<bool, RedisStoreError> {
+        let mut connection = self.connection.clone();
+        let removed: usize = connection
+            .srem(self.user_key(user_id), session_id.to_string())
+            .await?;
+        if removed == 0 {
+            return Ok(false);
+        }
+        connection.unlink::<_, usize>(self.key(session_id)).await?;
+        Ok(true)
+    }
+
+    /// Deletes every session of a user except `keep`, returning how many were
+    /// revoked.
+    pub async fn revoke_all(
+        &self,
+        user_id: i64,
+        keep: Option<&Id>,
+    ) -> Result<u64, RedisStoreError> {
+        let mut revoked = 0_u64;
+        for record in self.list_by_user(user_id).await? {
+            if Some(&record.id) == keep {
+                continue;
+            }
+            if self.revoke(user_id, &record.id).await? {
+                revoked += 1;
+            }
+        }
+        Ok(revoked)
+    }
+
     async fn save_with_options(
         &self,
         record: &Record,
@@ -76,10 +180,25 @@ impl RedisStore {
         let result: Option<String> = connection
             .set_options(self.key(&record.id), rmp_serde::to_vec(record)?, options)
             .await?;
+
+        if result.is_some()
+            && let Some(user_id) = record_user_id(record)
+        {
+            self.index(user_id, record).await?;
+        }
+
         Ok(result.is_some())
     }
 }
 
+/// Reads the signed-in user's id from session data, if any.
+pub fn record_user_id(record: &Record) -> Option<i64> {
+    record
+        .data
+        .get(USER_ID_KEY)
+        .and_then(|value| value.as_i64())
+}
+
 #[async_trait]
 impl SessionStore for RedisStore {
     async fn create(&self, record: &mut Record) -> session_store::Result<()> {
@@ -121,3 +240,40 @@ impl SessionStore for RedisStore {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use time::OffsetDateTime;
+    use tower_sessions_core::session::{Id, Record};
+
+    use super::{USER_ID_KEY, record_user_id};
+
+    fn record(data: HashMap<String, serde_json::Value>) -> Record {
+        Record {
+            id: Id::default(),
+            data,
+            expiry_date: OffsetDateTime::now_utc(),
+        }
+    }
+
+    #[test]
+    fn user_id_is_read_from_session_data() {
+        assert_eq!(record_user_id(&record(HashMap::new())), None);
+        assert_eq!(
+            record_user_id(&record(HashMap::from([(
+                USER_ID_KEY.to_owned(),
+                serde_json::json!(42)
+            )]))),
+            Some(42)
+        );
+        assert_eq!(
+            record_user_id(&record(HashMap::from([(
+                USER_ID_KEY.to_owned(),
+                serde_json::json!("42")
+            )]))),
+            None
+        );
+    }
+}
@@ -81,3 +81,50 @@ async fn exercises_atomic_and_typed_operations() {
     assert!(cache.clear_namespace().await.unwrap() >= 3);
     assert!(!cache.exists("value").await.unwrap());
 }
+
+#[tokio::test]
+async fn indexes_and_revokes_sessions_per_user() {
+    use cds_cache::session::{RedisStore, USER_ID_KEY};
+    use tower_sessions_core::{
+        SessionStore,
+        session::{Id, Record},
+    };
+
+    let Some(url) = std::env::var_os("CDSCTF_TEST_CACHE_URL") else {
+        return;
+    };
+    let prefix = format!("cds-session-test-{}", std::process::id());
+    let cache = Cache::connect(
+        url.to_str().expect("test URL must be UTF-8"),
+        &prefix,
+        Duration::from_secs(2),
+        Duration::from_secs(2),
+        16,
+    )
+    .await
+    .expect("connect to test Valkey");
+    let store = RedisStore::new(cache.clone());
+
+    let mut records = Vec::new();
+    for _ in 0..3 {
+        let mut record = Record {
+            id: Id::default(),
+            data: [(USER_ID_KEY.to_owned(), serde_json::json!(7))].into(),
+            expiry_date: time::OffsetDateTime::now_utc() + time::Duration::minutes(5),
+        };
+        store.create(&mut record).await.expect("create session");
+        records.push(record);
+    }
+    assert_eq!(store.list_by_user(7).await.unwrap().len(), 3);
+
+    assert!(store.revoke(7, &records[0].id).await.unwrap());
+    assert!(!store.revoke(8, &records[1].id).await.unwrap());
+    assert!(store.load(&records[0].id).await.unwrap().is_none());
+
+    assert_eq!(store.revoke_all(7, Some(&records[2].id)).await.unwrap(), 1);
+    let remaining = store.list_by_user(7).await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].id, records[2].id);
+
+    cache.clear_namespace().await.unwrap();
+}
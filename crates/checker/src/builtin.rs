@@ -0,0 +1,328 @@
+//! Checkers selected by a challenge's `checker_config` that judge natively
+//! instead of running its Lua script.
+//!
+//! Webhook checkers POST `{challenge_id, operator_id, content, timestamp}` to
+//! the author's service and expect the same `{kind, feedback?, credit?,
+//! flag?}` object a Lua `check` returns. Each request carries
+//! `X-Cds-Timestamp` and `X-Cds-Signature: sha256=<hex>`, an HMAC-SHA256 of
+//! `"{timestamp}.{body}"` keyed with the configured secret.
+
+use std::time::{Duration, Instant};
+
+use cds_db::challenge::CheckerConfig;
+use cds_engine::{
+    modules::http::{HttpAccess, HttpCall},
+    traits::Trace,
+};
+use regex::{Regex, RegexBuilder};
+use reqwest::Url;
+use ring::hmac;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::{
+    StatusOutput,
+    modules::audit::{Flag, Judgement, Status},
+    traits::CheckerError,
+};
+
+/// Shortest webhook timeout an author may configure.
+pub const MIN_WEBHOOK_TIMEOUT_MS: u64 = 100;
+
+/// Longest webhook timeout an author may configure, kept under the worker's
+/// per-submission check timeout.
+pub const MAX_WEBHOOK_TIMEOUT_MS: u64 = 8_000;
+
+/// Largest webhook response body accepted.
+const MAX_WEBHOOK_RESPONSE_SIZE: usize = 64 * 1024;
+
+/// Compiled size limit for author-provided patterns.
+const MAX_REGEX_SIZE: usize = 1 << 20;
+
+pub const TIMESTAMP_HEADER: &str = "x-cds-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-cds-signature";
+
+/// Rejects configurations that cannot judge anything, before they are saved.
+pub fn validate(config: &CheckerConfig) -> Result<(), CheckerError> {
+    match config {
+        CheckerConfig::Lua => Ok(()),
+        CheckerConfig::Static { flags } | CheckerConfig::CaseInsensitive { flags } => {
+            if flags.iter().all(|flag| flag.trim().is_empty()) {
+                return Err(CheckerError::ScriptError("checker_flags_empty".to_owned()));
+            }
+            Ok(())
+        }
+        CheckerConfig::Regex { pattern } => compile(pattern).map(|_| ()),
+        CheckerConfig::Webhook {
+            url,
+            secret,
+            timeout_ms,
+        } => {
+            let url = Url::parse(url)
+                .map_err(|_| CheckerError::ScriptError("checker_webhook_url_invalid".to_owned()))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(CheckerError::ScriptError(
+                    "checker_webhook_url_invalid".to_owned(),
+                ));
+            }
+            if secret.is_empty() {
+                return Err(CheckerError::ScriptError(
+                    "checker_webhook_secret_empty".to_owned(),
+                ));
+            }
+            if !(MIN_WEBHOOK_TIMEOUT_MS..=MAX_WEBHOOK_TIMEOUT_MS).contains(timeout_ms) {
+                return Err(CheckerError::ScriptError(format!(
+                    "webhook timeout must be between {MIN_WEBHOOK_TIMEOUT_MS} and \
+                     {MAX_WEBHOOK_TIMEOUT_MS} ms"
+                )));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Compiles a pattern that must match the whole submission.
+pub(crate) fn compile(pattern: &str) -> Result<Regex, CheckerError> {
+    RegexBuilder::new(&format!("^(?:{pattern})$"))
+        .size_limit(MAX_REGEX_SIZE)
+        .build()
+        .map_err(|error| CheckerError::ScriptError(format!("invalid checker pattern: {error}")))
+}
+
+/// Exact match against any listed flag, ignoring surrounding whitespace.
+pub(crate) fn matches_static(flags: &[String], content: &str) -> bool {
+    let content = content.trim();
+    flags.iter().any(|flag| flag.trim() == content)
+}
+
+/// Case-insensitive match that also ignores whitespace just inside a
+/// `prefix{...}` wrapper.
+pub(crate) fn matches_case_insensitive(flags: &[String], content: &str) -> bool {
+    let content = normalize(content);
+    flags.iter().any(|flag| normalize(flag) == content)
+}
+
+fn normalize(value: &str) -> String {
+    match Flag::parse(value) {
+        Ok(flag) => flag.normalized(),
+        Err(_) => value.trim().to_lowercase(),
+    }
+}
+
+pub(crate) fn verdict(correct: bool) -> Judgement {
+    Judgement::from(if correct {
+        Status::Correct
+    } else {
+        Status::Incorrect
+    })
+}
+
+/// Hex HMAC-SHA256 of `"{timestamp}.{body}"`, as sent in the signature header.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let mut context = hmac::Context::with_key(&key);
+    context.update(timestamp.to_string().as_bytes());
+    context.update(b".");
+    context.update(body);
+    hex::encode(context.sign().as_ref())
+}
+
+#[derive(Serialize)]
+struct WebhookRequest<'a> {
+    challenge_id: i64,
+    operator_id: i64,
+    content: &'a str,
+    timestamp: i64,
+}
+
+pub(crate) struct Webhook<'a> {
+    pub url: &'a str,
+    pub secret: &'a str,
+    pub timeout_ms: u64,
+}
+
+impl Webhook<'_> {
+    /// Sends the submission and decodes the service's verdict, recording the
+    /// call in the returned trace.
+    pub(crate) async fn judge(
+        &self,
+        access: &HttpAccess,
+        challenge_id: i64,
+        operator_id: i64,
+        content: &str,
+        timestamp: i64,
+    ) -> (Result<Judgement, CheckerError>, Trace) {
+        let started = Instant::now();
+        let mut trace = Trace::default();
+        let mut status = None;
+        let result = self
+            .send(
+                access,
+                &WebhookRequest {
+                    challenge_id,
+                    operator_id,
+                    content,
+                    timestamp,
+                },
+                &mut status,
+            )
+            .await;
+        trace.http.push(HttpCall {
+            method: "POST".to_owned(),
+            url: self.url.to_owned(),
+            status,
+            error: result.as_ref().err().map(ToString::to_string),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+        let judgement = result.and_then(|value| {
+            trace.result = Some(value.clone());
+            serde_json::from_value::<StatusOutput>(value)
+                .map_err(|error| {
+                    CheckerError::ScriptError(format!("invalid webhook response: {error}"))
+                })
+                .and_then(Judgement::try_from)
+        });
+        if let Err(error) = &judgement {
+            trace.error = Trace::failed(error).error;
+        }
+        (judgement, trace)
+    }
+
+    async fn send(
+        &self,
+        access: &HttpAccess,
+        request: &WebhookRequest<'_>,
+        status: &mut Option<u16>,
+    ) -> Result<JsonValue, CheckerError> {
+        let url = Url::parse(self.url)
+            .map_err(|_| CheckerError::ScriptError("checker_webhook_url_invalid".to_owned()))?;
+        access.check_url(&url).map_err(CheckerError::ScriptError)?;
+        let body = serde_json::to_vec(request).map_err(anyhow::Error::from)?;
+        let signature = sign(self.secret, request.timestamp, &body);
+        let client = access
+            .client()
+            .map_err(|error| CheckerError::ScriptError(format!("webhook client: {error}")))?;
+        let timeout = self
+            .timeout_ms
+            .clamp(MIN_WEBHOOK_TIMEOUT_MS, MAX_WEBHOOK_TIMEOUT_MS);
+        let response = client
+            .post(url)
+            .timeout(Duration::from_millis(timeout))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(TIMESTAMP_HEADER, request.timestamp.to_string())
+            .header(SIGNATURE_HEADER, format!("sha256={signature}"))
+            .body(body)
+            .send()
+            .await
+            .map_err(|error| CheckerError::ScriptError(format!("webhook request: {error}")))?;
+        *status = Some(response.status().as_u16());
+        if !response.status().is_success() {
+            return Err(CheckerError::ScriptError(format!(
+                "webhook responded with {}",
+                response.status()
+            )));
+        }
+        if response
+            .content_length()
+            .is_some_and(|length| length as usize > MAX_WEBHOOK_RESPONSE_SIZE)
+        {
+            return Err(CheckerError::ScriptError(
+                "webhook response too large".to_owned(),
+            ));
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|error| CheckerError::ScriptError(format!("webhook response: {error}")))?;
+        if bytes.len() > MAX_WEBHOOK_RESPONSE_SIZE {
+            return Err(CheckerError::ScriptError(
+                "webhook response too large".to_owned(),
+            ));
+        }
+        serde_json::from_slice(&bytes).map_err(|error| {
+            CheckerError::ScriptError(format!("invalid webhook response: {error}"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cds_db::challenge::CheckerConfig;
+    use ring::hmac;
+
+    use super::{compile, matches_case_insensitive, matches_static, sign, validate};
+
+    #[test]
+    fn static_flags_ignore_surrounding_whitespace_only() {
+        let flags = vec!["flag{abc}".to_owned(), " flag{def} ".to_owned()];
+        assert!(matches_static(&flags, "flag{abc}\n"));
+        assert!(matches_static(&flags, "flag{def}"));
+        assert!(!matches_static(&flags, "FLAG{abc}"));
+        assert!(!matches_static(&flags, "flag{ abc }"));
+    }
+
+    #[test]
+    fn case_insensitive_flags_normalize_the_wrapper() {
+        let flags = vec!["flag{Hello_World}".to_owned()];
+        assert!(matches_case_insensitive(&flags, " FLAG{ hello_world } "));
+        assert!(!matches_case_insensitive(&flags, "flag{hello world}"));
+        assert!(matches_case_insensitive(
+            &["NoWrapper".to_owned()],
+            "nowrapper"
+        ));
+    }
+
+    #[test]
+    fn regex_must_match_the_whole_submission() {
+        let regex = compile("flag\\{[a-f0-9]{4}\\}|ctf\\{x\\}").unwrap();
+        assert!(regex.is_match("flag{beef}"));
+        assert!(regex.is_match("ctf{x}"));
+        assert!(!regex.is_match("xflag{beef}"));
+        assert!(!regex.is_match("ctf{x}y"));
+        assert!(compile("(").is_err());
+    }
+
+    #[test]
+    fn validates_configs() {
+        assert!(
+            validate(&CheckerConfig::Static {
+                flags: vec![" ".to_owned()]
+            })
+            .is_err()
+        );
+        assert!(
+            validate(&CheckerConfig::Webhook {
+                url: "ftp://example.com".to_owned(),
+                secret: "s".to_owned(),
+                timeout_ms: 1000,
+            })
+            .is_err()
+        );
+        assert!(
+            validate(&CheckerConfig::Webhook {
+                url: "https://example.com/check".to_owned(),
+                secret: "s".to_owned(),
+                timeout_ms: 1000,
+            })
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn signature_covers_timestamp_and_body() {
+        let signature = sign("secret", 1700000000, b"{\"content\":\"x\"}");
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"secret");
+        assert!(
+            hmac::verify(
+                &key,
+                b"1700000000.{\"content\":\"x\"}",
+                &hex::decode(&signature).unwrap()
+            )
+            .is_ok()
+        );
+        assert_ne!(
+            signature,
+            sign("secret", 1700000001, b"{\"content\":\"x\"}")
+        );
+    }
+}
@@ -14,6 +14,51 @@ pub enum Status {
     Cheat(i64),
 }
 
+/// Longest feedback message a checker may show to the player.
+pub const MAX_FEEDBACK_CHARS: usize = 512;
+
+/// Longest flag identifier a checker may return.
+pub const MAX_FLAG_ID_LEN: usize = 64;
+
+/// A checker status with the optional details a script may attach to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Judgement {
+    pub status: Status,
+    /// Message shown to the submitting player.
+    pub feedback: Option<String>,
+    /// Percent of the points awarded for a correct submission.
+    pub credit: i32,
+    /// Identifies which of a challenge's independent flags was solved.
+    pub flag_id: Option<String>,
+}
+
+impl From<Status> for Judgement {
+    fn from(status: Status) -> Self {
+        Self {
+            status,
+            feedback: None,
+            credit: 100,
+            flag_id: None,
+        }
+    }
+}
+
+/// Converts a credit fraction in `[0, 1]` to whole percent.
+pub fn credit_percent(credit: f64) -> Option<i32> {
+    (0.0..=1.0)
+        .contains(&credit)
+        .then(|| (credit * 100.0).round() as i32)
+}
+
+/// Flag ids are short ASCII identifiers such as `part1` or `web-a`.
+pub fn is_valid_flag_id(flag_id: &str) -> bool {
+    !flag_id.is_empty()
+        && flag_id.len() <= MAX_FLAG_ID_LEN
+        && flag_id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'-' | b'.'))
+}
+
 #[derive(Debug, Clone)]
 pub struct Flag {
     prefix: String,
@@ -40,6 +85,12 @@ impl Flag {
     pub fn format(&self) -> String {
         format!("{}{{{}}}", self.prefix, self.content)
     }
+
+    /// Lowercased form with whitespace trimmed inside the wrapper, so
+    /// `Flag{ Abc }` and `flag{abc}` compare equal.
+    pub fn normalized(&self) -> String {
+        format!("{}{{{}}}", self.prefix.trim(), self.content.trim()).to_lowercase()
+    }
 }
 
 fn flag_table(lua: &Lua, flag: Flag) -> mlua::Result<Table> {
@@ -49,12 +100,22 @@ fn flag_table(lua: &Lua, flag: Flag) -> mlua::Result<Table> {
     Ok(table)
 }
 
-fn status_table(lua: &Lua, kind: &str, operator_id: Option<i64>) -> mlua::Result<Table> {
+fn status_table(
+    lua: &Lua,
+    kind: &str,
+    operator_id: Option<i64>,
+    options: Option<Table>,
+) -> mlua::Result<Table> {
     let table = lua.create_table()?;
     table.set("kind", kind)?;
     if let Some(operator_id) = operator_id {
         table.set("operator_id", operator_id)?;
     }
+    if let Some(options) = options {
+        table.set("feedback", options.get::<Option<String>>("feedback")?)?;
+        table.set("credit", options.get::<Option<f64>>("credit")?)?;
+        table.set("flag", options.get::<Option<String>>("flag")?)?;
+    }
     Ok(table)
 }
 
@@ -91,22 +152,55 @@ pub fn install(lua: &Lua) -> Result<(), EngineError> {
     )?;
     module.set(
         "correct",
-        lua.create_function(|lua, ()| status_table(lua, "correct", None))?,
+        lua.create_function(|lua, options: Option<Table>| {
+            status_table(lua, "correct", None, options)
+        })?,
     )?;
     module.set(
         "incorrect",
-        lua.create_function(|lua, ()| status_table(lua, "incorrect", None))?,
+        lua.create_function(|lua, options: Option<Table>| {
+            status_table(lua, "incorrect", None, options)
+        })?,
     )?;
     module.set(
         "cheat",
-        lua.create_function(|lua, operator_id: i64| status_table(lua, "cheat", Some(operator_id)))?,
+        lua.create_function(|lua, (operator_id, options): (i64, Option<Table>)| {
+            status_table(lua, "cheat", Some(operator_id), options)
+        })?,
     )?;
+    cds_engine::api::register(
+        lua,
+        "checker.audit",
+        &[
+            (
+                "parse(content)",
+                "Splits `prefix{content}` into a flag table.",
+            ),
+            ("new()", "Empty flag table."),
+            (
+                "format(flag)",
+                "Joins a flag table back into `prefix{content}`.",
+            ),
+            (
+                "correct(options?)",
+                "Accepts the answer; options are `feedback`, `credit` (0 to 1) and `flag`.",
+            ),
+            (
+                "incorrect(options?)",
+                "Rejects the answer; options are `feedback`.",
+            ),
+            (
+                "cheat(operator_id, options?)",
+                "Flags the submission as copied from `operator_id`.",
+            ),
+        ],
+    );
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Flag;
+    use super::{Flag, credit_percent, is_valid_flag_id};
 
     #[test]
     fn parses_and_formats_flag() {
@@ -115,4 +209,22 @@ mod tests {
         assert_eq!(flag.content, "content");
         assert_eq!(flag.format(), "flag{content}");
     }
+
+    #[test]
+    fn converts_credit_fractions_to_percent() {
+        assert_eq!(credit_percent(1.0), Some(100));
+        assert_eq!(credit_percent(1.0 / 3.0), Some(33));
+        assert_eq!(credit_percent(0.0), Some(0));
+        assert_eq!(credit_percent(1.5), None);
+        assert_eq!(credit_percent(f64::NAN), None);
+    }
+
+    #[test]
+    fn validates_flag_ids() {
+        assert!(is_valid_flag_id("part-1"));
+        assert!(is_valid_flag_id("web.a_2"));
+        assert!(!is_valid_flag_id(""));
+        assert!(!is_valid_flag_id("part 1"));
+        assert!(!is_valid_flag_id(&"a".repeat(65)));
+    }
 }
@@ -62,6 +62,20 @@ pub fn install(lua: &Lua, media: Media, challenge_id: i64) -> Result<(), EngineE
             }
         })?,
     )?;
+    cds_engine::api::register(
+        lua,
+        "checker.fs",
+        &[
+            (
+                "read_to_string(path)",
+                "Reads a UTF-8 file from the challenge storage.",
+            ),
+            (
+                "write(path, content)",
+                "Writes a file to the challenge storage.",
+            ),
+        ],
+    );
     Ok(())
 }
 
@@ -26,6 +26,20 @@ pub fn install(lua: &Lua, default_key: Option<String>) -> Result<(), EngineError
             },
         )?,
     )?;
+    cds_engine::api::register(
+        lua,
+        "checker.leet",
+        &[
+            (
+                "encode(template, operator_id, options?)",
+                "Leet-encodes a per-operator flag; options are `key`.",
+            ),
+            (
+                "decode(template, payload, options?)",
+                "Recovers the operator id from a leet flag; options are `key`.",
+            ),
+        ],
+    );
     Ok(())
 }
 
@@ -22,6 +22,20 @@ pub fn install(lua: &Lua, default_key: Option<String>) -> Result<(), EngineError
             decode(&payload, &key).map_err(mlua::Error::external)
         })?,
     )?;
+    cds_engine::api::register(
+        lua,
+        "checker.suid",
+        &[
+            (
+                "encode(data, options?)",
+                "Encodes an id as a UUID-like string; options are `key` and `hyphenated`.",
+            ),
+            (
+                "decode(payload, options?)",
+                "Recovers the id; options are `key`.",
+            ),
+        ],
+    );
     Ok(())
 }
 
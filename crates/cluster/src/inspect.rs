@@ -0,0 +1,293 @@
+//! Read-only views of a running instance for admins: container states and
+//! resource usage, pod events and container logs.
+//!
+//! Usage comes from the `metrics.k8s.io` API served by metrics-server; when
+//! the cluster has none, the figures are `None` and the rest still works.
+
+use axum::extract::ws::{Message, Utf8Bytes, WebSocket};
+use futures_util::{AsyncBufReadExt, SinkExt, StreamExt, TryStreamExt};
+use k8s_openapi::api::core::v1::{Event, Pod};
+use kube::{
+    api::{Api, ApiResource, DynamicObject, ListParams, LogParams},
+    core::GroupVersionKind,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{Cluster, traits::ClusterError};
+
+/// One container of an instance.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ContainerStatus {
+    /// Container id as used by the shell and log endpoints.
+    pub id: String,
+    pub image: String,
+    pub ready: bool,
+    pub restart_count: i32,
+    /// `waiting`, `running` or `terminated`.
+    pub state: String,
+    /// Why the container is waiting or terminated, e.g. `CrashLoopBackOff`.
+    pub reason: Option<String>,
+    pub cpu_millicores: Option<i64>,
+    pub memory_bytes: Option<i64>,
+}
+
+/// State of an instance's pod and its containers.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct InstanceStatus {
+    pub phase: String,
+    pub node: Option<String>,
+    pub containers: Vec<ContainerStatus>,
+}
+
+/// A Kubernetes event about an instance's pod.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct InstanceEvent {
+    /// `Normal` or `Warning`.
+    pub type_: String,
+    pub reason: String,
+    pub message: String,
+    pub count: i32,
+    pub last_seen: Option<i64>,
+}
+
+/// Parses a CPU quantity (`250m`, `1`, `1500000n`) into millicores.
+fn parse_cpu(quantity: &str) -> Option<i64> {
+    let (number, scale) = match quantity.char_indices().last()? {
+        (i, 'n') => (&quantity[..i], 1e-6),
+        (i, 'u') => (&quantity[..i], 1e-3),
+        (i, 'm') => (&quantity[..i], 1.0),
+        _ => (quantity, 1e3),
+    };
+
+    Some((number.parse::<f64>().ok()? * scale).round() as i64)
+}
+
+/// Parses a memory quantity (`64Mi`, `1G`, `123456Ki`, `1000`) into bytes.
+fn parse_memory(quantity: &str) -> Option<i64> {
+    const SUFFIXES: [(&str, f64); 12] = [
+        ("Ki", 1024.0),
+        ("Mi", 1048576.0),
+        ("Gi", 1073741824.0),
+        ("Ti", 1099511627776.0),
+        ("Pi", 1125899906842624.0),
+        ("Ei", 1152921504606846976.0),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+        ("P", 1e15),
+        ("E", 1e18),
+    ];
+    let (number, scale) = SUFFIXES
+        .iter()
+        .find_map(|(suffix, scale)| Some((quantity.strip_suffix(suffix)?, *scale)))
+        .unwrap_or((quantity, 1.0));
+
+    Some((number.parse::<f64>().ok()? * scale).round() as i64)
+}
+
+/// Per-container `(cpu_millicores, memory_bytes)` from a `PodMetrics` object.
+fn usage(metrics: &DynamicObject) -> Vec<(String, Option<i64>, Option<i64>)> {
+    metrics
+        .data
+        .get("containers")
+        .and_then(|containers| containers.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|container| {
+            let name = container.get("name")?.as_str()?.to_owned();
+            let usage = container.get("usage");
+            let quantity = |key: &str| usage.and_then(|usage| usage.get(key)?.as_str());
+            Some((
+                name,
+                quantity("cpu").and_then(parse_cpu),
+                quantity("memory").and_then(parse_memory),
+            ))
+        })
+        .collect()
+}
+
+impl Cluster {
+    /// Returns the state of an instance's containers with their current
+    /// usage.
+    pub async fn instance_status(&self, id: &str) -> Result<InstanceStatus, ClusterError> {
+        let pod = self.get_pod(id).await?;
+        let name = format!("cds-{}", id);
+
+        let resource = ApiResource::from_gvk_with_plural(
+            &GroupVersionKind::gvk("metrics.k8s.io", "v1beta1", "PodMetrics"),
+            "pods",
+        );
+        let metrics_api: Api<DynamicObject> = Api::namespaced_with(
+            self.client.clone(),
+            pod.metadata
+                .namespace
+                .as_deref()
+                .unwrap_or(self.namespace.as_str()),
+            &resource,
+        );
+        // Metrics are best-effort: the API may be missing or lag behind.
+        let usage = metrics_api
+            .get_opt(&name)
+            .await
+            .ok()
+            .flatten()
+            .map(|metrics| usage(&metrics))
+            .unwrap_or_default();
+
+        Ok(status(pod, &usage))
+    }
+
+    /// Lists the Kubernetes events about an instance's pod, oldest first.
+    pub async fn instance_events(&self, id: &str) -> Result<Vec<InstanceEvent>, ClusterError> {
+        let Some(namespace) = self.namespace_of(id).await? else {
+            return Ok(Vec::new());
+        };
+        let event_api: Api<Event> = self.api_in(Some(&namespace));
+
+        let mut events = event_api
+            .list(&ListParams::default().fields(&format!(
+                "involvedObject.kind=Pod,involvedObject.name=cds-{}",
+                id
+            )))
+            .await?
+            .items
+            .into_iter()
+            .map(|event| InstanceEvent {
+                type_: event.type_.unwrap_or_default(),
+                reason: event.reason.unwrap_or_default(),
+                message: event.message.unwrap_or_default(),
+                count: event.count.unwrap_or(1),
+                last_seen: event
+                    .last_timestamp
+                    .map(|time| time.0.as_second())
+                    .or_else(|| event.event_time.map(|time| time.0.as_second())),
+            })
+            .collect::<Vec<_>>();
+        events.sort_by_key(|event| event.last_seen);
+
+        Ok(events)
+    }
+
+    /// Streams a container's log over WebSocket, one text frame per line,
+    /// starting with its last `tail_lines` lines and following new output
+    /// until either side closes.
+    pub async fn logs(
+        &self,
+        id: &str,
+        container_id: &str,
+        tail_lines: i64,
+        ws: WebSocket,
+    ) -> Result<(), ClusterError> {
+        let name = format!("cds-{}", id);
+        let namespace = self
+            .namespace_of(id)
+            .await?
+            .ok_or_else(|| ClusterError::NotFound("pod_not_found".to_owned()))?;
+        let pod_api: Api<Pod> = self.api_in(Some(&namespace));
+
+        let mut lines = pod_api
+            .log_stream(
+                &name,
+                &LogParams {
+                    container: Some(format!("cds-{}", container_id)),
+                    follow: true,
+                    tail_lines: Some(tail_lines),
+                    timestamps: true,
+                    ..Default::default()
+                },
+            )
+            .await?
+            .lines();
+
+        let (mut sender, mut receiver) = ws.split();
+        loop {
+            tokio::select! {
+                line = lines.try_next() => {
+                    let Ok(Some(line)) = line else {
+                        break;
+                    };
+                    if sender.send(Message::Text(Utf8Bytes::from(line))).await.is_err() {
+                        break;
+                    }
+                }
+                message = receiver.next() => {
+                    if !matches!(message, Some(Ok(message)) if !matches!(message, Message::Close(_))) {
+                        break;
+                    }
+                }
+            }
+        }
+        let _ = sender.close().await;
+
+        Ok(())
+    }
+}
+
+fn status(pod: Pod, usage: &[(String, Option<i64>, Option<i64>)]) -> InstanceStatus {
+    let spec = pod.spec.unwrap_or_default();
+    let pod_status = pod.status.unwrap_or_default();
+    let statuses = pod_status.container_statuses.unwrap_or_default();
+
+    InstanceStatus {
+        phase: pod_status.phase.unwrap_or_default(),
+        node: spec.node_name,
+        containers: spec
+            .containers
+            .into_iter()
+            .map(|container| {
+                let status = statuses.iter().find(|status| status.name == container.name);
+                let state = status.and_then(|status| status.state.clone());
+                let (state, reason) = match state {
+                    Some(state) if state.running.is_some() => ("running", None),
+                    Some(state) if state.terminated.is_some() => (
+                        "terminated",
+                        state.terminated.and_then(|terminated| terminated.reason),
+                    ),
+                    Some(state) => ("waiting", state.waiting.and_then(|waiting| waiting.reason)),
+                    None => ("waiting", None),
+                };
+                let (cpu_millicores, memory_bytes) = usage
+                    .iter()
+                    .find(|(name, ..)| *name == container.name)
+                    .map(|(_, cpu, memory)| (*cpu, *memory))
+                    .unwrap_or_default();
+
+                ContainerStatus {
+                    id: container
+                        .name
+                        .strip_prefix("cds-")
+                        .unwrap_or(&container.name)
+                        .to_owned(),
+                    image: container.image.unwrap_or_default(),
+                    ready: status.is_some_and(|status| status.ready),
+                    restart_count: status.map(|status| status.restart_count).unwrap_or(0),
+                    state: state.to_owned(),
+                    reason,
+                    cpu_millicores,
+                    memory_bytes,
+                }
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_cpu, parse_memory};
+
+    #[test]
+    fn parses_quantities() {
+        assert_eq!(parse_cpu("250m"), Some(250));
+        assert_eq!(parse_cpu("2"), Some(2000));
+        assert_eq!(parse_cpu("1500000n"), Some(2));
+        assert_eq!(parse_cpu("0.5"), Some(500));
+        assert_eq!(parse_cpu(""), None);
+
+        assert_eq!(parse_memory("64Mi"), Some(64 * 1024 * 1024));
+        assert_eq!(parse_memory("123456Ki"), Some(123456 * 1024));
+        assert_eq!(parse_memory("1G"), Some(1_000_000_000));
+        assert_eq!(parse_memory("4096"), Some(4096));
+        assert_eq!(parse_memory("lots"), None);
+    }
+}
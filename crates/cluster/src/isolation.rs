@@ -0,0 +1,641 @@
+//! Network isolation between instances: the namespaces they run in and the
+//! ingress NetworkPolicy each one gets.
+//!
+//! Every namespace holding instances carries the `cds-internet-restricted`
+//! and `cds-internet-allowed` egress policies. On top of those, each
+//! instance's pods only accept traffic from each other, from the platform
+//! as configured under `cluster.isolation`, from the WireGuard gateway when
+//! it runs, and under `expose` traffic from outside the pod network. With
+//! `namespace_per_team`, a team's instances — or a user's outside games — share
+//! a namespace of their own.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use cds_env::cluster::{Traffic, isolation::Config};
+use k8s_openapi::{
+    NamespaceResourceScope,
+    api::{
+        core::v1::{Namespace, Pod, Service},
+        networking::v1::{
+            IPBlock, NetworkPolicy, NetworkPolicyEgressRule, NetworkPolicyIngressRule,
+            NetworkPolicyPeer, NetworkPolicyPort, NetworkPolicySpec,
+        },
+    },
+    apimachinery::pkg::{
+        apis::meta::v1::{LabelSelector, ObjectMeta},
+        util::intstr::IntOrString,
+    },
+};
+use kube::{
+    Client as K8sClient, Resource,
+    api::{Api, DeleteParams, ListParams, Patch, PatchParams, PostParams},
+};
+use serde::de::DeserializeOwned;
+use tracing::info;
+
+use crate::{Cluster, traits::ClusterError, wireguard};
+
+/// Field manager of the policies the platform applies.
+const FIELD_MANAGER: &str = "cds";
+
+/// Namespaces known to exist with their baseline policies, and the
+/// namespace of each instance seen so far; neither changes once created.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Placement {
+    namespaces: Arc<Mutex<HashSet<String>>>,
+    instances: Arc<Mutex<HashMap<String, String>>>,
+}
+
+/// Namespace of an owner's instances when each team has its own.
+pub(crate) fn owner_namespace(
+    base: &str,
+    user_id: i64,
+    team_id: Option<i64>,
+    game_id: Option<i64>,
+) -> String {
+    match (game_id, team_id) {
+        (Some(_), Some(team_id)) => format!("{}-team-{}", base, team_id),
+        _ => format!("{}-user-{}", base, user_id),
+    }
+}
+
+fn labels(pairs: &[(&str, &str)]) -> Option<BTreeMap<String, String>> {
+    Some(
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    )
+}
+
+/// Rejects isolation settings under which `expose` traffic could only be
+/// admitted by letting every address in, pods included.
+pub(crate) fn check(config: &Config, traffic: &Traffic) -> Result<(), String> {
+    if config.network_policy
+        && *traffic == Traffic::Expose
+        && config.allowed_cidrs.is_empty()
+        && config.pod_cidrs.is_empty()
+    {
+        return Err(
+            "cluster.isolation needs pod_cidrs or allowed_cidrs with expose traffic".to_owned(),
+        );
+    }
+    Ok(())
+}
+
+/// Ingress policy admitting into instance `id` only its own pods, the
+/// platform's, the WireGuard gateway in `gateway_namespace` if there is one
+/// and the configured address ranges.
+pub(crate) fn ingress_policy(
+    config: &Config,
+    traffic: &Traffic,
+    gateway_namespace: Option<&str>,
+    id: &str,
+) -> NetworkPolicy {
+    let instance = LabelSelector {
+        match_labels: labels(&[("cds/instance_id", id)]),
+        ..Default::default()
+    };
+
+    let mut from = vec![NetworkPolicyPeer {
+        pod_selector: Some(instance.clone()),
+        ..Default::default()
+    }];
+    if !config.proxy_namespace_labels.is_empty() || !config.proxy_pod_labels.is_empty() {
+        let selector = |labels: &BTreeMap<String, String>| {
+            (!labels.is_empty()).then(|| LabelSelector {
+                match_labels: Some(labels.clone()),
+                ..Default::default()
+            })
+        };
+        from.push(NetworkPolicyPeer {
+            namespace_selector: selector(&config.proxy_namespace_labels),
+            pod_selector: selector(&config.proxy_pod_labels),
+            ..Default::default()
+        });
+    }
+    if let Some(namespace) = gateway_namespace {
+        from.push(NetworkPolicyPeer {
+            namespace_selector: Some(LabelSelector {
+                match_labels: labels(&[("kubernetes.io/metadata.name", namespace)]),
+                ..Default::default()
+            }),
+            pod_selector: Some(LabelSelector {
+                match_labels: labels(&[("cds/app", wireguard::APP)]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+    }
+    from.extend(config.allowed_cidrs.iter().map(|cidr| NetworkPolicyPeer {
+        ip_block: Some(IPBlock {
+            cidr: cidr.clone(),
+            except: None,
+        }),
+        ..Default::default()
+    }));
+    // Without the pod ranges to carve out, an open block could let other
+    // instances in on CNIs whose ipBlocks match pod addresses.
+    if *traffic == Traffic::Expose
+        && config.allowed_cidrs.is_empty()
+        && !config.pod_cidrs.is_empty()
+    {
+        from.push(NetworkPolicyPeer {
+            ip_block: Some(IPBlock {
+                cidr: "0.0.0.0/0".to_owned(),
+                except: Some(config.pod_cidrs.clone()),
+            }),
+            ..Default::default()
+        });
+    }
+
+    NetworkPolicy {
+        metadata: ObjectMeta {
+            name: Some(format!("cds-{}", id)),
+            labels: labels(&[("cds/app", "challenges"), ("cds/instance_id", id)]),
+            ..Default::default()
+        },
+        spec: Some(NetworkPolicySpec {
+            pod_selector: Some(instance),
+            policy_types: Some(vec!["Ingress".to_owned()]),
+            ingress: Some(vec![NetworkPolicyIngressRule {
+                from: Some(from),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }),
+    }
+}
+
+/// Egress policy letting pods labeled `cds/internet=true` reach DNS and
+/// every address outside `excluded_cidrs`.
+fn internet_allowed_policy(namespace: &str, excluded_cidrs: &[String]) -> NetworkPolicy {
+    NetworkPolicy {
+        metadata: ObjectMeta {
+            name: Some("cds-internet-allowed".to_owned()),
+            namespace: Some(namespace.to_owned()),
+            ..Default::default()
+        },
+        spec: Some(NetworkPolicySpec {
+            pod_selector: Some(LabelSelector {
+                match_labels: labels(&[("cds/internet", "true")]),
+                ..Default::default()
+            }),
+            policy_types: Some(vec!["Egress".to_owned()]),
+            egress: Some(vec![
+                NetworkPolicyEgressRule {
+                    to: Some(vec![NetworkPolicyPeer {
+                        namespace_selector: Some(LabelSelector {
+                            match_labels: labels(&[("kubernetes.io/metadata.name", "kube-system")]),
+                            ..Default::default()
+                        }),
+                        pod_selector: Some(LabelSelector {
+                            match_labels: labels(&[("k8s-app", "kube-dns")]),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }]),
+                    ports: Some(vec![
+                        NetworkPolicyPort {
+                            protocol: Some("UDP".to_string()),
+                            port: Some(IntOrString::Int(53)),
+                            ..Default::default()
+                        },
+                        NetworkPolicyPort {
+                            protocol: Some("TCP".to_string()),
+                            port: Some(IntOrString::Int(53)),
+                            ..Default::default()
+                        },
+                    ]),
+                },
+                NetworkPolicyEgressRule {
+                    to: Some(vec![NetworkPolicyPeer {
+                        ip_block: Some(IPBlock {
+                            cidr: "0.0.0.0/0".to_owned(),
+                            except: Some(excluded_cidrs.to_vec()),
+                        }),
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        }),
+    }
+}
+
+/// Creates `namespace` if missing and reconciles its egress policies, so
+/// pods labeled `cds/internet=false` cannot reach the public internet while
+/// `cds/internet=true` pods receive DNS + user-defined exceptions.
+pub(crate) async fn ensure_namespace(
+    client: &K8sClient,
+    namespace: &str,
+    excluded_cidrs: &[String],
+) -> Result<(), ClusterError> {
+    let namespace_api: Api<Namespace> = Api::all(client.clone());
+    if namespace_api.get_opt(namespace).await?.is_none() {
+        let _ = namespace_api
+            .create(
+                &PostParams::default(),
+                &Namespace {
+                    metadata: ObjectMeta {
+                        name: Some(namespace.to_owned()),
+                        labels: labels(&[("cds/app", "challenges")]),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .await;
+        info!(namespace, "Namespace is created successfully.");
+    }
+
+    let network_policy_api: Api<NetworkPolicy> = Api::namespaced(client.clone(), namespace);
+
+    if network_policy_api
+        .get("cds-internet-restricted")
+        .await
+        .is_err()
+    {
+        let network_policy = NetworkPolicy {
+            metadata: ObjectMeta {
+                name: Some("cds-internet-restricted".to_owned()),
+                namespace: Some(namespace.to_owned()),
+                ..Default::default()
+            },
+            spec: Some(NetworkPolicySpec {
+                pod_selector: Some(LabelSelector {
+                    match_labels: labels(&[("cds/internet", "false")]),
+                    ..Default::default()
+                }),
+                policy_types: Some(vec!["Egress".to_owned()]),
+                ..Default::default()
+            }),
+        };
+        network_policy_api
+            .create(&PostParams::default(), &network_policy)
+            .await?;
+
+        info!(
+            namespace,
+            "Restricted network policy is created successfully."
+        );
+    }
+
+    let network_policy = internet_allowed_policy(namespace, excluded_cidrs);
+    match network_policy_api.get("cds-internet-allowed").await {
+        Err(_) => {
+            network_policy_api
+                .create(&PostParams::default(), &network_policy)
+                .await?;
+            info!(namespace, "Allowed network policy is created successfully.");
+        }
+        Ok(np) => {
+            let current_excluded = np
+                .spec
+                .as_ref()
+                .and_then(|s| s.egress.as_ref())
+                .and_then(|egress| egress.get(1))
+                .and_then(|rule| rule.to.as_ref())
+                .and_then(|to| to.first())
+                .and_then(|peer| peer.ip_block.as_ref())
+                .and_then(|ipb| ipb.except.clone());
+
+            if current_excluded != Some(excluded_cidrs.to_vec()) {
+                network_policy_api
+                    .patch(
+                        "cds-internet-allowed",
+                        &PatchParams::default(),
+                        &Patch::Merge(&network_policy),
+                    )
+                    .await?;
+
+                info!(
+                    namespace,
+                    "Allowed network policy updated due to excluded CIDRs change."
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl Cluster {
+    /// API over every namespace instances may live in: all of them with
+    /// `namespace_per_team`, otherwise the platform's.
+    pub(crate) fn api<K>(&self) -> Api<K>
+    where
+        K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>
+            + Clone
+            + Debug
+            + DeserializeOwned, {
+        if self.isolation.namespace_per_team {
+            Api::all(self.client.clone())
+        } else {
+            Api::namespaced(self.client.clone(), self.namespace.as_str())
+        }
+    }
+
+    /// API over one namespace, the platform's when `None`.
+    pub(crate) fn api_in<K>(&self, namespace: Option<&str>) -> Api<K>
+    where
+        K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>, {
+        Api::namespaced(
+            self.client.clone(),
+            namespace.unwrap_or(self.namespace.as_str()),
+        )
+    }
+
+    /// Namespace a new instance of this owner starts in, created with its
+    /// egress policies on first use.
+    pub(crate) async fn instance_namespace(
+        &self,
+        user_id: i64,
+        team_id: Option<i64>,
+        game_id: Option<i64>,
+    ) -> Result<String, ClusterError> {
+        if !self.isolation.namespace_per_team {
+            return Ok(self.namespace.clone());
+        }
+
+        let namespace = owner_namespace(&self.namespace, user_id, team_id, game_id);
+        let known = self
+            .placement
+            .namespaces
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .contains(&namespace);
+        if !known {
+            ensure_namespace(&self.client, &namespace, &self.egress_excluded_cidrs).await?;
+            self.placement
+                .namespaces
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .insert(namespace.clone());
+        }
+
+        Ok(namespace)
+    }
+
+    /// Namespace instance `id` lives in, `None` when nothing of it is left.
+    pub(crate) async fn namespace_of(&self, id: &str) -> Result<Option<String>, ClusterError> {
+        if !self.isolation.namespace_per_team {
+            return Ok(Some(self.namespace.clone()));
+        }
+        if let Some(namespace) = self
+            .placement
+            .instances
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(id)
+        {
+            return Ok(Some(namespace.clone()));
+        }
+
+        let params = ListParams::default().labels(&format!("cds/instance_id={}", id));
+        let mut namespace = self
+            .api::<Pod>()
+            .list(&params)
+            .await?
+            .items
+            .into_iter()
+            .find_map(|pod| pod.metadata.namespace);
+        if namespace.is_none() {
+            namespace = self
+                .api::<Service>()
+                .list(&params)
+                .await?
+                .items
+                .into_iter()
+                .find_map(|service| service.metadata.namespace);
+        }
+        if let Some(namespace) = &namespace {
+            self.placement
+                .instances
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .insert(id.to_owned(), namespace.clone());
+        }
+
+        Ok(namespace)
+    }
+
+    /// Applies the ingress policy of instance `id` in `namespace` and
+    /// remembers where it lives. The policy selects on the instance's label,
+    /// so it is applied before the pod exists or receives its flag.
+    pub(crate) async fn isolate(&self, namespace: &str, id: &str) -> Result<(), ClusterError> {
+        if !self.isolation.network_policy {
+            return Ok(());
+        }
+        self.placement
+            .instances
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(id.to_owned(), namespace.to_owned());
+
+        let policy = ingress_policy(
+            &self.isolation,
+            &self.traffic,
+            self.wireguard.enabled.then_some(self.namespace.as_str()),
+            id,
+        );
+        self.api_in::<NetworkPolicy>(Some(namespace))
+            .patch(
+                &format!("cds-{}", id),
+                &PatchParams::apply(FIELD_MANAGER).force(),
+                &Patch::Apply(&policy),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Applies the ingress policy of every instance pod that has none, such
+    /// as those started before policies were.
+    pub(crate) async fn isolate_unguarded(&self, pods: &[Pod]) -> Result<(), ClusterError> {
+        if !self.isolation.network_policy {
+            return Ok(());
+        }
+
+        let guarded = self
+            .api::<NetworkPolicy>()
+            .list(&ListParams::default().labels("cds/instance_id"))
+            .await?
+            .items
+            .into_iter()
+            .filter_map(|policy| {
+                let id = policy.metadata.labels?.remove("cds/instance_id")?;
+                Some((policy.metadata.namespace?, id))
+            })
+            .collect::<HashSet<_>>();
+
+        for pod in pods {
+            let (Some(namespace), Some(id)) = (
+                pod.metadata.namespace.clone(),
+                pod.metadata
+                    .labels
+                    .as_ref()
+                    .and_then(|labels| labels.get("cds/instance_id"))
+                    .cloned(),
+            ) else {
+                continue;
+            };
+            if guarded.contains(&(namespace.clone(), id.clone())) {
+                continue;
+            }
+            self.isolate(&namespace, &id).await?;
+            info!(instance_id = %id, "ingress policy applied to running instance");
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the ingress policy of instance `id` and forgets where it
+    /// lived.
+    pub(crate) async fn release(&self, namespace: &str, id: &str) -> Result<(), ClusterError> {
+        self.api_in::<NetworkPolicy>(Some(namespace))
+            .delete_collection(
+                &DeleteParams::default(),
+                &ListParams::default().labels(&format!("cds/instance_id={id}")),
+            )
+            .await?;
+        self.placement
+            .instances
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(id);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cds_env::cluster::{Traffic, isolation::Config};
+
+    use super::{check, ingress_policy, owner_namespace};
+
+    #[test]
+    fn places_teams_and_users_apart() {
+        assert_eq!(owner_namespace("cds", 1, Some(2), Some(3)), "cds-team-2");
+        assert_eq!(owner_namespace("cds", 1, Some(2), None), "cds-user-1");
+        assert_eq!(owner_namespace("cds", 1, None, None), "cds-user-1");
+    }
+
+    #[test]
+    fn admits_only_the_instance_and_platform() {
+        let mut config = Config::default();
+        let policy = ingress_policy(&config, &Traffic::Proxy, None, "a");
+        let spec = policy.spec.unwrap();
+        let from = spec.ingress.unwrap()[0].from.clone().unwrap();
+        assert_eq!(from.len(), 1);
+        assert_eq!(
+            from[0]
+                .pod_selector
+                .as_ref()
+                .unwrap()
+                .match_labels
+                .as_ref()
+                .unwrap()["cds/instance_id"],
+            "a"
+        );
+        assert!(from[0].namespace_selector.is_none());
+
+        config
+            .proxy_namespace_labels
+            .insert("name".to_owned(), "cdsctf".to_owned());
+        let policy = ingress_policy(&config, &Traffic::Proxy, None, "a");
+        let from = policy.spec.unwrap().ingress.unwrap()[0]
+            .from
+            .clone()
+            .unwrap();
+        assert_eq!(from.len(), 2);
+        assert!(from[1].namespace_selector.is_some());
+        assert!(from[1].pod_selector.is_none());
+
+        let policy = ingress_policy(&config, &Traffic::Proxy, Some("cdsctf-challenges"), "a");
+        let from = policy.spec.unwrap().ingress.unwrap()[0]
+            .from
+            .clone()
+            .unwrap();
+        assert_eq!(from.len(), 3);
+        assert_eq!(
+            from[2]
+                .pod_selector
+                .as_ref()
+                .unwrap()
+                .match_labels
+                .as_ref()
+                .unwrap()["cds/app"],
+            "wireguard"
+        );
+    }
+
+    #[test]
+    fn admits_node_ports_from_outside_the_pod_network() {
+        let config = Config {
+            pod_cidrs: vec!["10.42.0.0/16".to_owned()],
+            ..Default::default()
+        };
+        let policy = ingress_policy(&config, &Traffic::Expose, None, "a");
+        let from = policy.spec.unwrap().ingress.unwrap()[0]
+            .from
+            .clone()
+            .unwrap();
+        let block = from[1].ip_block.clone().unwrap();
+        assert_eq!(block.cidr, "0.0.0.0/0");
+        assert_eq!(block.except, Some(vec!["10.42.0.0/16".to_owned()]));
+
+        let open = Config::default();
+        let policy = ingress_policy(&open, &Traffic::Expose, None, "a");
+        let from = policy.spec.unwrap().ingress.unwrap()[0]
+            .from
+            .clone()
+            .unwrap();
+        assert!(from.iter().all(|peer| peer.ip_block.is_none()));
+
+        let config = Config {
+            allowed_cidrs: vec!["192.168.0.0/24".to_owned()],
+            ..config
+        };
+        let policy = ingress_policy(&config, &Traffic::Expose, None, "a");
+        let from = policy.spec.unwrap().ingress.unwrap()[0]
+            .from
+            .clone()
+            .unwrap();
+        assert_eq!(from.len(), 2);
+        assert_eq!(from[1].ip_block.clone().unwrap().cidr, "192.168.0.0/24");
+    }
+
+    #[test]
+    fn refuses_expose_without_address_ranges() {
+        let config = Config::default();
+        assert!(check(&config, &Traffic::Expose).is_err());
+        assert!(check(&config, &Traffic::Proxy).is_ok());
+        assert!(
+            check(
+                &Config {
+                    pod_cidrs: vec!["10.42.0.0/16".to_owned()],
+                    ..Default::default()
+                },
+                &Traffic::Expose
+            )
+            .is_ok()
+        );
+        assert!(
+            check(
+                &Config {
+                    network_policy: false,
+                    ..Default::default()
+                },
+                &Traffic::Expose
+            )
+            .is_ok()
+        );
+    }
+}
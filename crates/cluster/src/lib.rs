@@ -5,7 +5,37 @@
 //! target namespace exists, and reconciles egress policies so pods labeled
 //! `cds/internet=false` cannot reach the public internet except through
 //! controlled rules, while `cds/internet=true` pods receive DNS + user-defined
-//! exceptions.
+//! exceptions. Instances are further kept apart as described in
+//! [`isolation`]. Teams of a game may also reach their instances' pods
+//! directly through the WireGuard gateway described in [`wireguard`].
+//!
+//! Every instance is also recorded in the `instances` table as it is created,
+//! renewed and torn down, so its history survives the pod; the
+//! [`worker::reconciler`] keeps both sides in agreement.
+
+/// Defines the `inspect` submodule (see sibling `*.rs` files).
+pub mod inspect;
+
+/// Defines the `isolation` submodule (see sibling `*.rs` files).
+mod isolation;
+
+/// Defines the `lifetime` submodule (see sibling `*.rs` files).
+pub mod lifetime;
+
+/// Defines the `pool` submodule (see sibling `*.rs` files).
+mod pool;
+
+/// Defines the `prepull` submodule (see sibling `*.rs` files).
+pub mod prepull;
+
+/// Defines the `reset` submodule (see sibling `*.rs` files).
+pub mod reset;
+
+/// Defines the `security` submodule (see sibling `*.rs` files).
+pub mod security;
+
+/// Defines the `tcp` submodule (see sibling `*.rs` files).
+pub mod tcp;
 
 /// Defines the `traits` submodule (see sibling `*.rs` files).
 pub mod traits;
@@ -13,38 +43,32 @@ pub mod traits;
 /// Defines the `util` submodule (see sibling `*.rs` files).
 mod util;
 
+/// Defines the `wireguard` submodule (see sibling `*.rs` files).
+mod wireguard;
+
 /// Defines the `worker` submodule (see sibling `*.rs` files).
 pub mod worker;
 
-use std::{collections::BTreeMap, path::Path, process};
+use std::{collections::BTreeMap, net::IpAddr, path::Path, process};
 
 use axum::extract::ws::{Message, Utf8Bytes, WebSocket};
 use cds_checker::Checker;
-use cds_db::challenge::Port;
+use cds_db::{DB, challenge::Port, instance::TerminationReason};
 use cds_env::Env;
+use cds_event::{EventManager, types::instance::InstanceEventType};
 use futures_util::{SinkExt, StreamExt as _, stream::SplitStream};
 pub use k8s_openapi;
 use k8s_openapi::{
-    api::{
-        core::v1::{
-            Container as K8sContainer, ContainerPort, EnvVar, Namespace, Pod, PodSpec,
-            ResourceRequirements, Service, ServicePort, ServiceSpec,
-        },
-        networking::v1::{
-            IPBlock, NetworkPolicy, NetworkPolicyEgressRule, NetworkPolicyPeer, NetworkPolicyPort,
-            NetworkPolicySpec,
-        },
-    },
-    apimachinery::pkg::{
-        api::resource::Quantity,
-        apis::meta::v1::{LabelSelector, ObjectMeta},
-        util::intstr::IntOrString,
+    api::core::v1::{
+        Container as K8sContainer, ContainerPort, EnvVar, Pod, PodSpec, ResourceRequirements,
+        Service, ServicePort, ServiceSpec, Volume, VolumeMount,
     },
+    apimachinery::pkg::{api::resource::Quantity, apis::meta::v1::ObjectMeta},
 };
 pub use kube;
 use kube::{
     Client as K8sClient, Config as K8sConfig, ResourceExt,
-    api::{Api, AttachParams, DeleteParams, ListParams, Patch, PatchParams, PostParams},
+    api::{Api, AttachParams, DeleteParams, ListParams, Patch, PatchParams},
     config::{KubeConfigOptions, Kubeconfig},
 };
 use serde_json::json;
@@ -56,22 +80,46 @@ use tokio_util::{
 use tracing::{error, info};
 use uuid::Uuid;
 
-use crate::traits::{ClusterError, Nat};
+use crate::{
+    tcp::{TcpAllocation, TcpProxy},
+    traits::{ClusterError, Nat},
+};
 
 /// Connected API client, target namespace, ingress mode (`Expose` vs `Proxy`),
-/// and checker for env generation.
+/// instance records, checker for env generation, and the event bus owners are
+/// notified on.
 #[derive(Clone)]
 pub struct Cluster {
     client: K8sClient,
     namespace: String,
     traffic: cds_env::cluster::Traffic,
-
+    public_entry: String,
+    access_token: bool,
+    tcp: Option<TcpProxy>,
+    security: cds_env::cluster::security::Config,
+    isolation: cds_env::cluster::isolation::Config,
+    placement: isolation::Placement,
+    egress_excluded_cidrs: Vec<String>,
+    warmup: cds_env::cluster::warmup::Config,
+    lifetime: cds_env::cluster::lifetime::Config,
+    reset: cds_env::cluster::reset::Config,
+    activity: lifetime::Activity,
+    wireguard: cds_env::cluster::wireguard::Config,
+    wireguard_counters: wireguard::Counters,
+
+    db: DB,
     checker: Checker,
+    event: EventManager,
 }
 
 /// Builds the Kubernetes client, ensures namespace + baseline NetworkPolicies,
-/// and spawns garbage-collection worker.
-pub async fn init(env: &Env, checker: &Checker) -> Result<Cluster, ClusterError> {
+/// and spawns the instance reconciler.
+pub async fn init(
+    env: &Env,
+    db: &DB,
+    checker: &Checker,
+    event: &EventManager,
+) -> Result<Cluster, ClusterError> {
     let client = if env.cluster.auto_infer {
         K8sClient::try_from(K8sConfig::infer().await?)?
     } else {
@@ -86,163 +134,50 @@ pub async fn init(env: &Env, checker: &Checker) -> Result<Cluster, ClusterError>
     }
     info!("Kubernetes client initialized successfully.");
 
-    let namespace_api: Api<Namespace> = Api::all(client.clone());
-    let namespaces = namespace_api.list(&ListParams::default()).await?;
-    if !namespaces
-        .items
-        .iter()
-        .any(|namespace| namespace.metadata.name == Some(env.cluster.namespace.to_owned()))
-    {
-        let namespace = Namespace {
-            metadata: ObjectMeta {
-                name: Some(env.cluster.namespace.to_owned()),
-                ..Default::default()
-            },
-            ..Default::default()
-        };
-        let _ = namespace_api
-            .create(&PostParams::default(), &namespace)
-            .await;
-        info!("Namespace is created successfully.");
+    if let Err(reason) = isolation::check(&env.cluster.isolation, &env.cluster.traffic) {
+        error!(
+            "Refusing to start with unsafe isolation settings: {}",
+            reason
+        );
+        process::exit(1);
     }
 
-    let network_policy_api: Api<NetworkPolicy> =
-        Api::namespaced(client.clone(), env.cluster.namespace.as_str());
-
-    if network_policy_api
-        .get("cds-internet-restricted")
-        .await
-        .is_err()
-    {
-        let network_policy = NetworkPolicy {
-            metadata: ObjectMeta {
-                name: Some("cds-internet-restricted".to_owned()),
-                namespace: Some(env.cluster.namespace.to_owned()),
-                ..Default::default()
-            },
-            spec: Some(NetworkPolicySpec {
-                pod_selector: Some(LabelSelector {
-                    match_labels: Some(BTreeMap::from([(
-                        "cds/internet".to_owned(),
-                        "false".to_owned(),
-                    )])),
-                    ..Default::default()
-                }),
-                policy_types: Some(vec!["Egress".to_owned()]),
-                ..Default::default()
-            }),
-        };
-        network_policy_api
-            .create(&PostParams::default(), &network_policy)
-            .await?;
-
-        info!("Restricted network policy is created successfully.");
-    }
-
-    let desired_excluded_cidrs = env.cluster.egress_excluded_cidrs.clone();
-    let network_policy = NetworkPolicy {
-        metadata: ObjectMeta {
-            name: Some("cds-internet-allowed".to_owned()),
-            namespace: Some(env.cluster.namespace.to_owned()),
-            ..Default::default()
-        },
-        spec: Some(NetworkPolicySpec {
-            pod_selector: Some(LabelSelector {
-                match_labels: Some(BTreeMap::from([(
-                    "cds/internet".to_owned(),
-                    "true".to_owned(),
-                )])),
-                ..Default::default()
-            }),
-            policy_types: Some(vec!["Egress".to_owned()]),
-            egress: Some(vec![
-                NetworkPolicyEgressRule {
-                    to: Some(vec![NetworkPolicyPeer {
-                        namespace_selector: Some(LabelSelector {
-                            match_labels: Some(BTreeMap::from([(
-                                "kubernetes.io/metadata.name".to_string(),
-                                "kube-system".to_string(),
-                            )])),
-                            ..Default::default()
-                        }),
-                        pod_selector: Some(LabelSelector {
-                            match_labels: Some(BTreeMap::from([(
-                                "k8s-app".to_string(),
-                                "kube-dns".to_string(),
-                            )])),
-                            ..Default::default()
-                        }),
-                        ..Default::default()
-                    }]),
-                    ports: Some(vec![
-                        NetworkPolicyPort {
-                            protocol: Some("UDP".to_string()),
-                            port: Some(IntOrString::Int(53)),
-                            ..Default::default()
-                        },
-                        NetworkPolicyPort {
-                            protocol: Some("TCP".to_string()),
-                            port: Some(IntOrString::Int(53)),
-                            ..Default::default()
-                        },
-                    ]),
-                    ..Default::default()
-                },
-                NetworkPolicyEgressRule {
-                    to: Some(vec![NetworkPolicyPeer {
-                        ip_block: Some(IPBlock {
-                            cidr: "0.0.0.0/0".to_owned(),
-                            except: Some(desired_excluded_cidrs.clone()),
-                        }),
-                        ..Default::default()
-                    }]),
-                    ..Default::default()
-                },
-            ]),
-            ..Default::default()
-        }),
-    };
-    match network_policy_api.get("cds-internet-allowed").await {
-        Err(_) => {
-            network_policy_api
-                .create(&PostParams::default(), &network_policy)
-                .await?;
-            info!("Allowed network policy is created successfully.");
-        }
-        Ok(np) => {
-            let current_excluded = np
-                .spec
-                .as_ref()
-                .and_then(|s| s.egress.as_ref())
-                .and_then(|egress| egress.first())
-                .and_then(|rule| rule.to.as_ref())
-                .and_then(|to| to.first())
-                .and_then(|peer| peer.ip_block.as_ref())
-                .and_then(|ipb| ipb.except.clone());
-
-            if current_excluded != Some(desired_excluded_cidrs.clone()) {
-                network_policy_api
-                    .patch(
-                        "cds-internet-allowed",
-                        &PatchParams::default(),
-                        &Patch::Merge(&network_policy),
-                    )
-                    .await?;
-
-                info!("Allowed network policy updated due to excluded CIDRs change.");
-            }
-        }
-    }
+    isolation::ensure_namespace(
+        &client,
+        &env.cluster.namespace,
+        &env.cluster.egress_excluded_cidrs,
+    )
+    .await?;
 
     let cluster = Cluster {
         client,
         namespace: env.cluster.namespace.clone(),
         traffic: env.cluster.traffic.clone(),
-
+        public_entry: env.cluster.public_entry.clone(),
+        access_token: env.cluster.ingress.access_token,
+        tcp: env
+            .cluster
+            .tcp_proxy
+            .enabled
+            .then(|| TcpProxy::new(env.cluster.tcp_proxy.clone())),
+        security: env.cluster.security.clone(),
+        isolation: env.cluster.isolation.clone(),
+        placement: isolation::Placement::default(),
+        egress_excluded_cidrs: env.cluster.egress_excluded_cidrs.clone(),
+        warmup: env.cluster.warmup.clone(),
+        lifetime: env.cluster.lifetime.clone(),
+        reset: env.cluster.reset.clone(),
+        activity: lifetime::Activity::default(),
+        wireguard: env.cluster.wireguard.clone(),
+        wireguard_counters: wireguard::Counters::default(),
+
+        db: db.clone(),
         checker: checker.clone(),
+        event: event.clone(),
     };
 
-    worker::cleaner(cluster.clone()).await;
+    cluster.ensure_wireguard_gateway().await?;
+    worker::reconciler(cluster.clone()).await;
 
     Ok(cluster)
 }
@@ -263,10 +198,8 @@ impl Cluster {
     /// Returns services by label.
 
     pub async fn get_services_by_label(&self, label: &str) -> Result<Vec<Service>, ClusterError> {
-        let service_api: Api<Service> =
-            Api::namespaced(self.client.clone(), self.namespace.as_str());
-
-        let services = service_api
+        let services = self
+            .api::<Service>()
             .list(&ListParams {
                 label_selector: Some(label.to_owned()),
                 ..Default::default()
@@ -279,8 +212,7 @@ impl Cluster {
     /// Creates service.
 
     pub async fn create_service(&self, service: Service) -> Result<Service, ClusterError> {
-        let service_api: Api<Service> =
-            Api::namespaced(self.client.clone(), self.namespace.as_str());
+        let service_api: Api<Service> = self.api_in(service.metadata.namespace.as_deref());
 
         let service = service_api.create(&Default::default(), &service).await?;
 
@@ -289,9 +221,8 @@ impl Cluster {
 
     /// Deletes service.
 
-    pub async fn delete_service(&self, id: &str) -> Result<(), ClusterError> {
-        let service_api: Api<Service> =
-            Api::namespaced(self.client.clone(), self.namespace.as_str());
+    pub async fn delete_service(&self, namespace: &str, id: &str) -> Result<(), ClusterError> {
+        let service_api: Api<Service> = self.api_in(Some(namespace));
 
         let _ = service_api
             .delete_collection(
@@ -319,9 +250,10 @@ impl Cluster {
     /// Returns pods list.
 
     pub async fn get_pods_list(&self) -> Result<Vec<Pod>, ClusterError> {
-        let pod_api: Api<Pod> = Api::namespaced(self.client.clone(), self.namespace.as_str());
-
-        let pods = pod_api.list(&ListParams::default()).await?;
+        let pods = self
+            .api::<Pod>()
+            .list(&ListParams::default().labels("cds/instance_id"))
+            .await?;
 
         Ok(pods.items)
     }
@@ -329,9 +261,8 @@ impl Cluster {
     /// Returns pods by label.
 
     pub async fn get_pods_by_label(&self, label: &str) -> Result<Vec<Pod>, ClusterError> {
-        let pod_api: Api<Pod> = Api::namespaced(self.client.clone(), self.namespace.as_str());
-
-        let pods = pod_api
+        let pods = self
+            .api::<Pod>()
             .list(&ListParams {
                 label_selector: Some(label.to_owned()),
                 field_selector: Some(
@@ -347,7 +278,7 @@ impl Cluster {
     /// Creates pod.
 
     pub async fn create_pod(&self, pod: Pod) -> Result<Pod, ClusterError> {
-        let pod_api: Api<Pod> = Api::namespaced(self.client.clone(), self.namespace.as_str());
+        let pod_api: Api<Pod> = self.api_in(pod.metadata.namespace.as_deref());
 
         let pod = pod_api.create(&Default::default(), &pod).await?;
 
@@ -356,13 +287,13 @@ impl Cluster {
 
     /// Deletes pod.
 
-    pub async fn delete_pod(&self, id: &str) -> Result<(), ClusterError> {
-        let pod_api: Api<Pod> = Api::namespaced(self.client.clone(), self.namespace.as_str());
+    pub async fn delete_pod(&self, namespace: &str, id: &str) -> Result<(), ClusterError> {
+        let pod_api: Api<Pod> = self.api_in(Some(namespace));
 
         let _ = pod_api
             .delete_collection(
                 &DeleteParams {
-                    grace_period_seconds: Some(0),
+                    grace_period_seconds: Some(self.lifetime.grace_period.max(0) as u32),
                     ..Default::default()
                 },
                 &ListParams::default().labels(&format!("cds/instance_id={id}")),
@@ -372,8 +303,13 @@ impl Cluster {
         Ok(())
     }
 
-    /// Creates challenge instance.
-
+    /// Creates challenge instance, from the challenge's warm pool when it
+    /// has a ready pod.
+    ///
+    /// The instance is recorded before any Kubernetes object exists, so a pod
+    /// can never run without a record; if the pod or service cannot be
+    /// created, whatever was made is removed and the record is closed as
+    /// `create_failed`.
     pub async fn create_challenge_instance(
         &self,
         user: cds_db::UserAccountView,
@@ -381,22 +317,153 @@ impl Cluster {
         game: Option<cds_db::GameDetail>,
         challenge: cds_db::ChallengeDetail,
     ) -> Result<String, ClusterError> {
+        let access_token = (self.traffic == cds_env::cluster::Traffic::Ingress
+            && self.access_token)
+            .then(util::gen_access_token);
+
+        let instance = challenge
+            .instance
+            .clone()
+            .ok_or_else(|| ClusterError::MissingEnvConfiguration)?;
+
+        // Pool pods start in the platform's namespace and cannot move to a
+        // team's.
+        if instance.warm_pool > 0
+            && !self.isolation.namespace_per_team
+            && let Some(id) = self
+                .claim_pool_instance(access_token.clone(), &user, &team, &game, &challenge)
+                .await?
+        {
+            return Ok(id);
+        }
+
         let id = util::gen_safe_nanoid();
-        let name = format!("cds-{}", id);
+        let limits = lifetime::limits(&self.lifetime, &instance);
+
+        cds_db::instance::create::<cds_db::InstanceView>(
+            &self.db.conn,
+            cds_db::instance::NewInstance {
+                id: id.clone(),
+                user_id: user.id,
+                team_id: team.as_ref().map(|team| team.id),
+                game_id: game.as_ref().map(|game| game.id),
+                challenge_id: challenge.id,
+                ports: instance
+                    .containers
+                    .iter()
+                    .flat_map(|container| container.ports.clone())
+                    .collect(),
+                renew: 0,
+                duration: instance.duration,
+                created_at: util::now(),
+                access_token: access_token.clone(),
+                max_renewals: limits.max_renewals,
+                max_lifetime: limits.max_lifetime,
+                idle_timeout: limits.idle_timeout,
+                max_resets: reset::max_resets(&self.reset, &instance),
+            },
+        )
+        .await?;
+
+        match self
+            .start_challenge_instance(&id, access_token, user, team, game, challenge)
+            .await
+        {
+            Ok(nats) => {
+                cds_db::instance::update_nats(&self.db.conn, &id, nats).await?;
+                Ok(id)
+            }
+            Err(err) => {
+                error!(instance_id = %id, error = %err, "failed to start challenge instance");
+                if let Err(err) = self.delete_resources(&id).await {
+                    error!(instance_id = %id, error = %err, "failed to clean up challenge instance");
+                }
+                cds_db::instance::terminate(
+                    &self.db.conn,
+                    &id,
+                    TerminationReason::CreateFailed,
+                    util::now(),
+                )
+                .await?;
+                Err(err)
+            }
+        }
+    }
 
+    /// Creates the pod and service of a recorded instance and returns the
+    /// node ports assigned to it.
+    async fn start_challenge_instance(
+        &self,
+        id: &str,
+        access_token: Option<String>,
+        user: cds_db::UserAccountView,
+        team: Option<cds_db::TeamView>,
+        game: Option<cds_db::GameDetail>,
+        challenge: cds_db::ChallengeDetail,
+    ) -> Result<Vec<Nat>, ClusterError> {
         let instance = challenge
             .clone()
             .instance
             .ok_or_else(|| ClusterError::MissingEnvConfiguration)?;
 
+        let operator_id = if let (Some(_), Some(team)) = (&game, &team) {
+            team.id
+        } else {
+            user.id
+        };
+        let checker_environ = self.checker.generate(&challenge, operator_id).await?;
+        let namespace = self
+            .instance_namespace(
+                user.id,
+                team.as_ref().map(|team| team.id),
+                game.as_ref().map(|game| game.id),
+            )
+            .await?;
+
+        self.isolate(&namespace, id).await?;
+
+        let mut metadata = self.instance_metadata(id, access_token, user, team, game, &challenge);
+        metadata.namespace = Some(namespace);
+        let checker_env_vars = checker_environ
+            .into_iter()
+            .map(|(k, v)| EnvVar {
+                name: k,
+                value: Some(v),
+                ..Default::default()
+            })
+            .collect::<Vec<EnvVar>>();
+
+        let pod = Pod {
+            metadata: metadata.clone(),
+            spec: Some(self.pod_spec(&instance, checker_env_vars, None)),
+            ..Default::default()
+        };
+
+        self.create_pod(pod).await?;
+
+        self.expose(id, metadata).await
+    }
+
+    /// Labels and annotations of an instance's pod and service.
+    pub(crate) fn instance_metadata(
+        &self,
+        id: &str,
+        access_token: Option<String>,
+        user: cds_db::UserAccountView,
+        team: Option<cds_db::TeamView>,
+        game: Option<cds_db::GameDetail>,
+        challenge: &cds_db::ChallengeDetail,
+    ) -> ObjectMeta {
+        let instance = challenge.instance.clone().unwrap_or_default();
         let all_ports = instance
             .containers
             .iter()
             .flat_map(|container| container.ports.clone())
             .collect::<Vec<Port>>();
+        let limits = lifetime::limits(&self.lifetime, &instance);
 
-        let metadata = ObjectMeta {
-            name: Some(name.clone()),
+        let mut metadata = ObjectMeta {
+            name: Some(format!("cds-{}", id)),
             labels: Some(BTreeMap::from([
                 ("cds/app".to_owned(), "challenges".to_owned()),
                 ("cds/instance_id".to_owned(), id.to_string()),
@@ -431,104 +498,167 @@ impl Cluster {
                 ("cds/game".to_owned(), json!(game).to_string()),
                 ("cds/renew".to_owned(), format!("{}", 0)),
                 ("cds/duration".to_owned(), format!("{}", instance.duration)),
+                (
+                    "cds/max_renewals".to_owned(),
+                    format!("{}", limits.max_renewals),
+                ),
+                (
+                    "cds/max_lifetime".to_owned(),
+                    format!("{}", limits.max_lifetime),
+                ),
+                (
+                    "cds/idle_timeout".to_owned(),
+                    format!("{}", limits.idle_timeout),
+                ),
+                ("cds/resets".to_owned(), format!("{}", 0)),
+                (
+                    "cds/max_resets".to_owned(),
+                    format!("{}", reset::max_resets(&self.reset, &instance)),
+                ),
                 ("cds/ports".to_owned(), json!(all_ports).to_string()),
             ])),
             ..Default::default()
         };
+        if let Some(access_token) = access_token {
+            metadata
+                .annotations
+                .get_or_insert_default()
+                .insert("cds/access_token".to_owned(), access_token);
+        }
 
-        let operator_id = if let (Some(_), Some(team)) = (game, team) {
-            team.id
-        } else {
-            user.id
-        };
-
-        let checker_environ = self.checker.generate(&challenge, operator_id).await?;
-
-        let checker_env_vars = checker_environ
-            .into_iter()
-            .map(|(k, v)| EnvVar {
-                name: k,
-                value: Some(v),
-                ..Default::default()
-            })
-            .collect::<Vec<EnvVar>>();
+        metadata
+    }
 
-        let pod = Pod {
-            metadata: metadata.clone(),
-            spec: Some(PodSpec {
-                containers: instance
-                    .containers
-                    .into_iter()
-                    .map(|container| {
-                        let merged_env_vars = container
-                            .envs
-                            .into_iter()
-                            .map(|env_var| EnvVar {
-                                name: env_var.key,
-                                value: Some(env_var.value),
-                                ..Default::default()
-                            })
-                            .chain(checker_env_vars.clone())
-                            .collect::<Vec<EnvVar>>();
-
-                        K8sContainer {
-                            name: format!("cds-{}", util::gen_safe_nanoid()),
-                            image: Some(container.image),
-                            env: Some(merged_env_vars),
-                            ports: Some(
-                                container
-                                    .ports
-                                    .into_iter()
-                                    .map(|port| ContainerPort {
-                                        container_port: port.port,
-                                        protocol: Some(port.protocol),
-                                        ..Default::default()
-                                    })
-                                    .collect::<Vec<ContainerPort>>(),
+    /// Pod spec of a challenge instance. With `env_dir`, an empty directory
+    /// is mounted there in every container for envs written after start.
+    pub(crate) fn pod_spec(
+        &self,
+        instance: &cds_db::challenge::Instance,
+        env_vars: Vec<EnvVar>,
+        env_dir: Option<&str>,
+    ) -> PodSpec {
+        let runtime_class_name = security::runtime_class_name(&self.security, instance);
+        let node_selector = security::node_selector(&self.security, instance);
+        let tolerations = security::tolerations(&self.security, instance);
+
+        PodSpec {
+            containers: instance
+                .containers
+                .iter()
+                .cloned()
+                .map(|container| {
+                    let security_context = security::security_context(&self.security, &container);
+                    let ephemeral_storage_limit =
+                        security::ephemeral_storage_limit(&self.security, &container);
+
+                    let merged_env_vars = container
+                        .envs
+                        .into_iter()
+                        .map(|env_var| EnvVar {
+                            name: env_var.key,
+                            value: Some(env_var.value),
+                            ..Default::default()
+                        })
+                        .chain(env_vars.clone())
+                        .collect::<Vec<EnvVar>>();
+
+                    K8sContainer {
+                        name: format!("cds-{}", util::gen_safe_nanoid()),
+                        image: Some(container.image),
+                        env: Some(merged_env_vars),
+                        ports: Some(
+                            container
+                                .ports
+                                .into_iter()
+                                .map(|port| ContainerPort {
+                                    container_port: port.port,
+                                    protocol: Some(port.protocol),
+                                    ..Default::default()
+                                })
+                                .collect::<Vec<ContainerPort>>(),
+                        ),
+                        image_pull_policy: Some(container.image_pull_policy),
+                        resources: Some(ResourceRequirements {
+                            requests: Some(
+                                [
+                                    ("cpu", "10m".to_owned()),
+                                    ("memory", "32Mi".to_owned()),
+                                    (
+                                        "ephemeral-storage",
+                                        format!("{}Mi", ephemeral_storage_limit.min(64)),
+                                    ),
+                                ]
+                                .iter()
+                                .cloned()
+                                .map(|(k, v)| (k.to_owned(), Quantity(v)))
+                                .collect(),
+                            ),
+                            limits: Some(
+                                [
+                                    ("cpu", container.cpu_limit.to_string()),
+                                    ("memory", format!("{}Mi", container.memory_limit)),
+                                    (
+                                        "ephemeral-storage",
+                                        format!("{}Mi", ephemeral_storage_limit),
+                                    ),
+                                ]
+                                .iter()
+                                .cloned()
+                                .map(|(k, v)| (k.to_owned(), Quantity(v)))
+                                .collect(),
                             ),
-                            image_pull_policy: Some(container.image_pull_policy),
-                            resources: Some(ResourceRequirements {
-                                requests: Some(
-                                    [
-                                        ("cpu", "10m".to_owned()),
-                                        ("memory", "32Mi".to_owned()),
-                                        ("ephemeral-storage", "64Mi".to_owned()),
-                                    ]
-                                    .iter()
-                                    .cloned()
-                                    .map(|(k, v)| (k.to_owned(), Quantity(v)))
-                                    .collect(),
-                                ),
-                                limits: Some(
-                                    [
-                                        ("cpu", container.cpu_limit.to_string()),
-                                        ("memory", format!("{}Mi", container.memory_limit)),
-                                    ]
-                                    .iter()
-                                    .cloned()
-                                    .map(|(k, v)| (k.to_owned(), Quantity(v)))
-                                    .collect(),
-                                ),
-                                ..Default::default()
-                            }),
                             ..Default::default()
-                        }
-                    })
-                    .collect::<Vec<K8sContainer>>(),
-                ..Default::default()
+                        }),
+                        security_context: Some(security_context),
+                        volume_mounts: env_dir.map(|env_dir| {
+                            vec![VolumeMount {
+                                name: "cds-env".to_owned(),
+                                mount_path: env_dir.to_owned(),
+                                ..Default::default()
+                            }]
+                        }),
+                        ..Default::default()
+                    }
+                })
+                .collect::<Vec<K8sContainer>>(),
+            volumes: env_dir.map(|_| {
+                vec![Volume {
+                    name: "cds-env".to_owned(),
+                    empty_dir: Some(Default::default()),
+                    ..Default::default()
+                }]
             }),
+            runtime_class_name,
+            node_selector: Some(node_selector).filter(|selector| !selector.is_empty()),
+            tolerations: Some(tolerations).filter(|tolerations| !tolerations.is_empty()),
+            automount_service_account_token: Some(false),
+            termination_grace_period_seconds: Some(self.lifetime.grace_period.max(0)),
             ..Default::default()
-        };
+        }
+    }
 
-        let mut pod = self.create_pod(pod).await?;
+    /// Creates the service of a started instance, records the node ports on
+    /// its pod and returns them.
+    pub(crate) async fn expose(
+        &self,
+        id: &str,
+        metadata: ObjectMeta,
+    ) -> Result<Vec<Nat>, ClusterError> {
+        let name = format!("cds-{}", id);
+        let all_ports = metadata
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.get("cds/ports"))
+            .and_then(|ports| serde_json::from_str::<Vec<Port>>(ports).ok())
+            .unwrap_or_default();
 
         let service_type = match self.traffic {
             cds_env::cluster::Traffic::Expose => "NodePort",
-            cds_env::cluster::Traffic::Proxy => "ClusterIP",
+            cds_env::cluster::Traffic::Proxy | cds_env::cluster::Traffic::Ingress => "ClusterIP",
         };
 
         let service = Service {
-            metadata: metadata.clone(),
+            metadata,
             spec: Some(ServiceSpec {
                 selector: Some(BTreeMap::from([(
                     "cds/instance_id".to_owned(),
@@ -552,13 +682,12 @@ impl Cluster {
             ..Default::default()
         };
 
-        let service = match self.create_service(service).await {
-            Ok(service) => service,
-            Err(err) => {
-                self.delete_challenge_instance(&id.to_string()).await?;
-                return Err(err);
-            }
-        };
+        let service = self.create_service(service).await?;
+        let namespace = service
+            .metadata
+            .namespace
+            .clone()
+            .unwrap_or_else(|| self.namespace.clone());
 
         let mut nats: Vec<Nat> = vec![];
 
@@ -576,10 +705,7 @@ impl Cluster {
             }
         }
 
-        let pod_api: Api<Pod> = Api::namespaced(self.client.clone(), self.namespace.as_str());
-
-        let annotations = pod.annotations_mut();
-        annotations.insert("cds/nats".to_owned(), json!(nats).to_string());
+        let pod_api: Api<Pod> = self.api_in(Some(&namespace));
 
         pod_api
             .patch(
@@ -587,21 +713,23 @@ impl Cluster {
                 &PatchParams::default(),
                 &Patch::Merge(json!({
                     "metadata": {
-                        "annotations": annotations,
+                        "annotations": {
+                            "cds/nats": json!(nats).to_string(),
+                        },
                     }
                 })),
             )
             .await?;
 
-        Ok(id)
+        Ok(nats)
     }
 
     /// Extends lifetime metadata on a running challenge pod.
     pub async fn renew_challenge_instance(&self, id: &str) -> Result<(), ClusterError> {
         let name = format!("cds-{}", id);
-        let pod_api: Api<Pod> = Api::namespaced(self.client.clone(), self.namespace.as_str());
 
         let mut pod = self.get_pod(id).await?;
+        let pod_api: Api<Pod> = self.api_in(pod.metadata.namespace.as_deref());
 
         let annotations = pod.annotations_mut();
 
@@ -621,31 +749,102 @@ impl Cluster {
             )
             .await?;
 
+        cds_db::instance::renew::<cds_db::InstanceView>(&self.db.conn, id, util::now()).await?;
+
         Ok(())
     }
 
-    /// Deletes challenge instance.
+    /// Deletes challenge instance, giving its containers the grace period to
+    /// shut down, closes its record with `reason` and tells its owner.
+    pub async fn delete_challenge_instance(
+        &self,
+        id: &str,
+        reason: TerminationReason,
+    ) -> Result<(), ClusterError> {
+        self.delete_resources(id).await?;
+
+        let now = util::now();
+        if cds_db::instance::terminate(&self.db.conn, id, reason.clone(), now).await?
+            && let Some(record) =
+                cds_db::instance::find_by_id::<cds_db::InstanceView>(&self.db.conn, id).await?
+        {
+            self.notify(&record, InstanceEventType::Terminated, now, &reason)
+                .await;
+        }
+
+        Ok(())
+    }
 
-    pub async fn delete_challenge_instance(&self, id: &str) -> Result<(), ClusterError> {
-        self.delete_pod(id).await?;
-        self.delete_service(id).await?;
+    /// Deletes the pod, service and ingress policy of an instance without
+    /// touching its record.
+    pub(crate) async fn delete_resources(&self, id: &str) -> Result<(), ClusterError> {
+        if let Some(tcp) = &self.tcp {
+            tcp.release(id);
+        }
+        let Some(namespace) = self.namespace_of(id).await? else {
+            return Ok(());
+        };
+        self.delete_pod(&namespace, id).await?;
+        self.delete_service(&namespace, id).await?;
+        self.release(&namespace, id).await?;
 
         Ok(())
     }
 
-    /// Proxies WebSocket traffic into a pod port via `wsrx`.
-    pub async fn wsrx(&self, id: &str, port: u16, ws: WebSocket) -> Result<(), ClusterError> {
+    /// Opens a byte stream to a pod port through the Kubernetes port-forward
+    /// API; traffic on it keeps the instance from idling out.
+    pub async fn port_forward(
+        &self,
+        id: &str,
+        port: u16,
+    ) -> Result<impl AsyncRead + AsyncWrite + Unpin + Send + use<>, ClusterError> {
         let name = format!("cds-{}", id);
+        let namespace = self
+            .namespace_of(id)
+            .await?
+            .ok_or_else(|| ClusterError::NotFound("pod_not_found".to_owned()))?;
 
-        let pod_api: Api<Pod> = Api::namespaced(self.client.clone(), self.namespace.as_str());
+        let pod_api: Api<Pod> = self.api_in(Some(&namespace));
         let mut pf = pod_api.portforward(&name, &[port]).await?;
-        let pfw = pf.take_stream(port);
-        if let Some(pfw) = pfw {
-            let stream = Framed::new(pfw, wsrx::proxy::MessageCodec::new());
-            let ws: wsrx::WrappedWsStream = ws.into();
-            let cancel_token = CancellationToken::new();
-            wsrx::proxy::proxy_stream(stream, ws, cancel_token).await?;
-        }
+
+        let stream = pf
+            .take_stream(port)
+            .ok_or_else(|| ClusterError::NotFound("port_not_found".to_owned()))?;
+
+        Ok(self.activity.track(id, stream))
+    }
+
+    /// Hands out a TCP proxy port for an instance port, issuing fresh
+    /// credentials for `client_ip`.
+    pub async fn allocate_tcp_port(
+        &self,
+        id: &str,
+        port: u16,
+        client_ip: Option<IpAddr>,
+    ) -> Result<TcpAllocation, ClusterError> {
+        let tcp = self
+            .tcp
+            .as_ref()
+            .ok_or_else(|| ClusterError::NotFound("tcp_proxy_disabled".to_owned()))?;
+
+        tcp.allocate(self, id, port, client_ip).await
+    }
+
+    /// Lists the TCP proxy ports handed out for an instance.
+    pub fn tcp_allocations(&self, id: &str) -> Vec<TcpAllocation> {
+        self.tcp
+            .as_ref()
+            .map(|tcp| tcp.list(id, &self.public_entry))
+            .unwrap_or_default()
+    }
+
+    /// Proxies WebSocket traffic into a pod port via `wsrx`.
+    pub async fn wsrx(&self, id: &str, port: u16, ws: WebSocket) -> Result<(), ClusterError> {
+        let pfw = self.port_forward(id, port).await?;
+        let stream = Framed::new(pfw, wsrx::proxy::MessageCodec::new());
+        let ws: wsrx::WrappedWsStream = ws.into();
+        let cancel_token = CancellationToken::new();
+        wsrx::proxy::proxy_stream(stream, ws, cancel_token).await?;
         Ok(())
     }
 
@@ -704,8 +903,12 @@ impl Cluster {
 
         let (sender, receiver) = ws.split();
         let name = format!("cds-{}", id);
+        let namespace = self
+            .namespace_of(id)
+            .await?
+            .ok_or_else(|| ClusterError::NotFound("pod_not_found".to_owned()))?;
 
-        let pod_api: Api<Pod> = Api::namespaced(self.client.clone(), self.namespace.as_str());
+        let pod_api: Api<Pod> = self.api_in(Some(&namespace));
 
         let attach_params = AttachParams {
             container: Some(format!("cds-{}", container_id)),
@@ -0,0 +1,363 @@
+//! Instance lifetimes: the limits a challenge resolves to, when a running
+//! instance is due to stop, and the proxied traffic that keeps it from
+//! idling out.
+//!
+//! Traffic is counted on the streams [`Cluster::port_forward`] hands out,
+//! which carry every `wsrx`, ingress and TCP proxy connection, and from the
+//! WireGuard gateway's forwarding counters, since peers reach pods directly
+//! (see [`crate::wireguard`]). Each server
+//! keeps the last time it saw traffic per instance in memory, and the
+//! reconciler writes it to the instance records every pass.
+
+use std::{
+    collections::HashMap,
+    io,
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicI64, Ordering},
+    },
+    task::{Context, Poll},
+};
+
+use cds_db::{InstanceView, challenge::Instance, instance::TerminationReason};
+use cds_env::cluster::lifetime::Config;
+use cds_event::types::{
+    Event,
+    instance::{InstanceEvent, InstanceEventType},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tracing::warn;
+
+use crate::{Cluster, traits::ClusterError, util};
+
+/// Lifetime limits of an instance, with the platform's defaults filled in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    pub max_renewals: i32,
+    /// Seconds, `0` for no limit.
+    pub max_lifetime: i64,
+    /// Seconds, `0` for never.
+    pub idle_timeout: i64,
+}
+
+/// Resolves a challenge's limits against the platform's.
+pub fn limits(config: &Config, instance: &Instance) -> Limits {
+    Limits {
+        max_renewals: instance.max_renewals.unwrap_or(config.max_renewals).max(0),
+        max_lifetime: instance.max_lifetime.unwrap_or(config.max_lifetime).max(0),
+        idle_timeout: instance.idle_timeout.unwrap_or(config.idle_timeout).max(0),
+    }
+}
+
+/// When a running instance is due to stop, and why: at its expiry, or
+/// earlier once it has seen no traffic for its idle timeout. Starting and
+/// renewing count as activity.
+pub(crate) fn deadline(record: &InstanceView) -> (i64, TerminationReason) {
+    let expiry = (record.expires_at, TerminationReason::Expired);
+    if record.idle_timeout <= 0 {
+        return expiry;
+    }
+
+    let active = record
+        .last_active_at
+        .into_iter()
+        .chain(record.renewed_at)
+        .fold(record.created_at, i64::max);
+    let idle = active + record.idle_timeout;
+
+    if idle < record.expires_at {
+        (idle, TerminationReason::Idle)
+    } else {
+        expiry
+    }
+}
+
+/// Whether the owner should be warned now. Each deadline is warned of once;
+/// traffic or a renewal that moves it allows another warning later.
+pub(crate) fn should_warn(record: &InstanceView, warning_before: i64, now: i64) -> bool {
+    let (deadline, _) = deadline(record);
+    let window = deadline - warning_before;
+
+    warning_before > 0
+        && (window..=deadline).contains(&now)
+        && record.warned_at.is_none_or(|warned_at| warned_at < window)
+}
+
+/// Snake-case name of a termination reason, as stored and sent in events.
+fn reason_code(reason: &TerminationReason) -> String {
+    serde_json::to_value(reason)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+#[derive(Debug)]
+struct Seen {
+    last: Arc<AtomicI64>,
+    flushed: i64,
+}
+
+/// Last time this server saw traffic on each instance.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Activity {
+    seen: Arc<Mutex<HashMap<String, Seen>>>,
+}
+
+impl Activity {
+    /// Counts traffic on `stream` as activity of instance `id`; opening it
+    /// already does.
+    pub(crate) fn track<S>(&self, id: &str, stream: S) -> Tracked<S> {
+        let mut seen = self.seen.lock().unwrap_or_else(|err| err.into_inner());
+        let last = seen
+            .entry(id.to_owned())
+            .or_insert_with(|| Seen {
+                last: Arc::new(AtomicI64::new(0)),
+                flushed: 0,
+            })
+            .last
+            .clone();
+        last.store(util::now(), Ordering::Relaxed);
+
+        Tracked {
+            inner: stream,
+            last,
+        }
+    }
+
+    /// Records traffic on instance `id` at `at` that did not go through a
+    /// tracked stream, such as packets forwarded by the WireGuard gateway.
+    pub(crate) fn mark(&self, id: &str, at: i64) {
+        let mut seen = self.seen.lock().unwrap_or_else(|err| err.into_inner());
+        seen.entry(id.to_owned())
+            .or_insert_with(|| Seen {
+                last: Arc::new(AtomicI64::new(0)),
+                flushed: 0,
+            })
+            .last
+            .fetch_max(at, Ordering::Relaxed);
+    }
+
+    /// Returns the instances that saw traffic since the last call, with
+    /// when, and forgets instances without open streams.
+    pub(crate) fn take(&self) -> Vec<(String, i64)> {
+        let mut seen = self.seen.lock().unwrap_or_else(|err| err.into_inner());
+        let mut active = Vec::new();
+
+        seen.retain(|id, seen| {
+            let last = seen.last.load(Ordering::Relaxed);
+            if last > seen.flushed {
+                active.push((id.clone(), last));
+                seen.flushed = last;
+            }
+            Arc::strong_count(&seen.last) > 1
+        });
+
+        active
+    }
+}
+
+/// A proxied stream that records when bytes last went through it.
+pub struct Tracked<S> {
+    inner: S,
+    last: Arc<AtomicI64>,
+}
+
+impl<S> Tracked<S> {
+    fn touch(&self) {
+        self.last.store(util::now(), Ordering::Relaxed);
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Tracked<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if matches!(poll, Poll::Ready(Ok(()))) && buf.filled().len() > filled {
+            self.touch();
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Tracked<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if matches!(poll, Poll::Ready(Ok(written)) if written > 0) {
+            self.touch();
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+impl Cluster {
+    /// Writes the traffic seen since the last call to the instance records.
+    pub(crate) async fn flush_activity(&self) -> Result<(), ClusterError> {
+        for (id, at) in self.activity.take() {
+            cds_db::instance::touch(&self.db.conn, &id, at).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Tells an instance's owner when and why it is about to stop.
+    pub(crate) async fn warn_owner(&self, record: &InstanceView) -> Result<(), ClusterError> {
+        let (at, reason) = deadline(record);
+        self.notify(record, InstanceEventType::Warning, at, &reason)
+            .await;
+        cds_db::instance::mark_warned(&self.db.conn, &record.id, util::now()).await?;
+
+        Ok(())
+    }
+
+    /// Publishes an instance event; a missed event must not keep an instance
+    /// from stopping, so failures are only logged.
+    pub(crate) async fn notify(
+        &self,
+        record: &InstanceView,
+        type_: InstanceEventType,
+        at: i64,
+        reason: &TerminationReason,
+    ) {
+        let event = Event::Instance(InstanceEvent {
+            type_,
+            instance_id: record.id.clone(),
+            user_id: record.user_id,
+            team_id: record.team_id,
+            game_id: record.game_id,
+            challenge_id: record.challenge_id,
+            at,
+            reason: reason_code(reason),
+        });
+        if let Err(err) = self.event.push(event).await {
+            warn!(instance_id = %record.id, error = %err, "failed to publish instance event");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cds_db::{
+        InstanceView,
+        challenge::Instance,
+        instance::{State, TerminationReason},
+    };
+    use cds_env::cluster::lifetime::Config;
+
+    use super::{Activity, deadline, limits, should_warn};
+
+    fn record(idle_timeout: i64, last_active_at: Option<i64>) -> InstanceView {
+        InstanceView {
+            id: "a".to_owned(),
+            user_id: 1,
+            team_id: None,
+            game_id: None,
+            challenge_id: 1,
+            ports: Vec::new(),
+            nats: Vec::new(),
+            state: State::Running,
+            renew: 0,
+            duration: 3600,
+            created_at: 0,
+            renewed_at: None,
+            expires_at: 3600,
+            terminated_at: None,
+            termination_reason: None,
+            access_token: None,
+            max_renewals: 3,
+            max_expires_at: None,
+            idle_timeout,
+            last_active_at,
+            warned_at: None,
+            resets: 0,
+            max_resets: 3,
+            reset_at: None,
+            checkpoints: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn challenge_limits_replace_platform_defaults() {
+        let config = Config::default();
+        let instance = Instance {
+            max_renewals: Some(1),
+            idle_timeout: Some(600),
+            ..Default::default()
+        };
+
+        let limits = limits(&config, &instance);
+        assert_eq!(limits.max_renewals, 1);
+        assert_eq!(limits.max_lifetime, config.max_lifetime);
+        assert_eq!(limits.idle_timeout, 600);
+    }
+
+    #[test]
+    fn idle_instances_stop_before_expiry() {
+        assert_eq!(
+            deadline(&record(0, None)),
+            (3600, TerminationReason::Expired)
+        );
+        assert_eq!(deadline(&record(600, None)), (600, TerminationReason::Idle));
+        assert_eq!(
+            deadline(&record(600, Some(1000))),
+            (1600, TerminationReason::Idle)
+        );
+        assert_eq!(
+            deadline(&record(600, Some(3500))),
+            (3600, TerminationReason::Expired)
+        );
+    }
+
+    #[test]
+    fn warns_once_per_deadline() {
+        let mut record = record(0, None);
+        assert!(!should_warn(&record, 300, 3000));
+        assert!(should_warn(&record, 300, 3400));
+
+        record.warned_at = Some(3400);
+        assert!(!should_warn(&record, 300, 3500));
+
+        // A renewal moves the deadline, so the next one is warned of again.
+        record.expires_at = 7200;
+        assert!(should_warn(&record, 300, 7000));
+    }
+
+    #[test]
+    fn forgets_instances_without_streams() {
+        let activity = Activity::default();
+        let stream = activity.track("a", ());
+
+        assert_eq!(activity.take().len(), 1);
+        assert!(activity.take().is_empty());
+
+        drop(stream);
+        assert!(activity.take().is_empty());
+        assert!(activity.seen.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn marked_traffic_is_flushed_once() {
+        let activity = Activity::default();
+        activity.mark("a", 10);
+        activity.mark("a", 5);
+
+        assert_eq!(activity.take(), vec![("a".to_owned(), 10)]);
+        assert!(activity.take().is_empty());
+    }
+}
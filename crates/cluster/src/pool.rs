@@ -0,0 +1,459 @@
+//! Warm pools: pods started ahead of demand for challenges with
+//! `instance.warm_pool > 0`, handed to the next player who asks.
+//!
+//! Pool pods carry the `cds/pool=true` label and no owner. The reconciler
+//! keeps `warm_pool` of them per challenge and replaces those started from
+//! an older instance configuration. A claim records the instance under the
+//! pool pod's id first — the primary key makes the claim exclusive — then
+//! writes the checker's environment variables into every container,
+//! relabels the pod for its owner and creates its service. A pod that
+//! cannot take the variables is discarded and the instance starts cold.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use cds_db::{
+    ChallengeDetail,
+    challenge::{FindChallengeOptions, Instance},
+    instance::TerminationReason,
+};
+use k8s_openapi::{api::core::v1::Pod, apimachinery::pkg::apis::meta::v1::ObjectMeta};
+use kube::api::{Api, AttachParams, Patch, PatchParams};
+use serde_json::{Value, json};
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use crate::{
+    Cluster, lifetime, reset,
+    traits::{ClusterError, Nat},
+    util,
+};
+
+/// Label marking a pod as unclaimed pool stock.
+const POOL_LABEL: &str = "cds/pool";
+
+/// Annotation holding the [`revision`] a pool pod was started from.
+const REVISION_ANNOTATION: &str = "cds/pool_revision";
+
+/// What the reconciler saw of a pool pod.
+#[derive(Clone, Debug)]
+pub(crate) struct PoolPod {
+    pub id: String,
+    pub challenge_id: i64,
+    pub revision: String,
+    /// Every container is ready to serve.
+    pub ready: bool,
+    /// The pod failed or was evicted and will not run again.
+    pub finished: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PoolAction {
+    /// Starts one more pool pod for a challenge.
+    Create(i64),
+    /// Deletes a surplus, stale or dead pool pod.
+    Delete(String),
+}
+
+/// Fingerprint of an instance configuration; pool pods started from another
+/// one are replaced.
+pub(crate) fn revision(instance: &Instance) -> String {
+    let mut hasher = DefaultHasher::new();
+    json!(instance).to_string().hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Reads a pool pod, skipping every other pod and pods already being
+/// deleted.
+pub(crate) fn observe(pod: &Pod) -> Option<PoolPod> {
+    if pod.metadata.deletion_timestamp.is_some() {
+        return None;
+    }
+
+    let labels = pod.metadata.labels.as_ref()?;
+    if labels.get(POOL_LABEL).map(String::as_str) != Some("true") {
+        return None;
+    }
+
+    let status = pod.status.as_ref();
+    Some(PoolPod {
+        id: labels.get("cds/instance_id")?.to_owned(),
+        challenge_id: labels.get("cds/challenge_id")?.parse().ok()?,
+        revision: pod
+            .metadata
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.get(REVISION_ANNOTATION))
+            .cloned()
+            .unwrap_or_default(),
+        ready: status
+            .and_then(|status| status.conditions.as_ref())
+            .is_some_and(|conditions| {
+                conditions
+                    .iter()
+                    .any(|condition| condition.type_ == "Ready" && condition.status == "True")
+            }),
+        finished: status
+            .and_then(|status| status.phase.as_deref())
+            .is_some_and(|phase| matches!(phase, "Failed" | "Succeeded")),
+    })
+}
+
+/// Decides which pool pods to start and delete so every challenge in
+/// `wanted` (challenge id to revision and size) has its pool.
+pub(crate) fn plan(pods: &[PoolPod], wanted: &BTreeMap<i64, (String, usize)>) -> Vec<PoolAction> {
+    let mut actions = Vec::new();
+    let mut kept = BTreeMap::<i64, usize>::new();
+
+    for pod in pods {
+        let keep = wanted
+            .get(&pod.challenge_id)
+            .is_some_and(|(revision, size)| {
+                !pod.finished
+                    && pod.revision == *revision
+                    && kept.get(&pod.challenge_id).copied().unwrap_or(0) < *size
+            });
+        if keep {
+            *kept.entry(pod.challenge_id).or_default() += 1;
+        } else {
+            actions.push(PoolAction::Delete(pod.id.clone()));
+        }
+    }
+
+    for (challenge_id, (_, size)) in wanted {
+        let missing = size.saturating_sub(kept.get(challenge_id).copied().unwrap_or(0));
+        actions.extend((0..missing).map(|_| PoolAction::Create(*challenge_id)));
+    }
+
+    actions
+}
+
+/// Renders environment variables as a file of `KEY='value'` lines that a
+/// POSIX shell can source.
+pub(crate) fn env_file(environ: &HashMap<String, String>) -> String {
+    let mut environ = environ.iter().collect::<Vec<_>>();
+    environ.sort();
+    environ
+        .into_iter()
+        .map(|(key, value)| format!("{}='{}'\n", key, value.replace('\'', r"'\''")))
+        .collect()
+}
+
+impl Cluster {
+    /// Starts and deletes pool pods until every pool matches its challenge.
+    pub(crate) async fn refill_pools(&self, pods: &[Pod]) -> Result<(), ClusterError> {
+        let (challenges, _) = cds_db::challenge::find::<ChallengeDetail>(
+            &self.db.conn,
+            FindChallengeOptions {
+                has_instance: Some(true),
+                ..Default::default()
+            },
+        )
+        .await?;
+        // Pools are emptied when teams get namespaces of their own.
+        let challenges = challenges
+            .into_iter()
+            .filter(|challenge| {
+                !self.isolation.namespace_per_team
+                    && challenge
+                        .instance
+                        .as_ref()
+                        .is_some_and(|instance| instance.warm_pool > 0)
+            })
+            .map(|challenge| (challenge.id, challenge))
+            .collect::<HashMap<_, _>>();
+
+        let wanted = challenges
+            .iter()
+            .filter_map(|(id, challenge)| {
+                let instance = challenge.instance.as_ref()?;
+                let size = instance.warm_pool.clamp(0, self.warmup.max_pool_size);
+                Some((*id, (revision(instance), size as usize)))
+            })
+            .collect::<BTreeMap<_, _>>();
+        let pods = pods.iter().filter_map(observe).collect::<Vec<_>>();
+
+        for action in plan(&pods, &wanted) {
+            match action {
+                PoolAction::Create(challenge_id) => {
+                    let Some(challenge) = challenges.get(&challenge_id) else {
+                        continue;
+                    };
+                    if let Err(err) = self.create_pool_pod(challenge).await {
+                        warn!(challenge_id, error = %err, "failed to start pool pod");
+                    }
+                }
+                PoolAction::Delete(id) => {
+                    if let Err(err) = self.delete_resources(&id).await {
+                        warn!(instance_id = %id, error = %err, "failed to delete pool pod");
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn create_pool_pod(&self, challenge: &ChallengeDetail) -> Result<(), ClusterError> {
+        let instance = challenge
+            .instance
+            .as_ref()
+            .ok_or_else(|| ClusterError::MissingEnvConfiguration)?;
+        let id = util::gen_safe_nanoid();
+
+        let pod = Pod {
+            metadata: ObjectMeta {
+                name: Some(format!("cds-{}", id)),
+                labels: Some(BTreeMap::from([
+                    ("cds/app".to_owned(), "challenges".to_owned()),
+                    ("cds/instance_id".to_owned(), id.clone()),
+                    ("cds/internet".to_owned(), format!("{}", instance.internet)),
+                    ("cds/challenge_id".to_owned(), format!("{}", challenge.id)),
+                    (POOL_LABEL.to_owned(), "true".to_owned()),
+                ])),
+                annotations: Some(BTreeMap::from([(
+                    REVISION_ANNOTATION.to_owned(),
+                    revision(instance),
+                )])),
+                ..Default::default()
+            },
+            spec: Some(self.pod_spec(instance, Vec::new(), Some(&self.warmup.env_dir))),
+            ..Default::default()
+        };
+        self.create_pod(pod).await?;
+        info!(instance_id = %id, challenge_id = challenge.id, "pool pod started");
+
+        Ok(())
+    }
+
+    /// Hands a ready pool pod of the challenge to the caller, returning the
+    /// new instance's id, or `None` when no pod could be claimed.
+    pub(crate) async fn claim_pool_instance(
+        &self,
+        access_token: Option<String>,
+        user: &cds_db::UserAccountView,
+        team: &Option<cds_db::TeamView>,
+        game: &Option<cds_db::GameDetail>,
+        challenge: &ChallengeDetail,
+    ) -> Result<Option<String>, ClusterError> {
+        let Some(instance) = challenge.instance.as_ref() else {
+            return Ok(None);
+        };
+        let revision = revision(instance);
+        let limits = lifetime::limits(&self.lifetime, instance);
+
+        let candidates = self
+            .get_pods_by_label(&format!(
+                "{}=true,cds/challenge_id={}",
+                POOL_LABEL, challenge.id
+            ))
+            .await?
+            .iter()
+            .filter_map(observe)
+            .filter(|pod| pod.ready && pod.revision == revision)
+            .collect::<Vec<_>>();
+
+        for pod in candidates {
+            let recorded = cds_db::instance::create::<cds_db::InstanceView>(
+                &self.db.conn,
+                cds_db::instance::NewInstance {
+                    id: pod.id.clone(),
+                    user_id: user.id,
+                    team_id: team.as_ref().map(|team| team.id),
+                    game_id: game.as_ref().map(|game| game.id),
+                    challenge_id: challenge.id,
+                    ports: instance
+                        .containers
+                        .iter()
+                        .flat_map(|container| container.ports.clone())
+                        .collect(),
+                    renew: 0,
+                    duration: instance.duration,
+                    created_at: util::now(),
+                    access_token: access_token.clone(),
+                    max_renewals: limits.max_renewals,
+                    max_lifetime: limits.max_lifetime,
+                    idle_timeout: limits.idle_timeout,
+                    max_resets: reset::max_resets(&self.reset, instance),
+                },
+            )
+            .await;
+            if recorded.is_err() {
+                // Claimed by someone else in the meantime.
+                continue;
+            }
+
+            return match self
+                .hand_over(&pod.id, access_token, user, team, game, challenge)
+                .await
+            {
+                Ok(nats) => {
+                    cds_db::instance::update_nats(&self.db.conn, &pod.id, nats).await?;
+                    info!(instance_id = %pod.id, challenge_id = challenge.id, "pool pod claimed");
+                    Ok(Some(pod.id))
+                }
+                Err(err) => {
+                    warn!(instance_id = %pod.id, error = %err, "failed to hand over pool pod");
+                    if let Err(err) = self.delete_resources(&pod.id).await {
+                        warn!(instance_id = %pod.id, error = %err, "failed to delete pool pod");
+                    }
+                    cds_db::instance::terminate(
+                        &self.db.conn,
+                        &pod.id,
+                        TerminationReason::CreateFailed,
+                        util::now(),
+                    )
+                    .await?;
+                    Ok(None)
+                }
+            };
+        }
+
+        Ok(None)
+    }
+
+    /// Isolates the pod, injects the checker's variables, relabels it for its
+    /// owner and creates its service.
+    async fn hand_over(
+        &self,
+        id: &str,
+        access_token: Option<String>,
+        user: &cds_db::UserAccountView,
+        team: &Option<cds_db::TeamView>,
+        game: &Option<cds_db::GameDetail>,
+        challenge: &ChallengeDetail,
+    ) -> Result<Vec<Nat>, ClusterError> {
+        let operator_id = if let (Some(_), Some(team)) = (game, team) {
+            team.id
+        } else {
+            user.id
+        };
+        let environ = self.checker.generate(challenge, operator_id).await?;
+        self.isolate(&self.namespace, id).await?;
+
+        let name = format!("cds-{}", id);
+        let pod_api: Api<Pod> = Api::namespaced(self.client.clone(), self.namespace.as_str());
+
+        if !environ.is_empty() {
+            let pod = self.get_pod(id).await?;
+            let containers = pod
+                .spec
+                .map(|spec| spec.containers)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|container| container.name);
+            let content = env_file(&environ);
+            for container in containers {
+                let mut attached = pod_api
+                    .exec(
+                        &name,
+                        vec![
+                            "sh".to_owned(),
+                            "-c".to_owned(),
+                            format!("cat > '{}/env'", self.warmup.env_dir),
+                        ],
+                        &AttachParams {
+                            container: Some(container),
+                            stdin: true,
+                            stdout: false,
+                            stderr: false,
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+                if let Some(mut stdin) = attached.stdin() {
+                    stdin
+                        .write_all(content.as_bytes())
+                        .await
+                        .map_err(anyhow::Error::from)?;
+                    stdin.shutdown().await.map_err(anyhow::Error::from)?;
+                }
+                let status = match attached.take_status() {
+                    Some(status) => status.await,
+                    None => None,
+                };
+                if status.and_then(|status| status.status).as_deref() != Some("Success") {
+                    return Err(anyhow::anyhow!("failed to write the environment file").into());
+                }
+            }
+        }
+
+        let metadata = self.instance_metadata(
+            id,
+            access_token,
+            user.clone(),
+            team.clone(),
+            game.clone(),
+            challenge,
+        );
+        let mut labels = json!(metadata.labels);
+        if let Some(labels) = labels.as_object_mut() {
+            labels.insert(POOL_LABEL.to_owned(), Value::Null);
+        }
+        pod_api
+            .patch(
+                &name,
+                &PatchParams::default(),
+                &Patch::Merge(json!({
+                    "metadata": {
+                        "labels": labels,
+                        "annotations": metadata.annotations,
+                    }
+                })),
+            )
+            .await?;
+
+        self.expose(id, metadata).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::{PoolAction, PoolPod, env_file, plan};
+
+    fn pod(id: &str, challenge_id: i64, revision: &str, finished: bool) -> PoolPod {
+        PoolPod {
+            id: id.to_owned(),
+            challenge_id,
+            revision: revision.to_owned(),
+            ready: true,
+            finished,
+        }
+    }
+
+    #[test]
+    fn fills_pools_and_replaces_stale_pods() {
+        let pods = vec![
+            pod("current", 1, "r2", false),
+            pod("stale", 1, "r1", false),
+            pod("dead", 1, "r2", true),
+            pod("kept", 2, "r1", false),
+            pod("surplus", 2, "r1", false),
+            pod("unwanted", 3, "r1", false),
+        ];
+        let wanted = BTreeMap::from([(1, ("r2".to_owned(), 2)), (2, ("r1".to_owned(), 1))]);
+        assert_eq!(
+            plan(&pods, &wanted),
+            vec![
+                PoolAction::Delete("stale".to_owned()),
+                PoolAction::Delete("dead".to_owned()),
+                PoolAction::Delete("surplus".to_owned()),
+                PoolAction::Delete("unwanted".to_owned()),
+                PoolAction::Create(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_env_values() {
+        let environ = HashMap::from([
+            ("FLAG".to_owned(), "flag{it's}".to_owned()),
+            ("A".to_owned(), "1".to_owned()),
+        ]);
+        assert_eq!(env_file(&environ), "A='1'\nFLAG='flag{it'\\''s}'\n");
+    }
+}
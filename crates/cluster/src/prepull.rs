@@ -0,0 +1,230 @@
+//! Image pre-pulling: one DaemonSet per key (a game id) whose init
+//! containers run every listed image once, so each schedulable node has the
+//! images cached before the first instance starts.
+//!
+//! The pods then idle on the pause image until the DaemonSet is deleted.
+//! They are scheduled like challenge pods, with the platform's node
+//! selector and tolerations.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cds_env::cluster::{security, warmup};
+use k8s_openapi::{
+    api::{
+        apps::v1::{DaemonSet, DaemonSetSpec},
+        core::v1::{
+            Container as K8sContainer, PodSpec, PodTemplateSpec, ResourceRequirements,
+            SecurityContext,
+        },
+    },
+    apimachinery::pkg::{
+        api::resource::Quantity,
+        apis::meta::v1::{LabelSelector, ObjectMeta},
+    },
+};
+use kube::api::{Api, DeleteParams, Patch, PatchParams};
+use serde::{Deserialize, Serialize};
+
+use crate::{Cluster, traits::ClusterError};
+
+/// Annotation listing the images a pre-pull DaemonSet pulls.
+const IMAGES_ANNOTATION: &str = "cds/images";
+
+/// Progress of a pre-pull.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PrepullStatus {
+    pub images: Vec<String>,
+    /// Nodes that should pull the images.
+    pub desired: i32,
+    /// Nodes that have pulled every image.
+    pub ready: i32,
+}
+
+fn name(key: &str) -> String {
+    format!("cds-prepull-{}", key)
+}
+
+/// Builds the DaemonSet pulling `images` on every node.
+fn daemon_set(
+    key: &str,
+    images: &BTreeSet<String>,
+    warmup: &warmup::Config,
+    security: &security::Config,
+) -> DaemonSet {
+    let labels = BTreeMap::from([
+        ("cds/app".to_owned(), "prepull".to_owned()),
+        ("cds/prepull".to_owned(), key.to_owned()),
+    ]);
+    let container = |name: String, image: &str, command: Option<Vec<String>>| K8sContainer {
+        name,
+        image: Some(image.to_owned()),
+        command,
+        image_pull_policy: Some("Always".to_owned()),
+        resources: Some(ResourceRequirements {
+            limits: Some(BTreeMap::from([
+                ("cpu".to_owned(), Quantity("100m".to_owned())),
+                ("memory".to_owned(), Quantity("64Mi".to_owned())),
+            ])),
+            ..Default::default()
+        }),
+        security_context: Some(SecurityContext {
+            allow_privilege_escalation: Some(false),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    DaemonSet {
+        metadata: ObjectMeta {
+            name: Some(name(key)),
+            labels: Some(labels.clone()),
+            annotations: Some(BTreeMap::from([(
+                IMAGES_ANNOTATION.to_owned(),
+                serde_json::json!(images).to_string(),
+            )])),
+            ..Default::default()
+        },
+        spec: Some(DaemonSetSpec {
+            selector: LabelSelector {
+                match_labels: Some(labels.clone()),
+                ..Default::default()
+            },
+            template: PodTemplateSpec {
+                metadata: Some(ObjectMeta {
+                    labels: Some(labels),
+                    ..Default::default()
+                }),
+                spec: Some(PodSpec {
+                    init_containers: Some(
+                        images
+                            .iter()
+                            .enumerate()
+                            .map(|(index, image)| {
+                                container(
+                                    format!("pull-{}", index),
+                                    image,
+                                    Some(warmup.pull_command.clone()),
+                                )
+                            })
+                            .collect(),
+                    ),
+                    containers: vec![container("pause".to_owned(), &warmup.pause_image, None)],
+                    node_selector: Some(security.node_selector.clone())
+                        .filter(|selector| !selector.is_empty()),
+                    tolerations: Some(
+                        security
+                            .tolerations
+                            .iter()
+                            .map(|toleration| k8s_openapi::api::core::v1::Toleration {
+                                key: Some(toleration.key.clone()).filter(|v| !v.is_empty()),
+                                operator: Some(toleration.operator.clone())
+                                    .filter(|v| !v.is_empty()),
+                                value: Some(toleration.value.clone()).filter(|v| !v.is_empty()),
+                                effect: Some(toleration.effect.clone()).filter(|v| !v.is_empty()),
+                                ..Default::default()
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .filter(|tolerations| !tolerations.is_empty()),
+                    automount_service_account_token: Some(false),
+                    termination_grace_period_seconds: Some(0),
+                    ..Default::default()
+                }),
+            },
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+impl Cluster {
+    /// Starts or replaces the pre-pull of `images` under `key`.
+    pub async fn prepull_images(
+        &self,
+        key: &str,
+        images: impl IntoIterator<Item = String>,
+    ) -> Result<PrepullStatus, ClusterError> {
+        let images = images
+            .into_iter()
+            .filter(|image| !image.is_empty())
+            .collect::<BTreeSet<_>>();
+        let api: Api<DaemonSet> = Api::namespaced(self.client.clone(), self.namespace.as_str());
+
+        let daemon_set = daemon_set(key, &images, &self.warmup, &self.security);
+        let daemon_set = api
+            .patch(
+                &name(key),
+                &PatchParams::apply("cdsctf").force(),
+                &Patch::Apply(&daemon_set),
+            )
+            .await?;
+
+        Ok(status(daemon_set))
+    }
+
+    /// Returns the progress of the pre-pull under `key`, if there is one.
+    pub async fn prepull_status(&self, key: &str) -> Result<Option<PrepullStatus>, ClusterError> {
+        let api: Api<DaemonSet> = Api::namespaced(self.client.clone(), self.namespace.as_str());
+
+        Ok(api.get_opt(&name(key)).await?.map(status))
+    }
+
+    /// Deletes the pre-pull under `key`; the pulled images stay cached.
+    pub async fn delete_prepull(&self, key: &str) -> Result<(), ClusterError> {
+        let api: Api<DaemonSet> = Api::namespaced(self.client.clone(), self.namespace.as_str());
+
+        match api.delete(&name(key), &DeleteParams::default()).await {
+            Ok(_) => Ok(()),
+            Err(kube::Error::Api(err)) if err.code == 404 => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+fn status(daemon_set: DaemonSet) -> PrepullStatus {
+    let images = daemon_set
+        .metadata
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.get(IMAGES_ANNOTATION))
+        .and_then(|images| serde_json::from_str(images).ok())
+        .unwrap_or_default();
+    let status = daemon_set.status.unwrap_or_default();
+
+    PrepullStatus {
+        images,
+        desired: status.desired_number_scheduled,
+        ready: status.number_ready,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use cds_env::cluster::{security, warmup};
+
+    use super::daemon_set;
+
+    #[test]
+    fn pulls_each_image_once_before_pausing() {
+        let images = BTreeSet::from(["web:1".to_owned(), "pwn:2".to_owned()]);
+        let daemon_set = daemon_set(
+            "7",
+            &images,
+            &warmup::Config::default(),
+            &security::Config::default(),
+        );
+        let spec = daemon_set.spec.unwrap().template.spec.unwrap();
+
+        let pulled = spec
+            .init_containers
+            .unwrap()
+            .into_iter()
+            .map(|container| container.image.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(pulled, vec!["pwn:2", "web:1"]);
+        assert_eq!(spec.containers.len(), 1);
+        assert_eq!(daemon_set.metadata.name.as_deref(), Some("cds-prepull-7"));
+    }
+}
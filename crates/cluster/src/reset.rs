@@ -0,0 +1,456 @@
+//! Resets and checkpoints: putting a running instance back to a known state
+//! without giving it a new id or new ports.
+//!
+//! A reset deletes the instance's pod and creates it again from the same
+//! spec, labels and annotations; its service, node ports, TCP proxy ports
+//! and ingress host are left alone. The record counts the reset before the
+//! pod goes, which enforces the limit and tells the reconciler that the
+//! missing pod is on its way back; a reset whose pod work fails is given
+//! back.
+//!
+//! Checkpoints are for images that can save and restore their own state:
+//! the challenge gives each such container a `save` and a `restore` command,
+//! run with the checkpoint's name appended. Restoring counts as a reset, and
+//! a reset starts from a fresh pod, dropping every checkpoint.
+
+use std::time::Duration;
+
+use cds_db::{
+    InstanceView,
+    challenge::{CheckpointCommands, Instance},
+    instance::Checkpoint,
+};
+use cds_env::cluster::reset::Config;
+use k8s_openapi::{
+    api::core::v1::{
+        DownwardAPIVolumeFile, DownwardAPIVolumeSource, ObjectFieldSelector, Pod, Volume,
+    },
+    apimachinery::pkg::apis::meta::v1::ObjectMeta,
+};
+use kube::{
+    api::{Api, AttachParams, DeleteParams},
+    runtime::wait::{await_condition, conditions},
+};
+use tokio::io::AsyncReadExt;
+use tracing::{info, warn};
+
+use crate::{Cluster, pool, traits::ClusterError, util};
+
+/// Annotation keeping the start of an instance's lifetime across resets.
+pub const STARTED_AT_ANNOTATION: &str = "cds/started_at";
+
+/// Annotation holding the environment file of a reset pool pod, mounted in
+/// place of the one written when it was claimed.
+const ENV_ANNOTATION: &str = "cds/env";
+
+/// Extra time a deleted pod gets to disappear on top of its grace period.
+const DELETE_TIMEOUT_SECONDS: u64 = 30;
+
+/// Resolves a challenge's reset limit against the platform's.
+pub fn max_resets(config: &Config, instance: &Instance) -> i32 {
+    instance.max_resets.unwrap_or(config.max_resets).max(0)
+}
+
+/// Whether `name` can name a checkpoint: 1 to 32 lowercase letters, digits,
+/// `-` and `_`, so it passes through commands unquoted.
+pub fn is_valid_checkpoint_name(name: &str) -> bool {
+    (1..=32).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// Pod container names paired with the checkpoint commands of the challenge
+/// containers they were started from.
+fn checkpoint_commands(pod: &Pod) -> Vec<(String, CheckpointCommands)> {
+    let instance = pod
+        .metadata
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.get("cds/challenge"))
+        .and_then(|challenge| serde_json::from_str::<cds_db::ChallengeDetail>(challenge).ok())
+        .and_then(|challenge| challenge.instance)
+        .unwrap_or_default();
+
+    // Containers are started in the order the challenge lists them.
+    pod.spec
+        .iter()
+        .flat_map(|spec| spec.containers.iter())
+        .zip(instance.containers)
+        .filter_map(|(container, spec)| Some((container.name.clone(), spec.checkpoint?)))
+        .collect()
+}
+
+/// Whether any container of the pod can save checkpoints.
+pub fn supports_checkpoints(pod: &Pod) -> bool {
+    !checkpoint_commands(pod).is_empty()
+}
+
+/// Adds `checkpoint` to `checkpoints`, replacing one of the same name;
+/// `false` when that would keep more than `max`.
+fn put_checkpoint(checkpoints: &mut Vec<Checkpoint>, checkpoint: Checkpoint, max: usize) -> bool {
+    checkpoints.retain(|existing| existing.name != checkpoint.name);
+    if checkpoints.len() >= max {
+        return false;
+    }
+    checkpoints.push(checkpoint);
+    true
+}
+
+/// The pod to create in place of `pod`: same name, namespace, labels,
+/// annotations and spec, without anything Kubernetes filled in, and not bound
+/// to its node.
+fn replacement(pod: Pod, started_at: i64, resets: i32) -> Pod {
+    let mut annotations = pod.metadata.annotations.unwrap_or_default();
+    annotations
+        .entry(STARTED_AT_ANNOTATION.to_owned())
+        .or_insert_with(|| started_at.to_string());
+    annotations.insert("cds/resets".to_owned(), resets.to_string());
+
+    let mut spec = pod.spec.unwrap_or_default();
+    spec.node_name = None;
+
+    Pod {
+        metadata: ObjectMeta {
+            name: pod.metadata.name,
+            namespace: pod.metadata.namespace,
+            labels: pod.metadata.labels,
+            annotations: Some(annotations),
+            ..Default::default()
+        },
+        spec: Some(spec),
+        ..Default::default()
+    }
+}
+
+impl Cluster {
+    /// Recreates a running instance's pod from its own spec, keeping its id,
+    /// service and ports, and returns the updated record.
+    ///
+    /// If the new pod cannot be created the instance is left without one,
+    /// and the reconciler closes it as `pod_lost`.
+    pub async fn reset_challenge_instance(&self, id: &str) -> Result<InstanceView, ClusterError> {
+        let pod = self.get_pod(id).await?;
+        let checkpoints = self.running_record(id).await?.checkpoints;
+        let record = cds_db::instance::reset::<InstanceView>(&self.db.conn, id, util::now())
+            .await?
+            .ok_or_else(|| ClusterError::Exhausted("no_more_reset".to_owned()))?;
+
+        if let Err(err) = self.replace_pod(pod, &record).await {
+            self.refund_reset(id, record.resets, checkpoints).await;
+            return Err(err);
+        }
+        info!(instance_id = %id, resets = record.resets, "instance reset");
+
+        Ok(record)
+    }
+
+    /// Deletes the instance's pod and creates it again under the same name.
+    async fn replace_pod(&self, pod: Pod, record: &InstanceView) -> Result<(), ClusterError> {
+        let name = format!("cds-{}", record.id);
+        let pod_api: Api<Pod> = self.api_in(pod.metadata.namespace.as_deref());
+        let uid = pod.metadata.uid.clone().unwrap_or_default();
+        let mut pod = replacement(pod, record.created_at, record.resets);
+        self.keep_env(&mut pod).await?;
+
+        pod_api
+            .delete(
+                &name,
+                &DeleteParams {
+                    grace_period_seconds: Some(self.lifetime.grace_period.max(0) as u32),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        // The replacement takes the same name, so the old pod must be gone.
+        let timeout = self.lifetime.grace_period.max(0) as u64 + DELETE_TIMEOUT_SECONDS;
+        tokio::time::timeout(
+            Duration::from_secs(timeout),
+            await_condition(pod_api, &name, conditions::is_deleted(&uid)),
+        )
+        .await
+        .map_err(|_| anyhow::anyhow!("timed out waiting for the pod to be deleted"))??;
+
+        self.create_pod(pod).await?;
+        Ok(())
+    }
+
+    /// Gives back a counted reset whose pod work failed, so a failure does
+    /// not use up the player's resets.
+    async fn refund_reset(&self, id: &str, resets: i32, checkpoints: Vec<Checkpoint>) {
+        if let Err(err) =
+            cds_db::instance::refund_reset(&self.db.conn, id, resets, checkpoints).await
+        {
+            warn!(instance_id = %id, error = %err, "failed to refund reset");
+        }
+    }
+
+    /// Pool pods had the checker's variables written into their env
+    /// directory once claimed, which a new pod would lack; the replacement
+    /// reads the same file from an annotation through the downward API.
+    async fn keep_env(&self, pod: &mut Pod) -> Result<(), ClusterError> {
+        let Some(spec) = pod.spec.as_mut() else {
+            return Ok(());
+        };
+        let Some(volume) = spec
+            .volumes
+            .iter_mut()
+            .flatten()
+            .find(|volume| volume.name == "cds-env")
+        else {
+            return Ok(());
+        };
+
+        let annotations = pod.metadata.annotations.get_or_insert_default();
+        if !annotations.contains_key(ENV_ANNOTATION) {
+            let challenge = annotations
+                .get("cds/challenge")
+                .and_then(|challenge| {
+                    serde_json::from_str::<cds_db::ChallengeDetail>(challenge).ok()
+                })
+                .ok_or_else(|| ClusterError::MissingField("cds/challenge".to_owned()))?;
+            let labels = pod.metadata.labels.clone().unwrap_or_default();
+            let label = |key: &str| {
+                labels
+                    .get(key)
+                    .and_then(|value| value.parse::<i64>().ok())
+                    .unwrap_or(0)
+            };
+            let operator_id = if label("cds/game_id") != 0 && label("cds/team_id") != 0 {
+                label("cds/team_id")
+            } else {
+                label("cds/user_id")
+            };
+            let environ = self.checker.generate(&challenge, operator_id).await?;
+            annotations.insert(ENV_ANNOTATION.to_owned(), pool::env_file(&environ));
+        }
+
+        *volume = Volume {
+            name: volume.name.clone(),
+            downward_api: Some(DownwardAPIVolumeSource {
+                items: Some(vec![DownwardAPIVolumeFile {
+                    path: "env".to_owned(),
+                    field_ref: Some(ObjectFieldSelector {
+                        field_path: format!("metadata.annotations['{}']", ENV_ANNOTATION),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        Ok(())
+    }
+
+    /// Saves a checkpoint named `name` in every container that supports
+    /// them, replacing one of the same name, and returns the instance's
+    /// checkpoints.
+    pub async fn save_checkpoint(
+        &self,
+        id: &str,
+        name: &str,
+    ) -> Result<Vec<Checkpoint>, ClusterError> {
+        let pod = self.get_pod(id).await?;
+        let record = self.running_record(id).await?;
+
+        let mut checkpoints = record.checkpoints;
+        let checkpoint = Checkpoint {
+            name: name.to_owned(),
+            created_at: util::now(),
+        };
+        if !put_checkpoint(&mut checkpoints, checkpoint, self.reset.max_checkpoints) {
+            return Err(ClusterError::Exhausted("too_many_checkpoints".to_owned()));
+        }
+
+        self.run_checkpoint_commands(&pod, name, |commands| commands.save)
+            .await?;
+        cds_db::instance::update_checkpoints(&self.db.conn, id, checkpoints.clone()).await?;
+        info!(instance_id = %id, checkpoint = name, "checkpoint saved");
+
+        Ok(checkpoints)
+    }
+
+    /// Restores the checkpoint named `name` in every container that
+    /// supports them, counting it as a reset, and returns the updated
+    /// record.
+    pub async fn restore_checkpoint(
+        &self,
+        id: &str,
+        name: &str,
+    ) -> Result<InstanceView, ClusterError> {
+        let pod = self.get_pod(id).await?;
+        let record = self.running_record(id).await?;
+        if !record
+            .checkpoints
+            .iter()
+            .any(|checkpoint| checkpoint.name == name)
+        {
+            return Err(ClusterError::NotFound("checkpoint_not_found".to_owned()));
+        }
+
+        let record = cds_db::instance::restore::<InstanceView>(&self.db.conn, id, util::now())
+            .await?
+            .ok_or_else(|| ClusterError::Exhausted("no_more_reset".to_owned()))?;
+        if let Err(err) = self
+            .run_checkpoint_commands(&pod, name, |commands| commands.restore)
+            .await
+        {
+            self.refund_reset(id, record.resets, record.checkpoints.clone())
+                .await;
+            return Err(err);
+        }
+        info!(instance_id = %id, checkpoint = name, "checkpoint restored");
+
+        Ok(record)
+    }
+
+    async fn running_record(&self, id: &str) -> Result<InstanceView, ClusterError> {
+        cds_db::instance::find_by_id::<InstanceView>(&self.db.conn, id)
+            .await?
+            .filter(|record| record.state == cds_db::instance::State::Running)
+            .ok_or_else(|| ClusterError::NotFound("instance_not_found".to_owned()))
+    }
+
+    /// Runs the command `pick` selects in each container that has
+    /// checkpoint commands, with `name` appended, failing on the first one
+    /// that does not succeed.
+    async fn run_checkpoint_commands(
+        &self,
+        pod: &Pod,
+        name: &str,
+        pick: impl Fn(CheckpointCommands) -> Vec<String>,
+    ) -> Result<(), ClusterError> {
+        let containers = checkpoint_commands(pod);
+        if containers.is_empty() {
+            return Err(ClusterError::NotFound("checkpoint_unsupported".to_owned()));
+        }
+
+        let pod_name = pod.metadata.name.clone().unwrap_or_default();
+        let pod_api: Api<Pod> = self.api_in(pod.metadata.namespace.as_deref());
+        for (container, commands) in containers {
+            let mut command = pick(commands);
+            command.push(name.to_owned());
+
+            let mut attached = pod_api
+                .exec(
+                    &pod_name,
+                    command,
+                    &AttachParams {
+                        container: Some(container.clone()),
+                        stdin: false,
+                        stdout: false,
+                        stderr: true,
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            let mut stderr = String::new();
+            if let Some(mut reader) = attached.stderr() {
+                reader
+                    .read_to_string(&mut stderr)
+                    .await
+                    .map_err(anyhow::Error::from)?;
+            }
+            let status = match attached.take_status() {
+                Some(status) => status.await,
+                None => None,
+            };
+            if status.and_then(|status| status.status).as_deref() != Some("Success") {
+                return Err(anyhow::anyhow!(
+                    "checkpoint command failed in {}: {}",
+                    container,
+                    stderr.trim()
+                )
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use cds_db::instance::Checkpoint;
+    use k8s_openapi::{api::core::v1::Pod, apimachinery::pkg::apis::meta::v1::ObjectMeta};
+
+    use super::{STARTED_AT_ANNOTATION, is_valid_checkpoint_name, put_checkpoint, replacement};
+
+    fn checkpoint(name: &str, created_at: i64) -> Checkpoint {
+        Checkpoint {
+            name: name.to_owned(),
+            created_at,
+        }
+    }
+
+    #[test]
+    fn checks_checkpoint_names() {
+        assert!(is_valid_checkpoint_name("before-exploit_2"));
+        assert!(!is_valid_checkpoint_name(""));
+        assert!(!is_valid_checkpoint_name("Upper"));
+        assert!(!is_valid_checkpoint_name("a; rm -rf /"));
+        assert!(!is_valid_checkpoint_name(&"a".repeat(33)));
+    }
+
+    #[test]
+    fn replaces_checkpoints_of_the_same_name() {
+        let mut checkpoints = vec![checkpoint("a", 1), checkpoint("b", 2)];
+
+        assert!(put_checkpoint(&mut checkpoints, checkpoint("a", 3), 2));
+        assert_eq!(checkpoints, vec![checkpoint("b", 2), checkpoint("a", 3)]);
+
+        assert!(!put_checkpoint(&mut checkpoints, checkpoint("c", 4), 2));
+    }
+
+    #[test]
+    fn replacement_keeps_identity_and_start() {
+        let pod = Pod {
+            metadata: ObjectMeta {
+                name: Some("cds-a".to_owned()),
+                uid: Some("uid".to_owned()),
+                resource_version: Some("1".to_owned()),
+                labels: Some(BTreeMap::from([(
+                    "cds/instance_id".to_owned(),
+                    "a".to_owned(),
+                )])),
+                ..Default::default()
+            },
+            spec: Some(k8s_openapi::api::core::v1::PodSpec {
+                node_name: Some("node".to_owned()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let pod = replacement(pod, 100, 1);
+        assert_eq!(pod.metadata.name.as_deref(), Some("cds-a"));
+        assert_eq!(pod.metadata.uid, None);
+        assert_eq!(pod.metadata.resource_version, None);
+        assert_eq!(pod.spec.and_then(|spec| spec.node_name), None);
+
+        let annotations = pod.metadata.annotations.unwrap_or_default();
+        assert_eq!(annotations[STARTED_AT_ANNOTATION], "100");
+        assert_eq!(annotations["cds/resets"], "1");
+
+        // A second reset keeps the original start.
+        let pod = replacement(
+            Pod {
+                metadata: ObjectMeta {
+                    annotations: Some(annotations),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            200,
+            2,
+        );
+        assert_eq!(
+            pod.metadata.annotations.unwrap_or_default()[STARTED_AT_ANNOTATION],
+            "100"
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Pod hardening: merges the platform's `cluster.security` settings with a
+//! challenge's own, keeping whichever is stricter.
+//!
+//! [`validate`] rejects challenge settings that would loosen the platform's,
+//! so authors get an error when saving; the builders below are still
+//! defensive and ignore such settings when a pod is created.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cds_db::challenge::{Container, Instance};
+use cds_env::cluster::security::Config;
+use k8s_openapi::api::core::v1::{Capabilities, SeccompProfile, SecurityContext, Toleration};
+
+/// Seccomp profiles from least to most restrictive for the platform's
+/// purposes; a localhost profile is assumed to be a tightened default.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Seccomp {
+    Unconfined,
+    RuntimeDefault,
+    Localhost(String),
+}
+
+impl Seccomp {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "unconfined" => Some(Self::Unconfined),
+            "runtime_default" => Some(Self::RuntimeDefault),
+            _ => value
+                .strip_prefix("localhost/")
+                .filter(|profile| !profile.is_empty())
+                .map(|profile| Self::Localhost(profile.to_owned())),
+        }
+    }
+
+    /// The profile applied when an author asks for `wanted`.
+    fn tighten(&self, wanted: Option<Self>) -> Self {
+        match (self, wanted) {
+            (Self::Localhost(_), _) => self.clone(),
+            (_, Some(wanted @ Self::Localhost(_))) => wanted,
+            (Self::Unconfined, Some(wanted)) => wanted,
+            _ => self.clone(),
+        }
+    }
+
+    fn into_k8s(self) -> SeccompProfile {
+        match self {
+            Self::Unconfined => SeccompProfile {
+                type_: "Unconfined".to_owned(),
+                ..Default::default()
+            },
+            Self::RuntimeDefault => SeccompProfile {
+                type_: "RuntimeDefault".to_owned(),
+                ..Default::default()
+            },
+            Self::Localhost(profile) => SeccompProfile {
+                type_: "Localhost".to_owned(),
+                localhost_profile: Some(profile),
+            },
+        }
+    }
+}
+
+fn platform_seccomp(config: &Config) -> Seccomp {
+    Seccomp::parse(&config.seccomp_profile).unwrap_or(Seccomp::RuntimeDefault)
+}
+
+fn non_empty(value: &str) -> Option<&str> {
+    Some(value).filter(|value| !value.is_empty())
+}
+
+/// Rejects challenge settings that are malformed or looser than the
+/// platform's, returning an error code for the first problem found.
+pub fn validate(config: &Config, instance: &Instance) -> Result<(), String> {
+    let err = |code: &str| Err(code.to_owned());
+
+    if let (Some(forced), Some(wanted)) = (
+        non_empty(&config.runtime_class_name),
+        instance.runtime_class_name.as_deref(),
+    ) && forced != wanted
+    {
+        return err("runtime_class_forced_by_platform");
+    }
+
+    if instance
+        .node_selector
+        .iter()
+        .any(|(key, value)| config.node_selector.get(key).is_some_and(|v| v != value))
+    {
+        return err("node_selector_conflicts_with_platform");
+    }
+
+    if instance
+        .tolerations
+        .iter()
+        .any(|toleration| !config.allowed_toleration_keys.contains(&toleration.key))
+    {
+        return err("toleration_not_allowed");
+    }
+
+    let platform = platform_seccomp(config);
+    for container in &instance.containers {
+        if let Some(limit) = container.ephemeral_storage_limit
+            && !(1..=config.max_ephemeral_storage).contains(&limit)
+        {
+            return err("ephemeral_storage_limit_out_of_range");
+        }
+
+        if container.drop_capabilities.iter().any(|capability| {
+            capability.is_empty()
+                || !capability
+                    .bytes()
+                    .all(|b| b.is_ascii_uppercase() || b == b'_')
+        }) {
+            return err("capability_invalid");
+        }
+
+        if let Some(profile) = container.seccomp_profile.as_deref() {
+            match Seccomp::parse(profile) {
+                None => return err("seccomp_profile_invalid"),
+                Some(wanted) if platform.tighten(Some(wanted.clone())) != wanted => {
+                    return err("seccomp_profile_looser_than_platform");
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Security context of one container.
+pub fn security_context(config: &Config, container: &Container) -> SecurityContext {
+    let drop = config
+        .drop_capabilities
+        .iter()
+        .chain(&container.drop_capabilities)
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    SecurityContext {
+        allow_privilege_escalation: Some(false),
+        privileged: Some(false),
+        read_only_root_filesystem: Some(
+            config.read_only_root_filesystem || container.read_only_root_filesystem,
+        ),
+        run_as_non_root: Some(config.run_as_non_root || container.run_as_non_root),
+        capabilities: Some(Capabilities {
+            drop: Some(drop.into_iter().collect()),
+            ..Default::default()
+        }),
+        seccomp_profile: Some(
+            platform_seccomp(config)
+                .tighten(
+                    container
+                        .seccomp_profile
+                        .as_deref()
+                        .and_then(Seccomp::parse),
+                )
+                .into_k8s(),
+        ),
+        ..Default::default()
+    }
+}
+
+/// Ephemeral storage limit of one container, in MiB.
+pub fn ephemeral_storage_limit(config: &Config, container: &Container) -> i64 {
+    container
+        .ephemeral_storage_limit
+        .map_or(config.max_ephemeral_storage, |limit| {
+            limit.min(config.max_ephemeral_storage)
+        })
+}
+
+/// RuntimeClass of the pod.
+pub fn runtime_class_name(config: &Config, instance: &Instance) -> Option<String> {
+    non_empty(&config.runtime_class_name)
+        .map(str::to_owned)
+        .or_else(|| instance.runtime_class_name.clone())
+}
+
+/// Node selector of the pod; the platform's labels win.
+pub fn node_selector(config: &Config, instance: &Instance) -> BTreeMap<String, String> {
+    let mut selector = instance.node_selector.clone();
+    selector.extend(config.node_selector.clone());
+    selector
+}
+
+/// Tolerations of the pod: the platform's, plus the author's allowed ones.
+pub fn tolerations(config: &Config, instance: &Instance) -> Vec<Toleration> {
+    let toleration = |key: &str, operator: &str, value: &str, effect: &str| Toleration {
+        key: non_empty(key).map(str::to_owned),
+        operator: non_empty(operator).map(str::to_owned),
+        value: non_empty(value).map(str::to_owned),
+        effect: non_empty(effect).map(str::to_owned),
+        ..Default::default()
+    };
+
+    let platform = config
+        .tolerations
+        .iter()
+        .map(|t| toleration(&t.key, &t.operator, &t.value, &t.effect));
+    let authored = instance
+        .tolerations
+        .iter()
+        .filter(|t| config.allowed_toleration_keys.contains(&t.key))
+        .map(|t| toleration(&t.key, &t.operator, &t.value, &t.effect));
+
+    platform.chain(authored).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use cds_db::challenge::{Container, Instance, Toleration};
+    use cds_env::cluster::security::Config;
+
+    use super::{
+        ephemeral_storage_limit, node_selector, runtime_class_name, security_context, validate,
+    };
+
+    fn instance(container: Container) -> Instance {
+        Instance {
+            containers: vec![container],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn authors_can_tighten_but_not_loosen() {
+        let config = Config::default();
+
+        let container = Container {
+            read_only_root_filesystem: true,
+            drop_capabilities: vec!["ALL".to_owned()],
+            seccomp_profile: Some("localhost/strict.json".to_owned()),
+            ephemeral_storage_limit: Some(128),
+            ..Default::default()
+        };
+        assert_eq!(validate(&config, &instance(container.clone())), Ok(()));
+
+        let context = security_context(&config, &container);
+        assert_eq!(context.read_only_root_filesystem, Some(true));
+        assert_eq!(context.allow_privilege_escalation, Some(false));
+        assert!(
+            context
+                .capabilities
+                .unwrap()
+                .drop
+                .unwrap()
+                .contains(&"ALL".to_owned())
+        );
+        assert_eq!(
+            context
+                .seccomp_profile
+                .unwrap()
+                .localhost_profile
+                .as_deref(),
+            Some("strict.json")
+        );
+        assert_eq!(ephemeral_storage_limit(&config, &container), 128);
+
+        let unconfined = Container {
+            seccomp_profile: Some("unconfined".to_owned()),
+            ..Default::default()
+        };
+        assert!(validate(&config, &instance(unconfined.clone())).is_err());
+        assert_eq!(
+            security_context(&config, &unconfined)
+                .seccomp_profile
+                .unwrap()
+                .type_,
+            "RuntimeDefault"
+        );
+
+        let hungry = Container {
+            ephemeral_storage_limit: Some(config.max_ephemeral_storage + 1),
+            ..Default::default()
+        };
+        assert!(validate(&config, &instance(hungry.clone())).is_err());
+        assert_eq!(
+            ephemeral_storage_limit(&config, &hungry),
+            config.max_ephemeral_storage
+        );
+    }
+
+    #[test]
+    fn platform_scheduling_wins() {
+        let config = Config {
+            runtime_class_name: "gvisor".to_owned(),
+            node_selector: [("pool".to_owned(), "challenges".to_owned())].into(),
+            allowed_toleration_keys: vec!["dedicated".to_owned()],
+            ..Default::default()
+        };
+
+        let mut instance = Instance {
+            runtime_class_name: Some("kata".to_owned()),
+            ..Default::default()
+        };
+        assert!(validate(&config, &instance).is_err());
+        assert_eq!(
+            runtime_class_name(&config, &instance).as_deref(),
+            Some("gvisor")
+        );
+
+        instance.runtime_class_name = None;
+        instance
+            .node_selector
+            .insert("pool".to_owned(), "default".to_owned());
+        assert!(validate(&config, &instance).is_err());
+        assert_eq!(
+            node_selector(&config, &instance).get("pool").unwrap(),
+            "challenges"
+        );
+
+        instance.node_selector.clear();
+        instance.tolerations = vec![Toleration {
+            key: "gpu".to_owned(),
+            ..Default::default()
+        }];
+        assert!(validate(&config, &instance).is_err());
+        instance.tolerations[0].key = "dedicated".to_owned();
+        assert_eq!(validate(&config, &instance), Ok(()));
+    }
+}
@@ -0,0 +1,445 @@
+//! Raw TCP access to instance ports (`cluster.tcp_proxy`).
+//!
+//! A player asks for one of an instance's ports and is given a port from the
+//! configured range; a listener is bound on it until the instance is torn
+//! down, and every accepted connection is forwarded to the pod through the
+//! Kubernetes port-forward API.
+//!
+//! With `auth = "token"`, a connection from an address that has not yet
+//! authenticated must first send the allocation's one-time token followed by
+//! a newline; using the token binds the sender's address, and asking for the
+//! port again issues a fresh token. With `auth = "source_ip"`, only the address
+//! that asked for the port may connect.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use cds_env::cluster::tcp_proxy::{Auth, Config};
+use serde::Serialize;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    time::Instant,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::{Cluster, traits::ClusterError, util};
+
+/// Longest token line accepted before the connection is dropped.
+const MAX_TOKEN_LINE: u64 = 128;
+
+/// Pause after a failed accept, which tends to repeat at once while its
+/// cause, such as running out of file descriptors, lasts.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Ports handed out to players, keyed by listening port.
+#[derive(Clone)]
+pub struct TcpProxy {
+    config: Config,
+    allocations: Arc<Mutex<HashMap<u16, Arc<Allocation>>>>,
+    /// Serializes allocation so one instance port never gets two listeners.
+    allocating: Arc<tokio::sync::Mutex<()>>,
+}
+
+struct Allocation {
+    instance_id: String,
+    target_port: u16,
+    port: u16,
+    /// Address allowed to connect without a token.
+    bound_ip: Mutex<Option<IpAddr>>,
+    /// Token the next unauthenticated connection must present.
+    token: Mutex<Option<String>>,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    connections: AtomicU64,
+    cancel: CancellationToken,
+}
+
+/// A port allocation as shown to its owner.
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct TcpAllocation {
+    pub instance_id: String,
+    /// Instance port the connections reach.
+    pub target_port: u16,
+    /// Host and port players connect to.
+    pub host: String,
+    pub port: u16,
+    /// One-time token to send as the first line, under `token` auth.
+    pub token: Option<String>,
+    /// Bytes sent by players to the instance.
+    pub bytes_in: u64,
+    /// Bytes sent by the instance to players.
+    pub bytes_out: u64,
+    /// Connections currently open.
+    pub connections: u64,
+}
+
+impl TcpProxy {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            allocations: Arc::default(),
+            allocating: Arc::default(),
+        }
+    }
+
+    fn public_host(&self, fallback: &str) -> String {
+        if self.config.public_host.is_empty() {
+            fallback.to_owned()
+        } else {
+            self.config.public_host.clone()
+        }
+    }
+
+    fn view(&self, allocation: &Allocation, host: &str) -> TcpAllocation {
+        TcpAllocation {
+            instance_id: allocation.instance_id.clone(),
+            target_port: allocation.target_port,
+            host: self.public_host(host),
+            port: allocation.port,
+            token: allocation.token.lock().unwrap().clone(),
+            bytes_in: allocation.bytes_in.load(Ordering::Relaxed),
+            bytes_out: allocation.bytes_out.load(Ordering::Relaxed),
+            connections: allocation.connections.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Lists the allocations of an instance.
+    pub(crate) fn list(&self, instance_id: &str, host: &str) -> Vec<TcpAllocation> {
+        let mut allocations = self
+            .allocations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|allocation| allocation.instance_id == instance_id)
+            .map(|allocation| self.view(allocation, host))
+            .collect::<Vec<_>>();
+        allocations.sort_by_key(|allocation| allocation.target_port);
+        allocations
+    }
+
+    /// Returns the allocation of `instance_id:target_port`, binding a new
+    /// listener when there is none, and renews its credentials for
+    /// `client_ip`.
+    pub(crate) async fn allocate(
+        &self,
+        cluster: &Cluster,
+        instance_id: &str,
+        target_port: u16,
+        client_ip: Option<IpAddr>,
+    ) -> Result<TcpAllocation, ClusterError> {
+        let _guard = self.allocating.lock().await;
+        let existing = self
+            .allocations
+            .lock()
+            .unwrap()
+            .values()
+            .find(|allocation| {
+                allocation.instance_id == instance_id && allocation.target_port == target_port
+            })
+            .cloned();
+
+        let allocation = match existing {
+            Some(allocation) => allocation,
+            None => self.bind(cluster, instance_id, target_port).await?,
+        };
+
+        match self.config.auth {
+            Auth::Token => {
+                *allocation.token.lock().unwrap() = Some(util::gen_access_token());
+            }
+            Auth::SourceIp => {
+                *allocation.bound_ip.lock().unwrap() = client_ip;
+            }
+        }
+
+        Ok(self.view(&allocation, &cluster.public_entry))
+    }
+
+    async fn bind(
+        &self,
+        cluster: &Cluster,
+        instance_id: &str,
+        target_port: u16,
+    ) -> Result<Arc<Allocation>, ClusterError> {
+        for port in self.config.port_start..=self.config.port_end {
+            if self.allocations.lock().unwrap().contains_key(&port) {
+                continue;
+            }
+            let Ok(listener) = TcpListener::bind((self.config.host.as_str(), port)).await else {
+                continue;
+            };
+
+            let allocation = Arc::new(Allocation {
+                instance_id: instance_id.to_owned(),
+                target_port,
+                port,
+                bound_ip: Mutex::new(None),
+                token: Mutex::new(None),
+                bytes_in: AtomicU64::new(0),
+                bytes_out: AtomicU64::new(0),
+                connections: AtomicU64::new(0),
+                cancel: CancellationToken::new(),
+            });
+            self.allocations
+                .lock()
+                .unwrap()
+                .insert(port, allocation.clone());
+            info!(instance_id, target_port, port, "tcp proxy port allocated");
+
+            tokio::spawn(accept(
+                cluster.clone(),
+                self.config.clone(),
+                listener,
+                allocation.clone(),
+            ));
+            return Ok(allocation);
+        }
+
+        Err(ClusterError::Exhausted("tcp_proxy_ports".to_owned()))
+    }
+
+    /// Closes the listeners and connections of an instance.
+    pub(crate) fn release(&self, instance_id: &str) {
+        self.retain(|id| id != instance_id);
+    }
+
+    /// Closes the listeners and connections of every instance for which
+    /// `keep` returns `false`.
+    pub(crate) fn retain(&self, keep: impl Fn(&str) -> bool) {
+        self.allocations.lock().unwrap().retain(|port, allocation| {
+            if keep(&allocation.instance_id) {
+                return true;
+            }
+            allocation.cancel.cancel();
+            info!(instance_id = %allocation.instance_id, port, "tcp proxy port released");
+            false
+        });
+    }
+}
+
+async fn accept(
+    cluster: Cluster,
+    config: Config,
+    listener: TcpListener,
+    allocation: Arc<Allocation>,
+) {
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = allocation.cancel.cancelled() => return,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    warn!(port = allocation.port, error = %err, "tcp proxy accept failed");
+                    tokio::select! {
+                        _ = allocation.cancel.cancelled() => return,
+                        _ = tokio::time::sleep(ACCEPT_BACKOFF) => continue,
+                    }
+                }
+            },
+        };
+
+        let cluster = cluster.clone();
+        let config = config.clone();
+        let allocation = allocation.clone();
+        tokio::spawn(async move {
+            if let Err(err) = serve(&cluster, &config, &allocation, stream, peer).await {
+                debug!(port = allocation.port, peer = %peer, error = %err, "tcp proxy connection closed");
+            }
+        });
+    }
+}
+
+async fn serve(
+    cluster: &Cluster,
+    config: &Config,
+    allocation: &Allocation,
+    stream: TcpStream,
+    peer: SocketAddr,
+) -> Result<(), ClusterError> {
+    // Bytes read past the token line stay buffered and are forwarded.
+    let mut client = BufReader::new(stream);
+
+    if !authorize(config, allocation, &mut client, peer.ip()).await {
+        let _ = client.write_all(b"unauthorized\n").await;
+        return Err(ClusterError::NotFound("tcp_proxy_unauthorized".to_owned()));
+    }
+
+    let upstream = cluster
+        .port_forward(&allocation.instance_id, allocation.target_port)
+        .await?;
+
+    allocation.connections.fetch_add(1, Ordering::Relaxed);
+    pipe(
+        client,
+        upstream,
+        Duration::from_secs(config.idle_timeout),
+        allocation,
+    )
+    .await;
+    allocation.connections.fetch_sub(1, Ordering::Relaxed);
+
+    Ok(())
+}
+
+async fn authorize(
+    config: &Config,
+    allocation: &Allocation,
+    client: &mut BufReader<TcpStream>,
+    ip: IpAddr,
+) -> bool {
+    if *allocation.bound_ip.lock().unwrap() == Some(ip) {
+        return true;
+    }
+    if config.auth != Auth::Token {
+        return false;
+    }
+
+    let mut line = String::new();
+    let read = tokio::time::timeout(
+        Duration::from_secs(config.token_timeout),
+        (&mut *client).take(MAX_TOKEN_LINE).read_line(&mut line),
+    )
+    .await;
+    if !matches!(read, Ok(Ok(n)) if n > 0) {
+        return false;
+    }
+
+    let mut token = allocation.token.lock().unwrap();
+    if !token
+        .as_deref()
+        .is_some_and(|token| util::constant_time_eq(token.as_bytes(), line.trim().as_bytes()))
+    {
+        return false;
+    }
+    *token = None;
+    *allocation.bound_ip.lock().unwrap() = Some(ip);
+
+    true
+}
+
+/// Copies both ways until both sides finish, the connection stays idle for
+/// `idle`, or the allocation is released.
+async fn pipe<C, U>(client: C, upstream: U, idle: Duration, allocation: &Allocation)
+where
+    C: AsyncRead + AsyncWrite,
+    U: AsyncRead + AsyncWrite, {
+    let (mut client_r, mut client_w) = tokio::io::split(client);
+    let (mut upstream_r, mut upstream_w) = tokio::io::split(upstream);
+    let mut inbound = vec![0u8; 16 * 1024];
+    let mut outbound = vec![0u8; 16 * 1024];
+    let (mut client_open, mut upstream_open) = (true, true);
+    let mut deadline = Instant::now() + idle;
+
+    while client_open || upstream_open {
+        tokio::select! {
+            _ = allocation.cancel.cancelled() => break,
+            _ = tokio::time::sleep_until(deadline) => break,
+            read = client_r.read(&mut inbound), if client_open => match read {
+                Ok(n) if n > 0 => {
+                    if upstream_w.write_all(&inbound[..n]).await.is_err() {
+                        break;
+                    }
+                    allocation.bytes_in.fetch_add(n as u64, Ordering::Relaxed);
+                    deadline = Instant::now() + idle;
+                }
+                _ => {
+                    client_open = false;
+                    let _ = upstream_w.shutdown().await;
+                }
+            },
+            read = upstream_r.read(&mut outbound), if upstream_open => match read {
+                Ok(n) if n > 0 => {
+                    if client_w.write_all(&outbound[..n]).await.is_err() {
+                        break;
+                    }
+                    allocation.bytes_out.fetch_add(n as u64, Ordering::Relaxed);
+                    deadline = Instant::now() + idle;
+                }
+                _ => {
+                    upstream_open = false;
+                    let _ = client_w.shutdown().await;
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Mutex, time::Duration};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_util::sync::CancellationToken;
+
+    use super::{Allocation, pipe};
+
+    fn allocation() -> Allocation {
+        Allocation {
+            instance_id: "test".to_owned(),
+            target_port: 80,
+            port: 40000,
+            bound_ip: Mutex::new(None),
+            token: Mutex::new(None),
+            bytes_in: Default::default(),
+            bytes_out: Default::default(),
+            connections: Default::default(),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn pipe_counts_bytes_and_honours_half_close() {
+        let allocation = allocation();
+        let (client, mut player) = tokio::io::duplex(64);
+        let (upstream, mut pod) = tokio::io::duplex(64);
+
+        let pod_side = async {
+            let mut request = Vec::new();
+            pod.read_to_end(&mut request).await.unwrap();
+            pod.write_all(b"pong!").await.unwrap();
+            pod.shutdown().await.unwrap();
+            request
+        };
+        let player_side = async {
+            player.write_all(b"ping").await.unwrap();
+            player.shutdown().await.unwrap();
+            let mut response = Vec::new();
+            player.read_to_end(&mut response).await.unwrap();
+            response
+        };
+
+        let ((), request, response) = tokio::join!(
+            pipe(client, upstream, Duration::from_secs(5), &allocation),
+            pod_side,
+            player_side
+        );
+
+        assert_eq!(request, b"ping");
+        assert_eq!(response, b"pong!");
+        assert_eq!(allocation.bytes_in.into_inner(), 4);
+        assert_eq!(allocation.bytes_out.into_inner(), 5);
+    }
+
+    #[tokio::test]
+    async fn pipe_closes_idle_connections() {
+        let allocation = allocation();
+        let (client, _player) = tokio::io::duplex(64);
+        let (upstream, _pod) = tokio::io::duplex(64);
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            pipe(client, upstream, Duration::from_millis(50), &allocation),
+        )
+        .await
+        .unwrap();
+    }
+}
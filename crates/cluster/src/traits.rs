@@ -1,6 +1,6 @@
 //! Shared traits and error types for the `cluster` crate.
 
-use serde::{Deserialize, Serialize};
+pub use cds_db::instance::Nat;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -19,17 +19,14 @@ pub enum ClusterError {
     NotFound(String),
     #[error("missing field: {0}")]
     MissingField(String),
+    #[error("exhausted: {0}")]
+    Exhausted(String),
     #[error("missing env configuration")]
     MissingEnvConfiguration,
     #[error(transparent)]
     OtherError(#[from] anyhow::Error),
     #[error("checker error: {0}")]
     CheckerError(#[from] cds_checker::traits::CheckerError),
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
-pub struct Nat {
-    pub port: i32,
-    pub node_port: i32,
-    pub protocol: String,
+    #[error("db error: {0}")]
+    DbError(#[from] cds_db::traits::DbError),
 }
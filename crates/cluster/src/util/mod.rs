@@ -9,3 +9,23 @@ pub fn gen_safe_nanoid() -> String {
 
     nanoid::nanoid!(12, &ALPHABET)
 }
+
+/// Current Unix time in seconds.
+pub fn now() -> i64 {
+    cds_db::sea_orm::sqlx::types::time::OffsetDateTime::now_utc().unix_timestamp()
+}
+
+/// Generates a URL- and cookie-safe secret for an instance's ingress host.
+pub fn gen_access_token() -> String {
+    nanoid::nanoid!(32)
+}
+
+/// Compares two secrets in time independent of where they differ.
+pub fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0u8, |diff, (left, right)| diff | (left ^ right))
+            == 0
+}
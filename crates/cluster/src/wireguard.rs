@@ -0,0 +1,746 @@
+//! WireGuard access to instance networks: a gateway Deployment in the
+//! platform's namespace, and one peer per team of a game.
+//!
+//! Everything the gateway needs is kept in the database: its key pair and
+//! the peers issued to teams. Each reconciler pass revokes the peers of
+//! banned teams and ended games, then renders the gateway's interface config
+//! and forwarding rules into a Secret the gateway reloads, so a peer only
+//! reaches the pod IPs of its team's running instances in its game and never
+//! another peer. Changes reach the gateway once the kubelet refreshes the
+//! mounted Secret, usually within a minute.
+//!
+//! Peer traffic goes straight from the gateway to the pods, so it never
+//! passes through [`Cluster::port_forward`]. Each pass also reads the byte
+//! counters of the gateway's forwarding rules and counts any that grew as
+//! activity of the instance behind that pod IP, keeping instances used only
+//! over WireGuard from being reaped as idle.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    net::Ipv4Addr,
+    sync::{Arc, Mutex},
+};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use cds_db::{WireguardPeerView, wireguard_gateway, wireguard_peer::NewPeer};
+use cds_env::cluster::wireguard::Config;
+use ipnet::Ipv4Net;
+use k8s_openapi::{
+    ByteString,
+    api::{
+        apps::v1::{Deployment, DeploymentSpec, DeploymentStrategy},
+        core::v1::{
+            Container as K8sContainer, ContainerPort, Pod, PodSpec, PodTemplateSpec, Secret,
+            SecretVolumeSource, SecurityContext, Service, ServicePort, ServiceSpec, Volume,
+            VolumeMount,
+        },
+    },
+    apimachinery::pkg::apis::meta::v1::{LabelSelector, ObjectMeta},
+};
+use kube::api::{Api, AttachParams, ListParams, Patch, PatchParams};
+use tokio::io::AsyncReadExt;
+use tracing::warn;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::{Cluster, traits::ClusterError, util};
+
+/// Name of the gateway's Deployment, Service and Secret.
+const NAME: &str = "cds-wireguard";
+
+/// `cds/app` label of the gateway's pods.
+pub(crate) const APP: &str = "wireguard";
+
+/// UDP port the gateway listens on inside its pod.
+const LISTEN_PORT: i32 = 51820;
+
+/// Brings `wg0` up from the mounted Secret, then reloads its peers and the
+/// forwarding rules whenever the Secret changes.
+const GATEWAY_SCRIPT: &str = r#"set -eu
+dir=/etc/cds-wireguard
+load() {
+    cp "$dir/wg0.conf" /tmp/wg0.conf
+    chmod 600 /tmp/wg0.conf
+    iptables-restore < "$dir/rules"
+    cat "$dir/wg0.conf" "$dir/rules" | md5sum
+}
+sysctl -w net.ipv4.ip_forward=1
+applied=$(load)
+wg-quick up /tmp/wg0.conf
+while sleep 5; do
+    current=$(cat "$dir/wg0.conf" "$dir/rules" | md5sum)
+    [ "$current" = "$applied" ] && continue
+    applied=$(load)
+    wg-quick strip /tmp/wg0.conf > /tmp/wg0.stripped
+    wg syncconf wg0 /tmp/wg0.stripped
+done
+"#;
+
+/// Returns a new private key and its public key, base64 encoded.
+fn key_pair() -> (String, String) {
+    let secret = StaticSecret::random();
+    let public = PublicKey::from(&secret);
+
+    (
+        STANDARD.encode(secret.to_bytes()),
+        STANDARD.encode(public.as_bytes()),
+    )
+}
+
+fn network(config: &Config) -> Result<Ipv4Net, ClusterError> {
+    config
+        .network
+        .parse::<Ipv4Net>()
+        .map_err(|_| ClusterError::MissingField("wireguard_network".to_owned()))
+}
+
+/// The gateway's tunnel address: the first of the network.
+fn gateway_address(network: &Ipv4Net) -> Option<Ipv4Addr> {
+    network.hosts().next()
+}
+
+/// The lowest address of the network neither the gateway's nor `taken`.
+fn free_address(network: &Ipv4Net, taken: &HashSet<Ipv4Addr>) -> Option<Ipv4Addr> {
+    network
+        .hosts()
+        .skip(1)
+        .find(|address| !taken.contains(address))
+}
+
+/// Pod IPs of the running instances each peer may reach, by peer address.
+fn reachable(peers: &[WireguardPeerView], pods: &[Pod]) -> BTreeMap<String, BTreeSet<String>> {
+    let label = |pod: &Pod, key: &str| {
+        pod.metadata
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(key))
+            .and_then(|value| value.parse::<i64>().ok())
+    };
+
+    peers
+        .iter()
+        .map(|peer| {
+            let ips = pods
+                .iter()
+                .filter(|pod| {
+                    label(pod, "cds/game_id") == Some(peer.game_id)
+                        && label(pod, "cds/team_id") == Some(peer.team_id)
+                })
+                .filter_map(|pod| pod.status.as_ref())
+                .filter(|status| status.phase.as_deref() == Some("Running"))
+                .filter_map(|status| status.pod_ip.clone())
+                .collect();
+            (peer.address.clone(), ips)
+        })
+        .collect()
+}
+
+/// `wg-quick` config of the gateway's interface.
+fn interface_config(
+    private_key: &str,
+    network: &Ipv4Net,
+    peers: &[WireguardPeerView],
+) -> Result<String, ClusterError> {
+    let address = gateway_address(network)
+        .ok_or_else(|| ClusterError::Exhausted("wireguard_network".to_owned()))?;
+
+    let mut config = format!(
+        "[Interface]\nAddress = {}/{}\nListenPort = {}\nPrivateKey = {}\n",
+        address,
+        network.prefix_len(),
+        LISTEN_PORT,
+        private_key
+    );
+    for peer in peers {
+        config.push_str(&format!(
+            "\n[Peer]\nPublicKey = {}\nAllowedIPs = {}/32\n",
+            peer.public_key, peer.address
+        ));
+    }
+
+    Ok(config)
+}
+
+/// `iptables-restore` rules of the gateway: nothing is forwarded but replies
+/// and each peer's traffic to its reachable pods, masqueraded so the pods
+/// answer the gateway.
+fn forward_rules(network: &Ipv4Net, reachable: &BTreeMap<String, BTreeSet<String>>) -> String {
+    let mut rules = String::from(
+        "*filter\n:INPUT ACCEPT [0:0]\n:FORWARD DROP [0:0]\n:OUTPUT ACCEPT [0:0]\n-A FORWARD -m \
+         conntrack --ctstate ESTABLISHED,RELATED -j ACCEPT\n",
+    );
+    for (address, ips) in reachable {
+        for ip in ips {
+            rules.push_str(&format!(
+                "-A FORWARD -i wg0 -s {}/32 -d {}/32 -j ACCEPT\n",
+                address, ip
+            ));
+        }
+    }
+    rules.push_str(&format!(
+        "COMMIT\n*nat\n:PREROUTING ACCEPT [0:0]\n:INPUT ACCEPT [0:0]\n:OUTPUT ACCEPT \
+         [0:0]\n:POSTROUTING ACCEPT [0:0]\n-A POSTROUTING -s {} ! -o wg0 -j MASQUERADE\nCOMMIT\n",
+        network.trunc()
+    ));
+
+    rules
+}
+
+/// Bytes the gateway forwarded from peers to each pod IP, summed over the
+/// `-i wg0 ... -d <pod>/32` rules of an `iptables-save -c` dump.
+fn forwarded_bytes(saved: &str) -> HashMap<String, u64> {
+    let mut bytes = HashMap::new();
+    for line in saved.lines() {
+        let Some((counters, rule)) = line
+            .strip_prefix('[')
+            .and_then(|line| line.split_once("] "))
+        else {
+            continue;
+        };
+        let tokens = rule.split_whitespace().collect::<Vec<_>>();
+        if tokens.get(..2) != Some(&["-A", "FORWARD"][..])
+            || !tokens.windows(2).any(|pair| pair == ["-i", "wg0"])
+        {
+            continue;
+        }
+        let destination = tokens
+            .windows(2)
+            .find(|pair| pair[0] == "-d")
+            .map(|pair| pair[1].trim_end_matches("/32"));
+        let count = counters
+            .split_once(':')
+            .and_then(|(_, count)| count.parse::<u64>().ok());
+        if let (Some(destination), Some(count)) = (destination, count) {
+            *bytes.entry(destination.to_owned()).or_default() += count;
+        }
+    }
+
+    bytes
+}
+
+/// Last forwarded byte counts read from the gateway, by pod IP.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Counters {
+    last: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl Counters {
+    /// Stores `current` and returns the pod IPs whose counter moved since
+    /// the last read. Reloading the rules resets their counters, so any
+    /// change, not only growth, counts as traffic.
+    fn advance(&self, current: HashMap<String, u64>) -> Vec<String> {
+        let mut last = self.last.lock().unwrap_or_else(|err| err.into_inner());
+        let moved = current
+            .iter()
+            .filter(|(ip, count)| **count > 0 && last.get(*ip) != Some(*count))
+            .map(|(ip, _)| ip.clone())
+            .collect();
+        *last = current;
+
+        moved
+    }
+}
+
+/// `wg-quick` config handed to a team for `peer`.
+fn peer_config(
+    config: &Config,
+    endpoint: &str,
+    routes: &[String],
+    gateway_public_key: &str,
+    peer: &WireguardPeerView,
+) -> String {
+    let mut interface = format!(
+        "[Interface]\nPrivateKey = {}\nAddress = {}/32\n",
+        peer.private_key, peer.address
+    );
+    if !config.dns.is_empty() {
+        interface.push_str(&format!("DNS = {}\n", config.dns.join(", ")));
+    }
+
+    let mut gateway = format!(
+        "\n[Peer]\nPublicKey = {}\nEndpoint = {}\nAllowedIPs = {}\n",
+        gateway_public_key,
+        endpoint,
+        routes.join(", ")
+    );
+    if config.persistent_keepalive > 0 {
+        gateway.push_str(&format!(
+            "PersistentKeepalive = {}\n",
+            config.persistent_keepalive
+        ));
+    }
+
+    interface + &gateway
+}
+
+fn gateway_labels() -> BTreeMap<String, String> {
+    BTreeMap::from([("cds/app".to_owned(), APP.to_owned())])
+}
+
+/// Builds the gateway's single-replica Deployment, recreated rather than
+/// rolled so two gateways never hold the same key.
+fn deployment(config: &Config) -> Deployment {
+    let labels = gateway_labels();
+
+    Deployment {
+        metadata: ObjectMeta {
+            name: Some(NAME.to_owned()),
+            labels: Some(labels.clone()),
+            ..Default::default()
+        },
+        spec: Some(DeploymentSpec {
+            replicas: Some(1),
+            selector: LabelSelector {
+                match_labels: Some(labels.clone()),
+                ..Default::default()
+            },
+            strategy: Some(DeploymentStrategy {
+                type_: Some("Recreate".to_owned()),
+                ..Default::default()
+            }),
+            template: PodTemplateSpec {
+                metadata: Some(ObjectMeta {
+                    labels: Some(labels),
+                    ..Default::default()
+                }),
+                spec: Some(PodSpec {
+                    containers: vec![K8sContainer {
+                        name: "wireguard".to_owned(),
+                        image: Some(config.image.clone()),
+                        command: Some(vec![
+                            "sh".to_owned(),
+                            "-c".to_owned(),
+                            GATEWAY_SCRIPT.to_owned(),
+                        ]),
+                        ports: Some(vec![ContainerPort {
+                            container_port: LISTEN_PORT,
+                            protocol: Some("UDP".to_owned()),
+                            ..Default::default()
+                        }]),
+                        security_context: Some(SecurityContext {
+                            privileged: Some(true),
+                            ..Default::default()
+                        }),
+                        volume_mounts: Some(vec![VolumeMount {
+                            name: "config".to_owned(),
+                            mount_path: "/etc/cds-wireguard".to_owned(),
+                            read_only: Some(true),
+                            ..Default::default()
+                        }]),
+                        ..Default::default()
+                    }],
+                    volumes: Some(vec![Volume {
+                        name: "config".to_owned(),
+                        secret: Some(SecretVolumeSource {
+                            secret_name: Some(NAME.to_owned()),
+                            default_mode: Some(0o600),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }]),
+                    automount_service_account_token: Some(false),
+                    ..Default::default()
+                }),
+            },
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Builds the `NodePort` Service players' peers connect to.
+fn service(config: &Config) -> Service {
+    Service {
+        metadata: ObjectMeta {
+            name: Some(NAME.to_owned()),
+            labels: Some(gateway_labels()),
+            ..Default::default()
+        },
+        spec: Some(ServiceSpec {
+            type_: Some("NodePort".to_owned()),
+            selector: Some(gateway_labels()),
+            ports: Some(vec![ServicePort {
+                name: Some("wireguard".to_owned()),
+                port: LISTEN_PORT,
+                protocol: Some("UDP".to_owned()),
+                node_port: Some(config.node_port),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+impl Cluster {
+    /// Applies the gateway's Deployment and Service, when the WireGuard
+    /// access mode is enabled; its Secret follows on the next reconciler
+    /// pass.
+    pub(crate) async fn ensure_wireguard_gateway(&self) -> Result<(), ClusterError> {
+        if !self.wireguard.enabled {
+            return Ok(());
+        }
+        network(&self.wireguard)?;
+
+        let params = PatchParams::apply("cdsctf").force();
+        self.api_in::<Deployment>(None)
+            .patch(NAME, &params, &Patch::Apply(&deployment(&self.wireguard)))
+            .await?;
+        self.api_in::<Service>(None)
+            .patch(NAME, &params, &Patch::Apply(&service(&self.wireguard)))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn wireguard_gateway_key(&self) -> Result<wireguard_gateway::Model, ClusterError> {
+        if let Some(gateway) = wireguard_gateway::get(&self.db.conn).await? {
+            return Ok(gateway);
+        }
+        let (private_key, public_key) = key_pair();
+
+        Ok(wireguard_gateway::create(&self.db.conn, private_key, public_key).await?)
+    }
+
+    /// Returns the team's peer, unless it has none or it was revoked.
+    pub async fn wireguard_peer(
+        &self,
+        game_id: i64,
+        team_id: i64,
+    ) -> Result<Option<WireguardPeerView>, ClusterError> {
+        Ok(cds_db::wireguard_peer::find_active_by_team(&self.db.conn, game_id, team_id).await?)
+    }
+
+    /// Issues the team a new peer with fresh keys, revoking the one it had.
+    pub async fn issue_wireguard_peer(
+        &self,
+        game_id: i64,
+        team_id: i64,
+    ) -> Result<WireguardPeerView, ClusterError> {
+        let network = network(&self.wireguard)?;
+        cds_db::wireguard_peer::revoke_by_team(&self.db.conn, game_id, team_id, util::now())
+            .await?;
+
+        let taken = cds_db::wireguard_peer::find_active::<WireguardPeerView>(&self.db.conn)
+            .await?
+            .into_iter()
+            .filter_map(|peer| peer.address.parse::<Ipv4Addr>().ok())
+            .collect::<HashSet<_>>();
+        let address = free_address(&network, &taken)
+            .ok_or_else(|| ClusterError::Exhausted("wireguard_address".to_owned()))?;
+        let (private_key, public_key) = key_pair();
+
+        Ok(cds_db::wireguard_peer::create(
+            &self.db.conn,
+            NewPeer {
+                game_id,
+                team_id,
+                address: address.to_string(),
+                private_key,
+                public_key,
+            },
+        )
+        .await?)
+    }
+
+    /// Revokes the team's peer; returns whether it had one.
+    pub async fn revoke_wireguard_peer(
+        &self,
+        game_id: i64,
+        team_id: i64,
+    ) -> Result<bool, ClusterError> {
+        Ok(
+            cds_db::wireguard_peer::revoke_by_team(&self.db.conn, game_id, team_id, util::now())
+                .await?,
+        )
+    }
+
+    /// Returns the `wg-quick` config of `peer`.
+    pub async fn wireguard_config(&self, peer: &WireguardPeerView) -> Result<String, ClusterError> {
+        let gateway = self.wireguard_gateway_key().await?;
+        let endpoint = if self.wireguard.endpoint.is_empty() {
+            format!("{}:{}", self.public_entry, self.wireguard.node_port)
+        } else {
+            self.wireguard.endpoint.clone()
+        };
+        let routes = if self.wireguard.routes.is_empty() {
+            &self.isolation.pod_cidrs
+        } else {
+            &self.wireguard.routes
+        };
+
+        Ok(peer_config(
+            &self.wireguard,
+            &endpoint,
+            routes,
+            &gateway.public_key,
+            peer,
+        ))
+    }
+
+    /// Dumps the gateway's filter table with its counters.
+    async fn gateway_rules(&self) -> Result<String, ClusterError> {
+        let api: Api<Pod> = self.api_in(None);
+        let pod = api
+            .list(&ListParams::default().labels(&format!("cds/app={APP}")))
+            .await?
+            .items
+            .into_iter()
+            .find(|pod| {
+                pod.metadata.deletion_timestamp.is_none()
+                    && pod
+                        .status
+                        .as_ref()
+                        .and_then(|status| status.phase.as_deref())
+                        == Some("Running")
+            })
+            .and_then(|pod| pod.metadata.name)
+            .ok_or_else(|| ClusterError::NotFound("wireguard_gateway".to_owned()))?;
+
+        let mut attached = api
+            .exec(
+                &pod,
+                vec!["iptables-save", "-c", "-t", "filter"],
+                &AttachParams {
+                    stdin: false,
+                    stdout: true,
+                    stderr: false,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let mut saved = String::new();
+        if let Some(mut reader) = attached.stdout() {
+            reader
+                .read_to_string(&mut saved)
+                .await
+                .map_err(anyhow::Error::from)?;
+        }
+        let status = match attached.take_status() {
+            Some(status) => status.await,
+            None => None,
+        };
+        if status.and_then(|status| status.status).as_deref() != Some("Success") {
+            return Err(anyhow::anyhow!("iptables-save failed in the wireguard gateway").into());
+        }
+
+        Ok(saved)
+    }
+
+    /// Counts traffic peers sent through the gateway since the last pass as
+    /// activity of the instances in `pods` it reached. When the counters
+    /// cannot be read, every instance a peer can reach counts as active, so
+    /// a gateway outage never gets instances in use reaped as idle.
+    pub(crate) async fn observe_wireguard_traffic(&self, pods: &[Pod]) {
+        if !self.wireguard.enabled {
+            return;
+        }
+
+        let instances = pods
+            .iter()
+            .filter_map(|pod| {
+                let id = pod.metadata.labels.as_ref()?.get("cds/instance_id")?;
+                let ip = pod.status.as_ref()?.pod_ip.as_ref()?;
+                Some((ip.clone(), id.clone()))
+            })
+            .collect::<HashMap<_, _>>();
+        let active = match self.gateway_rules().await {
+            Ok(saved) => self.wireguard_counters.advance(forwarded_bytes(&saved)),
+            Err(err) => {
+                warn!(error = %err, "failed to read wireguard gateway counters");
+                match cds_db::wireguard_peer::find_active::<WireguardPeerView>(&self.db.conn).await
+                {
+                    Ok(peers) => reachable(&peers, pods).into_values().flatten().collect(),
+                    Err(err) => {
+                        warn!(error = %err, "failed to load wireguard peers");
+                        return;
+                    }
+                }
+            }
+        };
+
+        let now = util::now();
+        for ip in active {
+            if let Some(id) = instances.get(&ip) {
+                self.activity.mark(id, now);
+            }
+        }
+    }
+
+    /// Revokes stale peers and applies the gateway's Secret for the peers
+    /// left and the instance pods in `pods`.
+    pub(crate) async fn sync_wireguard(&self, pods: &[Pod]) -> Result<(), ClusterError> {
+        if !self.wireguard.enabled {
+            return Ok(());
+        }
+        let network = network(&self.wireguard)?;
+
+        cds_db::wireguard_peer::revoke_stale(&self.db.conn, util::now()).await?;
+        let peers = cds_db::wireguard_peer::find_active::<WireguardPeerView>(&self.db.conn).await?;
+        let gateway = self.wireguard_gateway_key().await?;
+
+        let secret = Secret {
+            metadata: ObjectMeta {
+                name: Some(NAME.to_owned()),
+                labels: Some(gateway_labels()),
+                ..Default::default()
+            },
+            data: Some(BTreeMap::from([
+                (
+                    "wg0.conf".to_owned(),
+                    ByteString(interface_config(&gateway.private_key, &network, &peers)?.into()),
+                ),
+                (
+                    "rules".to_owned(),
+                    ByteString(forward_rules(&network, &reachable(&peers, pods)).into()),
+                ),
+            ])),
+            ..Default::default()
+        };
+        let api: Api<Secret> = self.api_in(None);
+        api.patch(
+            NAME,
+            &PatchParams::apply("cdsctf").force(),
+            &Patch::Apply(&secret),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+
+    use cds_db::WireguardPeerView;
+    use cds_env::cluster::wireguard::Config;
+    use ipnet::Ipv4Net;
+    use k8s_openapi::{
+        api::core::v1::{Pod, PodStatus},
+        apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    };
+
+    use super::{Counters, forward_rules, forwarded_bytes, free_address, peer_config, reachable};
+
+    fn peer(team_id: i64, address: &str) -> WireguardPeerView {
+        WireguardPeerView {
+            id: team_id,
+            game_id: 1,
+            team_id,
+            address: address.to_owned(),
+            private_key: "private".to_owned(),
+            public_key: "public".to_owned(),
+            created_at: 0,
+            revoked_at: None,
+        }
+    }
+
+    fn pod(game_id: i64, team_id: i64, ip: &str, phase: &str) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                labels: Some(BTreeMap::from([
+                    ("cds/game_id".to_owned(), game_id.to_string()),
+                    ("cds/team_id".to_owned(), team_id.to_string()),
+                ])),
+                ..Default::default()
+            },
+            status: Some(PodStatus {
+                phase: Some(phase.to_owned()),
+                pod_ip: Some(ip.to_owned()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn peers_take_the_lowest_free_address_after_the_gateway() {
+        let network = "10.213.0.0/30".parse::<Ipv4Net>().unwrap();
+
+        assert_eq!(
+            free_address(&network, &HashSet::new()).map(|a| a.to_string()),
+            Some("10.213.0.2".to_owned())
+        );
+        let taken = HashSet::from(["10.213.0.2".parse().unwrap()]);
+        assert_eq!(free_address(&network, &taken), None);
+    }
+
+    #[test]
+    fn peers_only_reach_their_team_s_running_pods() {
+        let network = "10.213.0.0/16".parse::<Ipv4Net>().unwrap();
+        let peers = vec![peer(7, "10.213.0.2"), peer(8, "10.213.0.3")];
+        let pods = vec![
+            pod(1, 7, "10.42.0.5", "Running"),
+            pod(1, 7, "10.42.0.6", "Pending"),
+            pod(2, 7, "10.42.0.7", "Running"),
+            pod(1, 8, "10.42.0.8", "Running"),
+        ];
+
+        let rules = forward_rules(&network, &reachable(&peers, &pods));
+
+        assert!(rules.contains(":FORWARD DROP"));
+        assert!(rules.contains("-s 10.213.0.2/32 -d 10.42.0.5/32 -j ACCEPT"));
+        assert!(rules.contains("-s 10.213.0.3/32 -d 10.42.0.8/32 -j ACCEPT"));
+        assert!(!rules.contains("10.42.0.6"));
+        assert!(!rules.contains("10.42.0.7"));
+        assert!(rules.contains("-A POSTROUTING -s 10.213.0.0/16 ! -o wg0 -j MASQUERADE"));
+    }
+
+    #[test]
+    fn forwarded_bytes_are_read_per_pod_from_saved_counters() {
+        let saved = "*filter\n:FORWARD DROP [0:0]\n\
+             [90:5400] -A FORWARD -m conntrack --ctstate RELATED,ESTABLISHED -j ACCEPT\n\
+             [3:180] -A FORWARD -s 10.213.0.2/32 -d 10.42.0.5/32 -i wg0 -j ACCEPT\n\
+             [1:60] -A FORWARD -s 10.213.0.3/32 -d 10.42.0.5/32 -i wg0 -j ACCEPT\n\
+             [0:0] -A FORWARD -s 10.213.0.3/32 -d 10.42.0.8/32 -i wg0 -j ACCEPT\nCOMMIT\n";
+
+        let bytes = forwarded_bytes(saved);
+
+        assert_eq!(bytes.len(), 2);
+        assert_eq!(bytes["10.42.0.5"], 240);
+        assert_eq!(bytes["10.42.0.8"], 0);
+    }
+
+    #[test]
+    fn only_moved_counters_count_as_traffic() {
+        let counters = Counters::default();
+        let read = |pairs: &[(&str, u64)]| {
+            pairs
+                .iter()
+                .map(|(ip, count)| (ip.to_string(), *count))
+                .collect()
+        };
+
+        let mut moved = counters.advance(read(&[("10.42.0.5", 240), ("10.42.0.8", 0)]));
+        moved.sort();
+        assert_eq!(moved, vec!["10.42.0.5".to_owned()]);
+        assert!(counters.advance(read(&[("10.42.0.5", 240)])).is_empty());
+        // A reload resets the counters; new traffic still shows up.
+        assert_eq!(
+            counters.advance(read(&[("10.42.0.5", 60)])),
+            vec!["10.42.0.5".to_owned()]
+        );
+    }
+
+    #[test]
+    fn issued_configs_route_only_the_configured_ranges() {
+        let config = Config {
+            dns: vec!["10.43.0.10".to_owned()],
+            ..Default::default()
+        };
+
+        let issued = peer_config(
+            &config,
+            "ctf.example.com:31820",
+            &["10.42.0.0/16".to_owned()],
+            "gateway",
+            &peer(7, "10.213.0.2"),
+        );
+
+        assert!(issued.contains("PrivateKey = private\nAddress = 10.213.0.2/32\n"));
+        assert!(issued.contains("DNS = 10.43.0.10\n"));
+        assert!(issued.contains("PublicKey = gateway\nEndpoint = ctf.example.com:31820\n"));
+        assert!(issued.contains("AllowedIPs = 10.42.0.0/16\n"));
+        assert!(issued.contains("PersistentKeepalive = 25\n"));
+    }
+}
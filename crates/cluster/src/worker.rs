@@ -1,52 +1,437 @@
 //! Kubernetes integration — `worker` (cluster operations and helpers).
+//!
+//! The reconciler compares challenge pods with the `instances` table every
+//! few seconds. Running records past their deadline — `expires_at`, or the
+//! end of their idle timeout without traffic — are torn down, after their
+//! owner was warned. Pods whose record is already terminated are deleted as
+//! orphans, failed or evicted pods close their record, and running records
+//! whose pod has vanished are closed as `pod_lost`. Pods started before
+//! instance records existed are adopted from their labels and annotations.
+//! Instance pods without an ingress policy get one. Each pass also refills
+//! the warm pools of challenges that keep pre-started instances and brings
+//! the WireGuard gateway up to date.
 
-use cds_db::sea_orm::sqlx::types::time;
-use tracing::info;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
-use crate::Cluster;
+use cds_db::{
+    InstanceView,
+    challenge::Port,
+    instance::{NewInstance, State, TerminationReason},
+};
+use k8s_openapi::api::core::v1::Pod;
+use tracing::{info, warn};
 
-/// Background task that reaps stale resources.
-pub async fn cleaner(cluster: Cluster) {
+use crate::{Cluster, lifetime, traits::ClusterError, util};
+
+/// Time between two reconciliation passes.
+const INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long a running record may go without a pod before it is considered
+/// lost; covers instances whose pod is still being created or recreated by
+/// a reset.
+const POD_GRACE_SECONDS: i64 = 60;
+
+/// What the reconciler saw of a challenge pod.
+#[derive(Clone, Debug)]
+struct ObservedPod {
+    id: String,
+    /// The pod failed or was evicted and will not run again.
+    finished: bool,
+    /// Record to create if the pod has none.
+    adoption: NewInstance,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Action {
+    /// Records a pod started before instance records existed.
+    Adopt(String),
+    /// Deletes the resources of an instance whose record is terminated.
+    DeleteOrphan(String),
+    /// Deletes the resources of a running instance and closes its record.
+    Terminate(String, TerminationReason),
+    /// Tells the owner of a running instance that it is about to stop.
+    Warn(String),
+}
+
+/// Background task that keeps instance records and pods in agreement.
+pub async fn reconciler(cluster: Cluster) {
     tokio::spawn(async move {
-        let interval = std::time::Duration::from_secs(10);
         loop {
-            let pods = cluster.get_pods_list().await.unwrap_or_default();
-            for pod in pods {
-                let id = pod
-                    .metadata
-                    .labels
-                    .unwrap_or_default()
-                    .get("cds/instance_id")
-                    .map(|s| s.to_owned())
-                    .unwrap_or_default();
-
-                // SAFETY: the creation_timestamp could be safely unwrapped.
-                let started_at = pod.metadata.creation_timestamp.unwrap().0.as_second();
-
-                let annotations = pod.metadata.annotations.unwrap_or_default();
-
-                let renew = annotations
-                    .get("cds/renew")
-                    .map(|s| s.to_owned())
-                    .unwrap_or_default()
-                    .parse::<i64>()
-                    .unwrap_or(3);
-                let duration = annotations
-                    .get("cds/duration")
-                    .map(|s| s.to_owned())
-                    .unwrap_or_default()
-                    .parse::<i64>()
-                    .unwrap_or(0);
-
-                let now = time::OffsetDateTime::now_utc().unix_timestamp();
-
-                if now > started_at + (renew + 1) * duration {
-                    cluster.delete_pod(&id).await.unwrap();
-                    cluster.delete_service(&id).await.unwrap();
-                    info!("Cleaned up invalid cluster {0}", id);
+            if let Err(err) = reconcile(&cluster).await {
+                warn!(error = %err, "instance reconciliation failed");
+            }
+            tokio::time::sleep(INTERVAL).await;
+        }
+    });
+}
+
+async fn reconcile(cluster: &Cluster) -> Result<(), ClusterError> {
+    // Pods are listed before records are read, so an instance created in
+    // between has a record but no pod yet, which the grace period covers.
+    let all_pods = cluster.get_pods_list().await?;
+    let pods = all_pods
+        .iter()
+        .cloned()
+        .filter_map(observe)
+        .collect::<Vec<_>>();
+    // Policies are applied before orphans are deleted, which removes theirs.
+    if let Err(err) = cluster.isolate_unguarded(&all_pods).await {
+        warn!(error = %err, "failed to isolate running instances");
+    }
+    // Traffic is written first so an instance in use is not reaped as idle.
+    cluster.observe_wireguard_traffic(&all_pods).await;
+    cluster.flush_activity().await?;
+    let now = util::now();
+
+    let ids = pods.iter().map(|pod| pod.id.clone()).collect::<Vec<_>>();
+    let records = cds_db::instance::find_by_ids::<InstanceView>(&cluster.db.conn, &ids)
+        .await?
+        .into_iter()
+        .map(|record| (record.id.clone(), record))
+        .collect::<HashMap<_, _>>();
+    let running = cds_db::instance::find_running::<InstanceView>(&cluster.db.conn).await?;
+
+    if let Some(tcp) = &cluster.tcp {
+        tcp.retain(|id| running.iter().any(|record| record.id == id));
+    }
+
+    for action in plan(
+        &pods,
+        &records,
+        &running,
+        now,
+        cluster.lifetime.warning_before,
+    ) {
+        match action {
+            Action::Adopt(id) => {
+                let Some(pod) = pods.iter().find(|pod| pod.id == id) else {
+                    continue;
+                };
+                match cds_db::instance::create::<InstanceView>(
+                    &cluster.db.conn,
+                    pod.adoption.clone(),
+                )
+                .await
+                {
+                    Ok(_) => info!(instance_id = %id, "adopted unrecorded challenge pod"),
+                    Err(err) => {
+                        warn!(instance_id = %id, error = %err, "failed to adopt challenge pod")
+                    }
+                }
+            }
+            Action::DeleteOrphan(id) => match cluster.delete_resources(&id).await {
+                Ok(()) => info!(instance_id = %id, "deleted orphan challenge pod"),
+                Err(err) => {
+                    warn!(instance_id = %id, error = %err, "failed to delete orphan challenge pod")
+                }
+            },
+            Action::Terminate(id, reason) => {
+                if let Err(err) = cluster.delete_challenge_instance(&id, reason).await {
+                    warn!(instance_id = %id, error = %err, "failed to terminate challenge instance");
+                }
+            }
+            Action::Warn(id) => {
+                let Some(record) = running.iter().find(|record| record.id == id) else {
+                    continue;
+                };
+                if let Err(err) = cluster.warn_owner(record).await {
+                    warn!(instance_id = %id, error = %err, "failed to warn instance owner");
                 }
             }
-            tokio::time::sleep(interval).await;
         }
+    }
+
+    if let Err(err) = cluster.refill_pools(&all_pods).await {
+        warn!(error = %err, "failed to refill warm pools");
+    }
+
+    if let Err(err) = cluster.sync_wireguard(&all_pods).await {
+        warn!(error = %err, "failed to sync wireguard gateway");
+    }
+
+    Ok(())
+}
+
+/// Decides what to do with every observed pod and running record.
+fn plan(
+    pods: &[ObservedPod],
+    records: &HashMap<String, InstanceView>,
+    running: &[InstanceView],
+    now: i64,
+    warning_before: i64,
+) -> Vec<Action> {
+    let mut actions = Vec::new();
+    let mut handled = HashSet::new();
+
+    for pod in pods {
+        handled.insert(pod.id.as_str());
+        match records.get(&pod.id) {
+            None => actions.push(Action::Adopt(pod.id.clone())),
+            Some(record) if record.state == State::Terminated => {
+                actions.push(Action::DeleteOrphan(pod.id.clone()))
+            }
+            Some(_) if pod.finished => actions.push(Action::Terminate(
+                pod.id.clone(),
+                TerminationReason::Evicted,
+            )),
+            Some(_) => {}
+        }
+    }
+
+    for record in running {
+        let (deadline, reason) = lifetime::deadline(record);
+        if now > deadline {
+            actions.push(Action::Terminate(record.id.clone(), reason));
+        } else if !handled.contains(record.id.as_str())
+            && now > record.reset_at.unwrap_or(record.created_at) + POD_GRACE_SECONDS
+        {
+            actions.push(Action::Terminate(
+                record.id.clone(),
+                TerminationReason::PodLost,
+            ));
+        } else if lifetime::should_warn(record, warning_before, now) {
+            actions.push(Action::Warn(record.id.clone()));
+        }
+    }
+
+    // A pod both finished and expired is closed once, as evicted.
+    let mut terminated = HashSet::new();
+    actions.retain(|action| match action {
+        Action::Terminate(id, _) => terminated.insert(id.clone()),
+        _ => true,
     });
+
+    actions
+}
+
+/// Reads a challenge pod, skipping pods of other apps, unclaimed warm pool
+/// pods and pods already being deleted.
+fn observe(pod: Pod) -> Option<ObservedPod> {
+    if pod.metadata.deletion_timestamp.is_some() {
+        return None;
+    }
+
+    let labels = pod.metadata.labels.unwrap_or_default();
+    if labels.get("cds/pool").is_some_and(|pool| pool == "true") {
+        return None;
+    }
+    let id = labels.get("cds/instance_id").filter(|id| !id.is_empty())?;
+    let label = |key: &str| {
+        labels
+            .get(key)
+            .and_then(|value| value.parse::<i64>().ok())
+            .unwrap_or(0)
+    };
+
+    let annotations = pod.metadata.annotations.unwrap_or_default();
+    let annotation = |key: &str| {
+        annotations
+            .get(key)
+            .and_then(|value| value.parse::<i64>().ok())
+    };
+    let finished = pod
+        .status
+        .and_then(|status| status.phase)
+        .is_some_and(|phase| matches!(phase.as_str(), "Failed" | "Succeeded"));
+
+    Some(ObservedPod {
+        id: id.to_owned(),
+        finished,
+        adoption: NewInstance {
+            id: id.to_owned(),
+            user_id: label("cds/user_id"),
+            team_id: Some(label("cds/team_id")).filter(|id| *id != 0),
+            game_id: Some(label("cds/game_id")).filter(|id| *id != 0),
+            challenge_id: label("cds/challenge_id"),
+            ports: annotations
+                .get("cds/ports")
+                .and_then(|ports| serde_json::from_str::<Vec<Port>>(ports).ok())
+                .unwrap_or_default(),
+            renew: annotations
+                .get("cds/renew")
+                .and_then(|renew| renew.parse().ok())
+                .unwrap_or(0),
+            duration: annotations
+                .get("cds/duration")
+                .and_then(|duration| duration.parse().ok())
+                .unwrap_or(0),
+            created_at: pod
+                .metadata
+                .creation_timestamp
+                .map(|timestamp| timestamp.0.as_second())
+                .unwrap_or_else(util::now),
+            access_token: annotations.get("cds/access_token").cloned(),
+            max_renewals: annotation("cds/max_renewals").unwrap_or(3) as i32,
+            max_lifetime: annotation("cds/max_lifetime").unwrap_or(0),
+            idle_timeout: annotation("cds/idle_timeout").unwrap_or(0),
+            max_resets: annotation("cds/max_resets").unwrap_or(3) as i32,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use cds_db::{
+        InstanceView,
+        instance::{NewInstance, State, TerminationReason},
+    };
+
+    use super::{Action, ObservedPod, POD_GRACE_SECONDS, plan};
+
+    fn pod(id: &str, finished: bool) -> ObservedPod {
+        ObservedPod {
+            id: id.to_owned(),
+            finished,
+            adoption: NewInstance {
+                id: id.to_owned(),
+                user_id: 1,
+                team_id: None,
+                game_id: None,
+                challenge_id: 1,
+                ports: Vec::new(),
+                renew: 0,
+                duration: 600,
+                created_at: 0,
+                access_token: None,
+                max_renewals: 3,
+                max_lifetime: 0,
+                idle_timeout: 0,
+                max_resets: 3,
+            },
+        }
+    }
+
+    fn record(id: &str, state: State, created_at: i64, expires_at: i64) -> InstanceView {
+        InstanceView {
+            id: id.to_owned(),
+            user_id: 1,
+            team_id: None,
+            game_id: None,
+            challenge_id: 1,
+            ports: Vec::new(),
+            nats: Vec::new(),
+            state,
+            renew: 0,
+            duration: expires_at - created_at,
+            created_at,
+            renewed_at: None,
+            expires_at,
+            terminated_at: None,
+            termination_reason: None,
+            access_token: None,
+            max_renewals: 3,
+            max_expires_at: None,
+            idle_timeout: 0,
+            last_active_at: None,
+            warned_at: None,
+            resets: 0,
+            max_resets: 3,
+            reset_at: None,
+            checkpoints: Vec::new(),
+        }
+    }
+
+    fn index(records: &[InstanceView]) -> HashMap<String, InstanceView> {
+        records
+            .iter()
+            .map(|record| (record.id.clone(), record.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn expires_running_records() {
+        let running = vec![
+            record("old", State::Running, 0, 600),
+            record("new", State::Running, 500, 1100),
+        ];
+        let pods = vec![pod("old", false), pod("new", false)];
+        assert_eq!(
+            plan(&pods, &index(&running), &running, 700, 0),
+            vec![Action::Terminate(
+                "old".to_owned(),
+                TerminationReason::Expired
+            )]
+        );
+    }
+
+    #[test]
+    fn adopts_unrecorded_pods_and_deletes_orphans() {
+        let stopped = record("stopped", State::Terminated, 0, 600);
+        let pods = vec![pod("legacy", false), pod("stopped", false)];
+        assert_eq!(
+            plan(&pods, &index(&[stopped]), &[], 100, 0),
+            vec![
+                Action::Adopt("legacy".to_owned()),
+                Action::DeleteOrphan("stopped".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn closes_evicted_pods_once() {
+        let running = vec![record("evicted", State::Running, 0, 600)];
+        let pods = vec![pod("evicted", true)];
+        assert_eq!(
+            plan(&pods, &index(&running), &running, 700, 0),
+            vec![Action::Terminate(
+                "evicted".to_owned(),
+                TerminationReason::Evicted
+            )]
+        );
+    }
+
+    #[test]
+    fn closes_records_without_pods_after_grace() {
+        let running = vec![
+            record("lost", State::Running, 0, 6000),
+            record("starting", State::Running, 100, 6100),
+        ];
+        assert_eq!(
+            plan(&[], &index(&running), &running, 100 + POD_GRACE_SECONDS, 0),
+            vec![Action::Terminate(
+                "lost".to_owned(),
+                TerminationReason::PodLost
+            )]
+        );
+    }
+
+    #[test]
+    fn waits_for_reset_pods() {
+        let mut reset = record("reset", State::Running, 0, 6000);
+        reset.reset_at = Some(1000);
+        let running = vec![reset];
+        assert!(plan(&[], &index(&running), &running, 1000 + POD_GRACE_SECONDS, 0).is_empty());
+        assert_eq!(
+            plan(&[], &index(&running), &running, 1001 + POD_GRACE_SECONDS, 0),
+            vec![Action::Terminate(
+                "reset".to_owned(),
+                TerminationReason::PodLost
+            )]
+        );
+    }
+
+    #[test]
+    fn warns_before_stopping_idle_instances() {
+        let mut idle = record("idle", State::Running, 0, 6000);
+        idle.idle_timeout = 600;
+        let running = vec![idle];
+        let pods = vec![pod("idle", false)];
+
+        assert_eq!(
+            plan(&pods, &index(&running), &running, 400, 300),
+            vec![Action::Warn("idle".to_owned())]
+        );
+        assert_eq!(
+            plan(&pods, &index(&running), &running, 700, 300),
+            vec![Action::Terminate(
+                "idle".to_owned(),
+                TerminationReason::Idle
+            )]
+        );
+    }
 }
@@ -0,0 +1,76 @@
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+
+use crate::entity::api_token::Scope;
+
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
+)]
+pub struct ApiTokenView {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+    pub last_used_ip: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl ApiTokenView {
+    /// Parsed scopes; unknown values left behind by older releases are
+    /// ignored.
+    pub fn scopes(&self) -> Vec<Scope> {
+        self.scopes.iter().filter_map(|s| s.parse().ok()).collect()
+    }
+
+    /// Returns whether the token can no longer be used at `now`.
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiTokenView;
+    use crate::entity::api_token::Scope;
+
+    fn token(scopes: &[&str], expires_at: Option<i64>) -> ApiTokenView {
+        ApiTokenView {
+            id: 1,
+            user_id: 2,
+            name: "ci".to_owned(),
+            prefix: "abcd1234".to_owned(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            expires_at,
+            last_used_at: None,
+            last_used_ip: None,
+            created_at: 3,
+            updated_at: 4,
+        }
+    }
+
+    #[test]
+    fn scopes_skip_unknown_values() {
+        assert_eq!(
+            token(&["submit", "legacy", "instance"], None).scopes(),
+            vec![Scope::Submit, Scope::Instance]
+        );
+    }
+
+    #[test]
+    fn expiry_is_inclusive_and_optional() {
+        assert!(!token(&[], None).is_expired(i64::MAX));
+        assert!(!token(&[], Some(10)).is_expired(9));
+        assert!(token(&[], Some(10)).is_expired(10));
+    }
+
+    #[test]
+    fn token_json_never_contains_hash() {
+        let value = serde_json::to_value(token(&["read_only"], None)).unwrap();
+        assert!(value.get("token_hash").is_none());
+        assert_eq!(value["prefix"], "abcd1234");
+    }
+}
@@ -1,7 +1,7 @@
 use sea_orm::FromQueryResult;
 use serde::{Deserialize, Serialize};
 
-use crate::entity::challenge::Instance;
+use crate::entity::challenge::{CheckerConfig, Instance};
 
 #[derive(
     Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
@@ -18,6 +18,9 @@ pub struct ChallengeDetail {
     pub has_writeup: bool,
     pub instance: Option<Instance>,
     pub checker: Option<String>,
+    pub checker_config: CheckerConfig,
+    /// Hosts the checker may reach over HTTP, including internal ones.
+    pub allowed_hosts: Vec<String>,
     pub writeup: Option<String>,
     pub deleted_at: Option<i64>,
     pub created_at: i64,
@@ -70,7 +73,7 @@ pub struct ChallengeSummary {
 #[cfg(test)]
 mod tests {
     use super::{ChallengeDetail, ChallengeView};
-    use crate::entity::challenge::Instance;
+    use crate::entity::challenge::{CheckerConfig, Instance};
 
     #[test]
     fn desensitize_removes_private_challenge_material() {
@@ -86,6 +89,12 @@ mod tests {
             has_writeup: true,
             instance: Some(Instance::default()),
             checker: Some("checker".to_owned()),
+            checker_config: CheckerConfig::Webhook {
+                url: "https://checker.example.com".to_owned(),
+                secret: "secret".to_owned(),
+                timeout_ms: 1000,
+            },
+            allowed_hosts: vec![],
             writeup: Some("writeup".to_owned()),
             deleted_at: None,
             created_at: 1,
@@ -95,6 +104,7 @@ mod tests {
         let value = serde_json::to_value(ChallengeView::from(&challenge)).unwrap();
         assert!(value.get("instance").is_none());
         assert!(value.get("checker").is_none());
+        assert!(value.get("checker_config").is_none());
         assert!(value.get("public").is_none());
         assert!(value.get("deleted_at").is_none());
         assert_eq!(value["writeup"], serde_json::Value::Null);
@@ -0,0 +1,16 @@
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
+)]
+pub struct CheckerLogView {
+    pub id: i64,
+    pub submission_id: i64,
+    pub challenge_id: i64,
+    /// Engine execution trace: log lines, outbound requests, return value,
+    /// error span, instruction count and duration.
+    #[schema(value_type = Object)]
+    pub trace: serde_json::Value,
+    pub created_at: i64,
+}
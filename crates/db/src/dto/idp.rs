@@ -12,6 +12,8 @@ pub struct IdpView {
     pub avatar_hash: Option<String>,
     pub portal: Option<String>,
     pub script: String,
+    /// Hosts the script may reach over HTTP, including internal ones.
+    pub allowed_hosts: Vec<String>,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -51,6 +53,7 @@ mod tests {
             avatar_hash: None,
             portal: Some("https://example.com".to_owned()),
             script: "secret provider script".to_owned(),
+            allowed_hosts: vec![],
             created_at: 2,
             updated_at: 3,
         }))
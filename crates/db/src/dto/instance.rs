@@ -0,0 +1,50 @@
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+
+use crate::entity::{
+    challenge::Port,
+    instance::{Checkpoint, Nat, State, TerminationReason},
+};
+
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
+)]
+pub struct InstanceView {
+    pub id: String,
+    pub user_id: i64,
+    pub team_id: Option<i64>,
+    pub game_id: Option<i64>,
+    pub challenge_id: i64,
+    pub ports: Vec<Port>,
+    pub nats: Vec<Nat>,
+    pub state: State,
+    /// Renewals granted so far.
+    pub renew: i32,
+    /// Seconds of lifetime granted by each start or renewal.
+    pub duration: i64,
+    pub created_at: i64,
+    pub renewed_at: Option<i64>,
+    pub expires_at: i64,
+    pub terminated_at: Option<i64>,
+    pub termination_reason: Option<TerminationReason>,
+    /// Required to open the instance through the ingress host, when set.
+    pub access_token: Option<String>,
+    /// Renewals the owner may ask for.
+    pub max_renewals: i32,
+    /// No renewal extends the instance past this time.
+    pub max_expires_at: Option<i64>,
+    /// Seconds without traffic before the instance is stopped, `0` for never.
+    pub idle_timeout: i64,
+    /// Last time proxied traffic was seen, if ever.
+    pub last_active_at: Option<i64>,
+    /// When the owner was last warned of the coming shutdown.
+    pub warned_at: Option<i64>,
+    /// Resets and checkpoint restores done so far.
+    pub resets: i32,
+    /// Resets the owner may ask for.
+    pub max_resets: i32,
+    /// When the instance was last reset or restored.
+    pub reset_at: Option<i64>,
+    /// Checkpoints saved in the instance's containers, oldest first.
+    pub checkpoints: Vec<Checkpoint>,
+}
@@ -0,0 +1,17 @@
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
+)]
+pub struct LuaLibraryView {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub script: String,
+    /// Incremented on every script change; cached scripts built against an
+    /// older version are rebuilt.
+    pub version: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
@@ -4,32 +4,48 @@
 //! describe storage and relation graphs; DTOs describe the data a caller is
 //! allowed to consume.
 
+pub mod api_token;
 pub mod challenge;
+pub mod checker_log;
 pub mod config;
 pub mod email;
 pub mod game;
 pub mod game_challenge;
 pub mod game_notice;
 pub mod idp;
+pub mod instance;
+pub mod lua_library;
 pub mod note;
+pub mod rejudge;
+pub mod role_assignment;
 pub mod scoreboard;
+pub mod script_revision;
 pub mod submission;
 pub mod team;
 pub mod team_user;
 pub mod user;
 pub mod user_idp;
+pub mod wireguard_peer;
 
+pub use api_token::ApiTokenView;
 pub use challenge::{ChallengeDetail, ChallengeSummary, ChallengeView};
+pub use checker_log::CheckerLogView;
 pub use config::{PublicCaptchaConfig, PublicCaptchaSiteConfig, PublicConfig, PublicEmailConfig};
 pub use email::EmailView;
 pub use game::{GameDetail, GameSummary, GameView};
 pub use game_challenge::{GameChallengeSummary, GameChallengeView};
 pub use game_notice::GameNoticeView;
 pub use idp::{IdpSummary, IdpView};
+pub use instance::InstanceView;
+pub use lua_library::LuaLibraryView;
 pub use note::NoteView;
+pub use rejudge::{RejudgeEntryView, RejudgeJobView};
+pub use role_assignment::RoleAssignmentView;
 pub use scoreboard::{ScoreboardEntry, ScoreboardSubmission, ScoreboardTeam};
+pub use script_revision::{ScriptRevisionSummary, ScriptRevisionView};
 pub use submission::{SubmissionSummary, SubmissionView};
 pub use team::{PlayerTeamView, TeamView};
-pub use team_user::TeamUserView;
+pub use team_user::{TeamRosterEntry, TeamUserView};
 pub use user::{UserAccountView, UserProfile, UserSummary};
 pub use user_idp::{UserIdpSummary, UserIdpView};
+pub use wireguard_peer::WireguardPeerView;
@@ -0,0 +1,109 @@
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+
+use crate::entity::{rejudge_job::State, submission::Status};
+
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
+)]
+pub struct RejudgeJobView {
+    pub id: i64,
+    pub game_id: i64,
+    pub challenge_id: i64,
+    /// Only submissions that had this status were re-judged.
+    pub status_filter: Option<Status>,
+    /// Inclusive submission `created_at` bounds of the job.
+    pub created_from: Option<i64>,
+    pub created_until: Option<i64>,
+    pub state: State,
+    pub total: i32,
+    pub processed: i32,
+    /// Re-judged submissions whose status, flag or credit changed.
+    pub changed: i32,
+    pub author_id: Option<i64>,
+    pub created_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+/// One re-judged submission with its verdict before and after.
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
+)]
+pub struct RejudgeEntryView {
+    pub id: i64,
+    pub job_id: i64,
+    pub submission_id: i64,
+    pub user_id: i64,
+    pub team_id: Option<i64>,
+    pub created_at: i64,
+    pub previous_status: Status,
+    pub previous_flag_id: Option<String>,
+    pub previous_credit: i32,
+    pub previous_checked_at: Option<i64>,
+    /// Absent until the checker has run again.
+    pub status: Option<Status>,
+    pub flag_id: Option<String>,
+    pub credit: Option<i32>,
+    pub finished_at: Option<i64>,
+}
+
+impl RejudgeEntryView {
+    /// Whether the new verdict differs from the one it replaced.
+    pub fn changed(&self) -> bool {
+        self.status.as_ref().is_some_and(|status| {
+            status != &self.previous_status
+                || self.flag_id != self.previous_flag_id
+                || self.credit != Some(self.previous_credit)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RejudgeEntryView, Status};
+
+    #[test]
+    fn entry_changes_only_count_once_judged() {
+        let entry = RejudgeEntryView {
+            id: 1,
+            job_id: 2,
+            submission_id: 3,
+            user_id: 4,
+            team_id: Some(5),
+            created_at: 1_700_000_000,
+            previous_status: Status::Correct,
+            previous_flag_id: None,
+            previous_credit: 100,
+            previous_checked_at: Some(1_700_000_001),
+            status: None,
+            flag_id: None,
+            credit: None,
+            finished_at: None,
+        };
+        assert!(!entry.changed());
+        assert!(
+            !RejudgeEntryView {
+                status: Some(Status::Correct),
+                credit: Some(100),
+                ..entry.clone()
+            }
+            .changed()
+        );
+        assert!(
+            RejudgeEntryView {
+                status: Some(Status::Correct),
+                credit: Some(50),
+                ..entry.clone()
+            }
+            .changed()
+        );
+        assert!(
+            RejudgeEntryView {
+                status: Some(Status::Incorrect),
+                credit: Some(100),
+                ..entry
+            }
+            .changed()
+        );
+    }
+}
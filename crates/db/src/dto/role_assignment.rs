@@ -0,0 +1,119 @@
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+
+use crate::entity::role_assignment::Role;
+
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
+)]
+pub struct RoleAssignmentView {
+    pub id: i64,
+    pub user_id: i64,
+    pub role: Role,
+    pub game_id: Option<i64>,
+    pub challenge_id: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Action on the admin surface that a non-admin user may be granted through a
+/// [`Role`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    /// Read admin views, either platform-wide (`None`) or of one game.
+    View { game_id: Option<i64> },
+    /// Read and edit one challenge, its checker, writeup and attachments.
+    ManageChallenge { challenge_id: i64 },
+    /// Manage teams and notices of one game.
+    ManageGame { game_id: i64 },
+}
+
+impl RoleAssignmentView {
+    /// Returns whether this assignment grants `permission`.
+    pub fn grants(&self, permission: Permission) -> bool {
+        match (self.role, permission) {
+            (Role::Observer, Permission::View { game_id }) => {
+                self.game_id.is_none() || self.game_id == game_id
+            }
+            (Role::ChallengeAuthor, Permission::ManageChallenge { challenge_id }) => {
+                self.challenge_id == Some(challenge_id)
+            }
+            (Role::GameOperator, Permission::ManageGame { game_id })
+            | (
+                Role::GameOperator,
+                Permission::View {
+                    game_id: Some(game_id),
+                },
+            ) => self.game_id == Some(game_id),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the role carries the scope column it requires.
+    pub fn is_well_scoped(&self) -> bool {
+        match self.role {
+            Role::ChallengeAuthor => self.challenge_id.is_some() && self.game_id.is_none(),
+            Role::GameOperator => self.game_id.is_some() && self.challenge_id.is_none(),
+            Role::Observer => self.challenge_id.is_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Permission, RoleAssignmentView};
+    use crate::entity::role_assignment::Role;
+
+    fn assignment(
+        role: Role,
+        game_id: Option<i64>,
+        challenge_id: Option<i64>,
+    ) -> RoleAssignmentView {
+        RoleAssignmentView {
+            id: 1,
+            user_id: 2,
+            role,
+            game_id,
+            challenge_id,
+            created_at: 3,
+            updated_at: 4,
+        }
+    }
+
+    #[test]
+    fn observers_read_within_their_scope_only() {
+        let global = assignment(Role::Observer, None, None);
+        assert!(global.grants(Permission::View { game_id: None }));
+        assert!(global.grants(Permission::View { game_id: Some(7) }));
+        assert!(!global.grants(Permission::ManageGame { game_id: 7 }));
+
+        let scoped = assignment(Role::Observer, Some(7), None);
+        assert!(scoped.grants(Permission::View { game_id: Some(7) }));
+        assert!(!scoped.grants(Permission::View { game_id: Some(8) }));
+        assert!(!scoped.grants(Permission::View { game_id: None }));
+    }
+
+    #[test]
+    fn authors_and_operators_are_bound_to_one_resource() {
+        let author = assignment(Role::ChallengeAuthor, None, Some(5));
+        assert!(author.grants(Permission::ManageChallenge { challenge_id: 5 }));
+        assert!(!author.grants(Permission::ManageChallenge { challenge_id: 6 }));
+        assert!(!author.grants(Permission::View { game_id: None }));
+
+        let operator = assignment(Role::GameOperator, Some(7), None);
+        assert!(operator.grants(Permission::ManageGame { game_id: 7 }));
+        assert!(operator.grants(Permission::View { game_id: Some(7) }));
+        assert!(!operator.grants(Permission::ManageGame { game_id: 8 }));
+        assert!(!operator.grants(Permission::View { game_id: None }));
+    }
+
+    #[test]
+    fn scope_columns_must_match_role() {
+        assert!(assignment(Role::ChallengeAuthor, None, Some(1)).is_well_scoped());
+        assert!(!assignment(Role::ChallengeAuthor, Some(1), None).is_well_scoped());
+        assert!(assignment(Role::GameOperator, Some(1), None).is_well_scoped());
+        assert!(!assignment(Role::GameOperator, None, None).is_well_scoped());
+        assert!(assignment(Role::Observer, None, None).is_well_scoped());
+        assert!(!assignment(Role::Observer, None, Some(1)).is_well_scoped());
+    }
+}
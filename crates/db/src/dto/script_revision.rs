@@ -0,0 +1,36 @@
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+
+/// A saved script revision without its source, for history listings.
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
+)]
+pub struct ScriptRevisionSummary {
+    pub id: i64,
+    /// Sequence number within the checker's or IdP's history, from 1.
+    pub number: i32,
+    pub author_id: Option<i64>,
+    /// Revision this one was rolled back to, when created by a rollback.
+    pub restored_from: Option<i32>,
+    /// Lint markers recorded when the revision was saved.
+    #[schema(value_type = Vec<Object>)]
+    pub markers: serde_json::Value,
+    pub created_at: i64,
+}
+
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
+)]
+pub struct ScriptRevisionView {
+    pub id: i64,
+    pub challenge_id: Option<i64>,
+    pub idp_id: Option<i64>,
+    pub number: i32,
+    pub script: String,
+    pub allowed_hosts: Vec<String>,
+    pub author_id: Option<i64>,
+    pub restored_from: Option<i32>,
+    #[schema(value_type = Vec<Object>)]
+    pub markers: serde_json::Value,
+    pub created_at: i64,
+}
@@ -26,6 +26,12 @@ pub struct SubmissionView {
     pub checked_at: Option<i64>,
     pub pts: i64,
     pub rank: i64,
+    /// Checker message for the submitting player.
+    pub feedback: Option<String>,
+    /// Which of the challenge's independent flags this submission solved.
+    pub flag_id: Option<String>,
+    /// Percent of the flag's points awarded when correct.
+    pub credit: i32,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
@@ -48,6 +54,11 @@ pub struct SubmissionSummary {
     pub checked_at: Option<i64>,
     pub pts: i64,
     pub rank: i64,
+    pub flag_id: Option<String>,
+    pub credit: i32,
+    /// Only present when the summary is built for the submitter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback: Option<String>,
 }
 
 impl From<&SubmissionView> for SubmissionSummary {
@@ -71,6 +82,20 @@ impl From<&SubmissionView> for SubmissionSummary {
             checked_at: submission.checked_at,
             pts: submission.pts,
             rank: submission.rank,
+            flag_id: submission.flag_id.clone(),
+            credit: submission.credit,
+            feedback: None,
+        }
+    }
+}
+
+impl SubmissionSummary {
+    /// Builds the summary shown to the submitter, which includes the checker
+    /// feedback other players must not see.
+    pub fn for_submitter(submission: &SubmissionView) -> Self {
+        Self {
+            feedback: submission.feedback.clone(),
+            ..Self::from(submission)
         }
     }
 }
@@ -101,10 +126,18 @@ mod tests {
             checked_at: Some(1_700_000_003),
             pts: 100,
             rank: 1,
+            feedback: Some("close".to_owned()),
+            flag_id: None,
+            credit: 100,
         };
 
         let value = serde_json::to_value(SubmissionSummary::from(&submission)).unwrap();
         assert!(value.get("content").is_none());
+        assert!(value.get("feedback").is_none());
+        assert_eq!(
+            serde_json::to_value(SubmissionSummary::for_submitter(&submission)).unwrap()["feedback"],
+            "close"
+        );
         assert_eq!(value["team_id"], 3);
         assert_eq!(value["game_id"], 4);
         assert_eq!(value["processing_at"], 1_700_000_001_i64);
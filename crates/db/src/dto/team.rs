@@ -14,6 +14,7 @@ pub struct TeamView {
     pub slogan: Option<String>,
     pub avatar_hash: Option<String>,
     pub has_writeup: bool,
+    pub division: Option<String>,
     pub state: State,
     pub pts: i64,
     pub rank: i64,
@@ -30,6 +31,7 @@ pub struct PlayerTeamView {
     pub slogan: Option<String>,
     pub avatar_hash: Option<String>,
     pub has_writeup: bool,
+    pub division: Option<String>,
     pub state: State,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pts: Option<i64>,
@@ -47,6 +49,7 @@ impl PlayerTeamView {
             slogan: team.slogan,
             avatar_hash: team.avatar_hash,
             has_writeup: team.has_writeup,
+            division: team.division,
             state: team.state,
             pts: (!blacked_out).then_some(team.pts),
             rank: (!blacked_out).then_some(team.rank),
@@ -80,6 +83,7 @@ mod tests {
             slogan: None,
             avatar_hash: None,
             has_writeup: false,
+            division: None,
             state: State::Passed,
             pts: 500,
             rank: 3,
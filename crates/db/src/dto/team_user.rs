@@ -1,6 +1,8 @@
 use sea_orm::FromQueryResult;
 use serde::{Deserialize, Serialize};
 
+use crate::entity::team::State;
+
 #[derive(
     Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
 )]
@@ -8,3 +10,20 @@ pub struct TeamUserView {
     pub team_id: i64,
     pub user_id: i64,
 }
+
+/// One member of one team in a game, flattened for roster exports.
+#[derive(
+    Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult, utoipa::ToSchema,
+)]
+pub struct TeamRosterEntry {
+    pub team_id: i64,
+    pub team_name: String,
+    pub division: Option<String>,
+    pub state: State,
+    pub pts: i64,
+    pub rank: i64,
+    pub user_id: i64,
+    pub username: String,
+    pub user_name: String,
+    pub email: Option<String>,
+}
@@ -0,0 +1,17 @@
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromQueryResult)]
+pub struct WireguardPeerView {
+    pub id: i64,
+    pub game_id: i64,
+    pub team_id: i64,
+    /// Tunnel address of the peer, without prefix length.
+    pub address: String,
+    /// Only handed to the team's members, inside their config.
+    #[serde(skip)]
+    pub private_key: String,
+    pub public_key: String,
+    pub created_at: i64,
+    pub revoked_at: Option<i64>,
+}
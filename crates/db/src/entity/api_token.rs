@@ -0,0 +1,99 @@
+//! SeaORM `api_token` entity — maps personal API tokens owned by local users.
+
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use sea_orm::{Set, entity::prelude::*};
+use serde::{Deserialize, Serialize};
+
+#[sea_orm::model]
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "api_tokens")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub prefix: String,
+    #[sea_orm(unique)]
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+    pub last_used_ip: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    #[sea_orm(belongs_to, from = "user_id", to = "id", on_delete = "Cascade")]
+    pub user: BelongsTo<super::user::Entity>,
+}
+
+/// Capability granted to a personal API token.
+///
+/// Every scope implies [`Scope::ReadOnly`]; [`Scope::Admin`] only takes effect
+/// for users whose group is Admin.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    ReadOnly,
+    Submit,
+    Instance,
+    Admin,
+}
+
+impl Scope {
+    /// Stable string stored in the `scopes` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ReadOnly => "read_only",
+            Self::Submit => "submit",
+            Self::Instance => "instance",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read_only" => Ok(Self::ReadOnly),
+            "submit" => Ok(Self::Submit),
+            "instance" => Ok(Self::Instance),
+            "admin" => Ok(Self::Admin),
+            _ => Err(()),
+        }
+    }
+}
+
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait, {
+        let ts = time::OffsetDateTime::now_utc().unix_timestamp();
+        self.updated_at = Set(ts);
+        if insert {
+            self.created_at = Set(ts);
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Iterable;
+
+    use super::Scope;
+
+    #[test]
+    fn scope_column_values_match_serde_names() {
+        for scope in Scope::iter() {
+            assert_eq!(serde_json::to_value(scope).unwrap(), scope.as_str());
+            assert_eq!(scope.as_str().parse::<Scope>(), Ok(scope));
+        }
+        assert!("everything".parse::<Scope>().is_err());
+    }
+}
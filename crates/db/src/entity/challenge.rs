@@ -1,5 +1,7 @@
 //! SeaORM `challenge` entity — maps the `challenge` table and its relations.
 
+use std::collections::BTreeMap;
+
 use async_trait::async_trait;
 use sea_orm::{
     ActiveModelBehavior, ConnectionTrait, DbErr, EntityTrait, FromJsonQueryResult, Set,
@@ -26,6 +28,9 @@ pub struct Model {
     pub instance: Option<Instance>,
     #[sea_orm(column_type = "Text")]
     pub checker: Option<String>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub checker_config: CheckerConfig,
+    pub allowed_hosts: Vec<String>,
     #[sea_orm(column_type = "Text")]
     pub writeup: Option<String>,
     pub deleted_at: Option<i64>,
@@ -39,6 +44,40 @@ pub struct Model {
     pub games: HasMany<super::game::Entity>,
 }
 
+/// How submissions to a challenge are judged. Every kind but `lua` is
+/// evaluated natively, without the script engine.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    FromJsonQueryResult,
+    utoipa::ToSchema,
+)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CheckerConfig {
+    /// The challenge's `checker` script.
+    #[default]
+    Lua,
+    /// Accepts any of the flags verbatim, ignoring surrounding whitespace.
+    Static { flags: Vec<String> },
+    /// Accepts submissions the pattern matches in full.
+    Regex { pattern: String },
+    /// Accepts any of the flags regardless of case, ignoring whitespace around
+    /// the submission and inside a `prefix{...}` wrapper.
+    CaseInsensitive { flags: Vec<String> },
+    /// Posts the submission to an author-run service that returns the
+    /// verdict, signed with HMAC-SHA256 over `secret`.
+    Webhook {
+        url: String,
+        secret: String,
+        timeout_ms: u64,
+    },
+}
+
 #[derive(
     Clone,
     Debug,
@@ -54,6 +93,33 @@ pub struct Instance {
     pub duration: i64,
     pub internet: bool,
     pub containers: Vec<Container>,
+    /// RuntimeClass for the pod (e.g. `gvisor`), when the platform does not
+    /// force one.
+    #[serde(default)]
+    pub runtime_class_name: Option<String>,
+    /// Node labels the pod must be scheduled on, on top of the platform's.
+    #[serde(default)]
+    pub node_selector: BTreeMap<String, String>,
+    /// Taints the pod tolerates; keys must be allowed by the platform.
+    #[serde(default)]
+    pub tolerations: Vec<Toleration>,
+    /// Pre-started instances kept ready to hand out.
+    #[serde(default)]
+    pub warm_pool: i32,
+    /// Renewals an owner may ask for; the platform's when unset.
+    #[serde(default)]
+    pub max_renewals: Option<i32>,
+    /// Longest the instance may run in seconds, renewals included; the
+    /// platform's when unset and unlimited when `0`.
+    #[serde(default)]
+    pub max_lifetime: Option<i64>,
+    /// Seconds without traffic after which the instance is stopped; the
+    /// platform's when unset and never when `0`.
+    #[serde(default)]
+    pub idle_timeout: Option<i64>,
+    /// Resets an owner may ask for; the platform's when unset.
+    #[serde(default)]
+    pub max_resets: Option<i32>,
 }
 
 #[derive(
@@ -75,6 +141,41 @@ pub struct Container {
     pub envs: Vec<EnvVar>,
     #[serde(default = "default_image_pull_policy")]
     pub image_pull_policy: String,
+    /// Ephemeral storage limit in MiB, at most the platform's.
+    #[serde(default)]
+    pub ephemeral_storage_limit: Option<i64>,
+    #[serde(default)]
+    pub read_only_root_filesystem: bool,
+    #[serde(default)]
+    pub run_as_non_root: bool,
+    /// Capabilities dropped on top of the platform's (`ALL` drops every one).
+    #[serde(default)]
+    pub drop_capabilities: Vec<String>,
+    /// `runtime_default` or `localhost/<profile>`; the platform's when unset.
+    #[serde(default)]
+    pub seccomp_profile: Option<String>,
+    /// Commands saving and restoring named checkpoints, for images that
+    /// support them.
+    #[serde(default)]
+    pub checkpoint: Option<CheckpointCommands>,
+}
+
+/// Commands run in a container with the checkpoint name appended; exiting
+/// non-zero fails the operation.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    FromJsonQueryResult,
+    utoipa::ToSchema,
+)]
+pub struct CheckpointCommands {
+    pub save: Vec<String>,
+    pub restore: Vec<String>,
 }
 
 /// Default Kubernetes `imagePullPolicy` when unspecified.
@@ -82,6 +183,28 @@ fn default_image_pull_policy() -> String {
     "Always".to_string()
 }
 
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    FromJsonQueryResult,
+    utoipa::ToSchema,
+)]
+#[serde(default)]
+pub struct Toleration {
+    pub key: String,
+    /// `Equal` or `Exists`.
+    pub operator: String,
+    pub value: String,
+    /// `NoSchedule`, `PreferNoSchedule` or `NoExecute`; every effect when
+    /// empty.
+    pub effect: String,
+}
+
 #[derive(
     Clone,
     Debug,
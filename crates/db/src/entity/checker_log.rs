@@ -0,0 +1,31 @@
+//! SeaORM `checker_log` entity — stores the execution trace of each checker
+//! run against a submission.
+
+use async_trait::async_trait;
+use sea_orm::{Set, entity::prelude::*};
+use serde::{Deserialize, Serialize};
+
+#[sea_orm::model]
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "checker_logs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub submission_id: i64,
+    pub challenge_id: i64,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub trace: Json,
+    pub created_at: i64,
+}
+
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait, {
+        if insert {
+            self.created_at = Set(time::OffsetDateTime::now_utc().unix_timestamp());
+        }
+        Ok(self)
+    }
+}
@@ -28,7 +28,8 @@ impl ActiveModelBehavior for ActiveModel {
         C: ConnectionTrait, {
         let ts = time::OffsetDateTime::now_utc().unix_timestamp();
 
-        if insert {
+        // Restored rows arrive with their original creation time.
+        if insert && self.created_at.is_not_set() {
             self.created_at = Set(ts);
         }
 
@@ -17,6 +17,7 @@ pub struct Model {
     pub portal: Option<String>,
     #[sea_orm(column_type = "Text")]
     pub script: String,
+    pub allowed_hosts: Vec<String>,
     pub created_at: i64,
     pub updated_at: i64,
     #[sea_orm(has_many)]
@@ -0,0 +1,140 @@
+//! SeaORM `instance` entity — one challenge instance started by the cluster
+//! driver, kept after its pod is gone.
+
+use sea_orm::{FromJsonQueryResult, entity::prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::challenge::Port;
+
+#[sea_orm::model]
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "instances")]
+pub struct Model {
+    /// The `cds/instance_id` label of the instance's pod and service.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub user_id: i64,
+    pub team_id: Option<i64>,
+    pub game_id: Option<i64>,
+    pub challenge_id: i64,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub ports: Vec<Port>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub nats: Vec<Nat>,
+    pub state: State,
+    pub renew: i32,
+    pub duration: i64,
+    pub created_at: i64,
+    pub renewed_at: Option<i64>,
+    pub expires_at: i64,
+    pub terminated_at: Option<i64>,
+    pub termination_reason: Option<TerminationReason>,
+    pub access_token: Option<String>,
+    pub max_renewals: i32,
+    pub max_expires_at: Option<i64>,
+    pub idle_timeout: i64,
+    pub last_active_at: Option<i64>,
+    pub warned_at: Option<i64>,
+    pub resets: i32,
+    pub max_resets: i32,
+    pub reset_at: Option<i64>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    EnumIter,
+    DeriveActiveEnum,
+    utoipa::ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    #[default]
+    #[sea_orm(string_value = "running")]
+    Running,
+    #[sea_orm(string_value = "terminated")]
+    Terminated,
+}
+
+/// Why an instance stopped running.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    EnumIter,
+    DeriveActiveEnum,
+    utoipa::ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+#[serde(rename_all = "snake_case")]
+pub enum TerminationReason {
+    /// Stopped by its owner.
+    #[sea_orm(string_value = "stopped")]
+    Stopped,
+    /// Stopped by an admin.
+    #[sea_orm(string_value = "admin_stopped")]
+    AdminStopped,
+    /// Reached the end of its lifetime.
+    #[sea_orm(string_value = "expired")]
+    Expired,
+    /// The pod or service could not be created.
+    #[sea_orm(string_value = "create_failed")]
+    CreateFailed,
+    /// The pod failed or was evicted by Kubernetes.
+    #[sea_orm(string_value = "evicted")]
+    Evicted,
+    /// The pod disappeared without the platform deleting it.
+    #[sea_orm(string_value = "pod_lost")]
+    PodLost,
+    /// Saw no traffic for its idle timeout.
+    #[sea_orm(string_value = "idle")]
+    Idle,
+}
+
+/// A service port reachable on the cluster nodes.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    FromJsonQueryResult,
+    utoipa::ToSchema,
+)]
+pub struct Nat {
+    pub port: i32,
+    pub node_port: i32,
+    pub protocol: String,
+}
+
+/// A named state saved inside the instance's containers.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    FromJsonQueryResult,
+    utoipa::ToSchema,
+)]
+pub struct Checkpoint {
+    pub name: String,
+    pub created_at: i64,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,37 @@
+//! SeaORM `lua_library` entity — maps shared Lua libraries that checker and
+//! IdP scripts load with `require("lib/<name>")`.
+
+use async_trait::async_trait;
+use sea_orm::{Set, entity::prelude::*};
+use serde::{Deserialize, Serialize};
+
+#[sea_orm::model]
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "lua_libraries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(unique)]
+    pub name: String,
+    #[sea_orm(column_type = "Text")]
+    pub description: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub script: String,
+    pub version: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait, {
+        let ts = time::OffsetDateTime::now_utc().unix_timestamp();
+        self.updated_at = Set(ts);
+        if insert {
+            self.created_at = Set(ts);
+        }
+        Ok(self)
+    }
+}
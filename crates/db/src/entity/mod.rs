@@ -1,8 +1,14 @@
 //! SeaORM `mod` entity — maps the `mod` table and its relations.
 
+/// Defines the `api_token` submodule (see sibling `*.rs` files).
+pub mod api_token;
+
 /// Defines the `challenge` submodule (see sibling `*.rs` files).
 pub mod challenge;
 
+/// Defines the `checker_log` submodule (see sibling `*.rs` files).
+pub mod checker_log;
+
 /// Defines the `config` submodule (see sibling `*.rs` files).
 pub mod config;
 
@@ -21,9 +27,27 @@ pub mod game_notice;
 /// Defines the `idp` submodule (see sibling `*.rs` files).
 pub mod idp;
 
+/// Defines the `instance` submodule (see sibling `*.rs` files).
+pub mod instance;
+
+/// Defines the `lua_library` submodule (see sibling `*.rs` files).
+pub mod lua_library;
+
 /// Defines the `note` submodule (see sibling `*.rs` files).
 pub mod note;
 
+/// Defines the `rejudge_entry` submodule (see sibling `*.rs` files).
+pub mod rejudge_entry;
+
+/// Defines the `rejudge_job` submodule (see sibling `*.rs` files).
+pub mod rejudge_job;
+
+/// Defines the `role_assignment` submodule (see sibling `*.rs` files).
+pub mod role_assignment;
+
+/// Defines the `script_revision` submodule (see sibling `*.rs` files).
+pub mod script_revision;
+
 /// Defines the `submission` submodule (see sibling `*.rs` files).
 pub mod submission;
 
@@ -38,3 +62,9 @@ pub mod user_idp;
 
 /// Defines the `user` submodule (see sibling `*.rs` files).
 pub mod user;
+
+/// Defines the `wireguard_gateway` submodule (see sibling `*.rs` files).
+pub mod wireguard_gateway;
+
+/// Defines the `wireguard_peer` submodule (see sibling `*.rs` files).
+pub mod wireguard_peer;
@@ -0,0 +1,29 @@
+//! SeaORM `rejudge_entry` entity — one submission re-queued by a re-judge job,
+//! with the verdict it had before and the one it received.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::submission::Status;
+
+#[sea_orm::model]
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "rejudge_entries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub job_id: i64,
+    pub submission_id: i64,
+    pub previous_status: Status,
+    pub previous_flag_id: Option<String>,
+    pub previous_credit: i32,
+    pub previous_checked_at: Option<i64>,
+    pub status: Option<Status>,
+    pub flag_id: Option<String>,
+    pub credit: Option<i32>,
+    pub finished_at: Option<i64>,
+    #[sea_orm(belongs_to, from = "submission_id", to = "id", on_delete = "Cascade")]
+    pub submission: BelongsTo<super::submission::Entity>,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
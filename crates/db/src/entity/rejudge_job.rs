@@ -0,0 +1,62 @@
+//! SeaORM `rejudge_job` entity — an admin request to re-check a game's
+//! submissions for one challenge with the current checker.
+
+use async_trait::async_trait;
+use sea_orm::{Set, entity::prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::submission::Status;
+
+#[sea_orm::model]
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "rejudge_jobs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub game_id: i64,
+    pub challenge_id: i64,
+    pub status_filter: Option<Status>,
+    pub created_from: Option<i64>,
+    pub created_until: Option<i64>,
+    pub state: State,
+    pub total: i32,
+    pub processed: i32,
+    pub changed: i32,
+    pub author_id: Option<i64>,
+    pub created_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    EnumIter,
+    DeriveActiveEnum,
+    utoipa::ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    #[default]
+    #[sea_orm(string_value = "running")]
+    Running,
+    #[sea_orm(string_value = "completed")]
+    Completed,
+}
+
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait, {
+        if insert {
+            self.created_at = Set(time::OffsetDateTime::now_utc().unix_timestamp());
+        }
+        Ok(self)
+    }
+}
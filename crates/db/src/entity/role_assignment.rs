@@ -0,0 +1,64 @@
+//! SeaORM `role_assignment` entity — grants scoped administrative roles to
+//! users below the Admin group.
+
+use async_trait::async_trait;
+use sea_orm::{DeriveActiveEnum, EnumIter, Set, entity::prelude::*};
+use serde::{Deserialize, Serialize};
+
+#[sea_orm::model]
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "role_assignments")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub user_id: i64,
+    pub role: Role,
+    pub game_id: Option<i64>,
+    pub challenge_id: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    #[sea_orm(belongs_to, from = "user_id", to = "id", on_delete = "Cascade")]
+    pub user: BelongsTo<super::user::Entity>,
+}
+
+/// Scoped role. The scope column each role requires is noted per variant.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    EnumIter,
+    DeriveActiveEnum,
+    utoipa::ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Edits one challenge (`challenge_id`), including its checker.
+    #[sea_orm(string_value = "challenge_author")]
+    ChallengeAuthor,
+    /// Manages teams and notices of one game (`game_id`).
+    #[sea_orm(string_value = "game_operator")]
+    GameOperator,
+    /// Reads admin views, globally or within one game (`game_id`).
+    #[sea_orm(string_value = "observer")]
+    Observer,
+}
+
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait, {
+        let ts = time::OffsetDateTime::now_utc().unix_timestamp();
+        self.updated_at = Set(ts);
+        if insert {
+            self.created_at = Set(ts);
+        }
+        Ok(self)
+    }
+}
@@ -0,0 +1,37 @@
+//! SeaORM `script_revision` entity — one saved version of a challenge checker
+//! or IdP script, with its author and lint result.
+
+use async_trait::async_trait;
+use sea_orm::{Set, entity::prelude::*};
+use serde::{Deserialize, Serialize};
+
+#[sea_orm::model]
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "script_revisions")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub challenge_id: Option<i64>,
+    pub idp_id: Option<i64>,
+    pub number: i32,
+    #[sea_orm(column_type = "Text")]
+    pub script: String,
+    pub allowed_hosts: Vec<String>,
+    pub author_id: Option<i64>,
+    pub restored_from: Option<i32>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub markers: Json,
+    pub created_at: i64,
+}
+
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait, {
+        if insert {
+            self.created_at = Set(time::OffsetDateTime::now_utc().unix_timestamp());
+        }
+        Ok(self)
+    }
+}
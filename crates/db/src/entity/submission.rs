@@ -24,6 +24,12 @@ pub struct Model {
     pub pts: i64,
     #[sea_orm(default_value = 0)]
     pub rank: i64,
+    #[sea_orm(column_type = "Text")]
+    pub feedback: Option<String>,
+    pub flag_id: Option<String>,
+    /// Percent of the flag's points awarded when correct.
+    #[sea_orm(default_value = 100)]
+    pub credit: i32,
     #[sea_orm(belongs_to, from = "challenge_id", to = "id", on_delete = "Cascade")]
     pub challenge: BelongsTo<super::challenge::Entity>,
     #[sea_orm(belongs_to, from = "user_id", to = "id", on_delete = "Cascade")]
@@ -74,7 +80,8 @@ impl ActiveModelBehavior for ActiveModel {
         C: ConnectionTrait, {
         let ts = time::OffsetDateTime::now_utc().unix_timestamp();
 
-        if insert {
+        // Restored rows arrive with their original creation time.
+        if insert && self.created_at.is_not_set() {
             self.created_at = Set(ts);
         }
 
@@ -17,6 +17,7 @@ pub struct Model {
     pub slogan: Option<String>,
     pub avatar_hash: Option<String>,
     pub has_writeup: bool,
+    pub division: Option<String>,
 
     pub state: State,
 
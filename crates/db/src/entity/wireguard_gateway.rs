@@ -0,0 +1,18 @@
+//! SeaORM `wireguard_gateway` entity — the key pair of the WireGuard gateway,
+//! a single row created on first use.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[sea_orm::model]
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "wireguard_gateways")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: i64,
+    pub private_key: String,
+    pub public_key: String,
+    pub created_at: i64,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,23 @@
+//! SeaORM `wireguard_peer` entity — a WireGuard peer issued to a team of a
+//! game, kept after it is revoked.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[sea_orm::model]
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "wireguard_peers")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub game_id: i64,
+    pub team_id: i64,
+    /// Tunnel address of the peer, without prefix length.
+    pub address: String,
+    pub private_key: String,
+    pub public_key: String,
+    pub created_at: i64,
+    pub revoked_at: Option<i64>,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
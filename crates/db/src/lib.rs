@@ -24,17 +24,20 @@ use std::time::Duration;
 use cds_env::Env;
 pub use config::Config;
 pub use dto::{
-    ChallengeDetail, ChallengeSummary, ChallengeView, EmailView, GameChallengeSummary,
-    GameChallengeView, GameDetail, GameNoticeView, GameSummary, GameView, IdpSummary, IdpView,
-    NoteView, PlayerTeamView, PublicCaptchaConfig, PublicCaptchaSiteConfig, PublicConfig,
-    PublicEmailConfig, ScoreboardEntry, ScoreboardSubmission, ScoreboardTeam, SubmissionSummary,
-    SubmissionView, TeamUserView, TeamView, UserAccountView, UserIdpSummary, UserIdpView,
-    UserProfile, UserSummary,
+    ApiTokenView, ChallengeDetail, ChallengeSummary, ChallengeView, CheckerLogView, EmailView,
+    GameChallengeSummary, GameChallengeView, GameDetail, GameNoticeView, GameSummary, GameView,
+    IdpSummary, IdpView, InstanceView, LuaLibraryView, NoteView, PlayerTeamView,
+    PublicCaptchaConfig, PublicCaptchaSiteConfig, PublicConfig, PublicEmailConfig,
+    RejudgeEntryView, RejudgeJobView, RoleAssignmentView, ScoreboardEntry, ScoreboardSubmission,
+    ScoreboardTeam, ScriptRevisionSummary, ScriptRevisionView, SubmissionSummary, SubmissionView,
+    TeamRosterEntry, TeamUserView, TeamView, UserAccountView, UserIdpSummary, UserIdpView,
+    UserProfile, UserSummary, WireguardPeerView,
 };
 pub use entity::user_idp::Source as UserIdpSource;
 pub use repository::{
-    challenge, config, email, game, game_challenge, game_notice, idp, note, submission, team,
-    team_user, user, user_idp,
+    api_token, challenge, checker_log, config, email, game, game_challenge, game_notice, idp,
+    instance, lua_library, note, rejudge, role_assignment, script_revision, submission, team,
+    team_user, user, user_idp, wireguard_gateway, wireguard_peer,
 };
 pub use sea_orm;
 use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection};
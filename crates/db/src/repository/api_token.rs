@@ -0,0 +1,124 @@
+//! Database access for personal API tokens owned by local users.
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, Order, QueryFilter, QueryOrder,
+    Set, Unchanged,
+};
+use tracing::info;
+
+pub(crate) use crate::entity::api_token::{Column, Entity};
+use crate::traits::DbError;
+pub use crate::{
+    dto::api_token::ApiTokenView,
+    entity::api_token::{ActiveModel, Scope},
+};
+
+/// Lists every token of a user, newest first.
+pub async fn find_by_user_id(
+    conn: &impl ConnectionTrait,
+    user_id: i64,
+) -> Result<Vec<ApiTokenView>, DbError> {
+    Ok(Entity::find()
+        .filter(Column::UserId.eq(user_id))
+        .order_by(Column::Id, Order::Desc)
+        .into_model::<ApiTokenView>()
+        .all(conn)
+        .await?)
+}
+
+/// Looks up by id, restricted to the owning user.
+pub async fn find_by_id_and_user_id(
+    conn: &impl ConnectionTrait,
+    token_id: i64,
+    user_id: i64,
+) -> Result<Option<ApiTokenView>, DbError> {
+    Ok(Entity::find()
+        .filter(Column::Id.eq(token_id))
+        .filter(Column::UserId.eq(user_id))
+        .into_model::<ApiTokenView>()
+        .one(conn)
+        .await?)
+}
+
+/// Looks up by the SHA-256 hex digest of the presented secret.
+pub async fn find_by_token_hash(
+    conn: &impl ConnectionTrait,
+    token_hash: &str,
+) -> Result<Option<ApiTokenView>, DbError> {
+    Ok(Entity::find()
+        .filter(Column::TokenHash.eq(token_hash))
+        .into_model::<ApiTokenView>()
+        .one(conn)
+        .await?)
+}
+
+/// Inserts a new row and returns the persisted token.
+pub async fn create(
+    conn: &impl ConnectionTrait,
+    model: ActiveModel,
+) -> Result<ApiTokenView, DbError> {
+    let token = model.insert(conn).await?;
+    info!(
+        api_token_id = token.id,
+        user_id = token.user_id,
+        scopes = ?token.scopes,
+        "api token created"
+    );
+
+    find_by_id_and_user_id(conn, token.id, token.user_id)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("api_token_{}", token.id)))
+}
+
+/// Records the time and client address of the latest authenticated request.
+pub async fn touch(
+    conn: &impl ConnectionTrait,
+    token_id: i64,
+    client_ip: String,
+) -> Result<(), DbError> {
+    let _ = ActiveModel {
+        id: Unchanged(token_id),
+        last_used_at: Set(Some(time::OffsetDateTime::now_utc().unix_timestamp())),
+        last_used_ip: Set(Some(client_ip)),
+        ..Default::default()
+    }
+    .update(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Revokes one token of a user.
+pub async fn delete(
+    conn: &impl ConnectionTrait,
+    user_id: i64,
+    token_id: i64,
+) -> Result<(), DbError> {
+    let result = Entity::delete_many()
+        .filter(Column::Id.eq(token_id))
+        .filter(Column::UserId.eq(user_id))
+        .exec(conn)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(DbError::NotFound(format!("api_token_{token_id}")));
+    }
+    info!(api_token_id = token_id, user_id, "api token revoked");
+
+    Ok(())
+}
+
+/// Revokes every token of a user.
+pub async fn delete_by_user_id(conn: &impl ConnectionTrait, user_id: i64) -> Result<(), DbError> {
+    let result = Entity::delete_many()
+        .filter(Column::UserId.eq(user_id))
+        .exec(conn)
+        .await?;
+    info!(
+        user_id,
+        deleted = result.rows_affected,
+        "api tokens revoked"
+    );
+
+    Ok(())
+}
@@ -13,7 +13,10 @@ pub(crate) use crate::entity::challenge::{Column, Entity};
 use crate::traits::DbError;
 pub use crate::{
     dto::challenge::{ChallengeDetail, ChallengeSummary, ChallengeView},
-    entity::challenge::{ActiveModel, Container, EnvVar, Instance, Model, Port},
+    entity::challenge::{
+        ActiveModel, CheckerConfig, CheckpointCommands, Container, EnvVar, Instance, Model, Port,
+        Toleration,
+    },
 };
 
 #[derive(Clone, Debug, Default)]
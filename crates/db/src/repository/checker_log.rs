@@ -0,0 +1,80 @@
+//! Database access for checker execution logs.
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+use tracing::debug;
+
+pub(crate) use crate::entity::checker_log::{Column, Entity};
+use crate::traits::DbError;
+pub use crate::{dto::checker_log::CheckerLogView, entity::checker_log::ActiveModel};
+
+/// Number of checker logs kept per challenge; older ones are pruned as new
+/// runs are recorded.
+pub const RETAINED_PER_CHALLENGE: u64 = 200;
+
+pub async fn find_by_submission_id<T>(
+    conn: &impl ConnectionTrait,
+    submission_id: i64,
+) -> Result<Vec<T>, DbError>
+where
+    T: FromQueryResult, {
+    Ok(Entity::find()
+        .filter(Column::SubmissionId.eq(submission_id))
+        .order_by_desc(Column::Id)
+        .into_model::<T>()
+        .all(conn)
+        .await?)
+}
+
+/// Records one checker run and prunes the challenge's logs beyond
+/// [`RETAINED_PER_CHALLENGE`].
+pub async fn create(
+    conn: &impl ConnectionTrait,
+    submission_id: i64,
+    challenge_id: i64,
+    trace: serde_json::Value,
+) -> Result<(), DbError> {
+    let log = ActiveModel {
+        submission_id: Set(submission_id),
+        challenge_id: Set(challenge_id),
+        trace: Set(trace),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+    let pruned = prune(conn, challenge_id, RETAINED_PER_CHALLENGE).await?;
+    debug!(
+        checker_log_id = log.id,
+        submission_id, challenge_id, pruned, "checker log recorded"
+    );
+    Ok(())
+}
+
+/// Deletes all but the newest `keep` logs of a challenge.
+pub async fn prune(
+    conn: &impl ConnectionTrait,
+    challenge_id: i64,
+    keep: u64,
+) -> Result<u64, DbError> {
+    let cutoff = Entity::find()
+        .select_only()
+        .column(Column::Id)
+        .filter(Column::ChallengeId.eq(challenge_id))
+        .order_by_desc(Column::Id)
+        .offset(keep)
+        .limit(1)
+        .into_tuple::<i64>()
+        .one(conn)
+        .await?;
+    let Some(cutoff) = cutoff else {
+        return Ok(0);
+    };
+    let result = Entity::delete_many()
+        .filter(Column::ChallengeId.eq(challenge_id))
+        .filter(Column::Id.lte(cutoff))
+        .exec(conn)
+        .await?;
+    Ok(result.rows_affected)
+}
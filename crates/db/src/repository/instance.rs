@@ -0,0 +1,414 @@
+//! Database access for challenge instance records, written by the cluster
+//! driver as it creates, renews and tears down pods.
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+    sea_query::{Expr, ExprTrait},
+};
+use tracing::info;
+
+pub(crate) use crate::entity::instance::{Column, Entity};
+pub use crate::{
+    dto::instance::InstanceView,
+    entity::instance::{ActiveModel, Checkpoint, Nat, State, TerminationReason},
+};
+use crate::{entity::challenge::Port, traits::DbError};
+
+/// An instance about to be started.
+#[derive(Clone, Debug)]
+pub struct NewInstance {
+    pub id: String,
+    pub user_id: i64,
+    pub team_id: Option<i64>,
+    pub game_id: Option<i64>,
+    pub challenge_id: i64,
+    pub ports: Vec<Port>,
+    /// Renewals already granted, non-zero only when adopting a running pod.
+    pub renew: i32,
+    pub duration: i64,
+    /// Start of the instance's lifetime, normally now.
+    pub created_at: i64,
+    pub access_token: Option<String>,
+    pub max_renewals: i32,
+    /// Longest the instance may run in seconds, `0` for no limit.
+    pub max_lifetime: i64,
+    /// Seconds without traffic before the instance is stopped, `0` for never.
+    pub idle_timeout: i64,
+    pub max_resets: i32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FindInstanceOptions {
+    pub id: Option<String>,
+    pub user_id: Option<i64>,
+    pub team_id: Option<i64>,
+    pub game_id: Option<i64>,
+    pub challenge_id: Option<i64>,
+    pub state: Option<State>,
+    pub page: Option<u64>,
+    pub size: Option<u64>,
+}
+
+/// Records a running instance that expires `(renew + 1) * duration` seconds
+/// after `created_at`, or at the end of its maximum lifetime if sooner.
+pub async fn create<T>(conn: &impl ConnectionTrait, instance: NewInstance) -> Result<T, DbError>
+where
+    T: FromQueryResult, {
+    let max_expires_at =
+        (instance.max_lifetime > 0).then(|| instance.created_at + instance.max_lifetime);
+    let expires_at = instance.created_at + (instance.renew as i64 + 1) * instance.duration;
+
+    let instance = ActiveModel {
+        id: Set(instance.id),
+        user_id: Set(instance.user_id),
+        team_id: Set(instance.team_id),
+        game_id: Set(instance.game_id),
+        challenge_id: Set(instance.challenge_id),
+        ports: Set(instance.ports),
+        nats: Set(Vec::new()),
+        state: Set(State::Running),
+        renew: Set(instance.renew),
+        duration: Set(instance.duration),
+        created_at: Set(instance.created_at),
+        renewed_at: Set(None),
+        expires_at: Set(max_expires_at.map_or(expires_at, |max| std::cmp::min(expires_at, max))),
+        terminated_at: Set(None),
+        termination_reason: Set(None),
+        access_token: Set(instance.access_token),
+        max_renewals: Set(instance.max_renewals),
+        max_expires_at: Set(max_expires_at),
+        idle_timeout: Set(instance.idle_timeout),
+        last_active_at: Set(None),
+        warned_at: Set(None),
+        resets: Set(0),
+        max_resets: Set(instance.max_resets),
+        reset_at: Set(None),
+        checkpoints: Set(Vec::new()),
+    }
+    .insert(conn)
+    .await?;
+    info!(
+        instance_id = %instance.id,
+        user_id = instance.user_id,
+        challenge_id = instance.challenge_id,
+        "instance recorded"
+    );
+
+    find_by_id::<T>(conn, &instance.id)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("instance_{}", instance.id)))
+}
+
+/// Looks up by id.
+pub async fn find_by_id<T>(conn: &impl ConnectionTrait, id: &str) -> Result<Option<T>, DbError>
+where
+    T: FromQueryResult, {
+    Ok(Entity::find_by_id(id.to_owned())
+        .into_model::<T>()
+        .one(conn)
+        .await?)
+}
+
+/// Loads the records of the given instance ids, whatever their state.
+pub async fn find_by_ids<T>(
+    conn: &impl ConnectionTrait,
+    ids: &[String],
+) -> Result<Vec<T>, DbError>
+where
+    T: FromQueryResult, {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(Entity::find()
+        .filter(Column::Id.is_in(ids.iter().cloned()))
+        .into_model::<T>()
+        .all(conn)
+        .await?)
+}
+
+/// Loads every instance the platform believes is running.
+pub async fn find_running<T>(conn: &impl ConnectionTrait) -> Result<Vec<T>, DbError>
+where
+    T: FromQueryResult, {
+    Ok(Entity::find()
+        .filter(Column::State.eq(State::Running))
+        .order_by_asc(Column::ExpiresAt)
+        .into_model::<T>()
+        .all(conn)
+        .await?)
+}
+
+/// Queries records newest first and returns `(rows, total_count)`.
+pub async fn find<T>(
+    conn: &impl ConnectionTrait,
+    FindInstanceOptions {
+        id,
+        user_id,
+        team_id,
+        game_id,
+        challenge_id,
+        state,
+        page,
+        size,
+    }: FindInstanceOptions,
+) -> Result<(Vec<T>, u64), DbError>
+where
+    T: FromQueryResult, {
+    let mut sql = Entity::find();
+
+    if let Some(id) = id {
+        sql = sql.filter(Column::Id.eq(id));
+    }
+
+    if let Some(user_id) = user_id {
+        sql = sql.filter(Column::UserId.eq(user_id));
+    }
+
+    if let Some(team_id) = team_id {
+        sql = sql.filter(Column::TeamId.eq(team_id));
+    }
+
+    if let Some(game_id) = game_id {
+        sql = sql.filter(Column::GameId.eq(game_id));
+    }
+
+    if let Some(challenge_id) = challenge_id {
+        sql = sql.filter(Column::ChallengeId.eq(challenge_id));
+    }
+
+    if let Some(state) = state {
+        sql = sql.filter(Column::State.eq(state));
+    }
+
+    let total = sql.clone().count(conn).await?;
+
+    sql = sql
+        .order_by_desc(Column::CreatedAt)
+        .order_by_desc(Column::Id);
+
+    if let (Some(page), Some(size)) = (page, size) {
+        let offset = (page - 1) * size;
+        sql = sql.offset(offset).limit(size);
+    }
+
+    let instances = sql.into_model::<T>().all(conn).await?;
+
+    Ok((instances, total))
+}
+
+/// Stores the node ports assigned to the instance's service.
+pub async fn update_nats(
+    conn: &impl ConnectionTrait,
+    id: &str,
+    nats: Vec<Nat>,
+) -> Result<(), DbError> {
+    Entity::update_many()
+        .col_expr(Column::Nats, Expr::value(sea_orm::Value::from(nats)))
+        .filter(Column::Id.eq(id))
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
+/// Grants a running instance one more `duration`, up to its maximum
+/// lifetime, and returns the record; `None` when it is not running or has no
+/// renewals left.
+pub async fn renew<T>(
+    conn: &impl ConnectionTrait,
+    id: &str,
+    now: i64,
+) -> Result<Option<T>, DbError>
+where
+    T: FromQueryResult, {
+    let Some(instance) = Entity::find_by_id(id.to_owned())
+        .filter(Column::State.eq(State::Running))
+        .one(conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+    if instance.renew >= instance.max_renewals {
+        return Ok(None);
+    }
+
+    let expires_at = instance.created_at + (instance.renew as i64 + 2) * instance.duration;
+    let expires_at = instance
+        .max_expires_at
+        .map_or(expires_at, |max| std::cmp::min(expires_at, max));
+
+    // Matching on the old renewal count makes concurrent renewals count once.
+    let result = Entity::update_many()
+        .col_expr(Column::Renew, Expr::value(instance.renew + 1))
+        .col_expr(Column::RenewedAt, Expr::value(now))
+        .col_expr(Column::ExpiresAt, Expr::value(expires_at))
+        .col_expr(Column::WarnedAt, Expr::value(Option::<i64>::None))
+        .filter(Column::Id.eq(id))
+        .filter(Column::State.eq(State::Running))
+        .filter(Column::Renew.eq(instance.renew))
+        .exec(conn)
+        .await?;
+    if result.rows_affected == 0 {
+        return Ok(None);
+    }
+    find_by_id::<T>(conn, id).await
+}
+
+/// Counts a reset of a running instance and returns the record; `None` when
+/// it is not running or has no resets left. The instance's pod starts over,
+/// so its checkpoints are dropped.
+pub async fn reset<T>(
+    conn: &impl ConnectionTrait,
+    id: &str,
+    now: i64,
+) -> Result<Option<T>, DbError>
+where
+    T: FromQueryResult, {
+    count_reset(conn, id, now, true).await
+}
+
+/// Counts a checkpoint restore of a running instance like a reset, keeping
+/// its checkpoints.
+pub async fn restore<T>(
+    conn: &impl ConnectionTrait,
+    id: &str,
+    now: i64,
+) -> Result<Option<T>, DbError>
+where
+    T: FromQueryResult, {
+    count_reset(conn, id, now, false).await
+}
+
+async fn count_reset<T>(
+    conn: &impl ConnectionTrait,
+    id: &str,
+    now: i64,
+    drop_checkpoints: bool,
+) -> Result<Option<T>, DbError>
+where
+    T: FromQueryResult, {
+    let Some(instance) = Entity::find_by_id(id.to_owned())
+        .filter(Column::State.eq(State::Running))
+        .one(conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+    if instance.resets >= instance.max_resets {
+        return Ok(None);
+    }
+
+    // A reset is activity, and matching on the old count makes concurrent
+    // resets count once.
+    let mut update = Entity::update_many()
+        .col_expr(Column::Resets, Expr::value(instance.resets + 1))
+        .col_expr(Column::ResetAt, Expr::value(now))
+        .col_expr(Column::LastActiveAt, Expr::value(now));
+    if drop_checkpoints {
+        update = update.col_expr(
+            Column::Checkpoints,
+            Expr::value(sea_orm::Value::from(Vec::<Checkpoint>::new())),
+        );
+    }
+    let result = update
+        .filter(Column::Id.eq(id))
+        .filter(Column::State.eq(State::Running))
+        .filter(Column::Resets.eq(instance.resets))
+        .exec(conn)
+        .await?;
+    if result.rows_affected == 0 {
+        return Ok(None);
+    }
+    find_by_id::<T>(conn, id).await
+}
+
+/// Gives back a reset counted by [`reset`] or [`restore`] whose pod work
+/// failed, putting back the checkpoints it had. Matching on the counted
+/// value leaves a later reset alone.
+pub async fn refund_reset(
+    conn: &impl ConnectionTrait,
+    id: &str,
+    resets: i32,
+    checkpoints: Vec<Checkpoint>,
+) -> Result<(), DbError> {
+    Entity::update_many()
+        .col_expr(Column::Resets, Expr::value(resets - 1))
+        .col_expr(
+            Column::Checkpoints,
+            Expr::value(sea_orm::Value::from(checkpoints)),
+        )
+        .filter(Column::Id.eq(id))
+        .filter(Column::State.eq(State::Running))
+        .filter(Column::Resets.eq(resets))
+        .filter(Column::Resets.gt(0))
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
+/// Stores the checkpoints of a running instance.
+pub async fn update_checkpoints(
+    conn: &impl ConnectionTrait,
+    id: &str,
+    checkpoints: Vec<Checkpoint>,
+) -> Result<(), DbError> {
+    Entity::update_many()
+        .col_expr(
+            Column::Checkpoints,
+            Expr::value(sea_orm::Value::from(checkpoints)),
+        )
+        .filter(Column::Id.eq(id))
+        .filter(Column::State.eq(State::Running))
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
+/// Records traffic seen on a running instance at `at`.
+pub async fn touch(conn: &impl ConnectionTrait, id: &str, at: i64) -> Result<(), DbError> {
+    Entity::update_many()
+        .col_expr(Column::LastActiveAt, Expr::value(at))
+        .filter(Column::Id.eq(id))
+        .filter(Column::State.eq(State::Running))
+        .filter(
+            Column::LastActiveAt
+                .is_null()
+                .or(Column::LastActiveAt.lt(at)),
+        )
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
+/// Records that the owner of a running instance was warned of its shutdown.
+pub async fn mark_warned(conn: &impl ConnectionTrait, id: &str, now: i64) -> Result<(), DbError> {
+    Entity::update_many()
+        .col_expr(Column::WarnedAt, Expr::value(now))
+        .filter(Column::Id.eq(id))
+        .filter(Column::State.eq(State::Running))
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
+/// Marks a running instance terminated. Returns `false` when the record is
+/// missing or was already terminated, so the first reason recorded wins.
+pub async fn terminate(
+    conn: &impl ConnectionTrait,
+    id: &str,
+    reason: TerminationReason,
+    now: i64,
+) -> Result<bool, DbError> {
+    let result = Entity::update_many()
+        .col_expr(Column::State, Expr::value(State::Terminated))
+        .col_expr(Column::TerminatedAt, Expr::value(now))
+        .col_expr(Column::TerminationReason, Expr::value(reason.clone()))
+        .filter(Column::Id.eq(id))
+        .filter(Column::State.eq(State::Running))
+        .exec(conn)
+        .await?;
+    if result.rows_affected > 0 {
+        info!(instance_id = %id, reason = ?reason, "instance terminated");
+    }
+    Ok(result.rows_affected > 0)
+}
@@ -0,0 +1,75 @@
+//! Database access for shared Lua libraries.
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult, QueryFilter,
+    QueryOrder,
+};
+use tracing::info;
+
+pub(crate) use crate::entity::lua_library::{Column, Entity};
+use crate::traits::DbError;
+pub use crate::{dto::lua_library::LuaLibraryView, entity::lua_library::ActiveModel};
+
+pub async fn find_all<T>(conn: &impl ConnectionTrait) -> Result<Vec<T>, DbError>
+where
+    T: FromQueryResult, {
+    Ok(Entity::find()
+        .order_by_asc(Column::Name)
+        .into_model::<T>()
+        .all(conn)
+        .await?)
+}
+
+pub async fn find_by_id<T>(conn: &impl ConnectionTrait, id: i64) -> Result<Option<T>, DbError>
+where
+    T: FromQueryResult, {
+    Ok(Entity::find_by_id(id).into_model::<T>().one(conn).await?)
+}
+
+pub async fn find_by_name<T>(
+    conn: &impl ConnectionTrait,
+    name: &str,
+) -> Result<Option<T>, DbError>
+where
+    T: FromQueryResult, {
+    Ok(Entity::find()
+        .filter(Column::Name.eq(name))
+        .into_model::<T>()
+        .one(conn)
+        .await?)
+}
+
+pub async fn create<T>(conn: &impl ConnectionTrait, model: ActiveModel) -> Result<T, DbError>
+where
+    T: FromQueryResult, {
+    let library = model.insert(conn).await?;
+    info!(
+        library_id = library.id,
+        name = %library.name,
+        "lua library created"
+    );
+    find_by_id(conn, library.id)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("lua_library_{}", library.id)))
+}
+
+pub async fn update<T>(conn: &impl ConnectionTrait, model: ActiveModel) -> Result<T, DbError>
+where
+    T: FromQueryResult, {
+    let library = model.update(conn).await?;
+    info!(
+        library_id = library.id,
+        name = %library.name,
+        version = library.version,
+        "lua library updated"
+    );
+    find_by_id(conn, library.id)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("lua_library_{}", library.id)))
+}
+
+pub async fn delete(conn: &impl ConnectionTrait, id: i64) -> Result<(), DbError> {
+    let _ = Entity::delete_by_id(id).exec(conn).await?;
+    info!(library_id = id, "lua library deleted");
+    Ok(())
+}
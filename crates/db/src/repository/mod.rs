@@ -4,18 +4,27 @@
 //! `cds_db::user::find_by_id`; new code may also use the explicit
 //! `cds_db::repository::user` path.
 
+pub mod api_token;
 pub mod challenge;
+pub mod checker_log;
 pub mod config;
 pub mod email;
 pub mod game;
 pub mod game_challenge;
 pub mod game_notice;
 pub mod idp;
+pub mod instance;
+pub mod lua_library;
 pub mod note;
+pub mod rejudge;
+pub mod role_assignment;
+pub mod script_revision;
 pub mod submission;
 pub mod team;
 pub mod team_user;
 pub mod user;
 pub mod user_idp;
+pub mod wireguard_gateway;
+pub mod wireguard_peer;
 
 pub(crate) const BULK_UPDATE_BATCH_SIZE: usize = 500;
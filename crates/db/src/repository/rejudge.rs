@@ -0,0 +1,345 @@
+//! Database access for admin re-judge jobs and the verdicts they replace.
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult, JoinType,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set, SqlErr,
+    sea_query::{Expr, ExprTrait},
+};
+use tracing::info;
+
+pub(crate) use crate::entity::rejudge_job::{Column, Entity};
+pub use crate::{
+    dto::rejudge::{RejudgeEntryView, RejudgeJobView},
+    entity::{
+        rejudge_entry::ActiveModel as EntryActiveModel,
+        rejudge_job::{ActiveModel, State},
+        submission::Status,
+    },
+};
+use crate::{
+    entity::{
+        rejudge_entry::{Column as EntryColumn, Entity as EntryEntity, Relation as EntryRelation},
+        submission::{Column as SubmissionColumn, Entity as SubmissionEntity},
+        team::{Column as TeamColumn, Entity as TeamEntity, State as TeamState},
+    },
+    traits::DbError,
+};
+
+/// Statuses a re-judge may replace. Queued and in-flight submissions are
+/// still owned by the checker.
+pub const REJUDGEABLE_STATUSES: [Status; 5] = [
+    Status::Correct,
+    Status::Incorrect,
+    Status::Cheat,
+    Status::Expired,
+    Status::Duplicate,
+];
+
+/// Rows inserted per statement when recording a job's entries.
+const ENTRY_CHUNK: usize = 1000;
+
+/// Scope of a new re-judge job.
+#[derive(Clone, Debug)]
+pub struct NewRejudge {
+    pub game_id: i64,
+    pub challenge_id: i64,
+    pub status: Option<Status>,
+    pub created_from: Option<i64>,
+    pub created_until: Option<i64>,
+    pub author_id: Option<i64>,
+}
+
+#[derive(FromQueryResult)]
+struct Candidate {
+    id: i64,
+    team_id: Option<i64>,
+    status: Status,
+    flag_id: Option<String>,
+    credit: i32,
+    checked_at: Option<i64>,
+}
+
+/// Records a job, remembers the current verdict of every matching submission
+/// and returns those submissions to the queue. Returns the job and the ids to
+/// publish on the checker subject once the caller's transaction commits.
+///
+/// Fails with `cheat_teams_still_banned` while a matching `Cheat` submission's
+/// team is banned, since a different verdict would leave that ban in place.
+pub async fn create(
+    conn: &impl ConnectionTrait,
+    rejudge: NewRejudge,
+) -> Result<(RejudgeJobView, Vec<i64>), DbError> {
+    if rejudge
+        .status
+        .as_ref()
+        .is_some_and(|status| !REJUDGEABLE_STATUSES.contains(status))
+    {
+        return Err(DbError::BadRequest("status_not_rejudgeable".to_owned()));
+    }
+
+    let mut query = SubmissionEntity::find()
+        .select_only()
+        .columns([
+            SubmissionColumn::Id,
+            SubmissionColumn::TeamId,
+            SubmissionColumn::Status,
+            SubmissionColumn::FlagId,
+            SubmissionColumn::Credit,
+            SubmissionColumn::CheckedAt,
+        ])
+        .filter(SubmissionColumn::GameId.eq(rejudge.game_id))
+        .filter(SubmissionColumn::ChallengeId.eq(rejudge.challenge_id))
+        .filter(match &rejudge.status {
+            Some(status) => SubmissionColumn::Status.eq(status.clone()),
+            None => SubmissionColumn::Status.is_in(REJUDGEABLE_STATUSES),
+        });
+    if let Some(created_from) = rejudge.created_from {
+        query = query.filter(SubmissionColumn::CreatedAt.gte(created_from));
+    }
+    if let Some(created_until) = rejudge.created_until {
+        query = query.filter(SubmissionColumn::CreatedAt.lte(created_until));
+    }
+    let candidates = query
+        .order_by_asc(SubmissionColumn::CreatedAt)
+        .order_by_asc(SubmissionColumn::Id)
+        .into_model::<Candidate>()
+        .all(conn)
+        .await?;
+
+    // A cheat verdict also bans a peer team that is not recorded, so a
+    // re-judge cannot undo the bans of the verdicts it replaces. Refuse while
+    // their submitting teams are still banned; admins unban them by hand.
+    let cheat_team_ids = candidates
+        .iter()
+        .filter(|candidate| candidate.status == Status::Cheat)
+        .filter_map(|candidate| candidate.team_id)
+        .collect::<Vec<_>>();
+    if !cheat_team_ids.is_empty()
+        && TeamEntity::find()
+            .filter(TeamColumn::Id.is_in(cheat_team_ids))
+            .filter(TeamColumn::GameId.eq(rejudge.game_id))
+            .filter(TeamColumn::State.eq(TeamState::Banned))
+            .count(conn)
+            .await?
+            > 0
+    {
+        return Err(DbError::BadRequest("cheat_teams_still_banned".to_owned()));
+    }
+
+    let total = candidates.len() as i32;
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let job = ActiveModel {
+        game_id: Set(rejudge.game_id),
+        challenge_id: Set(rejudge.challenge_id),
+        status_filter: Set(rejudge.status),
+        created_from: Set(rejudge.created_from),
+        created_until: Set(rejudge.created_until),
+        state: Set(if total == 0 {
+            State::Completed
+        } else {
+            State::Running
+        }),
+        total: Set(total),
+        processed: Set(0),
+        changed: Set(0),
+        author_id: Set(rejudge.author_id),
+        finished_at: Set((total == 0).then_some(now)),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+    .map_err(|err| match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            DbError::BadRequest("rejudge_already_running".to_owned())
+        }
+        _ => DbError::from(err),
+    })?;
+
+    for chunk in candidates.chunks(ENTRY_CHUNK) {
+        EntryEntity::insert_many(chunk.iter().map(|candidate| EntryActiveModel {
+            job_id: Set(job.id),
+            submission_id: Set(candidate.id),
+            previous_status: Set(candidate.status.clone()),
+            previous_flag_id: Set(candidate.flag_id.clone()),
+            previous_credit: Set(candidate.credit),
+            previous_checked_at: Set(candidate.checked_at),
+            ..Default::default()
+        }))
+        .exec(conn)
+        .await?;
+
+        SubmissionEntity::update_many()
+            .set(crate::entity::submission::ActiveModel {
+                status: Set(Status::Queued),
+                processing_at: Set(None),
+                ..Default::default()
+            })
+            .filter(SubmissionColumn::Id.is_in(chunk.iter().map(|candidate| candidate.id)))
+            .exec(conn)
+            .await?;
+    }
+
+    info!(
+        rejudge_job_id = job.id,
+        game_id = job.game_id,
+        challenge_id = job.challenge_id,
+        total,
+        "rejudge job created"
+    );
+
+    let job = find_job_by_id(conn, job.id)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("rejudge_job_{}", job.id)))?;
+    Ok((
+        job,
+        candidates.iter().map(|candidate| candidate.id).collect(),
+    ))
+}
+
+/// Lists the jobs of one game challenge, newest first.
+pub async fn find_jobs(
+    conn: &impl ConnectionTrait,
+    game_id: i64,
+    challenge_id: i64,
+) -> Result<Vec<RejudgeJobView>, DbError> {
+    Ok(Entity::find()
+        .filter(Column::GameId.eq(game_id))
+        .filter(Column::ChallengeId.eq(challenge_id))
+        .order_by_desc(Column::Id)
+        .into_model::<RejudgeJobView>()
+        .all(conn)
+        .await?)
+}
+
+pub async fn find_job_by_id(
+    conn: &impl ConnectionTrait,
+    job_id: i64,
+) -> Result<Option<RejudgeJobView>, DbError> {
+    Ok(Entity::find_by_id(job_id)
+        .into_model::<RejudgeJobView>()
+        .one(conn)
+        .await?)
+}
+
+fn entry_query() -> sea_orm::Select<EntryEntity> {
+    EntryEntity::find()
+        .select_only()
+        .columns([
+            EntryColumn::Id,
+            EntryColumn::JobId,
+            EntryColumn::SubmissionId,
+            EntryColumn::PreviousStatus,
+            EntryColumn::PreviousFlagId,
+            EntryColumn::PreviousCredit,
+            EntryColumn::PreviousCheckedAt,
+            EntryColumn::Status,
+            EntryColumn::FlagId,
+            EntryColumn::Credit,
+            EntryColumn::FinishedAt,
+        ])
+        .column(SubmissionColumn::UserId)
+        .column(SubmissionColumn::TeamId)
+        .column(SubmissionColumn::CreatedAt)
+        .join(JoinType::InnerJoin, EntryRelation::Submission.def())
+}
+
+/// Lists a job's submissions in submission order.
+pub async fn find_entries(
+    conn: &impl ConnectionTrait,
+    job_id: i64,
+) -> Result<Vec<RejudgeEntryView>, DbError> {
+    Ok(entry_query()
+        .filter(EntryColumn::JobId.eq(job_id))
+        .order_by_asc(SubmissionColumn::CreatedAt)
+        .order_by_asc(EntryColumn::SubmissionId)
+        .into_model::<RejudgeEntryView>()
+        .all(conn)
+        .await?)
+}
+
+/// Returns the unfinished entry that re-queued a submission, if any.
+pub async fn find_pending_entry(
+    conn: &impl ConnectionTrait,
+    submission_id: i64,
+) -> Result<Option<RejudgeEntryView>, DbError> {
+    Ok(entry_query()
+        .filter(EntryColumn::SubmissionId.eq(submission_id))
+        .filter(EntryColumn::FinishedAt.is_null())
+        .order_by_desc(EntryColumn::Id)
+        .into_model::<RejudgeEntryView>()
+        .one(conn)
+        .await?)
+}
+
+/// Records the new verdict of an entry and advances its job. Returns the job
+/// when this entry was the last one outstanding.
+pub async fn finish_entry(
+    conn: &impl ConnectionTrait,
+    entry: &RejudgeEntryView,
+    status: Status,
+    flag_id: Option<String>,
+    credit: i32,
+) -> Result<Option<RejudgeJobView>, DbError> {
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let finished = RejudgeEntryView {
+        status: Some(status.clone()),
+        flag_id: flag_id.clone(),
+        credit: Some(credit),
+        ..entry.clone()
+    };
+    let result = EntryEntity::update_many()
+        .set(EntryActiveModel {
+            status: Set(Some(status)),
+            flag_id: Set(flag_id),
+            credit: Set(Some(credit)),
+            finished_at: Set(Some(now)),
+            ..Default::default()
+        })
+        .filter(EntryColumn::Id.eq(entry.id))
+        .filter(EntryColumn::FinishedAt.is_null())
+        .exec(conn)
+        .await?;
+    if result.rows_affected == 0 {
+        return Ok(None);
+    }
+
+    Entity::update_many()
+        .col_expr(Column::Processed, Expr::col(Column::Processed).add(1))
+        .col_expr(
+            Column::Changed,
+            Expr::col(Column::Changed).add(i32::from(finished.changed())),
+        )
+        .filter(Column::Id.eq(entry.job_id))
+        .exec(conn)
+        .await?;
+
+    // The counter update holds the job row lock, so exactly one entry sees
+    // the final count.
+    let completed = Entity::update_many()
+        .set(ActiveModel {
+            state: Set(State::Completed),
+            finished_at: Set(Some(now)),
+            ..Default::default()
+        })
+        .filter(Column::Id.eq(entry.job_id))
+        .filter(Column::State.eq(State::Running))
+        .filter(Expr::col(Column::Processed).gte(Expr::col(Column::Total)))
+        .exec(conn)
+        .await?;
+    if completed.rows_affected == 0 {
+        return Ok(None);
+    }
+
+    let job = find_job_by_id(conn, entry.job_id).await?;
+    if let Some(job) = &job {
+        info!(
+            rejudge_job_id = job.id,
+            game_id = job.game_id,
+            challenge_id = job.challenge_id,
+            total = job.total,
+            changed = job.changed,
+            "rejudge job completed"
+        );
+    }
+    Ok(job)
+}
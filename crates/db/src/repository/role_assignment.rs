@@ -0,0 +1,82 @@
+//! Database access for scoped role assignments.
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, Order, QueryFilter, QueryOrder,
+};
+use tracing::info;
+
+pub(crate) use crate::entity::role_assignment::{Column, Entity};
+use crate::traits::DbError;
+pub use crate::{
+    dto::role_assignment::{Permission, RoleAssignmentView},
+    entity::role_assignment::{ActiveModel, Role},
+};
+
+/// Lists every role assignment of a user.
+pub async fn find_by_user_id(
+    conn: &impl ConnectionTrait,
+    user_id: i64,
+) -> Result<Vec<RoleAssignmentView>, DbError> {
+    Ok(Entity::find()
+        .filter(Column::UserId.eq(user_id))
+        .order_by(Column::Id, Order::Asc)
+        .into_model::<RoleAssignmentView>()
+        .all(conn)
+        .await?)
+}
+
+/// Returns whether any assignment of the user grants `permission`.
+pub async fn is_granted(
+    conn: &impl ConnectionTrait,
+    user_id: i64,
+    permission: Permission,
+) -> Result<bool, DbError> {
+    Ok(find_by_user_id(conn, user_id)
+        .await?
+        .iter()
+        .any(|assignment| assignment.grants(permission)))
+}
+
+/// Inserts a new row and returns the persisted assignment.
+pub async fn create(
+    conn: &impl ConnectionTrait,
+    model: ActiveModel,
+) -> Result<RoleAssignmentView, DbError> {
+    let assignment = model.insert(conn).await?;
+    info!(
+        role_assignment_id = assignment.id,
+        user_id = assignment.user_id,
+        role = ?assignment.role,
+        game_id = ?assignment.game_id,
+        challenge_id = ?assignment.challenge_id,
+        "role assigned"
+    );
+
+    Entity::find_by_id(assignment.id)
+        .into_model::<RoleAssignmentView>()
+        .one(conn)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("role_assignment_{}", assignment.id)))
+}
+
+/// Removes one assignment of a user.
+pub async fn delete(
+    conn: &impl ConnectionTrait,
+    user_id: i64,
+    role_assignment_id: i64,
+) -> Result<(), DbError> {
+    let result = Entity::delete_many()
+        .filter(Column::Id.eq(role_assignment_id))
+        .filter(Column::UserId.eq(user_id))
+        .exec(conn)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(DbError::NotFound(format!(
+            "role_assignment_{role_assignment_id}"
+        )));
+    }
+    info!(role_assignment_id, user_id, "role unassigned");
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! Database access for checker and IdP script revisions.
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+use tracing::info;
+
+pub(crate) use crate::entity::script_revision::{Column, Entity};
+use crate::traits::DbError;
+pub use crate::{
+    dto::script_revision::{ScriptRevisionSummary, ScriptRevisionView},
+    entity::script_revision::ActiveModel,
+};
+
+/// The script a revision belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Owner {
+    Checker(i64),
+    Idp(i64),
+}
+
+impl Owner {
+    fn filter<Q: QueryFilter>(self, query: Q) -> Q {
+        match self {
+            Self::Checker(challenge_id) => query.filter(Column::ChallengeId.eq(challenge_id)),
+            Self::Idp(idp_id) => query.filter(Column::IdpId.eq(idp_id)),
+        }
+    }
+}
+
+/// Contents of a revision to record.
+#[derive(Clone, Debug, Default)]
+pub struct NewRevision {
+    pub script: String,
+    pub allowed_hosts: Vec<String>,
+    pub author_id: Option<i64>,
+    pub restored_from: Option<i32>,
+    pub markers: serde_json::Value,
+}
+
+/// Lists an owner's revisions, newest first.
+pub async fn find_by_owner<T>(
+    conn: &impl ConnectionTrait,
+    owner: Owner,
+) -> Result<Vec<T>, DbError>
+where
+    T: FromQueryResult, {
+    Ok(owner
+        .filter(Entity::find())
+        .order_by_desc(Column::Number)
+        .into_model::<T>()
+        .all(conn)
+        .await?)
+}
+
+pub async fn find_by_number<T>(
+    conn: &impl ConnectionTrait,
+    owner: Owner,
+    number: i32,
+) -> Result<Option<T>, DbError>
+where
+    T: FromQueryResult, {
+    Ok(owner
+        .filter(Entity::find())
+        .filter(Column::Number.eq(number))
+        .into_model::<T>()
+        .one(conn)
+        .await?)
+}
+
+/// Appends a revision numbered after the owner's latest one.
+pub async fn create<T>(
+    conn: &impl ConnectionTrait,
+    owner: Owner,
+    revision: NewRevision,
+) -> Result<T, DbError>
+where
+    T: FromQueryResult, {
+    let latest = owner
+        .filter(Entity::find())
+        .select_only()
+        .column_as(Column::Number.max(), "number")
+        .into_tuple::<Option<i32>>()
+        .one(conn)
+        .await?
+        .flatten()
+        .unwrap_or(0);
+    let (challenge_id, idp_id) = match owner {
+        Owner::Checker(challenge_id) => (Some(challenge_id), None),
+        Owner::Idp(idp_id) => (None, Some(idp_id)),
+    };
+    let model = ActiveModel {
+        challenge_id: Set(challenge_id),
+        idp_id: Set(idp_id),
+        number: Set(latest + 1),
+        script: Set(revision.script),
+        allowed_hosts: Set(revision.allowed_hosts),
+        author_id: Set(revision.author_id),
+        restored_from: Set(revision.restored_from),
+        markers: Set(revision.markers),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+    info!(
+        revision_id = model.id,
+        number = model.number,
+        ?owner,
+        "script revision recorded"
+    );
+    Entity::find_by_id(model.id)
+        .into_model::<T>()
+        .one(conn)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("script_revision_{}", model.id)))
+}
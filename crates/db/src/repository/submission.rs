@@ -77,7 +77,8 @@ fn solve_owner_lock_query(owner: SolveOwner) -> sea_orm::sea_query::SelectStatem
 }
 
 /// Checks whether the same team/game/challenge or standalone user/challenge
-/// scope has another correct submission.
+/// scope has another correct submission for the same flag. Submissions
+/// without a flag id form their own single-flag scope.
 pub async fn has_other_correct_in_scope(
     conn: &impl ConnectionTrait,
     submission: &SubmissionView,
@@ -92,21 +93,26 @@ pub async fn has_other_correct_in_scope(
 fn other_correct_in_scope_query(
     submission: &SubmissionView,
 ) -> Result<sea_orm::Select<Entity>, DbError> {
-    let mut query = Entity::find()
+    Ok(Entity::find()
         .select_only()
         .column(Column::Id)
-        .filter(Column::Id.ne(submission.id))
-        .filter(Column::ChallengeId.eq(submission.challenge_id))
-        .filter(Column::Status.eq(Status::Correct));
+        .filter(other_correct_in_scope(submission)?))
+}
 
-    query = match (submission.game_id, submission.team_id) {
-        (Some(game_id), Some(team_id)) => query
-            .filter(Column::GameId.eq(game_id))
-            .filter(Column::TeamId.eq(team_id)),
-        (None, None) => query
-            .filter(Column::GameId.is_null())
-            .filter(Column::TeamId.is_null())
-            .filter(Column::UserId.eq(submission.user_id)),
+fn other_correct_in_scope(submission: &SubmissionView) -> Result<Condition, DbError> {
+    let condition = Condition::all()
+        .add(Column::Id.ne(submission.id))
+        .add(Column::ChallengeId.eq(submission.challenge_id))
+        .add(Column::Status.eq(Status::Correct));
+
+    let condition = match (submission.game_id, submission.team_id) {
+        (Some(game_id), Some(team_id)) => condition
+            .add(Column::GameId.eq(game_id))
+            .add(Column::TeamId.eq(team_id)),
+        (None, None) => condition
+            .add(Column::GameId.is_null())
+            .add(Column::TeamId.is_null())
+            .add(Column::UserId.eq(submission.user_id)),
         _ => {
             return Err(DbError::BadRequest(format!(
                 "submission_{}_has_invalid_solve_scope",
@@ -115,7 +121,104 @@ fn other_correct_in_scope_query(
         }
     };
 
-    Ok(query)
+    Ok(match &submission.flag_id {
+        Some(flag_id) => condition.add(Column::FlagId.eq(flag_id.as_str())),
+        None => condition.add(Column::FlagId.is_null()),
+    })
+}
+
+/// Orders submissions by `(created_at, id)`, the order solves are ranked in.
+fn submitted_before(submission: &SubmissionView) -> Condition {
+    Condition::any()
+        .add(Column::CreatedAt.lt(submission.created_at))
+        .add(
+            Condition::all()
+                .add(Column::CreatedAt.eq(submission.created_at))
+                .add(Column::Id.lt(submission.id)),
+        )
+}
+
+/// Like [`has_other_correct_in_scope`], but ignores solves of lower credit, so
+/// a full-credit submission can still improve on an earlier partial one.
+pub async fn has_equal_or_higher_credit_correct_in_scope(
+    conn: &impl ConnectionTrait,
+    submission: &SubmissionView,
+) -> Result<bool, DbError> {
+    Ok(other_correct_in_scope_query(submission)?
+        .filter(Column::Credit.gte(submission.credit))
+        .into_tuple::<i64>()
+        .one(conn)
+        .await?
+        .is_some())
+}
+
+/// Turns lower-credit correct submissions in the same scope into duplicates,
+/// for a submission that supersedes them. This keeps a single correct solve
+/// per flag, so a replaced partial solve no longer takes a rank slot.
+pub async fn demote_lower_credit_correct_in_scope(
+    conn: &impl ConnectionTrait,
+    submission: &SubmissionView,
+) -> Result<u64, DbError> {
+    Ok(Entity::update_many()
+        .set(ActiveModel {
+            status: Set(Status::Duplicate),
+            pts: Set(0),
+            rank: Set(0),
+            ..Default::default()
+        })
+        .filter(other_correct_in_scope(submission)?)
+        .filter(Column::Credit.lt(submission.credit))
+        .exec(conn)
+        .await?
+        .rows_affected)
+}
+
+/// Matches submissions preferred over this one as the solve of its flag:
+/// higher credit first, then the earlier `(created_at, id)`.
+fn preferred_over(submission: &SubmissionView) -> Condition {
+    Condition::any()
+        .add(Column::Credit.gt(submission.credit))
+        .add(
+            Condition::all()
+                .add(Column::Credit.eq(submission.credit))
+                .add(submitted_before(submission)),
+        )
+}
+
+/// Like [`has_other_correct_in_scope`], but only counts correct submissions
+/// preferred over this one (see [`preferred_over`]). Re-judged submissions are
+/// finalized in any order, so the best and earliest correct one must win
+/// regardless of which is checked first.
+pub async fn has_preferred_correct_in_scope(
+    conn: &impl ConnectionTrait,
+    submission: &SubmissionView,
+) -> Result<bool, DbError> {
+    Ok(other_correct_in_scope_query(submission)?
+        .filter(preferred_over(submission))
+        .into_tuple::<i64>()
+        .one(conn)
+        .await?
+        .is_some())
+}
+
+/// Turns the other correct submissions in the same scope into duplicates, for
+/// a re-judged submission that becomes the preferred solve.
+pub async fn demote_other_correct_in_scope(
+    conn: &impl ConnectionTrait,
+    submission: &SubmissionView,
+) -> Result<u64, DbError> {
+    Ok(Entity::update_many()
+        .set(ActiveModel {
+            status: Set(Status::Duplicate),
+            pts: Set(0),
+            rank: Set(0),
+            ..Default::default()
+        })
+        .filter(other_correct_in_scope(submission)?)
+        .filter(preferred_over(submission).not())
+        .exec(conn)
+        .await?
+        .rows_affected)
 }
 
 impl TryFrom<crate::entity::submission::ModelEx> for SubmissionView {
@@ -176,6 +279,9 @@ impl TryFrom<crate::entity::submission::ModelEx> for SubmissionView {
             checked_at: submission.checked_at,
             pts: submission.pts,
             rank: submission.rank,
+            feedback: submission.feedback,
+            flag_id: submission.flag_id,
+            credit: submission.credit,
         })
     }
 }
@@ -247,6 +353,8 @@ pub struct ScoreInput {
     pub id: i64,
     pub challenge_id: i64,
     pub team_id: Option<i64>,
+    pub flag_id: Option<String>,
+    pub credit: i32,
     pub created_at: i64,
     pub pts: i64,
     pub rank: i64,
@@ -278,6 +386,8 @@ fn score_input_query(game_id: i64) -> sea_orm::Select<Entity> {
             Column::Id,
             Column::ChallengeId,
             Column::TeamId,
+            Column::FlagId,
+            Column::Credit,
             Column::CreatedAt,
             Column::Pts,
             Column::Rank,
@@ -517,18 +627,41 @@ pub async fn release_processing(
     Ok(result.rows_affected == 1)
 }
 
+/// Checker details stored alongside a final status.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Judgement {
+    pub feedback: Option<String>,
+    pub flag_id: Option<String>,
+    pub credit: i32,
+}
+
+impl Default for Judgement {
+    fn default() -> Self {
+        Self {
+            feedback: None,
+            flag_id: None,
+            credit: 100,
+        }
+    }
+}
+
 /// Stores a final status only when the submission is still owned by a checker.
+/// `checked_at` is normally now; re-judges keep the original time.
 pub async fn finish_processing(
     conn: &impl ConnectionTrait,
     submission_id: i64,
     processing_at: i64,
     status: Status,
+    judgement: Judgement,
+    checked_at: i64,
 ) -> Result<Option<SubmissionView>, DbError> {
-    let now = time::OffsetDateTime::now_utc().unix_timestamp();
     let result = Entity::update_many()
         .set(ActiveModel {
             status: Set(status),
-            checked_at: Set(Some(now)),
+            checked_at: Set(Some(checked_at)),
+            feedback: Set(judgement.feedback),
+            flag_id: Set(judgement.flag_id),
+            credit: Set(judgement.credit),
             ..Default::default()
         })
         .filter(Column::Id.eq(submission_id))
@@ -772,6 +905,9 @@ mod score_tests {
             checked_at: None,
             pts: 0,
             rank: 0,
+            feedback: None,
+            flag_id: None,
+            credit: 100,
         }
     }
 
@@ -819,6 +955,14 @@ mod score_tests {
         assert!(team.sql.contains("\"submissions\".\"status\" = $3"));
         assert!(team.sql.contains("\"submissions\".\"game_id\" = $4"));
         assert!(team.sql.contains("\"submissions\".\"team_id\" = $5"));
+        assert!(team.sql.contains("\"submissions\".\"flag_id\" IS NULL"));
+
+        let mut flagged = submission(7, 9, Some(3), Some(7));
+        flagged.flag_id = Some("part1".to_owned());
+        let flagged = other_correct_in_scope_query(&flagged)
+            .unwrap()
+            .build(DbBackend::Postgres);
+        assert!(flagged.sql.contains("\"submissions\".\"flag_id\" = $6"));
 
         let standalone = other_correct_in_scope_query(&submission(6, 9, None, None))
             .unwrap()
@@ -836,6 +980,19 @@ mod score_tests {
         assert!(standalone.sql.contains("\"submissions\".\"user_id\" = $4"));
     }
 
+    #[test]
+    fn preferred_correct_query_orders_by_credit_then_creation_then_id() {
+        let mut rejudged = submission(5, 9, Some(3), Some(7));
+        rejudged.created_at = 1_700_000_000;
+        let query = other_correct_in_scope_query(&rejudged)
+            .unwrap()
+            .filter(preferred_over(&rejudged))
+            .build(DbBackend::Postgres);
+        assert!(query.sql.contains(
+            "(\"submissions\".\"credit\" > $6 OR (\"submissions\".\"credit\" = $7 AND (\"submissions\".\"created_at\" < $8 OR (\"submissions\".\"created_at\" = $9 AND \"submissions\".\"id\" < $10))))"
+        ));
+    }
+
     #[test]
     fn score_updates_are_chunked_and_built_with_bound_values() {
         let updates = (0..super::super::BULK_UPDATE_BATCH_SIZE + 1)
@@ -471,6 +471,22 @@ where
         .await?)
 }
 
+/// Looks up a team by its exact name within one game.
+pub async fn find_by_name_in_game<T>(
+    conn: &impl ConnectionTrait,
+    game_id: i64,
+    name: &str,
+) -> Result<Option<T>, DbError>
+where
+    T: FromQueryResult, {
+    Ok(Entity::find()
+        .filter(Column::GameId.eq(game_id))
+        .filter(Column::Name.eq(name))
+        .into_model::<T>()
+        .one(conn)
+        .await?)
+}
+
 /// Inserts a new row and returns the persisted model.
 pub async fn create<T>(conn: &impl ConnectionTrait, model: ActiveModel) -> Result<T, DbError>
 where
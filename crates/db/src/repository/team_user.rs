@@ -1,8 +1,8 @@
 //! Database access for `team_user` — SeaORM queries, updates, and DTOs.
 
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult, PaginatorTrait,
-    QueryFilter,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult, JoinType,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait,
 };
 use tracing::info;
 
@@ -12,7 +12,10 @@ use super::{
 };
 pub(crate) use crate::entity::team_user::{Column, Entity, Relation};
 use crate::traits::DbError;
-pub use crate::{dto::team_user::TeamUserView, entity::team_user::ActiveModel};
+pub use crate::{
+    dto::team_user::{TeamRosterEntry, TeamUserView},
+    entity::team_user::ActiveModel,
+};
 
 #[derive(Clone, Debug, Default)]
 pub struct FindTeamUserOptions {
@@ -101,6 +104,48 @@ where
         .await?)
 }
 
+/// Returns every member of every team in a game, one row per member.
+///
+/// Users with several addresses are reported once, preferring a verified
+/// address.
+pub async fn find_roster(
+    conn: &impl ConnectionTrait,
+    game_id: i64,
+) -> Result<Vec<TeamRosterEntry>, DbError> {
+    let mut roster = roster_query(game_id)
+        .into_model::<TeamRosterEntry>()
+        .all(conn)
+        .await?;
+    roster.dedup_by_key(|entry| (entry.team_id, entry.user_id));
+
+    Ok(roster)
+}
+
+fn roster_query(game_id: i64) -> sea_orm::Select<TeamEntity> {
+    use crate::entity::email::{Column as EmailColumn, Relation as EmailRelation};
+
+    TeamEntity::find()
+        .select_only()
+        .column_as(TeamColumn::Id, "team_id")
+        .column_as(TeamColumn::Name, "team_name")
+        .column_as(TeamColumn::Division, "division")
+        .column_as(TeamColumn::State, "state")
+        .column_as(TeamColumn::Pts, "pts")
+        .column_as(TeamColumn::Rank, "rank")
+        .column_as(UserColumn::Id, "user_id")
+        .column_as(UserColumn::Username, "username")
+        .column_as(UserColumn::Name, "user_name")
+        .column_as(EmailColumn::Email, "email")
+        .join(JoinType::InnerJoin, Relation::Team.def().rev())
+        .join(JoinType::InnerJoin, Relation::User.def())
+        .join(JoinType::LeftJoin, EmailRelation::User.def().rev())
+        .filter(TeamColumn::GameId.eq(game_id))
+        .filter(UserColumn::DeletedAt.is_null())
+        .order_by_asc(TeamColumn::Id)
+        .order_by_asc(UserColumn::Id)
+        .order_by_desc(EmailColumn::Verified)
+}
+
 /// Inserts a new row and returns the persisted model.
 pub async fn create<T>(conn: &impl ConnectionTrait, model: ActiveModel) -> Result<T, DbError>
 where
@@ -165,4 +210,15 @@ mod tests {
         assert!(statement.sql.contains("\"user_id\" = $2"));
         assert_eq!(statement.values.unwrap().0.len(), 2);
     }
+
+    #[test]
+    fn roster_query_is_scoped_to_game_members() {
+        let statement = roster_query(7).build(DbBackend::Postgres);
+
+        assert!(statement.sql.contains("INNER JOIN \"team_users\""));
+        assert!(statement.sql.contains("INNER JOIN \"users\""));
+        assert!(statement.sql.contains("LEFT JOIN \"emails\""));
+        assert!(statement.sql.contains("\"teams\".\"game_id\" = $1"));
+        assert!(statement.sql.contains("\"deleted_at\" IS NULL"));
+    }
 }
@@ -0,0 +1,41 @@
+//! Database access for the WireGuard gateway's key pair.
+
+use sea_orm::{ConnectionTrait, EntityTrait, Set};
+use tracing::info;
+
+pub use crate::entity::wireguard_gateway::{ActiveModel, Model};
+pub(crate) use crate::entity::wireguard_gateway::{Column, Entity};
+use crate::traits::DbError;
+
+/// Id of the only row.
+const ID: i64 = 1;
+
+/// Loads the gateway's key pair, if one was created.
+pub async fn get(conn: &impl ConnectionTrait) -> Result<Option<Model>, DbError> {
+    Ok(Entity::find_by_id(ID).one(conn).await?)
+}
+
+/// Stores the gateway's key pair unless one exists, and returns the stored
+/// one; concurrent callers all get the first.
+pub async fn create(
+    conn: &impl ConnectionTrait,
+    private_key: String,
+    public_key: String,
+) -> Result<Model, DbError> {
+    let result = Entity::insert(ActiveModel {
+        id: Set(ID),
+        private_key: Set(private_key),
+        public_key: Set(public_key),
+        created_at: Set(time::OffsetDateTime::now_utc().unix_timestamp()),
+    })
+    .on_conflict_do_nothing_on([Column::Id])
+    .exec(conn)
+    .await?;
+    if matches!(result, sea_orm::TryInsertResult::Inserted(_)) {
+        info!("wireguard gateway key created");
+    }
+
+    get(conn)
+        .await?
+        .ok_or_else(|| DbError::NotFound("wireguard_gateway".to_owned()))
+}
@@ -0,0 +1,161 @@
+//! Database access for the WireGuard peers issued to teams.
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, FromQueryResult,
+    QueryFilter, QueryOrder, QuerySelect, QueryTrait, Set, sea_query::Expr,
+};
+use tracing::info;
+
+pub(crate) use crate::entity::wireguard_peer::{Column, Entity};
+use crate::traits::DbError;
+pub use crate::{
+    dto::wireguard_peer::WireguardPeerView,
+    entity::wireguard_peer::{ActiveModel, Model},
+};
+
+/// A peer about to be issued.
+#[derive(Clone, Debug)]
+pub struct NewPeer {
+    pub game_id: i64,
+    pub team_id: i64,
+    pub address: String,
+    pub private_key: String,
+    pub public_key: String,
+}
+
+/// Records a peer; fails if the team already has one or the address is
+/// taken.
+pub async fn create<T>(conn: &impl ConnectionTrait, peer: NewPeer) -> Result<T, DbError>
+where
+    T: FromQueryResult, {
+    let peer = ActiveModel {
+        game_id: Set(peer.game_id),
+        team_id: Set(peer.team_id),
+        address: Set(peer.address),
+        private_key: Set(peer.private_key),
+        public_key: Set(peer.public_key),
+        created_at: Set(time::OffsetDateTime::now_utc().unix_timestamp()),
+        revoked_at: Set(None),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+    info!(
+        game_id = peer.game_id,
+        team_id = peer.team_id,
+        address = %peer.address,
+        "wireguard peer issued"
+    );
+
+    Entity::find_by_id(peer.id)
+        .into_model::<T>()
+        .one(conn)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("wireguard_peer_{}", peer.id)))
+}
+
+/// Loads the team's peer unless it was revoked.
+pub async fn find_active_by_team<T>(
+    conn: &impl ConnectionTrait,
+    game_id: i64,
+    team_id: i64,
+) -> Result<Option<T>, DbError>
+where
+    T: FromQueryResult, {
+    Ok(Entity::find()
+        .filter(Column::GameId.eq(game_id))
+        .filter(Column::TeamId.eq(team_id))
+        .filter(Column::RevokedAt.is_null())
+        .into_model::<T>()
+        .one(conn)
+        .await?)
+}
+
+/// Loads every peer that was not revoked.
+pub async fn find_active<T>(conn: &impl ConnectionTrait) -> Result<Vec<T>, DbError>
+where
+    T: FromQueryResult, {
+    Ok(Entity::find()
+        .filter(Column::RevokedAt.is_null())
+        .order_by_asc(Column::Id)
+        .into_model::<T>()
+        .all(conn)
+        .await?)
+}
+
+/// Revokes the team's peer, if it has one.
+pub async fn revoke_by_team(
+    conn: &impl ConnectionTrait,
+    game_id: i64,
+    team_id: i64,
+    now: i64,
+) -> Result<bool, DbError> {
+    let result = Entity::update_many()
+        .col_expr(Column::RevokedAt, Expr::value(now))
+        .filter(Column::GameId.eq(game_id))
+        .filter(Column::TeamId.eq(team_id))
+        .filter(Column::RevokedAt.is_null())
+        .exec(conn)
+        .await?;
+    if result.rows_affected > 0 {
+        info!(game_id, team_id, "wireguard peer revoked");
+    }
+
+    Ok(result.rows_affected > 0)
+}
+
+/// Revokes the peers of banned teams and of games that ended before `now`,
+/// returning how many were revoked.
+pub async fn revoke_stale(conn: &impl ConnectionTrait, now: i64) -> Result<u64, DbError> {
+    let result = Entity::update_many()
+        .col_expr(Column::RevokedAt, Expr::value(now))
+        .filter(Column::RevokedAt.is_null())
+        .filter(stale_condition(now))
+        .exec(conn)
+        .await?;
+    if result.rows_affected > 0 {
+        info!(
+            count = result.rows_affected,
+            "stale wireguard peers revoked"
+        );
+    }
+
+    Ok(result.rows_affected)
+}
+
+fn stale_condition(now: i64) -> Condition {
+    let banned_teams = crate::entity::team::Entity::find()
+        .select_only()
+        .column(crate::entity::team::Column::Id)
+        .filter(crate::entity::team::Column::State.eq(crate::entity::team::State::Banned))
+        .into_query();
+    let ended_games = crate::entity::game::Entity::find()
+        .select_only()
+        .column(crate::entity::game::Column::Id)
+        .filter(crate::entity::game::Column::EndedAt.lt(now))
+        .into_query();
+
+    Condition::any()
+        .add(Column::TeamId.in_subquery(banned_teams))
+        .add(Column::GameId.in_subquery(ended_games))
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DbBackend, EntityTrait, QueryFilter, QueryTrait};
+
+    use super::{Entity, stale_condition};
+
+    #[test]
+    fn stale_peers_belong_to_banned_teams_or_ended_games() {
+        let statement = Entity::find()
+            .filter(stale_condition(1_000))
+            .build(DbBackend::Postgres);
+
+        assert!(statement.sql.contains("\"team_id\" IN (SELECT"));
+        assert!(statement.sql.contains("\"game_id\" IN (SELECT"));
+        assert!(statement.sql.contains(" OR "));
+        assert!(statement.sql.contains("\"ended_at\" < $2"));
+        assert_eq!(statement.values.unwrap().0.len(), 2);
+    }
+}
@@ -0,0 +1,65 @@
+use cds_db::{
+    DbError,
+    rejudge::{self, NewRejudge},
+    sea_orm,
+};
+use sea_orm::{ConnectOptions, ConnectionTrait, Database};
+
+fn new_rejudge() -> NewRejudge {
+    NewRejudge {
+        game_id: 7,
+        challenge_id: 10,
+        status: None,
+        created_from: None,
+        created_until: None,
+        author_id: None,
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires CDS_TEST_DATABASE_URL pointing to a migrated PostgreSQL database"]
+async fn rejudging_cheat_submissions_waits_for_manual_unban() {
+    let database_url = std::env::var("CDS_TEST_DATABASE_URL")
+        .expect("CDS_TEST_DATABASE_URL must be set for this ignored test");
+    let mut options = ConnectOptions::new(database_url);
+    options.max_connections(1).min_connections(1);
+    let conn = Database::connect(options).await.unwrap();
+
+    // A one-connection pool keeps these shadow tables visible to every query.
+    // Closing the connection removes all test data.
+    conn.execute_unprepared(
+        r#"
+            CREATE TEMP TABLE teams (LIKE public.teams INCLUDING ALL);
+            CREATE TEMP TABLE submissions (LIKE public.submissions INCLUDING ALL);
+            CREATE TEMP TABLE rejudge_jobs (LIKE public.rejudge_jobs INCLUDING ALL);
+            CREATE TEMP TABLE rejudge_entries (LIKE public.rejudge_entries INCLUDING ALL);
+
+            INSERT INTO teams (
+                id, game_id, name, email, slogan, avatar_hash, has_writeup,
+                state, pts, rank
+            ) VALUES (100, 7, 'Cheater', NULL, NULL, NULL, FALSE, 0, 0, 0);
+            INSERT INTO submissions (
+                id, content, status, challenge_id, user_id, team_id,
+                game_id, created_at, processing_at, checked_at, pts, rank
+            ) VALUES (1, 'flag', 'cheat', 10, 1, 100, 7, 0, NULL, 1, 0, 0);
+        "#,
+    )
+    .await
+    .unwrap();
+
+    let refused = rejudge::create(&conn, new_rejudge()).await;
+    assert!(
+        matches!(&refused, Err(DbError::BadRequest(reason)) if reason == "cheat_teams_still_banned"),
+        "{refused:?}"
+    );
+
+    // Once the admin has lifted the ban, the submission may be judged again.
+    conn.execute_unprepared("UPDATE teams SET state = 3 WHERE id = 100")
+        .await
+        .unwrap();
+    let (job, submission_ids) = rejudge::create(&conn, new_rejudge()).await.unwrap();
+    assert_eq!(job.total, 1);
+    assert_eq!(submission_ids, vec![1]);
+
+    conn.close().await.unwrap();
+}
@@ -0,0 +1,642 @@
+//! Static checks on a parsed script, reported as lint warnings.
+//!
+//! The walk resolves every name against the enclosing local scopes and the
+//! described API: unknown globals and namespace members, wrong argument
+//! counts for described functions, locals that are never read and statements
+//! that can never run. Globals the script assigns anywhere are treated as
+//! defined, since scripts commonly declare entry points and shared state
+//! that way.
+
+use std::collections::{BTreeMap, HashSet};
+
+use full_moon::{
+    ast::{
+        Block, Call, Expression, Field, FunctionArgs, FunctionBody, FunctionCall, Index, LastStmt,
+        Parameter, Prefix, Stmt, Suffix, TableConstructor, Var,
+    },
+    node::Node,
+    tokenizer::TokenReference,
+};
+
+use crate::{
+    api::{self, ApiEntry, ApiKind},
+    traits::{DiagnosticKind, DiagnosticMarker},
+    util::{self, DiagnosticSpan},
+};
+
+/// Globals every execution environment provides besides the described API.
+const ENVIRONMENT_GLOBALS: &[&str] = &["_ENV", "_G"];
+
+struct Local {
+    name: String,
+    span: DiagnosticSpan,
+    used: bool,
+    /// Parameters and loop variables are exempt from unused warnings.
+    reported: bool,
+}
+
+/// A dotted chain rooted at a global, e.g. `checker.audit.correct(...)`.
+struct GlobalRead {
+    segments: Vec<(String, DiagnosticSpan)>,
+    call: Option<CallSite>,
+}
+
+struct CallSite {
+    span: DiagnosticSpan,
+    arguments: usize,
+    /// The last argument may expand to several values.
+    open: bool,
+}
+
+struct Analyzer<'a> {
+    source: &'a str,
+    scopes: Vec<Vec<Local>>,
+    reads: Vec<GlobalRead>,
+    writes: HashSet<String>,
+    markers: Vec<DiagnosticMarker>,
+}
+
+/// Runs the static checks, assuming the script already compiled.
+pub(crate) fn analyze(source: &str, api: &BTreeMap<String, ApiEntry>) -> Vec<DiagnosticMarker> {
+    let parsed = full_moon::parse_fallible(source, full_moon::LuaVersion::lua54());
+    if !parsed.errors().is_empty() {
+        return Vec::new();
+    }
+    let mut analyzer = Analyzer {
+        source,
+        scopes: Vec::new(),
+        reads: Vec::new(),
+        writes: HashSet::new(),
+        markers: Vec::new(),
+    };
+    analyzer.block(parsed.ast().nodes(), Vec::new());
+    analyzer.resolve(api);
+    analyzer
+        .markers
+        .sort_by_key(|marker| (marker.start_line, marker.start_column));
+    analyzer.markers
+}
+
+fn name(token: &TokenReference) -> String {
+    token.token().to_string()
+}
+
+impl Analyzer<'_> {
+    fn span(&self, node: &impl Node) -> Option<DiagnosticSpan> {
+        let (start, end) = node.range()?;
+        Some(util::parser_span(
+            self.source,
+            start,
+            end,
+            start.line().saturating_sub(1),
+        ))
+    }
+
+    fn warn(&mut self, message: String, span: DiagnosticSpan) {
+        self.markers.push(DiagnosticMarker {
+            kind: DiagnosticKind::Warning,
+            message,
+            start_line: span.start_line,
+            start_column: span.start_column,
+            end_line: span.end_line,
+            end_column: span.end_column,
+        });
+    }
+
+    fn local(&self, token: &TokenReference, reported: bool) -> Option<Local> {
+        Some(Local {
+            name: name(token),
+            span: self.span(token)?,
+            used: false,
+            reported,
+        })
+    }
+
+    fn declare(&mut self, local: Option<Local>) {
+        if let (Some(local), Some(scope)) = (local, self.scopes.last_mut()) {
+            scope.push(local);
+        }
+    }
+
+    /// Marks the innermost local called `name` as read; false for globals.
+    fn use_local(&mut self, name: &str) -> bool {
+        let local = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.iter_mut().rev().find(|local| local.name == name));
+        match local {
+            Some(local) => {
+                local.used = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .any(|scope| scope.iter().any(|local| local.name == name))
+    }
+
+    fn block(&mut self, block: &Block, declared: Vec<Local>) {
+        self.scopes.push(declared);
+        self.statements(block);
+        self.close_scope();
+    }
+
+    fn close_scope(&mut self) {
+        for local in self.scopes.pop().unwrap_or_default() {
+            if local.reported && !local.used && !local.name.starts_with('_') {
+                self.warn(format!("Unused local `{}`", local.name), local.span);
+            }
+        }
+    }
+
+    fn statements(&mut self, block: &Block) {
+        let mut terminated = false;
+        let mut unreachable: Option<DiagnosticSpan> = None;
+        for stmt in block.stmts() {
+            if matches!(stmt, Stmt::Label(_)) {
+                terminated = false;
+                self.report_unreachable(unreachable.take());
+            } else if terminated && let Some(span) = self.span(stmt) {
+                unreachable = Some(match unreachable {
+                    Some(start) => DiagnosticSpan {
+                        end_line: span.end_line,
+                        end_column: span.end_column,
+                        ..start
+                    },
+                    None => span,
+                });
+            }
+            self.stmt(stmt);
+            terminated |= self.terminates(stmt);
+        }
+        if let Some(last) = block.last_stmt() {
+            if terminated && let Some(span) = self.span(last) {
+                unreachable = Some(match unreachable {
+                    Some(start) => DiagnosticSpan {
+                        end_line: span.end_line,
+                        end_column: span.end_column,
+                        ..start
+                    },
+                    None => span,
+                });
+            }
+            if let LastStmt::Return(ret) = last {
+                for expression in ret.returns() {
+                    self.expression(expression);
+                }
+            }
+        }
+        self.report_unreachable(unreachable);
+    }
+
+    fn report_unreachable(&mut self, span: Option<DiagnosticSpan>) {
+        if let Some(span) = span {
+            self.warn("Unreachable code".to_owned(), span);
+        }
+    }
+
+    /// Whether control never continues past `stmt` in its block.
+    fn terminates(&self, stmt: &Stmt) -> bool {
+        let ends = |block: &Block| {
+            matches!(
+                block.last_stmt(),
+                Some(LastStmt::Return(_) | LastStmt::Break(_))
+            ) || block.stmts().any(|stmt| self.terminates(stmt))
+        };
+        match stmt {
+            Stmt::Goto(_) => true,
+            Stmt::Do(block) => ends(block.block()),
+            Stmt::If(branch) => {
+                branch.else_block().is_some_and(ends)
+                    && ends(branch.block())
+                    && branch
+                        .else_if()
+                        .is_none_or(|branches| branches.iter().all(|branch| ends(branch.block())))
+            }
+            Stmt::FunctionCall(call) => {
+                matches!(call.prefix(), Prefix::Name(token) if name(token) == "error")
+                    && call.suffixes().count() == 1
+                    && !self.is_local("error")
+            }
+            _ => false,
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Assignment(assignment) => {
+                for expression in assignment.expressions() {
+                    self.expression(expression);
+                }
+                for var in assignment.variables() {
+                    self.assign(var);
+                }
+            }
+            Stmt::Do(block) => self.block(block.block(), Vec::new()),
+            Stmt::FunctionCall(call) => self.function_call(call),
+            Stmt::FunctionDeclaration(declaration) => {
+                let function_name = declaration.name();
+                let mut path = function_name.names().iter().map(name).collect::<Vec<_>>();
+                if let Some(method) = function_name.method_name() {
+                    path.push(name(method));
+                }
+                if path.len() == 1 {
+                    if !self.is_local(&path[0]) {
+                        self.writes.insert(path[0].clone());
+                    }
+                } else if !self.use_local(&path[0]) {
+                    self.writes.insert(path.join("."));
+                }
+                let receiver = function_name.method_colon().is_some();
+                self.function_body(declaration.body(), receiver);
+            }
+            Stmt::GenericFor(generic) => {
+                for expression in generic.expressions() {
+                    self.expression(expression);
+                }
+                let names = generic
+                    .names()
+                    .iter()
+                    .filter_map(|token| self.local(token, false))
+                    .collect();
+                self.block(generic.block(), names);
+            }
+            Stmt::If(branch) => {
+                self.expression(branch.condition());
+                self.block(branch.block(), Vec::new());
+                for branch in branch.else_if().into_iter().flatten() {
+                    self.expression(branch.condition());
+                    self.block(branch.block(), Vec::new());
+                }
+                if let Some(block) = branch.else_block() {
+                    self.block(block, Vec::new());
+                }
+            }
+            Stmt::LocalAssignment(assignment) => {
+                for expression in assignment.expressions() {
+                    self.expression(expression);
+                }
+                let attributes = assignment.attributes().collect::<Vec<_>>();
+                for (index, token) in assignment.names().iter().enumerate() {
+                    // `<close>` variables are used by going out of scope.
+                    let closed = attributes
+                        .get(index)
+                        .copied()
+                        .flatten()
+                        .is_some_and(|attribute| name(attribute.name()) == "close");
+                    let local = self.local(token, !closed);
+                    self.declare(local);
+                }
+            }
+            Stmt::LocalFunction(function) => {
+                let local = self.local(function.name(), true);
+                self.declare(local);
+                self.function_body(function.body(), false);
+            }
+            Stmt::NumericFor(numeric) => {
+                self.expression(numeric.start());
+                self.expression(numeric.end());
+                if let Some(step) = numeric.step() {
+                    self.expression(step);
+                }
+                let index = self.local(numeric.index_variable(), false);
+                self.block(numeric.block(), index.into_iter().collect());
+            }
+            Stmt::Repeat(repeat) => {
+                // The condition sees the body's locals.
+                self.scopes.push(Vec::new());
+                self.statements(repeat.block());
+                self.expression(repeat.until());
+                self.close_scope();
+            }
+            Stmt::While(body) => {
+                self.expression(body.condition());
+                self.block(body.block(), Vec::new());
+            }
+            _ => {}
+        }
+    }
+
+    fn function_body(&mut self, body: &FunctionBody, receiver: bool) {
+        let mut parameters = Vec::new();
+        if receiver {
+            parameters.push(Local {
+                name: "self".to_owned(),
+                span: DiagnosticSpan {
+                    start_line: 0,
+                    start_column: 0,
+                    end_line: 0,
+                    end_column: 0,
+                },
+                used: false,
+                reported: false,
+            });
+        }
+        for parameter in body.parameters() {
+            if let Parameter::Name(token) = parameter {
+                parameters.extend(self.local(token, false));
+            }
+        }
+        self.block(body.block(), parameters);
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::BinaryOperator { lhs, rhs, .. } => {
+                self.expression(lhs);
+                self.expression(rhs);
+            }
+            Expression::Parentheses { expression, .. }
+            | Expression::UnaryOperator { expression, .. } => self.expression(expression),
+            Expression::Function(function) => self.function_body(function.body(), false),
+            Expression::FunctionCall(call) => self.function_call(call),
+            Expression::TableConstructor(table) => self.table(table),
+            Expression::Var(Var::Name(token)) => self.read(token, &[], None),
+            Expression::Var(Var::Expression(var)) => {
+                self.chain(var.prefix(), &var.suffixes().collect::<Vec<_>>(), None);
+            }
+            _ => {}
+        }
+    }
+
+    fn table(&mut self, table: &TableConstructor) {
+        for field in table.fields() {
+            match field {
+                Field::ExpressionKey { key, value, .. } => {
+                    self.expression(key);
+                    self.expression(value);
+                }
+                Field::NameKey { value, .. } | Field::NoKey(value) => self.expression(value),
+                _ => {}
+            }
+        }
+    }
+
+    fn function_call(&mut self, call: &FunctionCall) {
+        let span = self.span(call);
+        self.chain(call.prefix(), &call.suffixes().collect::<Vec<_>>(), span);
+    }
+
+    /// Visits `prefix` and `suffixes`, recording the leading dotted chain when
+    /// it starts at a name.
+    fn chain(&mut self, prefix: &Prefix, suffixes: &[&Suffix], call_span: Option<DiagnosticSpan>) {
+        match prefix {
+            Prefix::Name(token) => {
+                let fields = suffixes
+                    .iter()
+                    .map_while(|suffix| match suffix {
+                        Suffix::Index(Index::Dot { name, .. }) => Some(name),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                let call = match (suffixes.get(fields.len()), call_span) {
+                    (Some(Suffix::Call(Call::AnonymousCall(args))), Some(span))
+                        if suffixes.len() == fields.len() + 1 =>
+                    {
+                        Some(call_site(args, span))
+                    }
+                    _ => None,
+                };
+                self.read(token, &fields, call);
+            }
+            Prefix::Expression(expression) => self.expression(expression),
+            _ => {}
+        }
+        for suffix in suffixes {
+            match suffix {
+                Suffix::Index(Index::Brackets { expression, .. }) => self.expression(expression),
+                Suffix::Call(Call::AnonymousCall(args)) => self.function_args(args),
+                Suffix::Call(Call::MethodCall(method)) => self.function_args(method.args()),
+                _ => {}
+            }
+        }
+    }
+
+    fn function_args(&mut self, args: &FunctionArgs) {
+        match args {
+            FunctionArgs::Parentheses { arguments, .. } => {
+                for argument in arguments {
+                    self.expression(argument);
+                }
+            }
+            FunctionArgs::TableConstructor(table) => self.table(table),
+            _ => {}
+        }
+    }
+
+    fn read(&mut self, root: &TokenReference, fields: &[&TokenReference], call: Option<CallSite>) {
+        let root_name = name(root);
+        if self.use_local(&root_name) {
+            return;
+        }
+        let segments = std::iter::once(root)
+            .chain(fields.iter().copied())
+            .filter_map(|token| Some((name(token), self.span(token)?)))
+            .collect::<Vec<_>>();
+        if segments.len() == fields.len() + 1 {
+            self.reads.push(GlobalRead { segments, call });
+        }
+    }
+
+    fn assign(&mut self, var: &Var) {
+        match var {
+            Var::Name(token) => {
+                let target = name(token);
+                if !self.is_local(&target) {
+                    self.writes.insert(target);
+                }
+            }
+            Var::Expression(var) => {
+                let suffixes = var.suffixes().collect::<Vec<_>>();
+                if let Prefix::Name(root) = var.prefix()
+                    && !self.is_local(&name(root))
+                {
+                    let fields = suffixes
+                        .iter()
+                        .map_while(|suffix| match suffix {
+                            Suffix::Index(Index::Dot { name: field, .. }) => Some(name(field)),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    if fields.len() == suffixes.len() {
+                        self.writes.insert(
+                            std::iter::once(name(root))
+                                .chain(fields)
+                                .collect::<Vec<_>>()
+                                .join("."),
+                        );
+                    }
+                }
+                // Writing a member reads the table it belongs to.
+                let (_, object) = suffixes.split_last().expect("var expression has suffixes");
+                self.chain(var.prefix(), object, None);
+                if let Some(Suffix::Index(Index::Brackets { expression, .. })) = suffixes.last() {
+                    self.expression(expression);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn resolve(&mut self, api: &BTreeMap<String, ApiEntry>) {
+        for read in std::mem::take(&mut self.reads) {
+            let (root, span) = &read.segments[0];
+            if self.writes.contains(root) || ENVIRONMENT_GLOBALS.contains(&root.as_str()) {
+                continue;
+            }
+            let Some(mut entry) = api.get(root) else {
+                self.warn(format!("Undefined global `{root}`"), *span);
+                continue;
+            };
+            let mut resolved = true;
+            for (field, span) in &read.segments[1..] {
+                if entry.kind != ApiKind::Namespace {
+                    resolved = false;
+                    break;
+                }
+                let path = format!("{}.{field}", entry.path);
+                match api.get(&path) {
+                    Some(member) => entry = member,
+                    None => {
+                        if !self.writes.contains(&path) {
+                            self.warn(
+                                format!("Unknown field `{field}` in `{}`", entry.path),
+                                *span,
+                            );
+                        }
+                        resolved = false;
+                        break;
+                    }
+                }
+            }
+            let (Some(call), true) = (&read.call, resolved) else {
+                continue;
+            };
+            if self.writes.contains(&entry.path) {
+                continue;
+            }
+            let Some((min, max)) = entry.arity() else {
+                continue;
+            };
+            let too_few = !call.open && call.arguments < min;
+            let given = call.arguments - usize::from(call.open);
+            let too_many = max.is_some_and(|max| given > max);
+            if too_few || too_many {
+                self.warn(
+                    format!(
+                        "`{}` expects {} argument(s), got {}{}",
+                        entry.path,
+                        api::describe_arity(min, max),
+                        given,
+                        if call.open { " or more" } else { "" }
+                    ),
+                    call.span,
+                );
+            }
+        }
+    }
+}
+
+fn call_site(args: &FunctionArgs, span: DiagnosticSpan) -> CallSite {
+    match args {
+        FunctionArgs::Parentheses { arguments, .. } => CallSite {
+            span,
+            arguments: arguments.len(),
+            open: arguments.last().is_some_and(|last| {
+                matches!(
+                    last.value(),
+                    Expression::FunctionCall(_) | Expression::Symbol(_)
+                )
+            }),
+        },
+        _ => CallSite {
+            span,
+            arguments: 1,
+            open: false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::analyze;
+    use crate::{api, create_lua};
+
+    fn messages(source: &str) -> Vec<String> {
+        let lua = create_lua().unwrap();
+        let api = api::snapshot(&lua).unwrap();
+        analyze(source, &api)
+            .into_iter()
+            .map(|marker| marker.message)
+            .collect()
+    }
+
+    #[test]
+    fn reports_undefined_globals_and_members() {
+        let found = messages(
+            r#"
+            counter = 0
+            function check(value)
+                counter = counter + 1
+                log.info(json.encode(value), undefined_name)
+                return json.bogus(value), string.trim(value)
+            end
+            function string.trim(value) return value end
+            "#,
+        );
+        assert_eq!(
+            found,
+            [
+                "Undefined global `undefined_name`",
+                "Unknown field `bogus` in `json`",
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_arity_of_described_functions() {
+        let found = messages(
+            r#"
+            local text = string.rep("a")
+            local many = tostring(1, 2)
+            local open = string.rep("a", table.unpack({ 2 }))
+            print(text, many, open, select(2, ...))
+            "#,
+        );
+        assert_eq!(
+            found,
+            [
+                "`string.rep` expects 2 to 3 argument(s), got 1",
+                "`tostring` expects 1 argument(s), got 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unused_locals_and_unreachable_code() {
+        let found = messages(
+            r#"
+            local unused = 1
+            local _ignored = 2
+            local function helper(argument) return 1 end
+            function check(value)
+                for index, item in ipairs(value) do
+                    if item then break end
+                end
+                if value then
+                    error("stop")
+                    print("after error")
+                end
+                repeat local done = true until done
+                return helper()
+            end
+            "#,
+        );
+        assert_eq!(found, ["Unused local `unused`", "Unreachable code"]);
+    }
+}
@@ -0,0 +1,302 @@
+//! Machine-readable description of the Lua API visible to scripts.
+//!
+//! Native modules register call signatures next to the functions they
+//! install; [`environment`] combines them with a walk over the globals of a
+//! configured state, so the description always matches what scripts can
+//! reach. The editor uses it for completion and `lint` uses it to resolve
+//! globals and check call arity.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Mutex,
+};
+
+use mlua::{Lua, Value};
+use serde::Serialize;
+
+use crate::{ConfigureLua, create_lua, traits::EngineError};
+
+/// Namespace tables nested deeper than this are listed but not expanded.
+const MAX_DEPTH: usize = 3;
+
+/// Signatures of the standard library functions left in the sandbox.
+const STANDARD_SIGNATURES: &[&str] = &[
+    "assert(value, message?, ...)",
+    "error(message, level?)",
+    "getmetatable(value)",
+    "ipairs(list)",
+    "next(table, key?)",
+    "pairs(table)",
+    "pcall(callback, ...)",
+    "rawequal(left, right)",
+    "rawget(table, key)",
+    "rawlen(value)",
+    "rawset(table, key, value)",
+    "require(name)",
+    "select(index, ...)",
+    "setmetatable(table, metatable)",
+    "tonumber(value, base?)",
+    "tostring(value)",
+    "type(value)",
+    "xpcall(callback, handler, ...)",
+    "coroutine.close(thread)",
+    "coroutine.create(callback)",
+    "coroutine.isyieldable()",
+    "coroutine.resume(thread, ...)",
+    "coroutine.running()",
+    "coroutine.status(thread)",
+    "coroutine.wrap(callback)",
+    "coroutine.yield(...)",
+    "math.abs(value)",
+    "math.ceil(value)",
+    "math.floor(value)",
+    "math.fmod(left, right)",
+    "math.max(value, ...)",
+    "math.min(value, ...)",
+    "math.modf(value)",
+    "math.random(min?, max?)",
+    "math.randomseed(seed?, ...)",
+    "math.sqrt(value)",
+    "math.tointeger(value)",
+    "math.type(value)",
+    "math.ult(left, right)",
+    "string.byte(value, start?, finish?)",
+    "string.char(...)",
+    "string.find(value, pattern, init?, plain?)",
+    "string.format(format, ...)",
+    "string.gmatch(value, pattern, init?)",
+    "string.gsub(value, pattern, replacement, limit?)",
+    "string.len(value)",
+    "string.lower(value)",
+    "string.match(value, pattern, init?)",
+    "string.pack(format, ...)",
+    "string.packsize(format)",
+    "string.rep(value, count, separator?)",
+    "string.reverse(value)",
+    "string.sub(value, start, finish?)",
+    "string.unpack(format, value, init?)",
+    "string.upper(value)",
+    "table.concat(list, separator?, start?, finish?)",
+    "table.insert(list, position_or_value, value?)",
+    "table.move(source, start, finish, target, destination?)",
+    "table.pack(...)",
+    "table.remove(list, position?)",
+    "table.sort(list, comparator?)",
+    "table.unpack(list, start?, finish?)",
+    "utf8.char(...)",
+    "utf8.codepoint(value, start?, finish?, lax?)",
+    "utf8.codes(value, lax?)",
+    "utf8.len(value, start?, finish?, lax?)",
+    "utf8.offset(value, n, start?)",
+];
+
+struct Signature {
+    text: String,
+    doc: Option<String>,
+}
+
+struct ApiRegistry(Mutex<HashMap<String, Signature>>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKind {
+    Namespace,
+    Function,
+    Value,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+pub struct ApiParam {
+    pub name: String,
+    pub optional: bool,
+    /// `...`: accepts any number of trailing arguments.
+    pub variadic: bool,
+}
+
+/// One global, namespace member or function reachable from a script.
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct ApiEntry {
+    /// Dotted path from the script's globals, e.g. `checker.audit.correct`.
+    pub path: String,
+    pub kind: ApiKind,
+    /// Call signature as shown to script authors, e.g. `correct(options?)`.
+    pub signature: Option<String>,
+    pub params: Vec<ApiParam>,
+    pub doc: Option<String>,
+}
+
+impl ApiEntry {
+    /// Accepted argument counts, when the function has a known signature.
+    pub fn arity(&self) -> Option<(usize, Option<usize>)> {
+        self.signature.as_ref()?;
+        let min = self
+            .params
+            .iter()
+            .filter(|param| !param.optional && !param.variadic)
+            .count();
+        let max = (!self.params.iter().any(|param| param.variadic)).then_some(self.params.len());
+        Some((min, max))
+    }
+}
+
+pub(crate) fn install(lua: &Lua) {
+    lua.set_app_data(ApiRegistry(Mutex::new(HashMap::new())));
+}
+
+/// Records the signatures of functions installed in the module at `path`,
+/// or among the globals when `path` is empty.
+/// Each item is `(signature, doc)`, e.g. `("encode(value)", "Encodes ...")`;
+/// optional parameters end in `?` and `...` accepts trailing arguments.
+pub fn register(lua: &Lua, path: &str, signatures: &[(&str, &str)]) {
+    let Some(registry) = lua.app_data_ref::<ApiRegistry>() else {
+        return;
+    };
+    let mut registry = registry.0.lock().expect("lua api registry poisoned");
+    for (signature, doc) in signatures {
+        let name = signature.split('(').next().unwrap_or(signature);
+        let key = if path.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{path}.{name}")
+        };
+        registry.insert(
+            key,
+            Signature {
+                text: (*signature).to_owned(),
+                doc: (!doc.is_empty()).then(|| (*doc).to_owned()),
+            },
+        );
+    }
+}
+
+/// Describes the API of a state configured like the ones scripts run in.
+pub fn environment(configure: &ConfigureLua) -> Result<Vec<ApiEntry>, EngineError> {
+    let lua = create_lua()?;
+    configure(&lua)?;
+    Ok(snapshot(&lua)?.into_values().collect())
+}
+
+/// Walks the globals of `lua`, keyed by dotted path.
+pub(crate) fn snapshot(lua: &Lua) -> mlua::Result<BTreeMap<String, ApiEntry>> {
+    let standard = STANDARD_SIGNATURES
+        .iter()
+        .filter_map(|signature| {
+            let (path, params) = signature.split_once('(')?;
+            let name = path.rsplit('.').next().unwrap_or(path);
+            Some((path, format!("{name}({params}")))
+        })
+        .collect::<HashMap<_, _>>();
+    let registry = lua.app_data_ref::<ApiRegistry>();
+    let registry = registry
+        .as_ref()
+        .map(|registry| registry.0.lock().expect("lua api registry poisoned"));
+
+    let mut entries = BTreeMap::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![(String::new(), lua.globals(), 0)];
+    while let Some((prefix, table, depth)) = pending.pop() {
+        if !visited.insert(table.to_pointer() as usize) {
+            continue;
+        }
+        for pair in table.pairs::<Value, Value>() {
+            let (Value::String(name), value) = pair? else {
+                continue;
+            };
+            let name = name.to_string_lossy();
+            if prefix.is_empty() && name == "_G" {
+                continue;
+            }
+            let path = if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}.{name}")
+            };
+            let kind = match value {
+                Value::Table(table) => {
+                    if depth + 1 < MAX_DEPTH {
+                        pending.push((path.clone(), table, depth + 1));
+                    }
+                    ApiKind::Namespace
+                }
+                Value::Function(_) => ApiKind::Function,
+                _ => ApiKind::Value,
+            };
+            let (signature, doc) = match registry.as_ref().and_then(|registry| registry.get(&path))
+            {
+                Some(signature) => (Some(signature.text.clone()), signature.doc.clone()),
+                None => (standard.get(path.as_str()).cloned(), None),
+            };
+            let signature = signature.filter(|_| kind == ApiKind::Function);
+            let params = signature.as_deref().map(parse_params).unwrap_or_default();
+            entries.insert(
+                path.clone(),
+                ApiEntry {
+                    path,
+                    kind,
+                    signature,
+                    params,
+                    doc,
+                },
+            );
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_params(signature: &str) -> Vec<ApiParam> {
+    let Some(params) = signature
+        .split_once('(')
+        .and_then(|(_, rest)| rest.strip_suffix(')'))
+    else {
+        return Vec::new();
+    };
+    params
+        .split(',')
+        .map(str::trim)
+        .filter(|param| !param.is_empty())
+        .map(|param| ApiParam {
+            name: param.trim_end_matches('?').to_owned(),
+            optional: param.ends_with('?'),
+            variadic: param == "...",
+        })
+        .collect()
+}
+
+/// Describes an accepted argument count range for diagnostics.
+pub(crate) fn describe_arity(min: usize, max: Option<usize>) -> String {
+    match max {
+        Some(max) if max == min => format!("{min}"),
+        Some(max) => format!("{min} to {max}"),
+        None => format!("at least {min}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiKind, environment, register};
+
+    #[test]
+    fn describes_registered_and_standard_functions() {
+        let api = environment(&|lua| {
+            let module = crate::module(lua, "checker", "demo")?;
+            module.set("run", lua.create_function(|_, ()| Ok(()))?)?;
+            register(lua, "checker.demo", &[("run(input, options?)", "Runs it.")]);
+            Ok(())
+        })
+        .unwrap();
+        let entry = |path: &str| api.iter().find(|entry| entry.path == path).unwrap();
+
+        assert_eq!(entry("checker").kind, ApiKind::Namespace);
+        let run = entry("checker.demo.run");
+        assert_eq!(run.signature.as_deref(), Some("run(input, options?)"));
+        assert_eq!(run.doc.as_deref(), Some("Runs it."));
+        assert_eq!(run.arity(), Some((1, Some(2))));
+        assert_eq!(entry("json.encode").arity(), Some((1, Some(1))));
+        assert_eq!(entry("string.format").arity(), Some((1, None)));
+        assert_eq!(
+            entry("select").signature.as_deref(),
+            Some("select(index, ...)")
+        );
+        assert!(!api.iter().any(|entry| entry.path.starts_with("_G")));
+    }
+}
@@ -5,9 +5,12 @@
 //! Platform-specific integrations install their APIs below an explicit script
 //! namespace, while generic runtime libraries remain top-level globals.
 
+pub mod api;
+pub mod library;
 pub mod modules;
 pub mod traits;
 
+mod analysis;
 mod logging;
 mod util;
 mod worker;
@@ -18,14 +21,15 @@ use std::{
         Arc, Mutex,
         atomic::{AtomicU32, Ordering},
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+use cds_observe::exporter::meter::engine::{self as metrics, PoolUsage};
 use dashmap::DashMap;
 pub use mlua;
 use mlua::{
-    Function, HookTriggers, Lua, LuaOptions, LuaSerdeExt, MultiValue, StdLib, Table, Value,
-    VmState, chunk::ChunkMode,
+    Function, HookTriggers, IntoLuaMulti, Lua, LuaOptions, LuaSerdeExt, MultiValue, StdLib, Table,
+    Value, VmState, chunk::ChunkMode,
 };
 use once_cell::sync::Lazy;
 use serde::{Serialize, de::DeserializeOwned};
@@ -34,7 +38,12 @@ use time::OffsetDateTime;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tracing::debug;
 
-use crate::traits::{DiagnosticKind, DiagnosticMarker, EngineError};
+pub use crate::logging::{LogLevel, LogLine};
+use crate::{
+    library::LibrarySet,
+    modules::http::{HttpAccess, HttpPolicy},
+    traits::{CachedScript, DiagnosticKind, DiagnosticMarker, EngineError, Trace},
+};
 
 const LUA_MEMORY_LIMIT: usize = 16 * 1024 * 1024;
 const LUA_CALL_TIMEOUT: Duration = Duration::from_secs(20);
@@ -45,6 +54,8 @@ struct EngineContext {
     script: Arc<str>,
     bytecode: Arc<[u8]>,
     created_at: OffsetDateTime,
+    http: Arc<HttpAccess>,
+    libraries: Arc<LibrarySet>,
     pool: Arc<LuaPool>,
 }
 
@@ -54,7 +65,10 @@ struct NamespaceRegistry(Mutex<HashSet<String>>);
 
 struct LuaPool {
     slots: Mutex<Vec<Lua>>,
+    capacity: usize,
     permits: Arc<Semaphore>,
+    http: Arc<HttpAccess>,
+    libraries: Arc<LibrarySet>,
 }
 
 struct LuaLease {
@@ -65,18 +79,22 @@ struct LuaLease {
 }
 
 impl LuaPool {
-    fn new() -> Self {
+    fn new(http: Arc<HttpAccess>, libraries: Arc<LibrarySet>) -> Self {
         let capacity = std::thread::available_parallelism()
             .map(usize::from)
             .unwrap_or(1)
             .min(32);
         Self {
             slots: Mutex::new(Vec::with_capacity(capacity)),
+            capacity,
             permits: Arc::new(Semaphore::new(capacity)),
+            http,
+            libraries,
         }
     }
 
     async fn checkout(self: &Arc<Self>, configure: &ConfigureLua) -> Result<LuaLease, EngineError> {
+        let waiting = Instant::now();
         let permit = self
             .permits
             .clone()
@@ -84,6 +102,7 @@ impl LuaPool {
             .await
             .map_err(|_| anyhow::anyhow!("lua pool closed"))?;
         let lua = self.slots.lock().expect("lua pool mutex poisoned").pop();
+        metrics::record_checkout(waiting.elapsed(), lua.is_some());
         let lua = match lua {
             Some(lua) => lua,
             None => {
@@ -93,7 +112,10 @@ impl LuaPool {
             }
         };
         reset_instruction_budget(&lua);
-        logging::reset_budget(&lua);
+        logging::reset(&lua);
+        modules::http::attach(&lua, self.http.clone());
+        modules::http::reset(&lua);
+        library::attach(&lua, self.libraries.clone());
         Ok(LuaLease {
             lua: Some(lua),
             pool: self.clone(),
@@ -108,6 +130,15 @@ impl LuaPool {
             .expect("lua pool mutex poisoned")
             .push(lua);
     }
+
+    fn usage(&self) -> PoolUsage {
+        let busy = self.capacity - self.permits.available_permits();
+        PoolUsage {
+            idle: self.slots.lock().expect("lua pool mutex poisoned").len() as u64,
+            busy: busy as u64,
+            capacity: self.capacity as u64,
+        }
+    }
 }
 
 impl LuaLease {
@@ -140,6 +171,21 @@ fn get_global_engine() -> Arc<DashMap<String, EngineContext>> {
 pub type ConfigureLua = dyn Fn(&Lua) -> Result<(), EngineError> + Send + Sync;
 
 pub async fn init() -> Result<(), EngineError> {
+    metrics::init_observable_gauges(
+        || GLOBAL_ENGINE.len() as u64,
+        || {
+            GLOBAL_ENGINE
+                .iter()
+                .fold(PoolUsage::default(), |total, context| {
+                    let usage = context.pool.usage();
+                    PoolUsage {
+                        idle: total.idle + usage.idle,
+                        busy: total.busy + usage.busy,
+                        capacity: total.capacity + usage.capacity,
+                    }
+                })
+        },
+    );
     worker::cleaner().await;
     Ok(())
 }
@@ -170,8 +216,10 @@ pub fn create_lua() -> Result<Lua, EngineError> {
     )?;
 
     lua.set_app_data(NamespaceRegistry(Mutex::new(HashSet::new())));
+    api::install(&lua);
     logging::install(&lua)?;
     modules::install(&lua)?;
+    library::install(&lua)?;
     Ok(lua)
 }
 
@@ -220,7 +268,9 @@ fn execution_environment(lua: &Lua) -> mlua::Result<Table> {
     let environment = lua.create_table()?;
 
     let mut names = [
+        "bytes",
         "crypto",
+        "encoding",
         "http",
         "json",
         "coroutine",
@@ -253,6 +303,10 @@ fn execution_environment(lua: &Lua) -> mlua::Result<Table> {
     }
     environment.set("print", globals.get::<Function>("print")?)?;
     environment.set("_G", environment.clone())?;
+    environment.set(
+        "require",
+        library::require_function(lua, environment.clone())?,
+    )?;
 
     let metatable = lua.create_table()?;
     metatable.set("__index", globals)?;
@@ -332,14 +386,18 @@ fn runtime_diagnostic(script: &str, error: impl ToString) -> DiagnosticMarker {
 }
 
 /// Compiles and validates a script and its required top-level functions.
+/// Problems that prevent the script from running are returned as an error
+/// carrying every marker; on success the static-analysis warnings are
+/// returned.
 pub async fn lint(
     script: impl AsRef<str>,
     required_functions: &[&str],
     configure: &ConfigureLua,
-) -> Result<(), EngineError> {
+) -> Result<Vec<DiagnosticMarker>, EngineError> {
     let script = script.as_ref();
     let lua = create_lua()?;
     configure(&lua)?;
+    library::attach_all(&lua);
 
     // Compile first so syntax errors are reported separately from errors caused
     // by top-level code. Both need to be surfaced as editor diagnostics.
@@ -356,14 +414,19 @@ pub async fn lint(
             )));
         }
     };
-    if let Err(error) = function.call::<()>(()) {
-        return Err(EngineError::DiagnosticsError(vec![runtime_diagnostic(
-            script, error,
-        )]));
+    let warnings = analysis::analyze(script, &api::snapshot(&lua)?);
+    let mut markers = library::missing_requires(script, &LibrarySet::all());
+    if !markers.is_empty() {
+        markers.extend(warnings);
+        return Err(EngineError::DiagnosticsError(markers));
+    }
+    if let Err(error) = function.call_async::<()>(()).await {
+        markers.push(runtime_diagnostic(script, error));
+        markers.extend(warnings);
+        return Err(EngineError::DiagnosticsError(markers));
     }
 
     let globals = lua.globals();
-    let mut markers = Vec::new();
     for function in required_functions {
         match globals.get::<Value>(*function)? {
             Value::Function(_) => {}
@@ -390,8 +453,9 @@ pub async fn lint(
     }
 
     if markers.is_empty() {
-        Ok(())
+        Ok(warnings)
     } else {
+        markers.extend(warnings);
         Err(EngineError::DiagnosticsError(markers))
     }
 }
@@ -399,18 +463,33 @@ pub async fn lint(
 /// Compiles and caches a script. VM slots are reused, but every call receives
 /// a fresh protected environment so script globals are not shared.
 pub async fn preload(
+    key: impl AsRef<str>,
+    script: impl AsRef<str>,
+    last_changed_at: Option<OffsetDateTime>,
+) -> Result<(), EngineError> {
+    preload_with_policy(key, script, last_changed_at, HttpPolicy::default()).await
+}
+
+/// Like [`preload`], additionally binding the script's outbound HTTP policy.
+/// A policy change replaces the cached context just like a source change.
+pub async fn preload_with_policy(
     key: impl AsRef<str>,
     script: impl AsRef<str>,
     _last_changed_at: Option<OffsetDateTime>,
+    policy: HttpPolicy,
 ) -> Result<(), EngineError> {
     let key = key.as_ref();
     let script = script.as_ref();
     if let Some(context) = GLOBAL_ENGINE.get(key)
         && context.script.as_ref() == script
+        && context.http.policy() == &policy
+        && context.libraries.is_current()
     {
         debug!(key, "Lua script is up to date, skipping preload");
+        metrics::record_cache_lookup(true);
         return Ok(());
     }
+    metrics::record_cache_lookup(false);
 
     let compiler = create_lua()?;
     let function = compiler
@@ -419,13 +498,17 @@ pub async fn preload(
         .set_mode(ChunkMode::Text)
         .into_function()?;
     let bytecode = function.dump(false);
+    let libraries = Arc::new(LibrarySet::resolve(script)?);
+    let http = Arc::new(HttpAccess::new(policy));
     GLOBAL_ENGINE.insert(
         key.to_owned(),
         EngineContext {
             script: Arc::from(script.to_owned()),
             bytecode: Arc::from(bytecode),
             created_at: OffsetDateTime::now_utc(),
-            pool: Arc::new(LuaPool::new()),
+            http: http.clone(),
+            libraries: libraries.clone(),
+            pool: Arc::new(LuaPool::new(http, libraries)),
         },
     );
     Ok(())
@@ -439,35 +522,9 @@ pub async fn execute<A, R>(
     configure: &ConfigureLua,
 ) -> Result<R, EngineError>
 where
-    A: mlua::IntoLuaMulti + Send,
+    A: IntoLuaMulti + Send,
     R: DeserializeOwned, {
-    let context = GLOBAL_ENGINE
-        .get(key.as_ref())
-        .ok_or_else(|| EngineError::MissingContext(key.as_ref().to_owned()))?;
-    let bytecode = context.bytecode.clone();
-    let pool = context.pool.clone();
-    drop(context);
-
-    let mut lease = pool.checkout(configure).await?;
-    let environment = execution_environment(lease.lua())?;
-    lease
-        .lua()
-        .load(bytecode.as_ref())
-        .set_mode(ChunkMode::Binary)
-        .set_environment(environment.clone())
-        .exec()?;
-    let function = environment
-        .get::<Function>(function)
-        .map_err(|_| EngineError::MissingFunction(function.to_owned()))?;
-    let result: Result<Value, EngineError> =
-        tokio::time::timeout(LUA_CALL_TIMEOUT, function.call_async(args))
-            .await
-            .map_err(|_| EngineError::Timeout)?
-            .map_err(EngineError::from);
-    let value = result?;
-    let output = lease.lua().from_value(value)?;
-    lease.mark_reusable();
-    Ok(output)
+    execute_traced(key, function, args, configure).await.0
 }
 
 /// Executes a function with JSON values as multiple Lua arguments.
@@ -477,39 +534,170 @@ pub async fn execute_json<R>(
     args: &[JsonValue],
     configure: &ConfigureLua,
 ) -> Result<R, EngineError>
+where
+    R: DeserializeOwned, {
+    execute_json_traced(key, function, args, configure).await.0
+}
+
+/// Like [`execute`], also returning a [`Trace`] of the call.
+pub async fn execute_traced<A, R>(
+    key: impl AsRef<str>,
+    function: &str,
+    args: A,
+    configure: &ConfigureLua,
+) -> (Result<R, EngineError>, Trace)
+where
+    A: IntoLuaMulti + Send,
+    R: DeserializeOwned, {
+    let mut trace = Trace::default();
+    let result = call(
+        key.as_ref(),
+        function,
+        |lua| args.into_lua_multi(lua),
+        configure,
+        &mut trace,
+    )
+    .await;
+    (result, trace)
+}
+
+/// Like [`execute_json`], also returning a [`Trace`] of the call.
+pub async fn execute_json_traced<R>(
+    key: impl AsRef<str>,
+    function: &str,
+    args: &[JsonValue],
+    configure: &ConfigureLua,
+) -> (Result<R, EngineError>, Trace)
+where
+    R: DeserializeOwned, {
+    let mut trace = Trace::default();
+    let result = call(
+        key.as_ref(),
+        function,
+        |lua| {
+            args.iter()
+                .map(|arg| lua.to_value(arg))
+                .collect::<mlua::Result<MultiValue>>()
+        },
+        configure,
+        &mut trace,
+    )
+    .await;
+    (result, trace)
+}
+
+impl Trace {
+    /// Trace of a call that failed before the script ran.
+    pub fn failed(error: impl ToString) -> Self {
+        Self {
+            error: Some(runtime_diagnostic("", error)),
+            ..Default::default()
+        }
+    }
+}
+
+async fn call<R>(
+    key: &str,
+    function: &str,
+    args: impl FnOnce(&Lua) -> mlua::Result<MultiValue>,
+    configure: &ConfigureLua,
+    trace: &mut Trace,
+) -> Result<R, EngineError>
 where
     R: DeserializeOwned, {
     let context = GLOBAL_ENGINE
-        .get(key.as_ref())
-        .ok_or_else(|| EngineError::MissingContext(key.as_ref().to_owned()))?;
+        .get(key)
+        .ok_or_else(|| EngineError::MissingContext(key.to_owned()));
+    let context = match context {
+        Ok(context) => context,
+        Err(error) => {
+            metrics::record_cache_lookup(false);
+            *trace = Trace::failed(&error);
+            return Err(error);
+        }
+    };
+    let script = context.script.clone();
     let bytecode = context.bytecode.clone();
     let pool = context.pool.clone();
     drop(context);
 
-    let mut lease = pool.checkout(configure).await?;
-    let environment = execution_environment(lease.lua())?;
-    lease
-        .lua()
-        .load(bytecode.as_ref())
+    let mut lease = match pool.checkout(configure).await {
+        Ok(lease) => lease,
+        Err(error) => {
+            *trace = Trace::failed(&error);
+            return Err(error);
+        }
+    };
+    let started = Instant::now();
+    let result = invoke(lease.lua(), &bytecode, function, args).await;
+    trace.duration_ms = started.elapsed().as_millis() as u64;
+    let lua = lease.lua();
+    (trace.logs, trace.logs_dropped) = logging::take(lua);
+    trace.http = modules::http::calls(lua);
+    let instruction_batches = lua
+        .app_data_ref::<InstructionBudget>()
+        .map_or(0, |budget| budget.0.load(Ordering::Relaxed));
+    trace.instructions = instruction_batches.min(LUA_INSTRUCTION_BATCH_LIMIT + 1) as u64
+        * LUA_INSTRUCTION_BATCH as u64;
+
+    let output = result.and_then(|value| {
+        trace.result = lua.from_value::<JsonValue>(value.clone()).ok();
+        Ok(lua.from_value::<R>(value)?)
+    });
+    let outcome = match &output {
+        Ok(_) => "ok",
+        Err(EngineError::Timeout) => "timeout",
+        Err(_) if instruction_batches > LUA_INSTRUCTION_BATCH_LIMIT => "instruction_limit",
+        Err(EngineError::LuaError(error)) if is_memory_error(error) => "memory_limit",
+        Err(_) => "error",
+    };
+    metrics::record_execution(key, function, outcome);
+    match outcome {
+        "instruction_limit" => metrics::record_limit_hit(key, "instructions"),
+        "memory_limit" => metrics::record_limit_hit(key, "memory"),
+        _ => {}
+    }
+    match output {
+        Ok(output) => {
+            lease.mark_reusable();
+            Ok(output)
+        }
+        Err(error) => {
+            trace.error = Some(runtime_diagnostic(&script, &error));
+            Err(error)
+        }
+    }
+}
+
+fn is_memory_error(error: &mlua::Error) -> bool {
+    match error {
+        mlua::Error::MemoryError(_) => true,
+        mlua::Error::CallbackError { cause, .. } | mlua::Error::WithContext { cause, .. } => {
+            is_memory_error(cause)
+        }
+        _ => false,
+    }
+}
+
+async fn invoke(
+    lua: &Lua,
+    bytecode: &[u8],
+    function: &str,
+    args: impl FnOnce(&Lua) -> mlua::Result<MultiValue>,
+) -> Result<Value, EngineError> {
+    let environment = execution_environment(lua)?;
+    lua.load(bytecode)
         .set_mode(ChunkMode::Binary)
         .set_environment(environment.clone())
         .exec()?;
     let function = environment
         .get::<Function>(function)
         .map_err(|_| EngineError::MissingFunction(function.to_owned()))?;
-    let mut values = MultiValue::new();
-    for arg in args {
-        values.push_back(lease.lua().to_value(arg)?);
-    }
-    let result: Result<Value, EngineError> =
-        tokio::time::timeout(LUA_CALL_TIMEOUT, function.call_async(values))
-            .await
-            .map_err(|_| EngineError::Timeout)?
-            .map_err(EngineError::from);
-    let value = result?;
-    let output = lease.lua().from_value(value)?;
-    lease.mark_reusable();
-    Ok(output)
+    let args = args(lua)?;
+    tokio::time::timeout(LUA_CALL_TIMEOUT, function.call_async(args))
+        .await
+        .map_err(|_| EngineError::Timeout)?
+        .map_err(EngineError::from)
 }
 
 pub fn from_lua<T>(lua: &Lua, value: Value) -> Result<T, EngineError>
@@ -524,8 +712,38 @@ where
     Ok(lua.to_value(value)?)
 }
 
-pub fn clear_cache() {
+/// Lists the compiled scripts in the cache, ordered by key.
+pub fn cached_scripts() -> Vec<CachedScript> {
+    let mut scripts = GLOBAL_ENGINE
+        .iter()
+        .map(|entry| {
+            let usage = entry.pool.usage();
+            CachedScript {
+                key: entry.key().clone(),
+                created_at: entry.created_at.unix_timestamp(),
+                script_size: entry.script.len(),
+                allowed_hosts: entry.http.policy().allowed_hosts.clone(),
+                idle_states: usage.idle as usize,
+                busy_states: usage.busy as usize,
+                capacity: usage.capacity as usize,
+            }
+        })
+        .collect::<Vec<_>>();
+    scripts.sort_by(|a, b| a.key.cmp(&b.key));
+    scripts
+}
+
+/// Drops one compiled script; the next call recompiles it. Executions already
+/// running keep their Lua state until they finish.
+pub fn evict(key: &str) -> bool {
+    GLOBAL_ENGINE.remove(key).is_some()
+}
+
+/// Drops every compiled script, returning how many were cached.
+pub fn clear_cache() -> usize {
+    let cached = GLOBAL_ENGINE.len();
     GLOBAL_ENGINE.clear();
+    cached
 }
 
 #[cfg(test)]
@@ -540,7 +758,9 @@ mod tests {
 
     use mlua::Lua;
 
-    use super::{ConfigureLua, clear_cache, execute, lint, preload};
+    use super::{
+        ConfigureLua, cached_scripts, clear_cache, evict, execute, execute_traced, lint, preload,
+    };
     use crate::traits::EngineError;
 
     static ENGINE_TEST_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
@@ -826,6 +1046,33 @@ mod tests {
         assert_eq!(result, 2);
     }
 
+    #[tokio::test]
+    async fn traces_logs_results_and_errors_per_execution() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        let script = "function check(value)\n  print('got', value)\n  if value == 'boom' then\n    error('bad input')\n  end\n  return { ok = true }\nend";
+        preload("test/trace", script, None).await.unwrap();
+
+        let (result, trace) =
+            execute_traced::<_, serde_json::Value>("test/trace", "check", ("flag",), configure())
+                .await;
+        assert_eq!(result.unwrap(), serde_json::json!({ "ok": true }));
+        assert_eq!(trace.logs.len(), 1);
+        assert_eq!(trace.logs[0].message, "got\tflag");
+        assert_eq!(trace.result, Some(serde_json::json!({ "ok": true })));
+        assert!(trace.error.is_none());
+
+        let (result, trace) =
+            execute_traced::<_, serde_json::Value>("test/trace", "check", ("boom",), configure())
+                .await;
+        assert!(result.is_err());
+        assert_eq!(trace.logs.len(), 1);
+        assert_eq!(trace.logs[0].message, "got\tboom");
+        let error = trace.error.unwrap();
+        assert!(error.message.contains("bad input"));
+        assert_eq!(error.start_line, 3);
+        assert_eq!(error.end_column, "    error('bad input')".len());
+    }
+
     #[tokio::test]
     async fn isolates_globals_between_pooled_executions() {
         let _guard = ENGINE_TEST_LOCK.lock().await;
@@ -875,4 +1122,48 @@ mod tests {
             .unwrap();
         assert_eq!(result, "function");
     }
+
+    #[tokio::test]
+    async fn lists_and_evicts_cached_scripts() {
+        let _guard = ENGINE_TEST_LOCK.lock().await;
+        clear_cache();
+        preload("test/cache-b", "function value() return 2 end", None)
+            .await
+            .unwrap();
+        preload("test/cache-a", "function value() return 1 end", None)
+            .await
+            .unwrap();
+        let _: i64 = execute("test/cache-a", "value", (), configure())
+            .await
+            .unwrap();
+
+        let scripts = cached_scripts()
+            .into_iter()
+            .filter(|script| script.key.starts_with("test/cache-"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            scripts
+                .iter()
+                .map(|script| script.key.as_str())
+                .collect::<Vec<_>>(),
+            ["test/cache-a", "test/cache-b"]
+        );
+        assert_eq!(scripts[0].idle_states, 1);
+        assert_eq!(scripts[0].busy_states, 0);
+        assert_eq!(scripts[1].idle_states, 0);
+
+        assert!(evict("test/cache-a"));
+        assert!(!evict("test/cache-a"));
+        assert!(
+            execute::<_, i64>("test/cache-a", "value", (), configure())
+                .await
+                .is_err()
+        );
+        clear_cache();
+        assert!(
+            !cached_scripts()
+                .iter()
+                .any(|script| script.key.starts_with("test/cache-"))
+        );
+    }
 }
@@ -0,0 +1,418 @@
+//! Shared Lua libraries loaded with `require("lib/<name>")`.
+//!
+//! The host registers library sources here; scripts never read them from
+//! disk. When a script is preloaded, the libraries it requires (directly or
+//! through other libraries) are snapshotted into its cached context, so one
+//! execution always sees a consistent set. Registering a new version of a
+//! library evicts every cached script that depends on it.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+};
+
+use dashmap::DashMap;
+use mlua::{Function, Lua, Table, Value, chunk::ChunkMode};
+use once_cell::sync::Lazy;
+
+use crate::{
+    GLOBAL_ENGINE, analysis, api, create_lua, runtime_diagnostic, syntax_diagnostics,
+    traits::{DiagnosticKind, DiagnosticMarker, EngineError},
+    util,
+};
+
+/// Module name prefix understood by `require`.
+pub const PREFIX: &str = "lib/";
+
+const REQUIRE_FACTORY: &str = r#"
+local loader, env = ...
+local loaded = {}
+return function(name)
+    local value = loaded[name]
+    if value == false then
+        error("circular require of `" .. tostring(name) .. "`", 2)
+    end
+    if value ~= nil then
+        return value
+    end
+    local chunk = loader(name, env)
+    loaded[name] = false
+    local ok, result = pcall(chunk, name)
+    if not ok then
+        loaded[name] = nil
+        error(result, 0)
+    end
+    if result == nil then
+        result = true
+    end
+    loaded[name] = result
+    return result
+end
+"#;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Library {
+    version: i64,
+    bytecode: Arc<[u8]>,
+    requires: Vec<String>,
+}
+
+static LIBRARIES: Lazy<DashMap<String, Library>> = Lazy::new(DashMap::new);
+
+/// The libraries visible to one cached script, keyed by library name.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LibrarySet(BTreeMap<String, Library>);
+
+impl LibrarySet {
+    /// Snapshots every registered library.
+    pub(crate) fn all() -> Self {
+        Self(
+            LIBRARIES
+                .iter()
+                .map(|entry| (entry.key().clone(), entry.value().clone()))
+                .collect(),
+        )
+    }
+
+    /// Resolves the libraries a script requires, following nested requires.
+    pub(crate) fn resolve(script: &str) -> Result<Self, EngineError> {
+        let mut set = BTreeMap::new();
+        let mut pending = util::literal_requires(script)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        while let Some(name) = pending.pop() {
+            if set.contains_key(&name) {
+                continue;
+            }
+            let library = LIBRARIES
+                .get(&name)
+                .map(|entry| entry.value().clone())
+                .ok_or_else(|| EngineError::MissingLibrary(format!("{PREFIX}{name}")))?;
+            pending.extend(library.requires.iter().cloned());
+            set.insert(name, library);
+        }
+        Ok(Self(set))
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Whether every snapshotted library still has its registered version.
+    pub(crate) fn is_current(&self) -> bool {
+        self.0.iter().all(|(name, library)| {
+            LIBRARIES
+                .get(name)
+                .is_some_and(|current| current.version == library.version)
+        })
+    }
+}
+
+struct AttachedLibraries(Mutex<Arc<LibrarySet>>);
+
+/// Compiles a library source, returning editor diagnostics on syntax errors.
+fn compile(name: &str, source: &str, version: i64) -> Result<Library, EngineError> {
+    let lua = create_lua()?;
+    let function = lua
+        .load(source)
+        .set_name(format!("@{PREFIX}{name}"))
+        .set_mode(ChunkMode::Text)
+        .into_function()
+        .map_err(|error| EngineError::DiagnosticsError(syntax_diagnostics(source, error)))?;
+    Ok(Library {
+        version,
+        bytecode: Arc::from(function.dump(false)),
+        requires: util::literal_requires(source)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect(),
+    })
+}
+
+/// Returns whether a library name is usable after the `lib/` prefix.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .split('/')
+            .all(|part| !part.is_empty() && part.chars().all(is_name_char))
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-'
+}
+
+/// Registers or replaces a library and evicts cached scripts that depend on
+/// an older version.
+pub fn register(name: &str, source: &str, version: i64) -> Result<(), EngineError> {
+    if !is_valid_name(name) {
+        return Err(EngineError::ScriptError(format!(
+            "invalid library name `{name}`"
+        )));
+    }
+    let library = compile(name, source, version)?;
+    let changed = LIBRARIES
+        .insert(name.to_owned(), library)
+        .is_none_or(|previous| previous.version != version);
+    if changed {
+        evict_dependents(name);
+    }
+    Ok(())
+}
+
+/// Removes a library and evicts cached scripts that depend on it.
+pub fn unregister(name: &str) {
+    if LIBRARIES.remove(name).is_some() {
+        evict_dependents(name);
+    }
+}
+
+/// Returns the registered version of a library.
+pub fn version(name: &str) -> Option<i64> {
+    LIBRARIES.get(name).map(|library| library.version)
+}
+
+fn evict_dependents(name: &str) {
+    GLOBAL_ENGINE.retain(|_, context| !context.libraries.contains(name));
+}
+
+/// Validates a library before it is saved: syntax, missing or circular
+/// requires, and errors raised while loading it. Returns the static-analysis
+/// warnings when the library is usable.
+pub async fn lint(name: &str, source: &str) -> Result<Vec<DiagnosticMarker>, EngineError> {
+    if !is_valid_name(name) {
+        return Err(EngineError::DiagnosticsError(vec![DiagnosticMarker {
+            kind: DiagnosticKind::Error,
+            message: format!("Invalid library name: {name}"),
+            start_line: 0,
+            start_column: 0,
+            end_line: 0,
+            end_column: 0,
+        }]));
+    }
+    let candidate = compile(name, source, 0)?;
+    let mut libraries = LibrarySet::all();
+    libraries.0.insert(name.to_owned(), candidate);
+
+    let mut markers = missing_requires(source, &libraries);
+    if markers.is_empty()
+        && let Some(cycle) = find_cycle(name, &libraries)
+    {
+        markers.push(DiagnosticMarker {
+            kind: DiagnosticKind::Error,
+            message: format!("Circular require: {}", cycle.join(" -> ")),
+            start_line: 0,
+            start_column: 0,
+            end_line: 0,
+            end_column: 0,
+        });
+    }
+    if !markers.is_empty() {
+        return Err(EngineError::DiagnosticsError(markers));
+    }
+
+    let lua = create_lua()?;
+    let warnings = analysis::analyze(source, &api::snapshot(&lua)?);
+    attach(&lua, Arc::new(libraries));
+    let require = lua.globals().get::<Function>("require")?;
+    if let Err(error) = require.call_async::<Value>(format!("{PREFIX}{name}")).await {
+        let mut markers = vec![runtime_diagnostic(source, error)];
+        markers.extend(warnings);
+        return Err(EngineError::DiagnosticsError(markers));
+    }
+    Ok(warnings)
+}
+
+fn find_cycle(name: &str, libraries: &LibrarySet) -> Option<Vec<String>> {
+    fn visit(
+        name: &str,
+        libraries: &LibrarySet,
+        path: &mut Vec<String>,
+        done: &mut BTreeSet<String>,
+    ) -> Option<Vec<String>> {
+        if let Some(index) = path.iter().position(|entry| entry == name) {
+            let mut cycle = path[index..].to_vec();
+            cycle.push(name.to_owned());
+            return Some(cycle);
+        }
+        if !done.insert(name.to_owned()) {
+            return None;
+        }
+        path.push(name.to_owned());
+        for dependency in libraries.0.get(name).map_or(&[][..], |l| &l.requires) {
+            if let Some(cycle) = visit(dependency, libraries, path, done) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        None
+    }
+    visit(name, libraries, &mut Vec::new(), &mut BTreeSet::new())
+}
+
+/// Diagnostics for literal `require("lib/...")` calls naming unknown
+/// libraries.
+pub(crate) fn missing_requires(source: &str, libraries: &LibrarySet) -> Vec<DiagnosticMarker> {
+    util::literal_requires(source)
+        .into_iter()
+        .filter(|(name, _)| !libraries.contains(name))
+        .map(|(name, span)| DiagnosticMarker {
+            kind: DiagnosticKind::Error,
+            message: format!("Unknown library: {PREFIX}{name}"),
+            start_line: span.start_line,
+            start_column: span.start_column,
+            end_line: span.end_line,
+            end_column: span.end_column,
+        })
+        .collect()
+}
+
+/// Installs the global `require` and the loader it shares with per-execution
+/// environments.
+pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
+    lua.set_app_data(AttachedLibraries(Mutex::new(Arc::default())));
+    let loader = lua.create_function(|lua, (name, env): (String, Table)| {
+        let library = name.strip_prefix(PREFIX).ok_or_else(|| {
+            mlua::Error::RuntimeError(format!(
+                "require only loads shared libraries (`{PREFIX}<name>`), got `{name}`"
+            ))
+        })?;
+        let bytecode = lua
+            .app_data_ref::<AttachedLibraries>()
+            .and_then(|attached| {
+                attached
+                    .0
+                    .lock()
+                    .expect("library state poisoned")
+                    .0
+                    .get(library)
+                    .map(|library| library.bytecode.clone())
+            })
+            .ok_or_else(|| mlua::Error::RuntimeError(format!("library `{name}` not found")))?;
+        lua.load(bytecode.as_ref())
+            .set_name(format!("@{name}"))
+            .set_mode(ChunkMode::Binary)
+            .set_environment(env)
+            .into_function()
+    })?;
+    let factory = lua
+        .load(REQUIRE_FACTORY)
+        .set_name("=require")
+        .into_function()?;
+    lua.set_named_registry_value("cds.require.loader", loader)?;
+    lua.set_named_registry_value("cds.require.factory", factory)?;
+    lua.globals()
+        .set("require", require_function(lua, lua.globals())?)?;
+    Ok(())
+}
+
+/// Creates a `require` bound to one environment with its own module cache.
+pub(crate) fn require_function(lua: &Lua, env: Table) -> mlua::Result<Function> {
+    let loader = lua.named_registry_value::<Function>("cds.require.loader")?;
+    let factory = lua.named_registry_value::<Function>("cds.require.factory")?;
+    factory.call((loader, env))
+}
+
+/// Makes a library snapshot available to a Lua state.
+pub(crate) fn attach(lua: &Lua, libraries: Arc<LibrarySet>) {
+    if let Some(attached) = lua.app_data_ref::<AttachedLibraries>() {
+        *attached.0.lock().expect("library state poisoned") = libraries;
+    }
+}
+
+/// Makes every registered library available to a Lua state.
+pub(crate) fn attach_all(lua: &Lua) {
+    attach(lua, Arc::new(LibrarySet::all()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_cycle, is_valid_name, register, unregister, version};
+    use crate::{create_lua, execute, lint, preload};
+
+    #[test]
+    fn validates_library_names() {
+        assert!(is_valid_name("flag"));
+        assert!(is_valid_name("ctf/flag-format_v2"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("Flag"));
+        assert!(!is_valid_name("a//b"));
+        assert!(!is_valid_name("../etc"));
+    }
+
+    #[tokio::test]
+    async fn scripts_require_registered_libraries() {
+        register(
+            "test/greeting",
+            "local M = {} function M.greet(name) return 'hi ' .. name end return M",
+            1,
+        )
+        .unwrap();
+        preload(
+            "test/library-user",
+            r#"local greeting = require("lib/test/greeting")
+               function run(name) return greeting.greet(name) end"#,
+            None,
+        )
+        .await
+        .unwrap();
+        let configure = |_: &mlua::Lua| Ok(());
+        let output: String = execute("test/library-user", "run", "bob", &configure)
+            .await
+            .unwrap();
+        assert_eq!(output, "hi bob");
+
+        register(
+            "test/greeting",
+            "local M = {} function M.greet(name) return 'hello ' .. name end return M",
+            2,
+        )
+        .unwrap();
+        assert_eq!(version("test/greeting"), Some(2));
+        assert!(
+            execute::<_, String>("test/library-user", "run", "bob", &configure)
+                .await
+                .is_err(),
+            "dependent scripts are evicted when a library changes"
+        );
+        unregister("test/greeting");
+    }
+
+    #[tokio::test]
+    async fn lint_reports_unknown_libraries() {
+        let error = lint(
+            "function check() return require('lib/test/missing') end",
+            &["check"],
+            &|_| Ok(()),
+        )
+        .await
+        .unwrap_err();
+        assert!(format!("{error:?}").contains("Unknown library: lib/test/missing"));
+    }
+
+    #[tokio::test]
+    async fn lint_rejects_circular_libraries() {
+        register("test/cycle-b", "return 1", 1).unwrap();
+        register("test/cycle-a", "return require('lib/test/cycle-b')", 1).unwrap();
+        let error = super::lint("test/cycle-b", "return require('lib/test/cycle-a')")
+            .await
+            .unwrap_err();
+        assert!(format!("{error:?}").contains("Circular require"));
+        let libraries = super::LibrarySet::all();
+        assert!(find_cycle("test/cycle-a", &libraries).is_none());
+        unregister("test/cycle-a");
+        unregister("test/cycle-b");
+    }
+
+    #[test]
+    fn require_rejects_non_library_modules() {
+        let lua = create_lua().unwrap();
+        let error = lua.load("return require('os')").exec().unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("require only loads shared libraries")
+        );
+    }
+}
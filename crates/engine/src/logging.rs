@@ -1,70 +1,140 @@
 //! Lua logging bridge backed by the application's `tracing` subscriber.
 
 use std::sync::{
-    Arc,
+    Arc, Mutex,
     atomic::{AtomicU32, Ordering},
 };
 
 use mlua::{Function, Lua, MultiValue, Table, Value};
+use serde::Serialize;
 
-use crate::{global_module, traits::EngineError};
+use crate::{api, global_module, traits::EngineError};
 
 const MAX_LOG_ENTRIES: u32 = 256;
 const MAX_LOG_MESSAGE_BYTES: usize = 8 * 1024;
+const MAX_CAPTURED_LOG_BYTES: usize = 64 * 1024;
 const TRUNCATION_SUFFIX: &str = "...[truncated]";
 
-#[derive(Clone, Copy)]
-enum Level {
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
     Debug,
     Info,
     Warn,
     Error,
 }
 
+/// One `print`/`log.*` line emitted during an execution.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+pub struct LogLine {
+    pub level: LogLevel,
+    pub message: String,
+}
+
+/// Lines kept for the current execution. Lines over the entry budget or the
+/// capture size are counted in `dropped` instead.
+#[derive(Default)]
+struct LogCapture {
+    lines: Vec<LogLine>,
+    bytes: usize,
+    dropped: u32,
+}
+
 /// Installs the global `log` API. `print` is intentionally mapped to debug for
 /// script compatibility.
 pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
     let budget = Arc::new(AtomicU32::new(0));
     lua.set_app_data(LogBudget(budget.clone()));
+    lua.set_app_data(Mutex::new(LogCapture::default()));
     let log = global_module(lua, "log")?;
 
-    install_level(lua, &log, "debug", Level::Debug, budget.clone())?;
-    install_level(lua, &log, "info", Level::Info, budget.clone())?;
-    install_level(lua, &log, "warn", Level::Warn, budget.clone())?;
-    install_level(lua, &log, "error", Level::Error, budget.clone())?;
+    install_level(lua, &log, "debug", LogLevel::Debug, budget.clone())?;
+    install_level(lua, &log, "info", LogLevel::Info, budget.clone())?;
+    install_level(lua, &log, "warn", LogLevel::Warn, budget.clone())?;
+    install_level(lua, &log, "error", LogLevel::Error, budget.clone())?;
 
     lua.globals()
-        .set("print", create_log_function(lua, Level::Debug, budget)?)?;
+        .set("print", create_log_function(lua, LogLevel::Debug, budget)?)?;
+    api::register(
+        lua,
+        "log",
+        &[
+            ("debug(...)", ""),
+            ("info(...)", ""),
+            ("warn(...)", ""),
+            ("error(...)", ""),
+        ],
+    );
+    api::register(
+        lua,
+        "",
+        &[("print(...)", "Logs its arguments at debug level.")],
+    );
     Ok(())
 }
 
-pub(crate) fn reset_budget(lua: &Lua) {
+/// Restores the entry budget and discards captured lines before a pooled
+/// state runs another execution.
+pub(crate) fn reset(lua: &Lua) {
     if let Some(budget) = lua.app_data_ref::<LogBudget>() {
         budget.0.store(0, Ordering::Relaxed);
     }
+    if let Some(capture) = lua.app_data_ref::<Mutex<LogCapture>>() {
+        *capture.lock().expect("log capture poisoned") = LogCapture::default();
+    }
+}
+
+/// Takes the lines captured since the last reset and the number of lines
+/// that were not kept.
+pub(crate) fn take(lua: &Lua) -> (Vec<LogLine>, u32) {
+    lua.app_data_ref::<Mutex<LogCapture>>()
+        .map(|capture| {
+            let capture = std::mem::take(&mut *capture.lock().expect("log capture poisoned"));
+            (capture.lines, capture.dropped)
+        })
+        .unwrap_or_default()
 }
 
 struct LogBudget(Arc<AtomicU32>);
 
+fn capture(lua: &Lua, line: Option<LogLine>) {
+    let Some(capture) = lua.app_data_ref::<Mutex<LogCapture>>() else {
+        return;
+    };
+    let mut capture = capture.lock().expect("log capture poisoned");
+    match line {
+        Some(line) if capture.bytes + line.message.len() <= MAX_CAPTURED_LOG_BYTES => {
+            capture.bytes += line.message.len();
+            capture.lines.push(line);
+        }
+        _ => capture.dropped += 1,
+    }
+}
+
 fn install_level(
     lua: &Lua,
     table: &Table,
     name: &str,
-    level: Level,
+    level: LogLevel,
     budget: Arc<AtomicU32>,
 ) -> Result<(), EngineError> {
     table.set(name, create_log_function(lua, level, budget)?)?;
     Ok(())
 }
 
-fn create_log_function(lua: &Lua, level: Level, budget: Arc<AtomicU32>) -> mlua::Result<Function> {
-    lua.create_function(move |_lua, values: MultiValue| {
+fn create_log_function(
+    lua: &Lua,
+    level: LogLevel,
+    budget: Arc<AtomicU32>,
+) -> mlua::Result<Function> {
+    lua.create_function(move |lua, values: MultiValue| {
         if budget
             .try_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
                 (count < MAX_LOG_ENTRIES).then_some(count + 1)
             })
             .is_err()
         {
+            capture(lua, None);
             return Ok(());
         }
 
@@ -76,11 +146,12 @@ fn create_log_function(lua: &Lua, level: Level, budget: Arc<AtomicU32>) -> mlua:
         let message = truncate_message(message);
 
         match level {
-            Level::Debug => tracing::debug!(target: "cds.lua", message = %message),
-            Level::Info => tracing::info!(target: "cds.lua", message = %message),
-            Level::Warn => tracing::warn!(target: "cds.lua", message = %message),
-            Level::Error => tracing::error!(target: "cds.lua", message = %message),
+            LogLevel::Debug => tracing::debug!(target: "cds.lua", message = %message),
+            LogLevel::Info => tracing::info!(target: "cds.lua", message = %message),
+            LogLevel::Warn => tracing::warn!(target: "cds.lua", message = %message),
+            LogLevel::Error => tracing::error!(target: "cds.lua", message = %message),
         }
+        capture(lua, Some(LogLine { level, message }));
         Ok(())
     })
 }
@@ -110,7 +181,8 @@ mod tests {
     use mlua::{Function, Lua};
 
     use super::{
-        MAX_LOG_ENTRIES, MAX_LOG_MESSAGE_BYTES, TRUNCATION_SUFFIX, install, truncate_message,
+        LogLevel, MAX_LOG_ENTRIES, MAX_LOG_MESSAGE_BYTES, TRUNCATION_SUFFIX, install, reset, take,
+        truncate_message,
     };
 
     #[test]
@@ -160,4 +232,38 @@ mod tests {
         );
         lua.load(script).exec().unwrap();
     }
+
+    #[test]
+    fn captures_lines_until_reset() {
+        let lua = Lua::new();
+        install(&lua).unwrap();
+
+        lua.load(
+            r#"
+                print("value", 42)
+                log.warn("careful")
+            "#,
+        )
+        .exec()
+        .unwrap();
+        let (lines, dropped) = take(&lua);
+        assert_eq!(dropped, 0);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].level, LogLevel::Debug);
+        assert_eq!(lines[0].message, "value\t42");
+        assert_eq!(lines[1].level, LogLevel::Warn);
+
+        reset(&lua);
+        let script = format!("for _ = 1, {} do log.debug('x') end", MAX_LOG_ENTRIES + 4);
+        lua.load(script).exec().unwrap();
+        let (lines, dropped) = take(&lua);
+        assert_eq!(lines.len(), MAX_LOG_ENTRIES as usize);
+        assert_eq!(dropped, 4);
+
+        lua.load("log.info('again')").exec().unwrap();
+        assert_eq!(take(&lua), (Vec::new(), 1));
+        reset(&lua);
+        lua.load("log.info('again')").exec().unwrap();
+        assert_eq!(take(&lua).0.len(), 1);
+    }
 }
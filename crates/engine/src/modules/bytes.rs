@@ -0,0 +1,124 @@
+//! Global Lua module `bytes`.
+//!
+//! Lua strings are byte strings; these helpers operate on them natively so
+//! scripts do not spend their instruction budget on per-byte loops. `pack`,
+//! `unpack` and `size` use the Lua 5.4 `string.pack` format language.
+
+use mlua::{Lua, LuaString, Table, Value};
+
+use crate::{api, global_module, traits::EngineError};
+
+pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
+    let module = global_module(lua, "bytes")?;
+    let string: Table = lua.globals().get("string")?;
+    module.set("pack", string.get::<Value>("pack")?)?;
+    module.set("unpack", string.get::<Value>("unpack")?)?;
+    module.set("size", string.get::<Value>("packsize")?)?;
+    module.set(
+        "from_table",
+        lua.create_function(|lua, values: Vec<u8>| lua.create_string(values))?,
+    )?;
+    module.set(
+        "to_table",
+        lua.create_function(|_, value: LuaString| Ok(value.as_bytes().to_vec()))?,
+    )?;
+    module.set(
+        "xor",
+        lua.create_function(|lua, (data, key): (LuaString, LuaString)| {
+            let key = key.as_bytes();
+            if key.is_empty() {
+                return Err(mlua::Error::RuntimeError(
+                    "bytes.xor key must not be empty".to_owned(),
+                ));
+            }
+            lua.create_string(xor(&data.as_bytes(), &key))
+        })?,
+    )?;
+    module.set(
+        "equal",
+        lua.create_function(|_, (left, right): (LuaString, LuaString)| {
+            Ok(constant_time_eq(&left.as_bytes(), &right.as_bytes()))
+        })?,
+    )?;
+    api::register(
+        lua,
+        "bytes",
+        &[
+            ("pack(format, ...)", "Packs values into a binary string."),
+            (
+                "unpack(format, data, init?)",
+                "Unpacks values from a binary string.",
+            ),
+            ("size(format)", "Size in bytes of a fixed-size format."),
+            (
+                "from_table(values)",
+                "Builds a string from a list of byte values.",
+            ),
+            ("to_table(data)", "Lists the byte values of a string."),
+            ("xor(data, key)", "XORs data with a repeating key."),
+            (
+                "equal(left, right)",
+                "Compares two strings in constant time.",
+            ),
+        ],
+    );
+    Ok(())
+}
+
+/// XORs `data` with `key`, repeating the key as needed.
+pub fn xor(data: &[u8], key: &[u8]) -> Vec<u8> {
+    data.iter()
+        .zip(key.iter().cycle())
+        .map(|(byte, key)| byte ^ key)
+        .collect()
+}
+
+/// Compares two byte strings in time independent of where they differ.
+pub fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0u8, |diff, (left, right)| diff | (left ^ right))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{constant_time_eq, xor};
+    use crate::create_lua;
+
+    #[test]
+    fn xors_with_repeating_key() {
+        assert_eq!(xor(b"\x01\x02\x03", b"\x01\x02"), b"\x00\x00\x02");
+    }
+
+    #[test]
+    fn compares_in_constant_time() {
+        assert!(constant_time_eq(b"flag", b"flag"));
+        assert!(!constant_time_eq(b"flag", b"flah"));
+        assert!(!constant_time_eq(b"flag", b"flag!"));
+    }
+
+    #[test]
+    fn packs_and_converts_byte_strings() {
+        let lua = create_lua().unwrap();
+        let ok: bool = lua
+            .load(
+                r#"
+                local packed = bytes.pack(">I2s1", 258, "ab")
+                local number, text = bytes.unpack(">I2s1", packed)
+                local list = bytes.to_table("\0\255")
+                return packed == "\1\2\2ab"
+                    and number == 258 and text == "ab"
+                    and bytes.size(">I4") == 4
+                    and list[1] == 0 and list[2] == 255
+                    and bytes.from_table({ 104, 105 }) == "hi"
+                    and bytes.equal(bytes.xor(bytes.xor("data", "k"), "k"), "data")
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert!(ok);
+    }
+}
@@ -1,38 +1,325 @@
 //! Global Lua module `crypto`.
+//!
+//! `sha256`/`sha512` return hex digests. `hmac` and the AES helpers take and
+//! return raw byte strings; combine them with `encoding` for text forms.
 
-use mlua::Lua;
-use ring::digest::{SHA256, SHA512};
+use aes::cipher::{BlockCipherDecrypt, BlockCipherEncrypt, KeyInit};
+use mlua::{ExternalResult, Lua, LuaString};
+use ring::{
+    aead,
+    digest::{SHA256, SHA512},
+    hmac,
+};
 
-use crate::{global_module, traits::EngineError};
+use crate::{api, global_module, traits::EngineError};
+
+const AES_BLOCK: usize = 16;
 
 pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
     let module = global_module(lua, "crypto")?;
     module.set(
         "sha256",
-        lua.create_function(|_, value: String| Ok(sha256(&value)))?,
+        lua.create_function(|_, value: LuaString| Ok(sha256(value.as_bytes())))?,
     )?;
     module.set(
         "sha512",
-        lua.create_function(|_, value: String| Ok(sha512(&value)))?,
+        lua.create_function(|_, value: LuaString| Ok(sha512(value.as_bytes())))?,
+    )?;
+    module.set(
+        "hmac",
+        lua.create_function(
+            |lua, (algorithm, key, message): (String, LuaString, LuaString)| {
+                let tag = hmac_sign(&algorithm, &key.as_bytes(), &message.as_bytes())
+                    .map_err(mlua::Error::RuntimeError)?;
+                lua.create_string(tag)
+            },
+        )?,
+    )?;
+    module.set(
+        "aes_gcm_encrypt",
+        lua.create_function(
+            |lua,
+             (key, nonce, plaintext, aad): (
+                LuaString,
+                LuaString,
+                LuaString,
+                Option<LuaString>,
+            )| {
+                let aad = aad.map(|aad| aad.as_bytes().to_vec()).unwrap_or_default();
+                let sealed = aes_gcm_encrypt(
+                    &key.as_bytes(),
+                    &nonce.as_bytes(),
+                    &plaintext.as_bytes(),
+                    &aad,
+                )
+                .map_err(mlua::Error::RuntimeError)?;
+                lua.create_string(sealed)
+            },
+        )?,
+    )?;
+    module.set(
+        "aes_gcm_decrypt",
+        lua.create_function(
+            |lua,
+             (key, nonce, sealed, aad): (
+                LuaString,
+                LuaString,
+                LuaString,
+                Option<LuaString>,
+            )| {
+                let aad = aad.map(|aad| aad.as_bytes().to_vec()).unwrap_or_default();
+                aes_gcm_decrypt(&key.as_bytes(), &nonce.as_bytes(), &sealed.as_bytes(), &aad)
+                    .map_err(mlua::Error::RuntimeError)?
+                    .map(|plaintext| lua.create_string(plaintext))
+                    .transpose()
+            },
+        )?,
+    )?;
+    module.set(
+        "aes_cbc_encrypt",
+        lua.create_function(
+            |lua, (key, iv, plaintext): (LuaString, LuaString, LuaString)| {
+                let ciphertext =
+                    aes_cbc_encrypt(&key.as_bytes(), &iv.as_bytes(), &plaintext.as_bytes())
+                        .map_err(mlua::Error::RuntimeError)?;
+                lua.create_string(ciphertext)
+            },
+        )?,
+    )?;
+    module.set(
+        "aes_cbc_decrypt",
+        lua.create_function(
+            |lua, (key, iv, ciphertext): (LuaString, LuaString, LuaString)| {
+                aes_cbc_decrypt(&key.as_bytes(), &iv.as_bytes(), &ciphertext.as_bytes())
+                    .map_err(mlua::Error::RuntimeError)?
+                    .map(|plaintext| lua.create_string(plaintext))
+                    .transpose()
+            },
+        )?,
+    )?;
+    module.set(
+        "random_bytes",
+        lua.create_function(|lua, length: usize| {
+            if length > 4096 {
+                return Err(mlua::Error::RuntimeError(
+                    "crypto.random_bytes length is too large".to_owned(),
+                ));
+            }
+            let mut buffer = vec![0u8; length];
+            ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut buffer)
+                .map_err(|_| "random generator failed")
+                .into_lua_err()?;
+            lua.create_string(buffer)
+        })?,
     )?;
+    api::register(
+        lua,
+        "crypto",
+        &[
+            ("sha256(data)", "Hex-encoded SHA-256 digest."),
+            ("sha512(data)", "Hex-encoded SHA-512 digest."),
+            (
+                "hmac(algorithm, key, message)",
+                "Raw HMAC tag; algorithm is `sha256` or `sha512`.",
+            ),
+            (
+                "aes_gcm_encrypt(key, nonce, plaintext, aad?)",
+                "AES-GCM ciphertext with the tag appended.",
+            ),
+            (
+                "aes_gcm_decrypt(key, nonce, sealed, aad?)",
+                "Plaintext, or nil when authentication fails.",
+            ),
+            (
+                "aes_cbc_encrypt(key, iv, plaintext)",
+                "AES-CBC ciphertext with PKCS#7 padding.",
+            ),
+            (
+                "aes_cbc_decrypt(key, iv, ciphertext)",
+                "Plaintext, or nil when the padding is invalid.",
+            ),
+            (
+                "random_bytes(length)",
+                "Up to 4096 cryptographically random bytes.",
+            ),
+        ],
+    );
     Ok(())
 }
 
-pub fn sha256(message: &str) -> String {
+pub fn sha256(message: impl AsRef<[u8]>) -> String {
     let mut context = ring::digest::Context::new(&SHA256);
-    context.update(message.as_bytes());
+    context.update(message.as_ref());
     hex::encode(context.finish().as_ref())
 }
 
-pub fn sha512(message: &str) -> String {
+pub fn sha512(message: impl AsRef<[u8]>) -> String {
     let mut context = ring::digest::Context::new(&SHA512);
-    context.update(message.as_bytes());
+    context.update(message.as_ref());
     hex::encode(context.finish().as_ref())
 }
 
+/// Computes an HMAC tag with `sha1`, `sha256`, `sha384` or `sha512`.
+pub fn hmac_sign(algorithm: &str, key: &[u8], message: &[u8]) -> Result<Vec<u8>, String> {
+    let algorithm = match algorithm.to_ascii_lowercase().as_str() {
+        "sha1" => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+        "sha256" => hmac::HMAC_SHA256,
+        "sha384" => hmac::HMAC_SHA384,
+        "sha512" => hmac::HMAC_SHA512,
+        other => return Err(format!("unsupported hmac algorithm `{other}`")),
+    };
+    let key = hmac::Key::new(algorithm, key);
+    Ok(hmac::sign(&key, message).as_ref().to_vec())
+}
+
+fn gcm_key(key: &[u8], nonce: &[u8]) -> Result<(aead::LessSafeKey, aead::Nonce), String> {
+    let algorithm = match key.len() {
+        16 => &aead::AES_128_GCM,
+        32 => &aead::AES_256_GCM,
+        _ => return Err("aes-gcm key must be 16 or 32 bytes".to_owned()),
+    };
+    let nonce = aead::Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| "aes-gcm nonce must be 12 bytes".to_owned())?;
+    let key = aead::UnboundKey::new(algorithm, key).map_err(|_| "invalid aes-gcm key")?;
+    Ok((aead::LessSafeKey::new(key), nonce))
+}
+
+/// Encrypts with AES-GCM and returns the ciphertext followed by the 16-byte
+/// tag.
+pub fn aes_gcm_encrypt(
+    key: &[u8],
+    nonce: &[u8],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, String> {
+    let (key, nonce) = gcm_key(key, nonce)?;
+    let mut buffer = plaintext.to_vec();
+    key.seal_in_place_append_tag(nonce, aead::Aad::from(aad), &mut buffer)
+        .map_err(|_| "aes-gcm encryption failed")?;
+    Ok(buffer)
+}
+
+/// Decrypts ciphertext with its trailing tag. Returns `None` when
+/// authentication fails.
+pub fn aes_gcm_decrypt(
+    key: &[u8],
+    nonce: &[u8],
+    sealed: &[u8],
+    aad: &[u8],
+) -> Result<Option<Vec<u8>>, String> {
+    let (key, nonce) = gcm_key(key, nonce)?;
+    let mut buffer = sealed.to_vec();
+    Ok(key
+        .open_in_place(nonce, aead::Aad::from(aad), &mut buffer)
+        .ok()
+        .map(|plaintext| plaintext.to_vec()))
+}
+
+enum CbcCipher {
+    Aes128(aes::Aes128),
+    Aes192(aes::Aes192),
+    Aes256(aes::Aes256),
+}
+
+impl CbcCipher {
+    fn new(key: &[u8], iv: &[u8]) -> Result<Self, String> {
+        if iv.len() != AES_BLOCK {
+            return Err("aes-cbc iv must be 16 bytes".to_owned());
+        }
+        let invalid = |_| "invalid aes-cbc key".to_owned();
+        Ok(match key.len() {
+            16 => Self::Aes128(aes::Aes128::new_from_slice(key).map_err(invalid)?),
+            24 => Self::Aes192(aes::Aes192::new_from_slice(key).map_err(invalid)?),
+            32 => Self::Aes256(aes::Aes256::new_from_slice(key).map_err(invalid)?),
+            _ => return Err("aes-cbc key must be 16, 24 or 32 bytes".to_owned()),
+        })
+    }
+
+    fn encrypt(&self, block: &mut aes::Block) {
+        match self {
+            Self::Aes128(cipher) => cipher.encrypt_block(block),
+            Self::Aes192(cipher) => cipher.encrypt_block(block),
+            Self::Aes256(cipher) => cipher.encrypt_block(block),
+        }
+    }
+
+    fn decrypt(&self, block: &mut aes::Block) {
+        match self {
+            Self::Aes128(cipher) => cipher.decrypt_block(block),
+            Self::Aes192(cipher) => cipher.decrypt_block(block),
+            Self::Aes256(cipher) => cipher.decrypt_block(block),
+        }
+    }
+}
+
+/// Encrypts with AES-CBC and PKCS#7 padding.
+pub fn aes_cbc_encrypt(key: &[u8], iv: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = CbcCipher::new(key, iv)?;
+    let padding = AES_BLOCK - plaintext.len() % AES_BLOCK;
+    let mut data = plaintext.to_vec();
+    data.resize(plaintext.len() + padding, padding as u8);
+
+    let mut previous = aes::Block::default();
+    previous.copy_from_slice(iv);
+    for chunk in data.chunks_mut(AES_BLOCK) {
+        let mut block = aes::Block::default();
+        for (index, byte) in block.iter_mut().enumerate() {
+            *byte = chunk[index] ^ previous[index];
+        }
+        cipher.encrypt(&mut block);
+        chunk.copy_from_slice(&block);
+        previous = block;
+    }
+    Ok(data)
+}
+
+/// Decrypts AES-CBC data and strips PKCS#7 padding. Returns `None` when the
+/// padding is invalid.
+pub fn aes_cbc_decrypt(
+    key: &[u8],
+    iv: &[u8],
+    ciphertext: &[u8],
+) -> Result<Option<Vec<u8>>, String> {
+    let cipher = CbcCipher::new(key, iv)?;
+    if ciphertext.is_empty() || !ciphertext.len().is_multiple_of(AES_BLOCK) {
+        return Err("aes-cbc ciphertext must be a non-empty multiple of 16 bytes".to_owned());
+    }
+
+    let mut previous = aes::Block::default();
+    previous.copy_from_slice(iv);
+    let mut data = Vec::with_capacity(ciphertext.len());
+    for chunk in ciphertext.chunks(AES_BLOCK) {
+        let mut block = aes::Block::default();
+        block.copy_from_slice(chunk);
+        cipher.decrypt(&mut block);
+        data.extend(
+            block
+                .iter()
+                .zip(previous.iter())
+                .map(|(byte, mask)| byte ^ mask),
+        );
+        previous.copy_from_slice(chunk);
+    }
+
+    let padding = data[data.len() - 1] as usize;
+    if padding == 0
+        || padding > AES_BLOCK
+        || !data[data.len() - padding..]
+            .iter()
+            .all(|byte| *byte as usize == padding)
+    {
+        return Ok(None);
+    }
+    data.truncate(data.len() - padding);
+    Ok(Some(data))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{sha256, sha512};
+    use super::{
+        aes_cbc_decrypt, aes_cbc_encrypt, aes_gcm_decrypt, aes_gcm_encrypt, hmac_sign, sha256,
+        sha512,
+    };
 
     #[test]
     fn hashes_strings() {
@@ -40,4 +327,53 @@ mod tests {
         assert_eq!(sha512("answer").len(), 128);
         assert_ne!(sha256("answer"), sha256("different"));
     }
+
+    #[test]
+    fn signs_rfc4231_vector() {
+        let tag = hmac_sign("sha256", &[0x0B; 20], b"Hi There").unwrap();
+        assert_eq!(
+            hex::encode(tag),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert!(hmac_sign("md5", b"key", b"message").is_err());
+    }
+
+    #[test]
+    fn round_trips_aes_gcm() {
+        let key = [7u8; 32];
+        let nonce = [1u8; 12];
+        let sealed = aes_gcm_encrypt(&key, &nonce, b"flag", b"aad").unwrap();
+        assert_eq!(sealed.len(), 4 + 16);
+        assert_eq!(
+            aes_gcm_decrypt(&key, &nonce, &sealed, b"aad").unwrap(),
+            Some(b"flag".to_vec())
+        );
+        assert_eq!(
+            aes_gcm_decrypt(&key, &nonce, &sealed, b"other").unwrap(),
+            None
+        );
+        assert!(aes_gcm_encrypt(&key, &[0u8; 8], b"flag", b"").is_err());
+    }
+
+    #[test]
+    fn matches_aes_cbc_vector_and_padding() {
+        // NIST SP 800-38A F.2.1, first block, followed by a full padding block.
+        let key = hex::decode("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+        let iv = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let plaintext = hex::decode("6bc1bee22e409f96e93d7e117393172a").unwrap();
+        let ciphertext = aes_cbc_encrypt(&key, &iv, &plaintext).unwrap();
+        assert_eq!(ciphertext.len(), 32);
+        assert_eq!(
+            hex::encode(&ciphertext[..16]),
+            "7649abac8119b246cee98e9b12e9197d"
+        );
+        assert_eq!(
+            aes_cbc_decrypt(&key, &iv, &ciphertext).unwrap(),
+            Some(plaintext)
+        );
+
+        let mut tampered = ciphertext.clone();
+        tampered[31] ^= 0xFF;
+        assert_eq!(aes_cbc_decrypt(&key, &iv, &tampered).unwrap(), None);
+    }
 }
@@ -0,0 +1,161 @@
+//! Global Lua module `encoding`.
+//!
+//! Text encodings for binary strings. Decoders raise an error on malformed
+//! input.
+
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use data_encoding::BASE32;
+use mlua::{ExternalResult, Lua, LuaString};
+
+use crate::{api, global_module, traits::EngineError};
+
+pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
+    let module = global_module(lua, "encoding")?;
+    module.set(
+        "base64_encode",
+        lua.create_function(|_, value: LuaString| Ok(STANDARD.encode(value.as_bytes())))?,
+    )?;
+    module.set(
+        "base64_decode",
+        lua.create_function(|lua, value: LuaString| {
+            lua.create_string(STANDARD.decode(value.as_bytes()).into_lua_err()?)
+        })?,
+    )?;
+    module.set(
+        "base64url_encode",
+        lua.create_function(|_, value: LuaString| Ok(URL_SAFE_NO_PAD.encode(value.as_bytes())))?,
+    )?;
+    module.set(
+        "base64url_decode",
+        lua.create_function(|lua, value: LuaString| {
+            lua.create_string(base64url_decode(&value.as_bytes()).into_lua_err()?)
+        })?,
+    )?;
+    module.set(
+        "base32_encode",
+        lua.create_function(|_, value: LuaString| Ok(BASE32.encode(&value.as_bytes())))?,
+    )?;
+    module.set(
+        "base32_decode",
+        lua.create_function(|lua, value: LuaString| {
+            lua.create_string(BASE32.decode(&value.as_bytes()).into_lua_err()?)
+        })?,
+    )?;
+    module.set(
+        "hex_encode",
+        lua.create_function(|_, value: LuaString| Ok(hex::encode(value.as_bytes())))?,
+    )?;
+    module.set(
+        "hex_decode",
+        lua.create_function(|lua, value: LuaString| {
+            lua.create_string(hex::decode(value.as_bytes()).into_lua_err()?)
+        })?,
+    )?;
+    module.set(
+        "url_encode",
+        lua.create_function(|_, value: LuaString| {
+            Ok(url::form_urlencoded::byte_serialize(&value.as_bytes()).collect::<String>())
+        })?,
+    )?;
+    module.set(
+        "url_decode",
+        lua.create_function(|lua, value: LuaString| {
+            lua.create_string(url_decode(&value.as_bytes()))
+        })?,
+    )?;
+    api::register(
+        lua,
+        "encoding",
+        &[
+            ("base64_encode(data)", "Standard base64 with padding."),
+            ("base64_decode(text)", ""),
+            ("base64url_encode(data)", "URL-safe base64 without padding."),
+            (
+                "base64url_decode(text)",
+                "Accepts input with or without padding.",
+            ),
+            ("base32_encode(data)", ""),
+            ("base32_decode(text)", ""),
+            ("hex_encode(data)", "Lowercase hex."),
+            ("hex_decode(text)", ""),
+            ("url_encode(data)", "Form encoding, spaces become `+`."),
+            ("url_decode(text)", "Form decoding, `+` becomes a space."),
+        ],
+    );
+    Ok(())
+}
+
+/// Decodes URL-safe base64, with or without trailing padding.
+pub fn base64url_decode(value: &[u8]) -> Result<Vec<u8>, base64::DecodeError> {
+    let end = value
+        .iter()
+        .rposition(|byte| *byte != b'=')
+        .map_or(0, |index| index + 1);
+    URL_SAFE_NO_PAD.decode(&value[..end])
+}
+
+/// Decodes `application/x-www-form-urlencoded` bytes, turning `+` into spaces.
+/// Malformed escapes are kept verbatim.
+pub fn url_decode(value: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(value.len());
+    let mut index = 0;
+    while index < value.len() {
+        let escaped = (value[index] == b'%')
+            .then(|| value.get(index + 1..index + 3))
+            .flatten()
+            .filter(|pair| pair.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|pair| std::str::from_utf8(pair).ok())
+            .and_then(|pair| u8::from_str_radix(pair, 16).ok());
+        match (escaped, value[index]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                index += 2;
+            }
+            (None, b'+') => decoded.push(b' '),
+            (None, byte) => decoded.push(byte),
+        }
+        index += 1;
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base64url_decode, url_decode};
+    use crate::create_lua;
+
+    #[test]
+    fn decodes_padded_and_unpadded_base64url() {
+        assert_eq!(base64url_decode(b"-_8").unwrap(), vec![0xFB, 0xFF]);
+        assert_eq!(base64url_decode(b"-_8=").unwrap(), vec![0xFB, 0xFF]);
+    }
+
+    #[test]
+    fn decodes_form_encoding() {
+        assert_eq!(url_decode(b"a+b%26c"), b"a b&c");
+        assert_eq!(url_decode(b"%ff%zz%"), b"\xff%zz%");
+    }
+
+    #[test]
+    fn round_trips_binary_strings() {
+        let lua = create_lua().unwrap();
+        let ok: bool = lua
+            .load(
+                r#"
+                local raw = "\0\255\128abc"
+                return encoding.base64_decode(encoding.base64_encode(raw)) == raw
+                    and encoding.base64url_decode(encoding.base64url_encode(raw)) == raw
+                    and encoding.base32_decode(encoding.base32_encode(raw)) == raw
+                    and encoding.hex_decode(encoding.hex_encode(raw)) == raw
+                    and encoding.url_decode(encoding.url_encode(raw)) == raw
+                    and encoding.hex_encode("\1\171") == "01ab"
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert!(ok);
+    }
+}
@@ -1,21 +1,269 @@
 //! Global asynchronous Lua module `http`.
+//!
+//! Outbound requests are confined by an [`HttpPolicy`]. Hosts are resolved by
+//! the module itself and loopback, private, link-local and similar addresses
+//! are refused unless the host is explicitly allowlisted. Every call is
+//! recorded for the current execution and emitted on the `cds.lua.http`
+//! tracing target.
 
-use std::time::Duration;
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
-use mlua::{ExternalResult, Lua, Table};
-use reqwest::{Method, redirect::Policy};
+use mlua::{ExternalResult, Lua, LuaString, Table};
+use once_cell::sync::OnceCell;
+use reqwest::{
+    Method, Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect::Policy,
+};
+use serde::{Deserialize, Serialize};
 
-use crate::{global_module, traits::EngineError};
+use crate::{api, global_module, traits::EngineError};
 
 const MAX_HTTP_BODY_SIZE: usize = 2 * 1024 * 1024;
 
+/// Outbound access rules for one script.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpPolicy {
+    /// Hostnames (or IP literals) the script may reach. `*.example.com`
+    /// matches subdomains. When non-empty, every other host is refused; listed
+    /// hosts may resolve to private addresses.
+    pub allowed_hosts: Vec<String>,
+    /// Requests allowed per execution.
+    pub max_requests: u32,
+    /// Redirects followed per request.
+    pub max_redirects: usize,
+}
+
+impl Default for HttpPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: Vec::new(),
+            max_requests: 16,
+            max_redirects: 5,
+        }
+    }
+}
+
+impl HttpPolicy {
+    /// Creates the default policy with the given host allowlist.
+    pub fn with_allowed_hosts(allowed_hosts: Vec<String>) -> Self {
+        Self {
+            allowed_hosts,
+            ..Default::default()
+        }
+    }
+
+    fn is_listed(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.allowed_hosts.iter().any(|pattern| {
+            let pattern = pattern.trim().trim_end_matches('.').to_ascii_lowercase();
+            match pattern.strip_prefix("*.") {
+                Some(suffix) => host
+                    .strip_suffix(suffix)
+                    .is_some_and(|prefix| prefix.ends_with('.') && prefix.len() > 1),
+                None => host == pattern,
+            }
+        })
+    }
+
+    /// Checks scheme and host of a URL before any connection is attempted.
+    fn check_url(&self, url: &Url) -> Result<(), String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("http only supports http and https URLs".to_owned());
+        }
+        let host = url.host_str().ok_or("http URL has no host")?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if !self.allowed_hosts.is_empty() && !self.is_listed(host) {
+            return Err(format!("http host `{host}` is not allowed"));
+        }
+        if let Ok(ip) = host.parse::<IpAddr>()
+            && is_forbidden(ip)
+            && !self.is_listed(host)
+        {
+            return Err(format!("http address `{ip}` is not allowed"));
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether an address is internal and must not be reached by default.
+pub fn is_forbidden(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_forbidden_v4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_forbidden_v4(ip);
+            }
+            let segments = ip.segments();
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (segments[0] & 0xfe00) == 0xfc00 // unique local
+                || (segments[0] & 0xffc0) == 0xfe80 // link-local
+                || (segments[0] == 0x64 && segments[1] == 0xff9b) // NAT64
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8) // documentation
+        }
+    }
+}
+
+fn is_forbidden_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)) // shared address space
+        || (a == 192 && b == 0 && ip.octets()[2] == 0) // IETF protocol assignments
+        || (a == 198 && (18..20).contains(&b)) // benchmarking
+        || a >= 240
+}
+
+/// DNS resolver that drops addresses the policy does not permit.
+struct GuardedResolver {
+    policy: Arc<HttpPolicy>,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.policy.clone();
+        let host = name.as_str().to_owned();
+        Box::pin(async move {
+            let listed = policy.is_listed(&host);
+            let addrs = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| listed || !is_forbidden(addr.ip()))
+                .collect::<Vec<SocketAddr>>();
+            if addrs.is_empty() {
+                return Err(format!("http host `{host}` resolves to no allowed address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// A policy with its lazily built client, shared by every Lua state of one
+/// cached script.
+pub struct HttpAccess {
+    policy: Arc<HttpPolicy>,
+    client: OnceCell<reqwest::Client>,
+}
+
+impl HttpAccess {
+    pub fn new(policy: HttpPolicy) -> Self {
+        Self {
+            policy: Arc::new(policy),
+            client: OnceCell::new(),
+        }
+    }
+
+    pub fn policy(&self) -> &HttpPolicy {
+        &self.policy
+    }
+
+    /// Checks a URL against the policy before it is requested.
+    pub fn check_url(&self, url: &Url) -> Result<(), String> {
+        self.policy.check_url(url)
+    }
+
+    /// Client confined by the policy, shared with native callers.
+    pub fn client(&self) -> Result<&reqwest::Client, reqwest::Error> {
+        self.client.get_or_try_init(|| {
+            let redirect_policy = self.policy.clone();
+            reqwest::Client::builder()
+                .connect_timeout(Duration::from_secs(5))
+                .timeout(Duration::from_secs(15))
+                .no_proxy()
+                .dns_resolver(GuardedResolver {
+                    policy: self.policy.clone(),
+                })
+                .redirect(Policy::custom(move |attempt| {
+                    if attempt.previous().len() > redirect_policy.max_redirects {
+                        return attempt.error("http redirect limit exceeded");
+                    }
+                    match redirect_policy.check_url(attempt.url()) {
+                        Ok(()) => attempt.follow(),
+                        Err(error) => attempt.error(error),
+                    }
+                }))
+                .build()
+        })
+    }
+}
+
+/// One outbound request made by a script.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+pub struct HttpCall {
+    pub method: String,
+    pub url: String,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+struct HttpState {
+    access: Mutex<Arc<HttpAccess>>,
+    requests: AtomicU32,
+    calls: Mutex<Vec<HttpCall>>,
+}
+
+/// Replaces the policy of a Lua state.
+pub fn set_policy(lua: &Lua, policy: HttpPolicy) {
+    attach(lua, Arc::new(HttpAccess::new(policy)));
+}
+
+/// Attaches shared access rules to a Lua state.
+pub(crate) fn attach(lua: &Lua, access: Arc<HttpAccess>) {
+    if let Some(state) = lua.app_data_ref::<HttpState>() {
+        *state.access.lock().expect("http state poisoned") = access;
+    }
+}
+
+/// Clears the per-execution request budget and call record.
+pub(crate) fn reset(lua: &Lua) {
+    if let Some(state) = lua.app_data_ref::<HttpState>() {
+        state.requests.store(0, Ordering::Relaxed);
+        state.calls.lock().expect("http state poisoned").clear();
+    }
+}
+
+/// Returns the calls made since the last reset.
+pub fn calls(lua: &Lua) -> Vec<HttpCall> {
+    lua.app_data_ref::<HttpState>()
+        .map(|state| state.calls.lock().expect("http state poisoned").clone())
+        .unwrap_or_default()
+}
+
+fn record(lua: &Lua, call: HttpCall) {
+    tracing::info!(
+        target: "cds.lua.http",
+        method = %call.method,
+        url = %call.url,
+        status = ?call.status,
+        error = ?call.error,
+        elapsed_ms = call.elapsed_ms,
+    );
+    if let Some(state) = lua.app_data_ref::<HttpState>() {
+        state.calls.lock().expect("http state poisoned").push(call);
+    }
+}
+
 pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
-    let client = reqwest::Client::builder()
-        .connect_timeout(Duration::from_secs(5))
-        .timeout(Duration::from_secs(15))
-        .redirect(Policy::limited(5))
-        .build()
-        .map_err(anyhow::Error::from)?;
+    lua.set_app_data(HttpState {
+        access: Mutex::new(Arc::new(HttpAccess::new(HttpPolicy::default()))),
+        requests: AtomicU32::new(0),
+        calls: Mutex::new(Vec::new()),
+    });
     let module = global_module(lua, "http")?;
     module.set(
         "url_encode",
@@ -31,79 +279,148 @@ pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
                 String,
                 String,
                 Option<Table>,
-                Option<String>,
-            )| {
-                let client = client.clone();
-                async move {
-                    if !(url.starts_with("https://") || url.starts_with("http://")) {
-                        return Err(mlua::Error::RuntimeError(
-                            "http only supports http and https URLs".to_owned(),
-                        ));
-                    }
-                    let method = Method::from_bytes(method.as_bytes()).into_lua_err()?;
-                    let mut request = client.request(method, url);
-                    if let Some(headers) = headers {
-                        for pair in headers.pairs::<String, String>() {
-                            let (name, value) = pair?;
-                            request = request.header(name, value);
-                        }
-                    }
-                    if let Some(body) = body {
-                        request = request.body(body);
-                    }
-                    let mut response = request.send().await.into_lua_err()?;
-                    if response
-                        .content_length()
-                        .is_some_and(|length| length > MAX_HTTP_BODY_SIZE as u64)
+                Option<LuaString>,
+            )| async move {
+                let access = {
+                    let state = lua.app_data_ref::<HttpState>().ok_or_else(|| {
+                        mlua::Error::RuntimeError("http is unavailable".to_owned())
+                    })?;
+                    let access = state.access.lock().expect("http state poisoned").clone();
+                    let max_requests = access.policy.max_requests;
+                    if state
+                        .requests
+                        .try_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                            (count < max_requests).then_some(count + 1)
+                        })
+                        .is_err()
                     {
                         return Err(mlua::Error::RuntimeError(
-                            "http response body is too large".to_owned(),
+                            "http request limit exceeded".to_owned(),
                         ));
                     }
+                    access
+                };
 
-                    let status = response.status().as_u16();
-                    let response_headers = response.headers().clone();
-                    let mut body = Vec::with_capacity(
-                        response
-                            .content_length()
-                            .unwrap_or_default()
-                            .min(MAX_HTTP_BODY_SIZE as u64) as usize,
-                    );
-                    while let Some(chunk) = response.chunk().await.into_lua_err()? {
-                        if chunk.len() > MAX_HTTP_BODY_SIZE.saturating_sub(body.len()) {
-                            return Err(mlua::Error::RuntimeError(
-                                "http response body is too large".to_owned(),
-                            ));
-                        }
-                        body.extend_from_slice(&chunk);
-                    }
-                    let body = String::from_utf8(body).into_lua_err()?;
-                    let result = lua.create_table()?;
-                    result.set("status", status)?;
-                    result.set("body", body)?;
-                    let headers = lua.create_table()?;
-                    for (name, value) in &response_headers {
-                        if let Ok(value) = value.to_str() {
-                            headers.set(name.as_str(), value)?;
-                        }
-                    }
-                    result.set("headers", headers)?;
-                    Ok(result)
-                }
+                let started = Instant::now();
+                let result = send(&lua, &access, &method, &url, headers, body).await;
+                record(
+                    &lua,
+                    HttpCall {
+                        method: method.to_ascii_uppercase(),
+                        url,
+                        status: result.as_ref().ok().map(|(status, _)| *status),
+                        error: result.as_ref().err().map(ToString::to_string),
+                        elapsed_ms: started.elapsed().as_millis() as u64,
+                    },
+                );
+                result.map(|(_, table)| table)
             },
         )?,
     )?;
+    api::register(
+        lua,
+        "http",
+        &[
+            ("url_encode(value)", "Form-encodes a string."),
+            (
+                "request(method, url, headers?, body?)",
+                "Sends a request to an allowed public host; returns `{ status, headers, body }`.",
+            ),
+        ],
+    );
     Ok(())
 }
 
+async fn send(
+    lua: &Lua,
+    access: &HttpAccess,
+    method: &str,
+    url: &str,
+    headers: Option<Table>,
+    body: Option<LuaString>,
+) -> mlua::Result<(u16, Table)> {
+    let parsed = Url::parse(url).into_lua_err()?;
+    access
+        .policy
+        .check_url(&parsed)
+        .map_err(mlua::Error::RuntimeError)?;
+    let method = Method::from_bytes(method.as_bytes()).into_lua_err()?;
+    let mut request = access.client().into_lua_err()?.request(method, parsed);
+    if let Some(headers) = headers {
+        for pair in headers.pairs::<String, String>() {
+            let (name, value) = pair?;
+            request = request.header(name, value);
+        }
+    }
+    if let Some(body) = body {
+        request = request.body(body.as_bytes().to_vec());
+    }
+    let mut response = request
+        .send()
+        .await
+        .map_err(|error| mlua::Error::RuntimeError(error_chain(&error)))?;
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_HTTP_BODY_SIZE as u64)
+    {
+        return Err(mlua::Error::RuntimeError(
+            "http response body is too large".to_owned(),
+        ));
+    }
+
+    let status = response.status().as_u16();
+    let response_headers = response.headers().clone();
+    let mut body = Vec::with_capacity(
+        response
+            .content_length()
+            .unwrap_or_default()
+            .min(MAX_HTTP_BODY_SIZE as u64) as usize,
+    );
+    while let Some(chunk) = response.chunk().await.into_lua_err()? {
+        if chunk.len() > MAX_HTTP_BODY_SIZE.saturating_sub(body.len()) {
+            return Err(mlua::Error::RuntimeError(
+                "http response body is too large".to_owned(),
+            ));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let result = lua.create_table()?;
+    result.set("status", status)?;
+    result.set("body", lua.create_string(body)?)?;
+    let headers = lua.create_table()?;
+    for (name, value) in &response_headers {
+        if let Ok(value) = value.to_str() {
+            headers.set(name.as_str(), value)?;
+        }
+    }
+    result.set("headers", headers)?;
+    Ok((status, result))
+}
+
+/// Joins an error with its sources; reqwest hides the resolver and redirect
+/// reasons behind a generic top-level message.
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+    message
+}
+
 #[cfg(test)]
 mod tests {
+    use std::net::IpAddr;
+
     use mlua::{Function, Value};
     use tokio::{
         io::{AsyncReadExt, AsyncWriteExt},
         net::TcpListener,
     };
 
+    use super::{HttpPolicy, calls, is_forbidden, set_policy};
     use crate::create_lua;
 
     #[test]
@@ -116,6 +433,133 @@ mod tests {
         assert_eq!(value, "hello+world%26answer%3D42");
     }
 
+    #[test]
+    fn classifies_internal_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(is_forbidden(ip.parse::<IpAddr>().unwrap()), "{ip}");
+        }
+        for ip in ["1.1.1.1", "8.8.8.8", "2606:4700:4700::1111"] {
+            assert!(!is_forbidden(ip.parse::<IpAddr>().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn matches_allowlisted_hosts() {
+        let policy = HttpPolicy::with_allowed_hosts(vec![
+            "api.example.com".to_owned(),
+            "*.corp.example".to_owned(),
+        ]);
+
+        assert!(policy.is_listed("API.example.com"));
+        assert!(policy.is_listed("sso.corp.example"));
+        assert!(!policy.is_listed("corp.example"));
+        assert!(!policy.is_listed("evilcorp.example"));
+        assert!(!policy.is_listed("example.com"));
+    }
+
+    #[tokio::test]
+    async fn rejects_internal_targets_by_default() {
+        let lua = create_lua().unwrap();
+        let request: Function = lua
+            .load("return function(url) return http.request('GET', url, nil, nil) end")
+            .eval()
+            .unwrap();
+
+        for url in [
+            "http://127.0.0.1:1/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[::1]/",
+            "http://localhost/",
+        ] {
+            let error = request.call_async::<Value>(url).await.unwrap_err();
+            assert!(
+                error.to_string().contains("not allowed")
+                    || error.to_string().contains("no allowed address"),
+                "{url}: {error}"
+            );
+        }
+
+        let calls = calls(&lua);
+        assert_eq!(calls.len(), 4);
+        assert!(calls.iter().all(|call| call.error.is_some()));
+    }
+
+    #[tokio::test]
+    async fn enforces_allowlist_and_request_budget() {
+        let lua = create_lua().unwrap();
+        set_policy(
+            &lua,
+            HttpPolicy {
+                allowed_hosts: vec!["api.example.com".to_owned()],
+                max_requests: 1,
+                ..Default::default()
+            },
+        );
+        let request: Function = lua
+            .load("return function(url) return http.request('GET', url, nil, nil) end")
+            .eval()
+            .unwrap();
+
+        let error = request
+            .call_async::<Value>("https://other.example.com/")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("is not allowed"));
+
+        let error = request
+            .call_async::<Value>("https://api.example.com/")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("request limit exceeded"));
+    }
+
+    #[tokio::test]
+    async fn sends_and_returns_binary_bodies() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0_u8; 1024];
+            while !request.ends_with(b"\xff\x00\x80") {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\n\x00\xfe\x01")
+                .await
+                .unwrap();
+        });
+
+        let lua = create_lua().unwrap();
+        set_policy(
+            &lua,
+            HttpPolicy::with_allowed_hosts(vec!["127.0.0.1".to_owned()]),
+        );
+        let request: Function = lua
+            .load("return function(url) return http.request('POST', url, nil, '\\255\\0\\128').body end")
+            .eval()
+            .unwrap();
+        let body = request
+            .call_async::<mlua::BString>(format!("http://{address}"))
+            .await
+            .unwrap();
+        assert_eq!(body.as_slice(), b"\x00\xfe\x01");
+        server.abort();
+    }
+
     #[tokio::test]
     async fn rejects_oversized_chunked_bodies() {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -138,6 +582,10 @@ mod tests {
         });
 
         let lua = create_lua().unwrap();
+        set_policy(
+            &lua,
+            HttpPolicy::with_allowed_hosts(vec!["127.0.0.1".to_owned()]),
+        );
         let request: Function = lua
             .load("return function(url) return http.request('GET', url, nil, nil) end")
             .eval()
@@ -2,7 +2,7 @@
 
 use mlua::{ExternalResult, Lua, LuaSerdeExt, Value};
 
-use crate::{global_module, traits::EngineError};
+use crate::{api, global_module, traits::EngineError};
 
 pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
     let module = global_module(lua, "json")?;
@@ -20,6 +20,14 @@ pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
             lua.to_value(&value)
         })?,
     )?;
+    api::register(
+        lua,
+        "json",
+        &[
+            ("encode(value)", "Serializes a Lua value to JSON text."),
+            ("decode(text)", "Parses JSON text into a Lua value."),
+        ],
+    );
     Ok(())
 }
 
@@ -1,6 +1,8 @@
 //! Global libraries available to every Lua script context.
 
+pub mod bytes;
 pub mod crypto;
+pub mod encoding;
 pub mod http;
 pub mod json;
 pub mod regex;
@@ -11,7 +13,9 @@ use mlua::Lua;
 use crate::traits::EngineError;
 
 pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
+    bytes::install(lua)?;
     crypto::install(lua)?;
+    encoding::install(lua)?;
     http::install(lua)?;
     json::install(lua)?;
     regex::install(lua)?;
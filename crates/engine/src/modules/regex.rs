@@ -2,7 +2,7 @@
 
 use mlua::Lua;
 
-use crate::{global_module, traits::EngineError};
+use crate::{api, global_module, traits::EngineError};
 
 pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
     let module = global_module(lua, "regex")?;
@@ -12,6 +12,14 @@ pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
             Ok(is_match(&pattern, &value))
         })?,
     )?;
+    api::register(
+        lua,
+        "regex",
+        &[(
+            "is_match(pattern, value)",
+            "Whether the pattern matches anywhere in the value.",
+        )],
+    );
     Ok(())
 }
 
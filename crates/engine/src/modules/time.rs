@@ -4,7 +4,7 @@ use std::time::Duration;
 
 use mlua::Lua;
 
-use crate::{global_module, traits::EngineError};
+use crate::{api, global_module, traits::EngineError};
 
 /// Installs the time helpers available to every Lua script.
 pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
@@ -21,6 +21,14 @@ pub(crate) fn install(lua: &Lua) -> Result<(), EngineError> {
             Ok(())
         })?,
     )?;
+    api::register(
+        lua,
+        "time",
+        &[(
+            "sleep(seconds)",
+            "Suspends the script without spending instructions.",
+        )],
+    );
     Ok(())
 }
 
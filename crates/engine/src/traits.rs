@@ -1,8 +1,11 @@
 //! Shared error and diagnostic types for the Lua engine.
 
 use serde::Serialize;
+use serde_json::Value as JsonValue;
 use thiserror::Error;
 
+use crate::{LogLine, modules::http::HttpCall};
+
 #[derive(Debug, Error)]
 pub enum EngineError {
     #[error("lua error: {0}")]
@@ -13,6 +16,8 @@ pub enum EngineError {
     MissingContext(String),
     #[error("missing function: {0}")]
     MissingFunction(String),
+    #[error("missing library: {0}")]
+    MissingLibrary(String),
     #[error("missing script: {0}")]
     MissingScript(String),
     #[error("script error: {0}")]
@@ -23,7 +28,7 @@ pub enum EngineError {
     OtherError(#[from] anyhow::Error),
 }
 
-#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct DiagnosticMarker {
     pub kind: DiagnosticKind,
@@ -34,9 +39,43 @@ pub struct DiagnosticMarker {
     pub end_column: usize,
 }
 
-#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum DiagnosticKind {
     Error,
     Warning,
 }
+
+/// A compiled script held in the engine cache, with its Lua state pool.
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct CachedScript {
+    pub key: String,
+    pub created_at: i64,
+    /// Source length in bytes.
+    pub script_size: usize,
+    pub allowed_hosts: Vec<String>,
+    /// Lua states parked in the pool, ready for reuse.
+    pub idle_states: usize,
+    /// Executions currently holding a Lua state.
+    pub busy_states: usize,
+    /// Concurrent executions the pool allows.
+    pub capacity: usize,
+}
+
+/// What one script call did: its log lines, outbound requests, return value
+/// or error, and how much it spent.
+#[derive(Clone, Debug, Default, Serialize, utoipa::ToSchema)]
+pub struct Trace {
+    pub logs: Vec<LogLine>,
+    /// Log lines dropped by the per-call entry or capture size limit.
+    pub logs_dropped: u32,
+    pub http: Vec<HttpCall>,
+    /// Return value converted to JSON, when it has a JSON representation.
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<JsonValue>,
+    /// Error with the source line it was raised from, when the call failed.
+    pub error: Option<DiagnosticMarker>,
+    /// Instructions executed, counted in hook batches of 10,000.
+    pub instructions: u64,
+    pub duration_ms: u64,
+}
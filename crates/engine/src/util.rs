@@ -83,7 +83,73 @@ pub(crate) fn syntax_diagnostics(source: &str, message: &str) -> Vec<ParserDiagn
     }
 }
 
-fn parser_span(
+/// Finds `require("lib/<name>")` calls with a literal name and returns the
+/// library names with the span of each call.
+pub(crate) fn literal_requires(source: &str) -> Vec<(String, DiagnosticSpan)> {
+    use full_moon::{
+        ast::{Call, Expression, FunctionArgs, FunctionCall, Prefix, Suffix},
+        node::Node,
+        tokenizer::{TokenReference, TokenType},
+        visitors::Visitor,
+    };
+
+    struct Requires<'a> {
+        source: &'a str,
+        found: Vec<(String, DiagnosticSpan)>,
+    }
+
+    fn literal(token: &TokenReference) -> Option<String> {
+        match token.token_type() {
+            TokenType::StringLiteral { literal, .. } => Some(literal.to_string()),
+            _ => None,
+        }
+    }
+
+    impl Visitor for Requires<'_> {
+        fn visit_function_call(&mut self, call: &FunctionCall) {
+            let Prefix::Name(name) = call.prefix() else {
+                return;
+            };
+            if name.token().to_string() != "require" {
+                return;
+            }
+            let argument = match call.suffixes().next() {
+                Some(Suffix::Call(Call::AnonymousCall(FunctionArgs::String(token)))) => {
+                    literal(token)
+                }
+                Some(Suffix::Call(Call::AnonymousCall(FunctionArgs::Parentheses {
+                    arguments,
+                    ..
+                }))) => match arguments.iter().next() {
+                    Some(Expression::String(token)) => literal(token),
+                    _ => None,
+                },
+                _ => None,
+            };
+            let Some(library) = argument.and_then(|argument| {
+                argument
+                    .strip_prefix(crate::library::PREFIX)
+                    .map(str::to_owned)
+            }) else {
+                return;
+            };
+            if let Some((start, end)) = call.range() {
+                let span = parser_span(self.source, start, end, start.line().saturating_sub(1));
+                self.found.push((library, span));
+            }
+        }
+    }
+
+    let parsed = full_moon::parse_fallible(source, full_moon::LuaVersion::lua54());
+    let mut visitor = Requires {
+        source,
+        found: Vec::new(),
+    };
+    visitor.visit_ast(parsed.ast());
+    visitor.found
+}
+
+pub(crate) fn parser_span(
     source: &str,
     start: full_moon::tokenizer::Position,
     end: full_moon::tokenizer::Position,
@@ -161,7 +227,22 @@ fn source_lines(source: &str) -> Vec<&str> {
 
 #[cfg(test)]
 mod tests {
-    use super::{error_line_span, syntax_diagnostics};
+    use super::{error_line_span, literal_requires, syntax_diagnostics};
+
+    #[test]
+    fn finds_literal_library_requires() {
+        let found = literal_requires(
+            "local a = require('lib/a')\nlocal b = require 'lib/b/c'\nrequire(name)\nrequire('json')",
+        );
+        let names = found
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b/c"]);
+        assert_eq!(found[0].1.start_line, 0);
+        assert_eq!(found[0].1.start_column, 10);
+        assert_eq!(found[1].1.start_line, 1);
+    }
 
     #[test]
     fn uses_full_moon_token_range() {
@@ -0,0 +1,28 @@
+//! Configuration section — `ingress` (loaded via Figment / `CDSCTF_*`).
+//!
+//! Used when `cluster.traffic = "ingress"`. The wildcard host
+//! `*.{public_entry}` must resolve to the server, and TLS for it is
+//! terminated in front of the server like the platform's own domain.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Whether instance hosts are served over HTTPS; picks the scheme of
+    /// the links handed to players and marks the access cookie `Secure`.
+    pub tls: bool,
+    /// Whether each instance gets a random token that must be presented
+    /// (once as `?cds_token=`, then as a cookie) before it is proxied.
+    pub access_token: bool,
+}
+
+impl Default for Config {
+    /// Returns the default value for this type.
+    fn default() -> Self {
+        Config {
+            tls: true,
+            access_token: true,
+        }
+    }
+}
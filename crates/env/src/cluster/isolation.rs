@@ -0,0 +1,47 @@
+//! Configuration section — `isolation` (loaded via Figment / `CDSCTF_*`).
+//!
+//! How instances are kept apart from each other. Each instance gets an
+//! ingress NetworkPolicy admitting only its own pods and the sources below.
+//! `wsrx`, ingress and TCP proxy traffic arrives through the Kubernetes
+//! port-forward API, which NetworkPolicies do not apply to.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Creates the per-instance ingress NetworkPolicies.
+    pub network_policy: bool,
+    /// Labels of the namespace the platform runs in, when it reaches
+    /// instances over the pod network.
+    pub proxy_namespace_labels: BTreeMap<String, String>,
+    /// Labels of the platform's pods in that namespace.
+    pub proxy_pod_labels: BTreeMap<String, String>,
+    /// Address ranges allowed in, such as the nodes' that `expose` traffic
+    /// arrives from.
+    pub allowed_cidrs: Vec<String>,
+    /// The cluster's pod address ranges. Under `expose` traffic with no
+    /// `allowed_cidrs`, any address outside them is allowed in; with neither
+    /// set, the server refuses to start rather than admit every address.
+    pub pod_cidrs: Vec<String>,
+    /// Starts each team's instances, or each user's outside games, in a
+    /// namespace of its own named after `cluster.namespace`. Warm pools are
+    /// not used in this mode.
+    pub namespace_per_team: bool,
+}
+
+impl Default for Config {
+    /// Returns the default value for this type.
+    fn default() -> Self {
+        Self {
+            network_policy: true,
+            proxy_namespace_labels: BTreeMap::new(),
+            proxy_pod_labels: BTreeMap::new(),
+            allowed_cidrs: Vec::new(),
+            pod_cidrs: Vec::new(),
+            namespace_per_team: false,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Configuration section — `lifetime` (loaded via Figment / `CDSCTF_*`).
+//!
+//! Platform defaults for how long an instance may live; a challenge can set
+//! its own limits in place of `max_renewals`, `max_lifetime` and
+//! `idle_timeout`. Durations are in seconds, and `0` means no limit.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Renewals an owner may ask for.
+    pub max_renewals: i32,
+    /// Longest an instance may run, renewals included.
+    pub max_lifetime: i64,
+    /// Time without `wsrx`, ingress or TCP proxy traffic after which an
+    /// instance is stopped.
+    pub idle_timeout: i64,
+    /// How long before an instance expires or idles out its owner is warned.
+    pub warning_before: i64,
+    /// Time containers get to shut down after `SIGTERM`.
+    pub grace_period: i64,
+}
+
+impl Default for Config {
+    /// Returns the default value for this type.
+    fn default() -> Self {
+        Self {
+            max_renewals: 3,
+            max_lifetime: 0,
+            idle_timeout: 0,
+            warning_before: 300,
+            grace_period: 10,
+        }
+    }
+}
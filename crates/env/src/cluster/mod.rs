@@ -1,5 +1,29 @@
 //! Configuration section — `mod` (loaded via Figment / `CDSCTF_*`).
 
+/// Defines the `ingress` submodule (see sibling `*.rs` files).
+mod ingress;
+
+/// Defines the `isolation` submodule (see sibling `*.rs` files).
+pub mod isolation;
+
+/// Defines the `lifetime` submodule (see sibling `*.rs` files).
+pub mod lifetime;
+
+/// Defines the `reset` submodule (see sibling `*.rs` files).
+pub mod reset;
+
+/// Defines the `security` submodule (see sibling `*.rs` files).
+pub mod security;
+
+/// Defines the `tcp_proxy` submodule (see sibling `*.rs` files).
+pub mod tcp_proxy;
+
+/// Defines the `warmup` submodule (see sibling `*.rs` files).
+pub mod warmup;
+
+/// Defines the `wireguard` submodule (see sibling `*.rs` files).
+pub mod wireguard;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -10,14 +34,27 @@ pub struct Config {
     pub config_path: String,
     pub traffic: Traffic,
     pub public_entry: String,
+    pub ingress: ingress::Config,
+    pub tcp_proxy: tcp_proxy::Config,
+    pub isolation: isolation::Config,
+    pub lifetime: lifetime::Config,
+    pub reset: reset::Config,
+    pub security: security::Config,
+    pub warmup: warmup::Config,
+    pub wireguard: wireguard::Config,
     pub egress_excluded_cidrs: Vec<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Traffic {
+    /// `NodePort` services reached at `public_entry` on the node ports.
     Expose,
+    /// `ClusterIP` services reached through the `wsrx` WebSocket tunnel.
     Proxy,
+    /// `ClusterIP` services reverse-proxied by the server at
+    /// `{instance_id}.{public_entry}`, for web challenges opened in a browser.
+    Ingress,
 }
 
 impl Default for Config {
@@ -29,6 +66,14 @@ impl Default for Config {
             config_path: "".to_owned(),
             traffic: Traffic::Proxy,
             public_entry: "0.0.0.0".to_owned(),
+            ingress: ingress::Config::default(),
+            tcp_proxy: tcp_proxy::Config::default(),
+            isolation: isolation::Config::default(),
+            lifetime: lifetime::Config::default(),
+            reset: reset::Config::default(),
+            security: security::Config::default(),
+            warmup: warmup::Config::default(),
+            wireguard: wireguard::Config::default(),
             egress_excluded_cidrs: vec![],
         }
     }
@@ -0,0 +1,25 @@
+//! Configuration section — `reset` (loaded via Figment / `CDSCTF_*`).
+//!
+//! Platform defaults for how often an owner may put an instance back to a
+//! known state; a challenge can set its own `max_resets`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Resets an owner may ask for, checkpoint restores included.
+    pub max_resets: i32,
+    /// Named checkpoints an instance may keep at once.
+    pub max_checkpoints: usize,
+}
+
+impl Default for Config {
+    /// Returns the default value for this type.
+    fn default() -> Self {
+        Self {
+            max_resets: 3,
+            max_checkpoints: 3,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Configuration section — `security` (loaded via Figment / `CDSCTF_*`).
+//!
+//! Platform-wide hardening applied to every challenge pod. Challenge authors
+//! may tighten each setting for their own containers but never loosen it;
+//! configurations that try are rejected when the challenge is saved.
+//!
+//! The defaults apply to every existing challenge as well, with no migration:
+//! containers now lose `NET_ADMIN`, `NET_RAW`, `SYS_ADMIN`, `SYS_MODULE` and
+//! `SYS_PTRACE` and run under the `RuntimeDefault` seccomp profile. Images that
+//! need one of these (e.g. `ping`, which needs `NET_RAW`, or a debugger-based
+//! pwn challenge) break until the platform opts out by setting
+//! `drop_capabilities = []` and `seccomp_profile = "unconfined"`; authors
+//! cannot loosen them per challenge.
+//!
+//! Kubernetes has no per-pod process limit, so none is offered here; cap
+//! processes on the challenge nodes with the kubelet's `podPidsLimit`, and
+//! pin challenges to those nodes with [`Config::node_selector`].
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Mounts every container's root filesystem read-only.
+    pub read_only_root_filesystem: bool,
+    /// Refuses to start containers whose image runs as root.
+    pub run_as_non_root: bool,
+    /// Capabilities removed from every container; authors may drop more.
+    pub drop_capabilities: Vec<String>,
+    /// `runtime_default`, `unconfined` or `localhost/<profile>`. Authors may
+    /// pick a localhost profile unless this is already one, and may not
+    /// fall back to `unconfined`.
+    pub seccomp_profile: String,
+    /// RuntimeClass forced on every pod (e.g. `gvisor`, `kata`); authors may
+    /// choose one only when this is empty.
+    pub runtime_class_name: String,
+    /// Node labels every pod must match; authors may add labels but not
+    /// change these.
+    pub node_selector: BTreeMap<String, String>,
+    /// Tolerations added to every pod.
+    pub tolerations: Vec<Toleration>,
+    /// Taint keys authors may tolerate in addition to [`Self::tolerations`].
+    pub allowed_toleration_keys: Vec<String>,
+    /// Ephemeral storage limit in MiB for each container, and the most an
+    /// author may ask for.
+    pub max_ephemeral_storage: i64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Toleration {
+    pub key: String,
+    /// `Equal` or `Exists`.
+    pub operator: String,
+    pub value: String,
+    /// `NoSchedule`, `PreferNoSchedule` or `NoExecute`; every effect when
+    /// empty.
+    pub effect: String,
+}
+
+impl Default for Config {
+    /// Returns the default value for this type.
+    fn default() -> Self {
+        Config {
+            read_only_root_filesystem: false,
+            run_as_non_root: false,
+            drop_capabilities: vec![
+                "NET_ADMIN".to_owned(),
+                "NET_RAW".to_owned(),
+                "SYS_ADMIN".to_owned(),
+                "SYS_MODULE".to_owned(),
+                "SYS_PTRACE".to_owned(),
+            ],
+            seccomp_profile: "runtime_default".to_owned(),
+            runtime_class_name: "".to_owned(),
+            node_selector: BTreeMap::new(),
+            tolerations: vec![],
+            allowed_toleration_keys: vec![],
+            max_ephemeral_storage: 1024,
+        }
+    }
+}
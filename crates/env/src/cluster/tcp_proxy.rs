@@ -0,0 +1,52 @@
+//! Configuration section — `tcp_proxy` (loaded via Figment / `CDSCTF_*`).
+//!
+//! When enabled, the server hands out ports from `port_start..=port_end` and
+//! forwards each connection to an instance port, so players can connect with
+//! plain `nc host port`. The range must be reachable from the players.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub enabled: bool,
+    /// Address the listeners bind to.
+    pub host: String,
+    /// Host shown to players; `cluster.public_entry` when empty.
+    pub public_host: String,
+    pub port_start: u16,
+    pub port_end: u16,
+    pub auth: Auth,
+    /// Seconds without traffic in either direction before a connection is
+    /// closed.
+    pub idle_timeout: u64,
+    /// Seconds a new connection has to send its token line.
+    pub token_timeout: u64,
+}
+
+/// How a connection to an allocated port proves it belongs to the player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Auth {
+    /// The first line sent must be a one-time token; the sender's address
+    /// may then reconnect without one.
+    Token,
+    /// Only the address that requested the port may connect.
+    SourceIp,
+}
+
+impl Default for Config {
+    /// Returns the default value for this type.
+    fn default() -> Self {
+        Config {
+            enabled: false,
+            host: "0.0.0.0".to_owned(),
+            public_host: "".to_owned(),
+            port_start: 40000,
+            port_end: 40999,
+            auth: Auth::Token,
+            idle_timeout: 300,
+            token_timeout: 10,
+        }
+    }
+}
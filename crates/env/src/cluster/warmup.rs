@@ -0,0 +1,36 @@
+//! Configuration section — `warmup` (loaded via Figment / `CDSCTF_*`).
+//!
+//! Image pre-pulling runs one DaemonSet per game whose init containers
+//! start every challenge image with `pull_command`, so each node caches the
+//! images before the game opens. Warm pools keep pre-started instances per
+//! challenge; when one is handed out, the checker's environment variables
+//! are written to `{env_dir}/env` in each container.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Image of the pre-pull pods' only long-running container.
+    pub pause_image: String,
+    /// Command each challenge image runs once pulled; it must exit 0, so
+    /// images without a shell need a different command.
+    pub pull_command: Vec<String>,
+    /// Directory mounted into warm pool containers for the claimed
+    /// instance's environment file.
+    pub env_dir: String,
+    /// Most pre-started instances a challenge may keep.
+    pub max_pool_size: i32,
+}
+
+impl Default for Config {
+    /// Returns the default value for this type.
+    fn default() -> Self {
+        Self {
+            pause_image: "registry.k8s.io/pause:3.10".to_owned(),
+            pull_command: vec!["sh".to_owned(), "-c".to_owned(), "exit 0".to_owned()],
+            env_dir: "/run/cds".to_owned(),
+            max_pool_size: 10,
+        }
+    }
+}
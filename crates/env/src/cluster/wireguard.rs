@@ -0,0 +1,50 @@
+//! Configuration section — `wireguard` (loaded via Figment / `CDSCTF_*`).
+//!
+//! The WireGuard access mode runs a gateway Deployment in
+//! `cluster.namespace`. Each team of a game may be issued a peer, and the
+//! gateway only forwards a peer's traffic to the pod IPs of its team's
+//! running instances in that game. Peers are revoked when their team is
+//! banned or their game ends.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Runs the gateway and lets teams be issued peers.
+    pub enabled: bool,
+    /// Image of the gateway. It needs `wg-quick` and `iptables-restore`, and
+    /// runs privileged to forward traffic.
+    pub image: String,
+    /// UDP node port the gateway listens on.
+    pub node_port: i32,
+    /// `host:port` written into issued configs,
+    /// `{cluster.public_entry}:{node_port}` when empty.
+    pub endpoint: String,
+    /// Tunnel network; the gateway takes its first address and each peer
+    /// the lowest free one.
+    pub network: String,
+    /// Ranges peers route through the tunnel, `cluster.isolation.pod_cidrs`
+    /// when empty.
+    pub routes: Vec<String>,
+    /// DNS servers written into issued configs.
+    pub dns: Vec<String>,
+    /// Keepalive interval of issued configs in seconds, `0` for none.
+    pub persistent_keepalive: i32,
+}
+
+impl Default for Config {
+    /// Returns the default value for this type.
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            image: "lscr.io/linuxserver/wireguard:latest".to_owned(),
+            node_port: 31820,
+            endpoint: "".to_owned(),
+            network: "10.213.0.0/16".to_owned(),
+            routes: Vec::new(),
+            dns: Vec::new(),
+            persistent_keepalive: 25,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! Event system — `instance` (types and traits for NATS-backed events).
+
+use serde::{Deserialize, Serialize};
+
+/// Sent to an instance's owner before and when the platform stops it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct InstanceEvent {
+    #[serde(rename = "type")]
+    pub type_: InstanceEventType,
+    pub instance_id: String,
+    pub user_id: i64,
+    pub team_id: Option<i64>,
+    pub game_id: Option<i64>,
+    pub challenge_id: i64,
+    /// When the instance will stop, or stopped.
+    pub at: i64,
+    /// Why it will stop, or stopped, e.g. `expired` or `idle`.
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceEventType {
+    /// The instance stops at `at` unless renewed or used.
+    Warning,
+    Terminated,
+}
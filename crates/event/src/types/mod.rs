@@ -2,13 +2,17 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::types::game_challenge::GameChallengeEvent;
+use crate::types::{game_challenge::GameChallengeEvent, instance::InstanceEvent};
 
 /// Defines the `game_challenge` submodule (see sibling `*.rs` files).
 pub mod game_challenge;
 
+/// Defines the `instance` submodule (see sibling `*.rs` files).
+pub mod instance;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "payload", rename_all = "snake_case")]
 pub enum Event {
     GameChallenge(GameChallengeEvent),
+    Instance(InstanceEvent),
 }
@@ -2,13 +2,22 @@
 //!
 //! Scripts expose top-level `login(params)` and `bind(params, user)` functions.
 //! JSON and HTTP integrations are available as top-level `json` and `http`
-//! libraries.
+//! libraries; outbound requests only reach public hosts unless the IdP
+//! allowlists them.
 
 use std::{collections::HashMap, sync::Arc};
 
-use cds_engine::{ConfigureLua, mlua::Lua, traits::EngineError};
+use cds_engine::{
+    ConfigureLua,
+    api::ApiEntry,
+    mlua::Lua,
+    modules::http::HttpPolicy,
+    traits::{DiagnosticMarker, EngineError, Trace},
+};
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 use thiserror::Error;
+use tracing::{debug, warn};
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct IdentityPayload {
@@ -41,29 +50,40 @@ impl Idp {
         format!("idp/{}", idp_id.to_string())
     }
 
-    pub async fn lint(script: impl AsRef<str>) -> Result<(), EngineError> {
+    /// Validates an IdP script, returning lint warnings.
+    pub async fn lint(script: impl AsRef<str>) -> Result<Vec<DiagnosticMarker>, EngineError> {
         let configure = Self::configure_lua();
         cds_engine::lint(script, &["login", "bind"], configure.as_ref()).await
     }
 
+    /// Describes the Lua API available to IdP scripts.
+    pub fn api() -> Result<Vec<ApiEntry>, EngineError> {
+        cds_engine::api::environment(Self::configure_lua().as_ref())
+    }
+
     pub async fn preload(
         idp_id: impl ToString,
         script: impl AsRef<str>,
+        allowed_hosts: &[String],
     ) -> Result<(), EngineError> {
-        cds_engine::preload(Self::key(idp_id), script, None).await
+        cds_engine::preload_with_policy(
+            Self::key(idp_id),
+            script,
+            None,
+            HttpPolicy::with_allowed_hosts(allowed_hosts.to_vec()),
+        )
+        .await
     }
 
     pub async fn login(
         idp_id: impl ToString,
         params: HashMap<String, String>,
     ) -> Result<IdentityPayload, IdpError> {
-        let configure = Self::configure_lua();
-        let result: HashMap<String, String> = cds_engine::execute_json(
-            Self::key(idp_id),
+        let result = Self::execute(
+            idp_id,
             "login",
             &[serde_json::to_value(params)
                 .map_err(|error| EngineError::OtherError(error.into()))?],
-            configure.as_ref(),
         )
         .await?;
         Self::decode_payload(result)
@@ -74,9 +94,8 @@ impl Idp {
         params: HashMap<String, String>,
         user: HashMap<String, String>,
     ) -> Result<IdentityPayload, IdpError> {
-        let configure = Self::configure_lua();
-        let result: HashMap<String, String> = cds_engine::execute_json(
-            Self::key(idp_id),
+        let result = Self::execute(
+            idp_id,
             "bind",
             &[
                 serde_json::to_value(params)
@@ -84,12 +103,50 @@ impl Idp {
                 serde_json::to_value(user)
                     .map_err(|error| EngineError::OtherError(error.into()))?,
             ],
-            configure.as_ref(),
         )
         .await?;
         Self::decode_payload(result)
     }
 
+    /// Runs an IdP function and reports its trace. Return values carry
+    /// identity data, so only the summary and error location are logged.
+    async fn execute(
+        idp_id: impl ToString,
+        function: &str,
+        args: &[JsonValue],
+    ) -> Result<HashMap<String, String>, EngineError> {
+        let key = Self::key(idp_id);
+        let configure = Self::configure_lua();
+        let (result, trace) =
+            cds_engine::execute_json_traced(&key, function, args, configure.as_ref()).await;
+        Self::report(&key, function, &trace);
+        result
+    }
+
+    fn report(key: &str, function: &str, trace: &Trace) {
+        match &trace.error {
+            Some(error) => warn!(
+                script = key,
+                function,
+                line = error.start_line + 1,
+                error = %error.message,
+                logs = trace.logs.len(),
+                instructions = trace.instructions,
+                duration_ms = trace.duration_ms,
+                "idp script failed"
+            ),
+            None => debug!(
+                script = key,
+                function,
+                logs = trace.logs.len(),
+                http_requests = trace.http.len(),
+                instructions = trace.instructions,
+                duration_ms = trace.duration_ms,
+                "idp script finished"
+            ),
+        }
+    }
+
     fn decode_payload(data: HashMap<String, String>) -> Result<IdentityPayload, IdpError> {
         let auth_key = data
             .get("auth_key")
@@ -141,7 +198,7 @@ mod tests {
     async fn scripts_can_use_global_libraries() {
         cds_engine::clear_cache();
         let script = "function login(params) return { auth_key = json.encode(params) } end function bind(params, user) return login(params) end";
-        Idp::preload("global-libraries", script).await.unwrap();
+        Idp::preload("global-libraries", script, &[]).await.unwrap();
         let payload = Idp::login(
             "global-libraries",
             HashMap::from([("answer".to_owned(), "42".to_owned())]),
@@ -154,7 +211,8 @@ mod tests {
     #[tokio::test]
     async fn bundled_templates_lint() {
         for script in [DEFAULT, GITHUB, CAS] {
-            Idp::lint(script).await.unwrap();
+            let warnings = Idp::lint(script).await.unwrap();
+            assert!(warnings.is_empty(), "{warnings:?}");
         }
     }
 }
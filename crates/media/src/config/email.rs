@@ -11,6 +11,7 @@ use crate::{Media, traits::MediaError};
 pub enum EmailType {
     Verify,
     Forget,
+    Credential,
 }
 
 impl EmailType {
@@ -19,6 +20,7 @@ impl EmailType {
         match self {
             EmailType::Verify => "verify",
             EmailType::Forget => "forget",
+            EmailType::Credential => "credential",
         }
     }
 }
@@ -33,6 +33,21 @@ impl MigratorTrait for Migrator {
             Box::new(migrations::m20260806_000011_create_note::Migration),
             Box::new(migrations::m20260806_000012_create_idp::Migration),
             Box::new(migrations::m20260806_000013_create_user_idp::Migration),
+            Box::new(migrations::m20260806_000014_create_api_token::Migration),
+            Box::new(migrations::m20260806_000015_create_role_assignment::Migration),
+            Box::new(migrations::m20260806_000016_add_team_division::Migration),
+            Box::new(migrations::m20260806_000017_add_script_allowed_hosts::Migration),
+            Box::new(migrations::m20260806_000018_create_lua_library::Migration),
+            Box::new(migrations::m20260806_000019_create_checker_log::Migration),
+            Box::new(migrations::m20260806_000020_add_submission_judgement::Migration),
+            Box::new(migrations::m20260806_000021_create_script_revision::Migration),
+            Box::new(migrations::m20260806_000022_create_rejudge::Migration),
+            Box::new(migrations::m20260806_000023_add_challenge_checker_config::Migration),
+            Box::new(migrations::m20260806_000024_create_instance::Migration),
+            Box::new(migrations::m20260806_000025_add_instance_access_token::Migration),
+            Box::new(migrations::m20260806_000026_add_instance_lifetime::Migration),
+            Box::new(migrations::m20260806_000027_add_instance_reset::Migration),
+            Box::new(migrations::m20260806_000028_create_wireguard::Migration),
         ]
     }
 }
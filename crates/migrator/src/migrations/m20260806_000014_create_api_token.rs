@@ -0,0 +1,75 @@
+//! SeaORM migration `m20260806_000014_create_api_token` — creates personal API
+//! token table.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000014_create_api_token"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE TABLE IF NOT EXISTS "api_tokens" (
+                    "id" BIGSERIAL PRIMARY KEY,
+                    "user_id" BIGINT NOT NULL,
+                    "name" VARCHAR NOT NULL,
+                    "prefix" VARCHAR(16) NOT NULL,
+                    "token_hash" VARCHAR(64) NOT NULL,
+                    "scopes" TEXT[] NOT NULL,
+                    "expires_at" BIGINT,
+                    "last_used_at" BIGINT,
+                    "last_used_ip" VARCHAR,
+                    "created_at" BIGINT NOT NULL,
+                    "updated_at" BIGINT NOT NULL,
+
+                    CONSTRAINT "fk_api_tokens_user_id"
+                        FOREIGN KEY ("user_id") REFERENCES "users" ("id")
+                            ON DELETE CASCADE,
+                    CONSTRAINT "uq_api_tokens_token_hash"
+                        UNIQUE ("token_hash")
+                );
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE INDEX IF NOT EXISTS "idx_api_tokens_user_id"
+                    ON "api_tokens" ("user_id");
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                DROP TABLE IF EXISTS "api_tokens";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+//! SeaORM migration `m20260806_000015_create_role_assignment` — creates scoped
+//! role assignment table.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000015_create_role_assignment"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE TABLE IF NOT EXISTS "role_assignments" (
+                    "id" BIGSERIAL PRIMARY KEY,
+                    "user_id" BIGINT NOT NULL,
+                    "role" VARCHAR NOT NULL,
+                    "game_id" BIGINT,
+                    "challenge_id" BIGINT,
+                    "created_at" BIGINT NOT NULL,
+                    "updated_at" BIGINT NOT NULL,
+
+                    CONSTRAINT "fk_role_assignments_user_id"
+                        FOREIGN KEY ("user_id") REFERENCES "users" ("id")
+                            ON DELETE CASCADE,
+                    CONSTRAINT "fk_role_assignments_game_id"
+                        FOREIGN KEY ("game_id") REFERENCES "games" ("id")
+                            ON DELETE CASCADE,
+                    CONSTRAINT "fk_role_assignments_challenge_id"
+                        FOREIGN KEY ("challenge_id") REFERENCES "challenges" ("id")
+                            ON DELETE CASCADE
+                );
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE UNIQUE INDEX IF NOT EXISTS "uq_role_assignments_scope"
+                    ON "role_assignments" (
+                        "user_id",
+                        "role",
+                        COALESCE("game_id", 0),
+                        COALESCE("challenge_id", 0)
+                    );
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                DROP TABLE IF EXISTS "role_assignments";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+//! SeaORM migration `m20260806_000016_add_team_division` — adds the optional
+//! team division label.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000016_add_team_division"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "teams" ADD COLUMN IF NOT EXISTS "division" VARCHAR;
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "teams" DROP COLUMN IF EXISTS "division";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+//! SeaORM migration `m20260806_000017_add_script_allowed_hosts` — adds the
+//! outbound HTTP host allowlists of IdP and checker scripts.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000017_add_script_allowed_hosts"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "idps" ADD COLUMN IF NOT EXISTS "allowed_hosts" TEXT[] NOT NULL DEFAULT '{}';
+                ALTER TABLE "challenges" ADD COLUMN IF NOT EXISTS "allowed_hosts" TEXT[] NOT NULL DEFAULT '{}';
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "challenges" DROP COLUMN IF EXISTS "allowed_hosts";
+                ALTER TABLE "idps" DROP COLUMN IF EXISTS "allowed_hosts";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
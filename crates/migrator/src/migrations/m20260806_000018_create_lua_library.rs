@@ -0,0 +1,58 @@
+//! SeaORM migration `m20260806_000018_create_lua_library` — creates the
+//! shared Lua libraries table.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000018_create_lua_library"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE TABLE IF NOT EXISTS "lua_libraries" (
+                    "id" BIGSERIAL PRIMARY KEY,
+                    "name" VARCHAR(64) NOT NULL,
+                    "description" TEXT,
+                    "script" TEXT NOT NULL,
+                    "version" BIGINT NOT NULL DEFAULT 1,
+                    "created_at" BIGINT NOT NULL,
+                    "updated_at" BIGINT NOT NULL,
+
+                    CONSTRAINT "uq_lua_libraries_name"
+                        UNIQUE ("name")
+                );
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                DROP TABLE IF EXISTS "lua_libraries";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
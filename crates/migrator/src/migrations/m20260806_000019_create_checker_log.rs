@@ -0,0 +1,78 @@
+//! SeaORM migration `m20260806_000019_create_checker_log` — creates the
+//! per-submission checker execution log table.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000019_create_checker_log"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE TABLE IF NOT EXISTS "checker_logs" (
+                    "id" BIGSERIAL PRIMARY KEY,
+                    "submission_id" BIGINT NOT NULL,
+                    "challenge_id" BIGINT NOT NULL,
+                    "trace" JSONB NOT NULL,
+                    "created_at" BIGINT NOT NULL,
+
+                    CONSTRAINT fk_checker_logs_submission FOREIGN KEY ("submission_id")
+                        REFERENCES submissions ("id") ON DELETE CASCADE,
+                    CONSTRAINT fk_checker_logs_challenge FOREIGN KEY ("challenge_id")
+                        REFERENCES challenges ("id") ON DELETE CASCADE
+                );
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE INDEX IF NOT EXISTS "idx_checker_logs_submission"
+                ON "checker_logs" ("submission_id");
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE INDEX IF NOT EXISTS "idx_checker_logs_challenge"
+                ON "checker_logs" ("challenge_id", "id");
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                DROP TABLE IF EXISTS "checker_logs";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
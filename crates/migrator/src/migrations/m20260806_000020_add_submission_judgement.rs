@@ -0,0 +1,52 @@
+//! SeaORM migration `m20260806_000020_add_submission_judgement` — stores
+//! checker feedback, partial credit and flag ids on submissions.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000020_add_submission_judgement"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "submissions" ADD COLUMN IF NOT EXISTS "feedback" TEXT;
+                ALTER TABLE "submissions" ADD COLUMN IF NOT EXISTS "flag_id" VARCHAR(64);
+                ALTER TABLE "submissions" ADD COLUMN IF NOT EXISTS "credit" INTEGER NOT NULL DEFAULT 100
+                    CONSTRAINT chk_submissions_credit CHECK ("credit" BETWEEN 0 AND 100);
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "submissions" DROP COLUMN IF EXISTS "credit";
+                ALTER TABLE "submissions" DROP COLUMN IF EXISTS "flag_id";
+                ALTER TABLE "submissions" DROP COLUMN IF EXISTS "feedback";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
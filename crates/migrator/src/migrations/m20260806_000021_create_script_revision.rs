@@ -0,0 +1,110 @@
+//! SeaORM migration `m20260806_000021_create_script_revision` — creates the
+//! revision history of checker and IdP scripts and seeds it with the scripts
+//! saved so far.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000021_create_script_revision"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE TABLE IF NOT EXISTS "script_revisions" (
+                    "id" BIGSERIAL PRIMARY KEY,
+                    "challenge_id" BIGINT,
+                    "idp_id" BIGINT,
+                    "number" INTEGER NOT NULL,
+                    "script" TEXT NOT NULL,
+                    "allowed_hosts" TEXT[] NOT NULL DEFAULT '{}',
+                    "author_id" BIGINT,
+                    "restored_from" INTEGER,
+                    "markers" JSONB NOT NULL DEFAULT '[]',
+                    "created_at" BIGINT NOT NULL,
+
+                    CONSTRAINT fk_script_revisions_challenge FOREIGN KEY ("challenge_id")
+                        REFERENCES challenges ("id") ON DELETE CASCADE,
+                    CONSTRAINT fk_script_revisions_idp FOREIGN KEY ("idp_id")
+                        REFERENCES idps ("id") ON DELETE CASCADE,
+                    CONSTRAINT fk_script_revisions_author FOREIGN KEY ("author_id")
+                        REFERENCES users ("id") ON DELETE SET NULL,
+                    CONSTRAINT chk_script_revisions_owner
+                        CHECK (("challenge_id" IS NULL) <> ("idp_id" IS NULL))
+                );
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE UNIQUE INDEX IF NOT EXISTS "idx_script_revisions_challenge"
+                ON "script_revisions" ("challenge_id", "number")
+                WHERE "challenge_id" IS NOT NULL;
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE UNIQUE INDEX IF NOT EXISTS "idx_script_revisions_idp"
+                ON "script_revisions" ("idp_id", "number")
+                WHERE "idp_id" IS NOT NULL;
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                INSERT INTO "script_revisions"
+                    ("challenge_id", "number", "script", "allowed_hosts", "created_at")
+                SELECT "id", 1, "checker", "allowed_hosts", "updated_at"
+                FROM "challenges"
+                WHERE "checker" IS NOT NULL
+                ON CONFLICT DO NOTHING;
+
+                INSERT INTO "script_revisions"
+                    ("idp_id", "number", "script", "allowed_hosts", "created_at")
+                SELECT "id", 1, "script", "allowed_hosts", "updated_at"
+                FROM "idps"
+                ON CONFLICT DO NOTHING;
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                DROP TABLE IF EXISTS "script_revisions";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+//! SeaORM migration `m20260806_000022_create_rejudge` — creates admin re-judge
+//! jobs and the per-submission record of the verdict each one replaced.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000022_create_rejudge"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE TABLE IF NOT EXISTS "rejudge_jobs" (
+                    "id" BIGSERIAL PRIMARY KEY,
+                    "game_id" BIGINT NOT NULL,
+                    "challenge_id" BIGINT NOT NULL,
+                    "status_filter" VARCHAR,
+                    "created_from" BIGINT,
+                    "created_until" BIGINT,
+                    "state" VARCHAR NOT NULL,
+                    "total" INTEGER NOT NULL,
+                    "processed" INTEGER NOT NULL DEFAULT 0,
+                    "changed" INTEGER NOT NULL DEFAULT 0,
+                    "author_id" BIGINT,
+                    "created_at" BIGINT NOT NULL,
+                    "finished_at" BIGINT,
+
+                    CONSTRAINT fk_rejudge_jobs_game FOREIGN KEY ("game_id")
+                        REFERENCES games ("id") ON DELETE CASCADE,
+                    CONSTRAINT fk_rejudge_jobs_challenge FOREIGN KEY ("challenge_id")
+                        REFERENCES challenges ("id") ON DELETE CASCADE,
+                    CONSTRAINT fk_rejudge_jobs_author FOREIGN KEY ("author_id")
+                        REFERENCES users ("id") ON DELETE SET NULL
+                );
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE UNIQUE INDEX IF NOT EXISTS "idx_rejudge_jobs_running"
+                ON "rejudge_jobs" ("game_id", "challenge_id")
+                WHERE "state" = 'running';
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE TABLE IF NOT EXISTS "rejudge_entries" (
+                    "id" BIGSERIAL PRIMARY KEY,
+                    "job_id" BIGINT NOT NULL,
+                    "submission_id" BIGINT NOT NULL,
+                    "previous_status" VARCHAR NOT NULL,
+                    "previous_flag_id" VARCHAR,
+                    "previous_credit" INTEGER NOT NULL,
+                    "previous_checked_at" BIGINT,
+                    "status" VARCHAR,
+                    "flag_id" VARCHAR,
+                    "credit" INTEGER,
+                    "finished_at" BIGINT,
+
+                    CONSTRAINT fk_rejudge_entries_job FOREIGN KEY ("job_id")
+                        REFERENCES rejudge_jobs ("id") ON DELETE CASCADE,
+                    CONSTRAINT fk_rejudge_entries_submission FOREIGN KEY ("submission_id")
+                        REFERENCES submissions ("id") ON DELETE CASCADE,
+                    CONSTRAINT uq_rejudge_entries_submission UNIQUE ("job_id", "submission_id")
+                );
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE INDEX IF NOT EXISTS "idx_rejudge_entries_pending"
+                ON "rejudge_entries" ("submission_id")
+                WHERE "finished_at" IS NULL;
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                DROP TABLE IF EXISTS "rejudge_entries";
+                DROP TABLE IF EXISTS "rejudge_jobs";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
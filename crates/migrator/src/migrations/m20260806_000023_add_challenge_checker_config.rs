@@ -0,0 +1,49 @@
+//! SeaORM migration `m20260806_000023_add_challenge_checker_config` — lets a
+//! challenge use a built-in checker instead of its Lua script.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000023_add_challenge_checker_config"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "challenges"
+                ADD COLUMN IF NOT EXISTS "checker_config" JSONB NOT NULL
+                    DEFAULT '{"kind": "lua"}';
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "challenges" DROP COLUMN IF EXISTS "checker_config";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
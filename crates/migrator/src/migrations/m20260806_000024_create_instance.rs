@@ -0,0 +1,94 @@
+//! SeaORM migration `m20260806_000024_create_instance` — creates the record
+//! of every challenge instance the cluster driver starts, kept after its pod
+//! is gone.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000024_create_instance"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE TABLE IF NOT EXISTS "instances" (
+                    "id" VARCHAR PRIMARY KEY,
+                    "user_id" BIGINT NOT NULL,
+                    "team_id" BIGINT,
+                    "game_id" BIGINT,
+                    "challenge_id" BIGINT NOT NULL,
+                    "ports" JSONB NOT NULL DEFAULT '[]',
+                    "nats" JSONB NOT NULL DEFAULT '[]',
+                    "state" VARCHAR NOT NULL,
+                    "renew" INTEGER NOT NULL DEFAULT 0,
+                    "duration" BIGINT NOT NULL,
+                    "created_at" BIGINT NOT NULL,
+                    "renewed_at" BIGINT,
+                    "expires_at" BIGINT NOT NULL,
+                    "terminated_at" BIGINT,
+                    "termination_reason" VARCHAR,
+
+                    CONSTRAINT fk_instances_user FOREIGN KEY ("user_id")
+                        REFERENCES users ("id") ON DELETE CASCADE,
+                    CONSTRAINT fk_instances_team FOREIGN KEY ("team_id")
+                        REFERENCES teams ("id") ON DELETE CASCADE,
+                    CONSTRAINT fk_instances_game FOREIGN KEY ("game_id")
+                        REFERENCES games ("id") ON DELETE CASCADE,
+                    CONSTRAINT fk_instances_challenge FOREIGN KEY ("challenge_id")
+                        REFERENCES challenges ("id") ON DELETE CASCADE
+                );
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE INDEX IF NOT EXISTS "idx_instances_running"
+                ON "instances" ("expires_at")
+                WHERE "state" = 'running';
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE INDEX IF NOT EXISTS "idx_instances_owner"
+                ON "instances" ("game_id", "team_id", "user_id", "created_at");
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                DROP TABLE IF EXISTS "instances";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
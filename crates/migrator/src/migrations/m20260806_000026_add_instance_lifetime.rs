@@ -0,0 +1,57 @@
+//! SeaORM migration `m20260806_000026_add_instance_lifetime` — stores each
+//! instance's renewal and lifetime limits, idle timeout and last activity.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000026_add_instance_lifetime"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "instances"
+                ADD COLUMN IF NOT EXISTS "max_renewals" INTEGER NOT NULL DEFAULT 3,
+                ADD COLUMN IF NOT EXISTS "max_expires_at" BIGINT,
+                ADD COLUMN IF NOT EXISTS "idle_timeout" BIGINT NOT NULL DEFAULT 0,
+                ADD COLUMN IF NOT EXISTS "last_active_at" BIGINT,
+                ADD COLUMN IF NOT EXISTS "warned_at" BIGINT;
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "instances"
+                DROP COLUMN IF EXISTS "max_renewals",
+                DROP COLUMN IF EXISTS "max_expires_at",
+                DROP COLUMN IF EXISTS "idle_timeout",
+                DROP COLUMN IF EXISTS "last_active_at",
+                DROP COLUMN IF EXISTS "warned_at";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+//! SeaORM migration `m20260806_000027_add_instance_reset` — stores how often
+//! each instance was reset, its limit and its named checkpoints.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000027_add_instance_reset"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "instances"
+                ADD COLUMN IF NOT EXISTS "resets" INTEGER NOT NULL DEFAULT 0,
+                ADD COLUMN IF NOT EXISTS "max_resets" INTEGER NOT NULL DEFAULT 3,
+                ADD COLUMN IF NOT EXISTS "reset_at" BIGINT,
+                ADD COLUMN IF NOT EXISTS "checkpoints" JSONB NOT NULL DEFAULT '[]'::jsonb;
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                ALTER TABLE "instances"
+                DROP COLUMN IF EXISTS "resets",
+                DROP COLUMN IF EXISTS "max_resets",
+                DROP COLUMN IF EXISTS "reset_at",
+                DROP COLUMN IF EXISTS "checkpoints";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
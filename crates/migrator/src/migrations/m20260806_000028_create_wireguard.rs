@@ -0,0 +1,107 @@
+//! SeaORM migration `m20260806_000028_create_wireguard` — creates the
+//! WireGuard gateway's key and the peers issued to teams, kept after they are
+//! revoked.
+
+use async_trait::async_trait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20260806_000028_create_wireguard"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE TABLE IF NOT EXISTS "wireguard_gateways" (
+                    "id" BIGINT PRIMARY KEY,
+                    "private_key" VARCHAR NOT NULL,
+                    "public_key" VARCHAR NOT NULL,
+                    "created_at" BIGINT NOT NULL
+                );
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE TABLE IF NOT EXISTS "wireguard_peers" (
+                    "id" BIGSERIAL PRIMARY KEY,
+                    "game_id" BIGINT NOT NULL,
+                    "team_id" BIGINT NOT NULL,
+                    "address" VARCHAR NOT NULL,
+                    "private_key" VARCHAR NOT NULL,
+                    "public_key" VARCHAR NOT NULL,
+                    "created_at" BIGINT NOT NULL,
+                    "revoked_at" BIGINT,
+
+                    CONSTRAINT fk_wireguard_peers_game FOREIGN KEY ("game_id")
+                        REFERENCES games ("id") ON DELETE CASCADE,
+                    CONSTRAINT fk_wireguard_peers_team FOREIGN KEY ("team_id")
+                        REFERENCES teams ("id") ON DELETE CASCADE
+                );
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE UNIQUE INDEX IF NOT EXISTS "idx_wireguard_peers_active_team"
+                ON "wireguard_peers" ("game_id", "team_id")
+                WHERE "revoked_at" IS NULL;
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                CREATE UNIQUE INDEX IF NOT EXISTS "idx_wireguard_peers_active_address"
+                ON "wireguard_peers" ("address")
+                WHERE "revoked_at" IS NULL;
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                DROP TABLE IF EXISTS "wireguard_peers";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        db.execute_raw(Statement::from_string(
+            manager.get_database_backend(),
+            r#"
+                DROP TABLE IF EXISTS "wireguard_gateways";
+            "#
+            .to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
@@ -51,3 +51,63 @@ pub mod m20260806_000012_create_idp;
 /// Defines the `m20260806_000013_create_user_idp` submodule (see sibling `*.rs`
 /// files).
 pub mod m20260806_000013_create_user_idp;
+
+/// Defines the `m20260806_000014_create_api_token` submodule (see sibling
+/// `*.rs` files).
+pub mod m20260806_000014_create_api_token;
+
+/// Defines the `m20260806_000015_create_role_assignment` submodule (see
+/// sibling `*.rs` files).
+pub mod m20260806_000015_create_role_assignment;
+
+/// Defines the `m20260806_000016_add_team_division` submodule (see sibling
+/// `*.rs` files).
+pub mod m20260806_000016_add_team_division;
+
+/// Defines the `m20260806_000017_add_script_allowed_hosts` submodule (see
+/// sibling `*.rs` files).
+pub mod m20260806_000017_add_script_allowed_hosts;
+
+/// Defines the `m20260806_000018_create_lua_library` submodule (see sibling
+/// `*.rs` files).
+pub mod m20260806_000018_create_lua_library;
+
+/// Defines the `m20260806_000019_create_checker_log` submodule (see sibling
+/// `*.rs` files).
+pub mod m20260806_000019_create_checker_log;
+
+/// Defines the `m20260806_000020_add_submission_judgement` submodule (see
+/// sibling `*.rs` files).
+pub mod m20260806_000020_add_submission_judgement;
+
+/// Defines the `m20260806_000021_create_script_revision` submodule (see
+/// sibling `*.rs` files).
+pub mod m20260806_000021_create_script_revision;
+
+/// Defines the `m20260806_000022_create_rejudge` submodule (see sibling `*.rs`
+/// files).
+pub mod m20260806_000022_create_rejudge;
+
+/// Defines the `m20260806_000023_add_challenge_checker_config` submodule (see
+/// sibling `*.rs` files).
+pub mod m20260806_000023_add_challenge_checker_config;
+
+/// Defines the `m20260806_000024_create_instance` submodule (see sibling `*.rs`
+/// files).
+pub mod m20260806_000024_create_instance;
+
+/// Defines the `m20260806_000025_add_instance_access_token` submodule (see
+/// sibling `*.rs` files).
+pub mod m20260806_000025_add_instance_access_token;
+
+/// Defines the `m20260806_000026_add_instance_lifetime` submodule (see sibling
+/// `*.rs` files).
+pub mod m20260806_000026_add_instance_lifetime;
+
+/// Defines the `m20260806_000027_add_instance_reset` submodule (see sibling
+/// `*.rs` files).
+pub mod m20260806_000027_add_instance_reset;
+
+/// Defines the `m20260806_000028_create_wireguard` submodule (see sibling
+/// `*.rs` files).
+pub mod m20260806_000028_create_wireguard;
@@ -0,0 +1,168 @@
+//! Observability — `engine` (Lua script cache and state pool metrics).
+//!
+//! The engine reports through the `record_*` helpers so it does not depend on
+//! OpenTelemetry types; gauges read engine state through the callbacks passed
+//! to [`init_observable_gauges`].
+
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use opentelemetry::{
+    KeyValue,
+    metrics::{Counter, Histogram, ObservableGauge},
+};
+
+/// Lua states held by every cached script's pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolUsage {
+    /// States parked in a pool, ready for reuse.
+    pub idle: u64,
+    /// States currently checked out by an execution.
+    pub busy: u64,
+    /// Upper bound on concurrent executions across all pools.
+    pub capacity: u64,
+}
+
+static CACHED_SCRIPTS_GAUGE: OnceCell<ObservableGauge<u64>> = OnceCell::new();
+static POOL_STATES_GAUGE: OnceCell<ObservableGauge<u64>> = OnceCell::new();
+static POOL_CAPACITY_GAUGE: OnceCell<ObservableGauge<u64>> = OnceCell::new();
+
+/// Registers the cache and pool size gauges. Later calls are ignored.
+pub fn init_observable_gauges(cached_scripts: fn() -> u64, pool_usage: fn() -> PoolUsage) {
+    CACHED_SCRIPTS_GAUGE
+        .set(
+            super::METER
+                .u64_observable_gauge("cdsctf.engine.cached_scripts")
+                .with_description("The number of compiled Lua scripts in the engine cache")
+                .with_callback(move |observer| observer.observe(cached_scripts(), &[]))
+                .build(),
+        )
+        .ok();
+    POOL_STATES_GAUGE
+        .set(
+            super::METER
+                .u64_observable_gauge("cdsctf.engine.pool_states")
+                .with_description("The number of Lua states held by the engine pools")
+                .with_callback(move |observer| {
+                    let usage = pool_usage();
+                    observer.observe(usage.idle, &[KeyValue::new("state", "idle")]);
+                    observer.observe(usage.busy, &[KeyValue::new("state", "busy")]);
+                })
+                .build(),
+        )
+        .ok();
+    POOL_CAPACITY_GAUGE
+        .set(
+            super::METER
+                .u64_observable_gauge("cdsctf.engine.pool_capacity")
+                .with_description("The number of concurrent executions the engine pools allow")
+                .with_callback(move |observer| observer.observe(pool_usage().capacity, &[]))
+                .build(),
+        )
+        .ok();
+}
+
+pub static PERMIT_WAIT: OnceCell<Histogram<f64>> = OnceCell::new();
+
+/// Returns permit wait.
+pub fn get_permit_wait() -> &'static Histogram<f64> {
+    PERMIT_WAIT.get_or_init(|| {
+        super::METER
+            .f64_histogram("cdsctf.engine.permit_wait")
+            .with_unit("s")
+            .with_description("Time spent waiting for a free Lua state permit")
+            .build()
+    })
+}
+
+pub static CHECKOUTS: OnceCell<Counter<u64>> = OnceCell::new();
+
+/// Returns checkouts.
+pub fn get_checkouts() -> &'static Counter<u64> {
+    CHECKOUTS.get_or_init(|| {
+        super::METER
+            .u64_counter("cdsctf.engine.checkouts")
+            .with_description("The number of Lua states checked out of the engine pools")
+            .build()
+    })
+}
+
+pub static CACHE_LOOKUPS: OnceCell<Counter<u64>> = OnceCell::new();
+
+/// Returns cache lookups.
+pub fn get_cache_lookups() -> &'static Counter<u64> {
+    CACHE_LOOKUPS.get_or_init(|| {
+        super::METER
+            .u64_counter("cdsctf.engine.cache_lookups")
+            .with_description("The number of compiled script cache lookups")
+            .build()
+    })
+}
+
+pub static EXECUTIONS: OnceCell<Counter<u64>> = OnceCell::new();
+
+/// Returns executions.
+pub fn get_executions() -> &'static Counter<u64> {
+    EXECUTIONS.get_or_init(|| {
+        super::METER
+            .u64_counter("cdsctf.engine.executions")
+            .with_description("The number of Lua function calls by script and outcome")
+            .build()
+    })
+}
+
+pub static LIMIT_HITS: OnceCell<Counter<u64>> = OnceCell::new();
+
+/// Returns limit hits.
+pub fn get_limit_hits() -> &'static Counter<u64> {
+    LIMIT_HITS.get_or_init(|| {
+        super::METER
+            .u64_counter("cdsctf.engine.limit_hits")
+            .with_description("The number of Lua calls stopped by an instruction or memory limit")
+            .build()
+    })
+}
+
+/// Records how long a checkout waited for a permit and whether it reused a
+/// pooled state.
+pub fn record_checkout(wait: Duration, reused: bool) {
+    get_permit_wait().record(wait.as_secs_f64(), &[]);
+    get_checkouts().add(
+        1,
+        &[KeyValue::new(
+            "source",
+            if reused { "pooled" } else { "created" },
+        )],
+    );
+}
+
+/// Records whether a script was already compiled in the cache.
+pub fn record_cache_lookup(hit: bool) {
+    get_cache_lookups().add(
+        1,
+        &[KeyValue::new("result", if hit { "hit" } else { "miss" })],
+    );
+}
+
+/// Records one function call of a cached script.
+pub fn record_execution(script_key: &str, function: &str, outcome: &'static str) {
+    get_executions().add(
+        1,
+        &[
+            KeyValue::new("script_key", script_key.to_owned()),
+            KeyValue::new("function", function.to_owned()),
+            KeyValue::new("outcome", outcome),
+        ],
+    );
+}
+
+/// Records a call stopped by `limit` (`instructions` or `memory`).
+pub fn record_limit_hit(script_key: &str, limit: &'static str) {
+    get_limit_hits().add(
+        1,
+        &[
+            KeyValue::new("script_key", script_key.to_owned()),
+            KeyValue::new("limit", limit),
+        ],
+    );
+}
@@ -1,5 +1,8 @@
 //! Observability — `mod` (metrics, tracing, or logging glue).
 
+/// Defines the `engine` submodule (see sibling `*.rs` files).
+pub mod engine;
+
 /// Defines the `system` submodule (see sibling `*.rs` files).
 mod system;
 
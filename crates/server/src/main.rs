@@ -17,7 +17,7 @@ use axum::http::HeaderValue;
 use cds_web::{router::router, traits::AppState};
 use mimalloc::MiMalloc;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{info, warn};
 
 /// Global allocator: `mimalloc` is chosen for predictable performance under
 /// concurrent load.
@@ -67,6 +67,20 @@ async fn main() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Registers the shared Lua libraries stored in the database with the script
+/// engine so checker and IdP scripts can `require` them.
+async fn load_lua_libraries(db: &cds_db::DB) -> Result<(), anyhow::Error> {
+    let libraries = cds_db::lua_library::find_all::<cds_db::LuaLibraryView>(&db.conn).await?;
+    for library in libraries {
+        if let Err(err) =
+            cds_engine::library::register(&library.name, &library.script, library.version)
+        {
+            warn!(library = %library.name, "skipping invalid lua library: {err:?}");
+        }
+    }
+    Ok(())
+}
+
 /// Loads environment, opens external services, runs migrations, and returns
 /// fully populated [`AppState`].
 async fn bootstrap() -> Result<Arc<AppState>, anyhow::Error> {
@@ -98,9 +112,10 @@ async fn bootstrap() -> Result<Arc<AppState>, anyhow::Error> {
 
     cds_migrator::run(&db).await?;
     cds_engine::init().await?;
+    load_lua_libraries(&db).await?;
     let checker = cds_checker::init(&media)?;
 
-    let cluster = cds_cluster::init(&env, &checker).await?;
+    let cluster = cds_cluster::init(&env, &db, &checker, &event).await?;
 
     let mailbox = cds_mailbox::Mailbox::new(db.clone());
     let captcha = cds_captcha::init(&db, &cache)?;
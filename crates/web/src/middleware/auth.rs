@@ -1,17 +1,21 @@
-//! Session-based authentication helpers and an admin-only gate.
+//! Session- and token-based authentication helpers and the admin gate.
 //!
-//! [`extract`] hydrates [`crate::traits::AuthPrincipal`] from `tower-sessions`
-//! (`user_id` key). [`admin_only`] rejects callers whose
-//! [`cds_db::user::Group`] is below Admin.
+//! [`extract`] hydrates [`crate::traits::AuthPrincipal`] from an
+//! `Authorization: Bearer` personal API token or, failing that, from
+//! `tower-sessions` (`user_id` key). [`admin_or_role`] gates `/admin`: it
+//! admits [`cds_db::user::Group::Admin`] callers and users whose scoped
+//! [`cds_db::role_assignment::Role`]s cover the requested admin route.
 
 use std::sync::Arc;
 
 use axum::{
-    extract::{FromRequestParts, Request, State},
+    extract::{FromRequestParts, OriginalUri, Request, State},
+    http::{HeaderMap, Method, header::AUTHORIZATION},
     middleware::Next,
     response::{IntoResponse, Response},
 };
-use cds_db::{UserAccountView, user::Group};
+use cds_cache::session::USER_ID_KEY;
+use cds_db::{UserAccountView, api_token::Scope, role_assignment::Permission, user::Group};
 use serde_json::json;
 use tower_sessions::Session;
 use tracing::{Span, debug, warn};
@@ -19,10 +23,14 @@ use tracing::{Span, debug, warn};
 use crate::{
     extract::Extension,
     traits::{AppState, AuthPrincipal, WebError},
+    util,
 };
 
 /// Loads the signed-in user (if any), enforces banned accounts, bumps a session
 /// counter, then continues the chain.
+///
+/// A bearer token takes precedence over the session cookie and is additionally
+/// checked for expiry and against [`token_permits`].
 pub async fn extract(
     State(s): State<Arc<AppState>>,
 
@@ -35,6 +43,8 @@ pub async fn extract(
         .await
         .map_err(|_| WebError::Unauthorized(json!("session_error")))?;
 
+    let bearer = bearer_token(&parts.headers);
+
     let mut req = Request::from_parts(parts, body);
 
     let mut ext = req
@@ -43,30 +53,78 @@ pub async fn extract(
         .unwrap_or(&AuthPrincipal::default())
         .to_owned();
 
-    if let Ok(Some(user_id)) = session.get::<i64>("user_id").await {
-        if let Some(user) = cds_db::user::find_by_id::<UserAccountView>(&s.db.conn, user_id).await?
-        {
-            if user.group == Group::Banned {
-                warn!(user_id = user.id, username = %user.username, "banned user rejected");
-                return Err(WebError::Forbidden(json!("forbidden")));
-            }
+    if let Some(secret) = bearer {
+        let token = cds_db::api_token::find_by_token_hash(
+            &s.db.conn,
+            &util::crypto::hash_api_token(&secret),
+        )
+        .await?
+        .ok_or(WebError::Unauthorized(json!("token_invalid")))?;
+
+        if token.is_expired(time::OffsetDateTime::now_utc().unix_timestamp()) {
+            return Err(WebError::Unauthorized(json!("token_expired")));
+        }
+
+        let user = cds_db::user::find_by_id::<UserAccountView>(&s.db.conn, token.user_id)
+            .await?
+            .ok_or(WebError::Unauthorized(json!("token_invalid")))?;
 
-            debug!(
+        if user.group == Group::Banned {
+            warn!(user_id = user.id, username = %user.username, "banned user rejected");
+            return Err(WebError::Forbidden(json!("forbidden")));
+        }
+
+        if !token_permits(&token.scopes(), req.method(), req.uri().path()) {
+            warn!(
                 user_id = user.id,
-                username = %user.username,
-                group = ?user.group,
-                "authenticated request principal loaded"
-            );
-            Span::current().record("username", user.username.as_str());
-            ext.operator = Some(user);
-
-            let called_times = session.get::<i64>("called_times").await?.unwrap_or(0);
-            session.insert("called_times", called_times + 1).await?;
-            debug!(
-                called_times = called_times + 1,
-                "session call counter updated"
+                api_token_id = token.id,
+                method = %req.method(),
+                path = %req.uri().path(),
+                "api token scope insufficient"
             );
+            return Err(WebError::Forbidden(json!("token_scope_insufficient")));
+        }
+
+        cds_db::api_token::touch(&s.db.conn, token.id, ext.client_ip.clone()).await?;
+
+        debug!(
+            user_id = user.id,
+            username = %user.username,
+            api_token_id = token.id,
+            "api token principal loaded"
+        );
+        Span::current().record("username", user.username.as_str());
+        ext.operator = Some(user);
+        ext.token = Some(token);
+    } else if let Ok(Some(user_id)) = session.get::<i64>(USER_ID_KEY).await
+        && let Some(user) = cds_db::user::find_by_id::<UserAccountView>(&s.db.conn, user_id).await?
+    {
+        if user.group == Group::Banned {
+            warn!(user_id = user.id, username = %user.username, "banned user rejected");
+            return Err(WebError::Forbidden(json!("forbidden")));
         }
+
+        debug!(
+            user_id = user.id,
+            username = %user.username,
+            group = ?user.group,
+            "authenticated request principal loaded"
+        );
+        Span::current().record("username", user.username.as_str());
+        ext.operator = Some(user);
+
+        let called_times = session.get::<i64>("called_times").await?.unwrap_or(0);
+        session.insert("called_times", called_times + 1).await?;
+        session
+            .insert(
+                "last_seen_at",
+                time::OffsetDateTime::now_utc().unix_timestamp(),
+            )
+            .await?;
+        debug!(
+            called_times = called_times + 1,
+            "session call counter updated"
+        );
     }
 
     req.extensions_mut().insert(ext);
@@ -74,21 +132,165 @@ pub async fn extract(
     Ok(next.run(req).await)
 }
 
-/// Requires an authenticated operator with [`Group::Admin`] or higher
-/// privileges.
-pub async fn admin_only(
+/// Returns the secret of an `Authorization: Bearer <token>` header, if any.
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    let token = token.trim();
+
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then(|| token.to_owned())
+}
+
+/// Decides whether a token carrying `scopes` may call `method path`.
+///
+/// Safe methods are open to every token, submissions need [`Scope::Submit`],
+/// instances (including the wsrx tunnel and the TCP proxy allocations, whose
+/// view carries a one-time token) and the team's WireGuard peer, whose
+/// config carries a private key, need [`Scope::Instance`], and everything
+/// else, including `/admin`, needs [`Scope::Admin`]. Token
+/// management itself is session-only so a leaked token cannot mint new ones.
+pub fn token_permits(scopes: &[Scope], method: &Method, path: &str) -> bool {
+    let path = path.strip_prefix("/api").unwrap_or(path);
+
+    if path.starts_with("/users/me/tokens") {
+        return false;
+    }
+    if scopes.contains(&Scope::Admin) {
+        return true;
+    }
+    if path.starts_with("/admin") {
+        return false;
+    }
+    let trimmed = path.trim_end_matches('/');
+    if path.starts_with("/instances")
+        && (trimmed.ends_with("/wsrx") || trimmed.ends_with("/tcp") || method != Method::GET)
+    {
+        return scopes.contains(&Scope::Instance);
+    }
+    if path.starts_with("/games") && trimmed.ends_with("/teams/us/wireguard") {
+        return scopes.contains(&Scope::Instance);
+    }
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return true;
+    }
+    if path.starts_with("/submissions") {
+        return scopes.contains(&Scope::Submit);
+    }
+
+    false
+}
+
+/// Lists the permissions any one of which admits a non-admin caller to
+/// `method path` under `/admin`. An empty list means the route is Admin-only.
+///
+/// This is an allowlist: a challenge author may read and edit the body,
+/// checker, writeup, attachments and instance env of their challenge, a game
+/// operator may manage teams, members and notices of their game, and
+/// observers may read game, team, rejudge and submission views. Everything
+/// else stays Admin-only, notably challenge listings (which carry checker
+/// scripts and webhook secrets), challenge deletion, bundle and roster
+/// exports, team imports and team tokens, as well as users, IdPs, configs and
+/// instances.
+pub fn route_permissions(method: &Method, path: &str) -> Vec<Permission> {
+    let path = path.strip_prefix("/api").unwrap_or(path);
+    let path = path.strip_prefix("/admin").unwrap_or(path);
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let id = |index: usize| segments.get(index).and_then(|s| s.parse::<i64>().ok());
+    // Every segment after a resource name that is not a literal must be an id.
+    let ids_at = |indexes: &[usize]| indexes.iter().all(|index| id(*index).is_some());
+    let verb = if *method == Method::HEAD {
+        "GET"
+    } else {
+        method.as_str()
+    };
+
+    match (segments.as_slice(), verb) {
+        (["challenges", _], "GET" | "PUT")
+        | (["challenges", _, "instance" | "writeup" | "checker"], "PUT")
+        | (["challenges", _, "checker", "lint"], "POST")
+        | (["challenges", _, "checker", "revisions"], "GET")
+        | (["challenges", _, "checker", "revisions", _], "GET")
+        | (["challenges", _, "checker", "revisions", _, "diff"], "GET")
+        | (["challenges", _, "attachments"], "GET" | "POST")
+        | (["challenges", _, "attachments", _], "GET")
+            if ids_at(&[1]) =>
+        {
+            vec![Permission::ManageChallenge {
+                challenge_id: id(1).unwrap_or_default(),
+            }]
+        }
+        (["games", _, "teams" | "notices"], "POST")
+        | (["games", _, "teams", _], "PUT" | "DELETE")
+        | (["games", _, "teams", _, "users"], "POST")
+        | (["games", _, "teams", _, "users", _], "DELETE")
+        | (["games", _, "notices", _], "DELETE")
+            if ids_at(&[1]) && (segments.len() < 4 || ids_at(&[3])) =>
+        {
+            vec![Permission::ManageGame {
+                game_id: id(1).unwrap_or_default(),
+            }]
+        }
+        (["games", _], "GET") | (["games", _, "challenges" | "teams" | "warmup"], "GET")
+            if ids_at(&[1]) =>
+        {
+            vec![Permission::View { game_id: id(1) }]
+        }
+        (["games", _, "teams", _, "users" | "writeup"], "GET")
+        | (["games", _, "challenges", _, "rejudges"], "GET")
+        | (["games", _, "challenges", _, "rejudges", _], "GET")
+            if ids_at(&[1, 3]) =>
+        {
+            vec![Permission::View { game_id: id(1) }]
+        }
+        (["games"] | ["submissions"], "GET") => vec![Permission::View { game_id: None }],
+        (["submissions", _], "GET") if ids_at(&[1]) => vec![Permission::View { game_id: None }],
+        _ => Vec::new(),
+    }
+}
+
+/// Admits [`Group::Admin`] operators, and otherwise any
+/// operator holding a role assignment that grants one of the
+/// [`route_permissions`] of the request.
+pub async fn admin_or_role(
+    State(s): State<Arc<AppState>>,
     Extension(ap): Extension<AuthPrincipal>,
     req: Request,
     next: Next,
 ) -> Result<impl IntoResponse, WebError> {
     let operator = ap.operator.ok_or(WebError::Unauthorized(json!("")))?;
 
-    if operator.group < Group::Admin {
+    if operator.group >= Group::Admin {
+        Span::current().record("username", operator.username.as_str());
+        return Ok(next.run(req).await);
+    }
+
+    let path = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.path().to_owned())
+        .unwrap_or_else(|| req.uri().path().to_owned());
+    let permissions = route_permissions(req.method(), &path);
+
+    let assignments = if permissions.is_empty() {
+        Vec::new()
+    } else {
+        cds_db::role_assignment::find_by_user_id(&s.db.conn, operator.id).await?
+    };
+
+    if !permissions.iter().any(|permission| {
+        assignments
+            .iter()
+            .any(|assignment| assignment.grants(*permission))
+    }) {
         warn!(
             user_id = operator.id,
             username = %operator.username,
-            group = ?operator.group,
-            "non-admin user rejected"
+            method = %req.method(),
+            path = %path,
+            "role-scoped admin access rejected"
         );
         return Err(WebError::Forbidden(json!("forbidden")));
     }
@@ -96,9 +298,236 @@ pub async fn admin_only(
     debug!(
         user_id = operator.id,
         username = %operator.username,
-        group = ?operator.group,
-        "admin access granted"
+        path = %path,
+        "role-scoped admin access granted"
     );
     Span::current().record("username", operator.username.as_str());
     Ok(next.run(req).await)
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderMap, HeaderValue, Method, header::AUTHORIZATION};
+    use cds_db::{
+        RoleAssignmentView,
+        api_token::Scope,
+        role_assignment::{Permission, Role},
+    };
+
+    use super::{bearer_token, route_permissions, token_permits};
+
+    #[test]
+    fn bearer_header_is_parsed_case_insensitively() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("bearer cds_abc"));
+        assert_eq!(bearer_token(&headers).as_deref(), Some("cds_abc"));
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Basic dXNlcg=="));
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer  "));
+        assert_eq!(bearer_token(&headers), None);
+    }
+
+    #[test]
+    fn read_only_tokens_cannot_mutate() {
+        let scopes = [Scope::ReadOnly];
+        assert!(token_permits(
+            &scopes,
+            &Method::GET,
+            "/api/games/1/scoreboard"
+        ));
+        assert!(!token_permits(&scopes, &Method::POST, "/api/submissions"));
+        assert!(!token_permits(&scopes, &Method::POST, "/api/instances"));
+        assert!(!token_permits(
+            &scopes,
+            &Method::GET,
+            "/api/instances/x/wsrx"
+        ));
+        assert!(!token_permits(
+            &scopes,
+            &Method::GET,
+            "/api/instances/x/tcp"
+        ));
+        assert!(token_permits(
+            &[Scope::Instance],
+            &Method::GET,
+            "/api/instances/x/tcp"
+        ));
+        assert!(!token_permits(&scopes, &Method::GET, "/api/admin/users"));
+    }
+
+    #[test]
+    fn submit_and_instance_scopes_are_independent() {
+        assert!(token_permits(
+            &[Scope::Submit],
+            &Method::POST,
+            "/api/submissions"
+        ));
+        assert!(!token_permits(
+            &[Scope::Submit],
+            &Method::POST,
+            "/api/instances"
+        ));
+        assert!(token_permits(
+            &[Scope::Instance],
+            &Method::POST,
+            "/api/instances/x/renew"
+        ));
+        assert!(!token_permits(
+            &[Scope::Instance],
+            &Method::POST,
+            "/api/submissions"
+        ));
+        assert!(!token_permits(
+            &[Scope::Submit],
+            &Method::PUT,
+            "/api/users/me"
+        ));
+        assert!(token_permits(
+            &[Scope::Instance],
+            &Method::GET,
+            "/api/games/1/teams/us/wireguard"
+        ));
+        assert!(!token_permits(
+            &[Scope::ReadOnly],
+            &Method::GET,
+            "/api/games/1/teams/us/wireguard/"
+        ));
+    }
+
+    #[test]
+    fn token_management_is_never_reachable_with_a_token() {
+        assert!(token_permits(
+            &[Scope::Admin],
+            &Method::DELETE,
+            "/api/admin/users/1"
+        ));
+        assert!(!token_permits(
+            &[Scope::Admin],
+            &Method::GET,
+            "/api/users/me/tokens"
+        ));
+        assert!(!token_permits(
+            &[Scope::Admin],
+            &Method::POST,
+            "/api/users/me/tokens"
+        ));
+    }
+
+    #[test]
+    fn challenge_routes_need_the_authoring_role() {
+        assert_eq!(
+            route_permissions(&Method::PUT, "/api/admin/challenges/4/checker"),
+            vec![Permission::ManageChallenge { challenge_id: 4 }]
+        );
+        assert_eq!(
+            route_permissions(&Method::GET, "/admin/challenges/4"),
+            vec![Permission::ManageChallenge { challenge_id: 4 }]
+        );
+        assert_eq!(
+            route_permissions(&Method::PUT, "/api/admin/challenges/4/instance"),
+            vec![Permission::ManageChallenge { challenge_id: 4 }]
+        );
+        assert_eq!(
+            route_permissions(&Method::GET, "/api/admin/challenges/4/attachments/a.zip"),
+            vec![Permission::ManageChallenge { challenge_id: 4 }]
+        );
+        assert!(route_permissions(&Method::POST, "/api/admin/challenges").is_empty());
+        assert!(route_permissions(&Method::GET, "/api/admin/challenges").is_empty());
+    }
+
+    #[test]
+    fn challenge_authors_cannot_delete_or_create_instances() {
+        for (method, path) in [
+            (Method::DELETE, "/api/admin/challenges/4"),
+            (Method::POST, "/api/admin/challenges/4/instance"),
+            (Method::DELETE, "/api/admin/challenges/4/attachments/a.zip"),
+            (
+                Method::POST,
+                "/api/admin/challenges/4/checker/revisions/2/rollback",
+            ),
+        ] {
+            assert!(
+                route_permissions(&method, path).is_empty(),
+                "{method} {path}"
+            );
+        }
+    }
+
+    #[test]
+    fn game_routes_split_reads_from_team_and_notice_management() {
+        assert_eq!(
+            route_permissions(&Method::POST, "/api/admin/games/2/notices"),
+            vec![Permission::ManageGame { game_id: 2 }]
+        );
+        assert_eq!(
+            route_permissions(&Method::DELETE, "/api/admin/games/2/teams/9"),
+            vec![Permission::ManageGame { game_id: 2 }]
+        );
+        assert_eq!(
+            route_permissions(&Method::GET, "/api/admin/games/2/teams"),
+            vec![Permission::View { game_id: Some(2) }]
+        );
+        assert!(route_permissions(&Method::PUT, "/api/admin/games/2").is_empty());
+        assert!(route_permissions(&Method::POST, "/api/admin/games/2/challenges").is_empty());
+    }
+
+    #[test]
+    fn sensitive_admin_areas_stay_admin_only() {
+        for path in [
+            "/api/admin/users",
+            "/api/admin/configs",
+            "/api/admin/idps",
+            "/api/admin/instances/x/containers/y",
+        ] {
+            assert!(route_permissions(&Method::GET, path).is_empty(), "{path}");
+        }
+        assert_eq!(
+            route_permissions(&Method::GET, "/api/admin/submissions"),
+            vec![Permission::View { game_id: None }]
+        );
+    }
+
+    #[test]
+    fn exports_tokens_and_imports_are_denied_to_every_role() {
+        let assignments = [
+            (Role::Observer, None, None),
+            (Role::Observer, Some(2), None),
+            (Role::GameOperator, Some(2), None),
+            (Role::ChallengeAuthor, None, Some(4)),
+        ]
+        .map(|(role, game_id, challenge_id)| RoleAssignmentView {
+            id: 1,
+            user_id: 1,
+            role,
+            game_id,
+            challenge_id,
+            created_at: 0,
+            updated_at: 0,
+        });
+
+        for method in [Method::GET, Method::POST, Method::PUT, Method::DELETE] {
+            for path in [
+                "/api/admin/games/2/export",
+                "/api/admin/games/2/teams/export",
+                "/api/admin/games/2/teams/import",
+                "/api/admin/games/2/teams/9/token",
+                "/api/admin/games/import",
+            ] {
+                let permissions = route_permissions(&method, path);
+                assert!(
+                    assignments.iter().all(|assignment| {
+                        permissions
+                            .iter()
+                            .all(|permission| !assignment.grants(*permission))
+                    }),
+                    "{method} {path}"
+                );
+                assert!(permissions.is_empty(), "{method} {path}");
+            }
+        }
+    }
+}
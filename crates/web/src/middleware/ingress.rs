@@ -0,0 +1,290 @@
+//! Reverse proxy for `cluster.traffic = "ingress"`.
+//!
+//! Requests whose host is `{instance_id}.{public_entry}` never reach the
+//! platform routes: they are forwarded to the instance's first TCP port through
+//! the Kubernetes port-forward API, WebSocket upgrades included.
+//!
+//! When the instance has an access token, the first visit must carry
+//! `?cds_token=<token>`. The server answers with a host-only cookie and a
+//! redirect to the same URL without the token; later requests must carry the
+//! cookie, which is removed before the request is forwarded.
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{
+        HeaderValue, StatusCode, Uri, Version,
+        header::{COOKIE, HOST, LOCATION, SET_COOKIE, UPGRADE},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use cds_db::{InstanceView, instance::State as InstanceState};
+use hyper_util::rt::TokioIo;
+use ring::digest::{SHA256, digest};
+use serde_json::json;
+use tower_sessions::cookie::{Cookie, SameSite};
+use tracing::debug;
+
+use crate::{
+    traits::{AppState, WebError},
+    util::network::get_client_ip,
+};
+
+/// Query parameter carrying the access token on the first visit.
+pub const TOKEN_QUERY: &str = "cds_token";
+
+/// Cookie carrying the access token afterwards.
+pub const TOKEN_COOKIE: &str = "cds_instance_token";
+
+/// Sends requests for instance hosts to the instance, and everything else
+/// down the stack.
+pub async fn dispatch(State(s): State<Arc<AppState>>, req: Request<Body>, next: Next) -> Response {
+    let host = req
+        .headers()
+        .get("x-forwarded-host")
+        .or_else(|| req.headers().get(HOST))
+        .and_then(|host| host.to_str().ok())
+        .map(str::to_ascii_lowercase);
+    let Some(instance_id) = host
+        .as_deref()
+        .and_then(|host| instance_id_from_host(host, &s.env.cluster.public_entry))
+        .map(str::to_owned)
+    else {
+        return next.run(req).await;
+    };
+
+    match proxy(&s, host.unwrap_or_default(), &instance_id, req).await {
+        Ok(response) => response,
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Extracts the instance id from `{instance_id}.{public_entry}[:port]`.
+fn instance_id_from_host<'a>(host: &'a str, public_entry: &str) -> Option<&'a str> {
+    let host = host.rsplit_once(':').map_or(host, |(host, _)| host);
+    let id = host
+        .strip_suffix(public_entry.to_ascii_lowercase().as_str())?
+        .strip_suffix('.')?;
+
+    (!id.is_empty()
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit()))
+    .then_some(id)
+}
+
+/// Splits the access token out of a query string, returning it with the
+/// remaining parameters.
+fn take_token(query: Option<&str>) -> (Option<&str>, String) {
+    let mut token = None;
+    let mut rest = Vec::new();
+    for pair in query.unwrap_or_default().split('&') {
+        match pair
+            .strip_prefix(TOKEN_QUERY)
+            .and_then(|v| v.strip_prefix('='))
+        {
+            Some(value) => token = Some(value),
+            None if !pair.is_empty() => rest.push(pair),
+            None => {}
+        }
+    }
+
+    (token, rest.join("&"))
+}
+
+/// Compares tokens through their digests so the comparison time does not
+/// depend on how much of the token matched.
+fn token_matches(given: &str, expected: &str) -> bool {
+    digest(&SHA256, given.as_bytes()).as_ref() == digest(&SHA256, expected.as_bytes()).as_ref()
+}
+
+async fn proxy(
+    s: &AppState,
+    host: String,
+    instance_id: &str,
+    mut req: Request<Body>,
+) -> Result<Response, WebError> {
+    let instance = cds_db::instance::find_by_id::<InstanceView>(&s.db.conn, instance_id)
+        .await?
+        .filter(|instance| instance.state == InstanceState::Running)
+        .ok_or_else(|| WebError::NotFound(json!("instance_not_found")))?;
+
+    if let Some(expected) = instance.access_token.as_deref() {
+        let (token, rest) = take_token(req.uri().query());
+        if let Some(token) = token {
+            if !token_matches(token, expected) {
+                return Err(WebError::Forbidden(json!("instance_token_invalid")));
+            }
+
+            let location = if rest.is_empty() {
+                req.uri().path().to_owned()
+            } else {
+                format!("{}?{}", req.uri().path(), rest)
+            };
+            let cookie = Cookie::build((TOKEN_COOKIE, token.to_owned()))
+                .path("/")
+                .http_only(true)
+                .secure(s.env.cluster.ingress.tls)
+                .same_site(SameSite::Lax)
+                .build();
+
+            return Ok((
+                StatusCode::SEE_OTHER,
+                [(LOCATION, location), (SET_COOKIE, cookie.to_string())],
+            )
+                .into_response());
+        }
+
+        let cookies = req
+            .headers()
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(Cookie::split_parse)
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+        if !cookies
+            .iter()
+            .any(|cookie| cookie.name() == TOKEN_COOKIE && token_matches(cookie.value(), expected))
+        {
+            return Err(WebError::Forbidden(json!("instance_token_required")));
+        }
+
+        let forwarded = cookies
+            .iter()
+            .filter(|cookie| cookie.name() != TOKEN_COOKIE)
+            .map(|cookie| cookie.stripped().to_string())
+            .collect::<Vec<_>>()
+            .join("; ");
+        req.headers_mut().remove(COOKIE);
+        if !forwarded.is_empty()
+            && let Ok(value) = HeaderValue::from_str(&forwarded)
+        {
+            req.headers_mut().insert(COOKIE, value);
+        }
+    }
+
+    let port = instance
+        .ports
+        .iter()
+        .find(|port| port.protocol.eq_ignore_ascii_case("tcp"))
+        .and_then(|port| u16::try_from(port.port).ok())
+        .ok_or_else(|| WebError::NotFound(json!("port_not_found")))?;
+
+    let client_ip = get_client_ip(&req);
+    let stream = s.cluster.port_forward(instance_id, port).await?;
+    let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(anyhow::Error::from)?;
+    tokio::spawn(async move {
+        if let Err(err) = conn.with_upgrades().await {
+            debug!(error = %err, "instance connection closed");
+        }
+    });
+
+    let client_upgrade = req
+        .headers()
+        .contains_key(UPGRADE)
+        .then(|| hyper::upgrade::on(&mut req));
+
+    // The upstream speaks HTTP/1.1 and expects an origin-form target.
+    *req.version_mut() = Version::HTTP_11;
+    *req.uri_mut() = req
+        .uri()
+        .path_and_query()
+        .map(|path| Uri::from(path.clone()))
+        .unwrap_or_else(|| Uri::from_static("/"));
+    let headers = req.headers_mut();
+    if let Ok(host) = HeaderValue::from_str(&host) {
+        headers.insert(HOST, host);
+    }
+    headers.insert(
+        "x-forwarded-proto",
+        HeaderValue::from_static(if s.env.cluster.ingress.tls {
+            "https"
+        } else {
+            "http"
+        }),
+    );
+    if let Some(ip) = client_ip
+        && let Ok(ip) = HeaderValue::from_str(&ip.to_string())
+    {
+        headers.insert("x-forwarded-for", ip);
+    }
+
+    let mut response = sender
+        .send_request(req)
+        .await
+        .map_err(anyhow::Error::from)?;
+
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS
+        && let Some(client_upgrade) = client_upgrade
+    {
+        let upstream_upgrade = hyper::upgrade::on(&mut response);
+        tokio::spawn(async move {
+            match tokio::try_join!(client_upgrade, upstream_upgrade) {
+                Ok((client, upstream)) => {
+                    let _ = tokio::io::copy_bidirectional(
+                        &mut TokioIo::new(client),
+                        &mut TokioIo::new(upstream),
+                    )
+                    .await;
+                }
+                Err(err) => debug!(error = %err, "instance upgrade failed"),
+            }
+        });
+    }
+
+    Ok(response.map(Body::new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{instance_id_from_host, take_token, token_matches};
+
+    #[test]
+    fn matches_instance_hosts_only() {
+        assert_eq!(
+            instance_id_from_host("ab12cd.ctf.example.com", "ctf.example.com"),
+            Some("ab12cd")
+        );
+        assert_eq!(
+            instance_id_from_host("ab12cd.ctf.example.com:8443", "CTF.example.com"),
+            Some("ab12cd")
+        );
+        assert_eq!(
+            instance_id_from_host("ctf.example.com", "ctf.example.com"),
+            None
+        );
+        assert_eq!(
+            instance_id_from_host("a.b.ctf.example.com", "ctf.example.com"),
+            None
+        );
+        assert_eq!(
+            instance_id_from_host("abctf.example.com", "ctf.example.com"),
+            None
+        );
+    }
+
+    #[test]
+    fn splits_the_token_from_the_query() {
+        assert_eq!(
+            take_token(Some("a=1&cds_token=xyz&b=2")),
+            (Some("xyz"), "a=1&b=2".to_owned())
+        );
+        assert_eq!(
+            take_token(Some("cds_tokens=1")),
+            (None, "cds_tokens=1".to_owned())
+        );
+        assert_eq!(take_token(None), (None, String::new()));
+    }
+
+    #[test]
+    fn compares_tokens() {
+        assert!(token_matches("secret", "secret"));
+        assert!(!token_matches("secre", "secret"));
+    }
+}
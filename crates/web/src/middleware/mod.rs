@@ -1,6 +1,6 @@
 //! Axum middleware layers: authentication/authorization, client IP + host
-//! normalization, rate-limit error mapping, request metrics, and shared error
-//! helpers.
+//! normalization, rate-limit error mapping, request metrics, instance ingress
+//! proxying, and shared error helpers.
 
 /// Defines the `auth` submodule (see sibling `*.rs` files).
 pub mod auth;
@@ -8,6 +8,9 @@ pub mod auth;
 /// Defines the `error` submodule (see sibling `*.rs` files).
 pub mod error;
 
+/// Defines the `ingress` submodule (see sibling `*.rs` files).
+pub mod ingress;
+
 /// Defines the `network` submodule (see sibling `*.rs` files).
 pub mod network;
 
@@ -0,0 +1,187 @@
+//! HTTP handlers for `checker` within the `challenge_id` API segment.
+
+/// Defines the `revision` submodule (see sibling `*.rs` files).
+mod revision;
+
+use std::sync::Arc;
+
+use axum::{Extension, Json, Router, extract::State};
+use cds_checker::traits::CheckerError;
+use cds_db::{
+    ChallengeDetail, ScriptRevisionSummary,
+    challenge::CheckerConfig,
+    script_revision::{NewRevision, Owner},
+    sea_orm::{NotSet, Set, Unchanged},
+};
+use cds_engine::{
+    api::ApiEntry,
+    traits::{DiagnosticMarker, EngineError},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::error;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+use validator::Validate;
+
+use crate::{
+    extract::{Path, VJson},
+    traits::{AppState, AuthPrincipal, EmptyJson, WebError},
+};
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(update_checker).with_state(state.clone()))
+        .routes(routes!(lint_checker).with_state(state.clone()))
+        .nest("/revisions", revision::router(state.clone()))
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, utoipa::ToSchema)]
+pub struct UpdateCheckerRequest {
+    pub checker: Option<String>,
+    /// Hosts the checker may reach over HTTP; others must be public.
+    pub allowed_hosts: Option<Vec<String>>,
+    /// Selects a built-in checker kind instead of the Lua script.
+    pub checker_config: Option<CheckerConfig>,
+}
+
+/// Updates checker, recording the saved script as a new revision.
+#[utoipa::path(
+    put,
+    path = "/",
+    tag = "admin-challenge",
+    params(
+        ("challenge_id" = i64, Path, description = "Challenge id"),
+    ),
+    request_body = UpdateCheckerRequest,
+    responses(
+        (status = 200, description = "Checker updated", body = EmptyJson),
+        (status = 400, description = "Invalid checker config", body = crate::traits::ErrorResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "update_checker"))]
+pub async fn update_checker(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+    Path(challenge_id): Path<i64>,
+    VJson(body): VJson<UpdateCheckerRequest>,
+) -> Result<Json<EmptyJson>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+    let _ = crate::util::loader::prepare_challenge(&s.db.conn, challenge_id).await?;
+    if let Some(config) = &body.checker_config {
+        validate_config(config)?;
+    }
+
+    let challenge = cds_db::challenge::update::<ChallengeDetail>(
+        &s.db.conn,
+        cds_db::challenge::ActiveModel {
+            id: Unchanged(challenge_id),
+            checker: body.checker.map_or(NotSet, |v| Set(Some(v))),
+            allowed_hosts: body.allowed_hosts.map_or(NotSet, Set),
+            checker_config: body.checker_config.map_or(NotSet, Set),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    record_revision(&s, &challenge, operator.id, None).await?;
+
+    Ok(Json(EmptyJson::default()))
+}
+
+/// Rejects built-in checker configs that could never judge a submission.
+pub(crate) fn validate_config(config: &CheckerConfig) -> Result<(), WebError> {
+    cds_checker::builtin::validate(config).map_err(|err| match err {
+        CheckerError::ScriptError(message) => WebError::BadRequest(json!(message)),
+        err => WebError::BadRequest(json!(err.to_string())),
+    })
+}
+
+/// Lints the challenge's current checker and stores it as the next revision.
+pub(crate) async fn record_revision(
+    s: &AppState,
+    challenge: &ChallengeDetail,
+    author_id: i64,
+    restored_from: Option<i32>,
+) -> Result<Option<ScriptRevisionSummary>, WebError> {
+    let Some(script) = challenge.checker.clone() else {
+        return Ok(None);
+    };
+    let markers = lint_markers(s, challenge).await;
+
+    let revision = cds_db::script_revision::create::<ScriptRevisionSummary>(
+        &s.db.conn,
+        Owner::Checker(challenge.id),
+        NewRevision {
+            script,
+            allowed_hosts: challenge.allowed_hosts.clone(),
+            author_id: Some(author_id),
+            restored_from,
+            markers: json!(markers),
+        },
+    )
+    .await?;
+
+    Ok(Some(revision))
+}
+
+async fn lint_markers(s: &AppState, challenge: &ChallengeDetail) -> Vec<DiagnosticMarker> {
+    match s.checker.lint(challenge).await {
+        Ok(warnings) => warnings,
+        Err(CheckerError::EngineError(EngineError::DiagnosticsError(markers))) => markers,
+        Err(err) => {
+            error!("{:?}", err);
+            Vec::new()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, utoipa::ToSchema)]
+pub struct LintCheckerRequest {
+    pub checker: Option<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CheckerLintResponse {
+    pub markers: Vec<DiagnosticMarker>,
+    /// Lua API available to the script, for editor completion.
+    pub api: Vec<ApiEntry>,
+}
+
+/// Runs static analysis on a challenge checker script via API.
+#[utoipa::path(
+    post,
+    path = "/lint",
+    tag = "admin-challenge",
+    params(
+        ("challenge_id" = i64, Path, description = "Challenge id"),
+    ),
+    request_body = LintCheckerRequest,
+    responses(
+        (status = 200, description = "Lint markers (empty if clean)", body = CheckerLintResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "lint_checker"))]
+pub async fn lint_checker(
+    State(s): State<Arc<AppState>>,
+    Path(challenge_id): Path<i64>,
+    VJson(body): VJson<LintCheckerRequest>,
+) -> Result<Json<CheckerLintResponse>, WebError> {
+    let mut challenge = crate::util::loader::prepare_challenge(&s.db.conn, challenge_id).await?;
+
+    challenge.checker = body.checker;
+
+    let markers = lint_markers(&s, &challenge).await;
+    let api = s.checker.api(challenge_id).map_err(|err| {
+        error!("failed to describe checker lua api: {err:?}");
+        WebError::InternalServerError(json!("lua_api_unavailable"))
+    })?;
+
+    Ok(Json(CheckerLintResponse { markers, api }))
+}
@@ -0,0 +1,310 @@
+//! HTTP handlers for the saved revisions of a challenge checker: history,
+//! diffs, rollback and re-judging a game's submissions with an old revision.
+
+use std::sync::Arc;
+
+use axum::{Extension, Json, Router, extract::State};
+use cds_db::{
+    ChallengeDetail, ScriptRevisionSummary, ScriptRevisionView,
+    script_revision::Owner,
+    sea_orm::{Set, Unchanged},
+    submission::{FindSubmissionsOptions, Status},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+use validator::Validate;
+
+use crate::{
+    extract::{Path, Query, VJson},
+    traits::{AppState, AuthPrincipal, WebError},
+    util::{diff::RevisionDiff, loader::prepare_script_revision},
+};
+
+/// Most submissions a single dry-run judge request will replay.
+const MAX_JUDGED_SUBMISSIONS: u64 = 500;
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(get_checker_revisions).with_state(state.clone()))
+        .routes(routes!(get_checker_revision).with_state(state.clone()))
+        .routes(routes!(diff_checker_revision).with_state(state.clone()))
+        .routes(routes!(rollback_checker_revision).with_state(state.clone()))
+        .routes(routes!(judge_checker_revision).with_state(state.clone()))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CheckerRevisionsResponse {
+    pub revisions: Vec<ScriptRevisionSummary>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CheckerRevisionResponse {
+    pub revision: ScriptRevisionView,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CheckerRevisionSummaryResponse {
+    pub revision: ScriptRevisionSummary,
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DiffCheckerRevisionRequest {
+    /// Revision to compare against; defaults to the previous one.
+    pub against: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Validate, utoipa::ToSchema)]
+pub struct JudgeCheckerRevisionRequest {
+    pub game_id: i64,
+    /// Only replay submissions that currently have this status.
+    pub status: Option<Status>,
+}
+
+/// How a stored submission fares under the replayed revision.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct RevisionJudgement {
+    pub submission_id: i64,
+    pub user_id: i64,
+    pub team_id: Option<i64>,
+    pub created_at: i64,
+    /// Status stored for the submission.
+    pub status: Status,
+    /// Status the revision assigns; absent when the script failed.
+    pub verdict: Option<Status>,
+    pub credit: Option<i32>,
+    pub flag_id: Option<String>,
+    pub feedback: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct JudgeCheckerRevisionResponse {
+    pub revision: i32,
+    /// Matching submissions, of which at most 500 were replayed.
+    pub total: u64,
+    /// Replayed submissions whose verdict differs from the stored status.
+    pub changed: u64,
+    pub judgements: Vec<RevisionJudgement>,
+}
+
+/// Lists the saved revisions of a challenge checker, newest first.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "admin-challenge",
+    params(
+        ("challenge_id" = i64, Path, description = "Challenge id"),
+    ),
+    responses(
+        (status = 200, description = "Checker revisions", body = CheckerRevisionsResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_checker_revisions"))]
+pub async fn get_checker_revisions(
+    State(s): State<Arc<AppState>>,
+    Path(challenge_id): Path<i64>,
+) -> Result<Json<CheckerRevisionsResponse>, WebError> {
+    let revisions =
+        cds_db::script_revision::find_by_owner(&s.db.conn, Owner::Checker(challenge_id)).await?;
+
+    Ok(Json(CheckerRevisionsResponse { revisions }))
+}
+
+/// Returns one checker revision including its script.
+#[utoipa::path(
+    get,
+    path = "/{number}",
+    tag = "admin-challenge",
+    params(
+        ("challenge_id" = i64, Path, description = "Challenge id"),
+        ("number" = i32, Path, description = "Revision number"),
+    ),
+    responses(
+        (status = 200, description = "Checker revision", body = CheckerRevisionResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_checker_revision"))]
+pub async fn get_checker_revision(
+    State(s): State<Arc<AppState>>,
+    Path((challenge_id, number)): Path<(i64, i32)>,
+) -> Result<Json<CheckerRevisionResponse>, WebError> {
+    let revision =
+        prepare_script_revision(&s.db.conn, Owner::Checker(challenge_id), number).await?;
+
+    Ok(Json(CheckerRevisionResponse { revision }))
+}
+
+/// Diffs a checker revision against another one, by default its predecessor.
+#[utoipa::path(
+    get,
+    path = "/{number}/diff",
+    tag = "admin-challenge",
+    params(
+        ("challenge_id" = i64, Path, description = "Challenge id"),
+        ("number" = i32, Path, description = "Revision number"),
+        DiffCheckerRevisionRequest,
+    ),
+    responses(
+        (status = 200, description = "Line diff", body = RevisionDiff),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "diff_checker_revision"))]
+pub async fn diff_checker_revision(
+    State(s): State<Arc<AppState>>,
+    Path((challenge_id, number)): Path<(i64, i32)>,
+    Query(params): Query<DiffCheckerRevisionRequest>,
+) -> Result<Json<RevisionDiff>, WebError> {
+    let owner = Owner::Checker(challenge_id);
+    let revision = prepare_script_revision(&s.db.conn, owner, number).await?;
+    let base = match params.against.or((number > 1).then_some(number - 1)) {
+        Some(against) => Some(prepare_script_revision(&s.db.conn, owner, against).await?),
+        None => None,
+    };
+
+    Ok(Json(RevisionDiff::between(base.as_ref(), &revision)))
+}
+
+/// Restores a checker revision, saving it again as the newest revision.
+#[utoipa::path(
+    post,
+    path = "/{number}/rollback",
+    tag = "admin-challenge",
+    params(
+        ("challenge_id" = i64, Path, description = "Challenge id"),
+        ("number" = i32, Path, description = "Revision number"),
+    ),
+    responses(
+        (status = 200, description = "Revision created by the rollback", body = CheckerRevisionSummaryResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "rollback_checker_revision"))]
+pub async fn rollback_checker_revision(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+    Path((challenge_id, number)): Path<(i64, i32)>,
+) -> Result<Json<CheckerRevisionSummaryResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+    let revision =
+        prepare_script_revision(&s.db.conn, Owner::Checker(challenge_id), number).await?;
+
+    let challenge = cds_db::challenge::update::<ChallengeDetail>(
+        &s.db.conn,
+        cds_db::challenge::ActiveModel {
+            id: Unchanged(challenge_id),
+            checker: Set(Some(revision.script)),
+            allowed_hosts: Set(revision.allowed_hosts),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let revision = super::record_revision(&s, &challenge, operator.id, Some(number))
+        .await?
+        .ok_or(WebError::InternalServerError(json!(
+            "revision_not_recorded"
+        )))?;
+
+    Ok(Json(CheckerRevisionSummaryResponse { revision }))
+}
+
+/// Replays a game's submissions through a checker revision without changing
+/// anything, for comparing its verdicts with the stored ones.
+#[utoipa::path(
+    post,
+    path = "/{number}/judge",
+    tag = "admin-challenge",
+    params(
+        ("challenge_id" = i64, Path, description = "Challenge id"),
+        ("number" = i32, Path, description = "Revision number"),
+    ),
+    request_body = JudgeCheckerRevisionRequest,
+    responses(
+        (status = 200, description = "Verdicts under the revision", body = JudgeCheckerRevisionResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "judge_checker_revision"))]
+pub async fn judge_checker_revision(
+    State(s): State<Arc<AppState>>,
+    Path((challenge_id, number)): Path<(i64, i32)>,
+    VJson(body): VJson<JudgeCheckerRevisionRequest>,
+) -> Result<Json<JudgeCheckerRevisionResponse>, WebError> {
+    let challenge = crate::util::loader::prepare_challenge(&s.db.conn, challenge_id).await?;
+    let revision =
+        prepare_script_revision(&s.db.conn, Owner::Checker(challenge_id), number).await?;
+
+    let (submissions, total) = cds_db::submission::find(
+        &s.db.conn,
+        FindSubmissionsOptions {
+            challenge_id: Some(challenge_id),
+            game_id: Some(Some(body.game_id)),
+            status: body.status,
+            page: Some(1),
+            size: Some(MAX_JUDGED_SUBMISSIONS),
+            sorts: Some("created_at".to_owned()),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let mut judgements = Vec::with_capacity(submissions.len());
+    for submission in submissions {
+        let operator_id = submission.team_id.unwrap_or(submission.user_id);
+        let result = s
+            .checker
+            .check_revision(&challenge, &revision, operator_id, &submission.content)
+            .await;
+        let (verdict, credit, flag_id, feedback, error) = match result {
+            Ok(judgement) => (
+                Some(match judgement.status {
+                    cds_checker::Status::Correct => Status::Correct,
+                    cds_checker::Status::Incorrect => Status::Incorrect,
+                    cds_checker::Status::Cheat(_) => Status::Cheat,
+                }),
+                Some(judgement.credit),
+                judgement.flag_id,
+                judgement.feedback,
+                None,
+            ),
+            Err(err) => (None, None, None, None, Some(err.to_string())),
+        };
+        judgements.push(RevisionJudgement {
+            submission_id: submission.id,
+            user_id: submission.user_id,
+            team_id: submission.team_id,
+            created_at: submission.created_at,
+            status: submission.status,
+            verdict,
+            credit,
+            flag_id,
+            feedback,
+            error,
+        });
+    }
+    let changed = judgements
+        .iter()
+        .filter(|judgement| judgement.verdict.as_ref() != Some(&judgement.status))
+        .count() as u64;
+
+    Ok(Json(JudgeCheckerRevisionResponse {
+        revision: number,
+        total,
+        changed,
+        judgements,
+    }))
+}
@@ -5,7 +5,7 @@
 mod attachment;
 
 /// Defines the `checker` submodule (see sibling `*.rs` files).
-mod checker;
+pub mod checker;
 
 /// Defines the `writeup` submodule (see sibling `*.rs` files).
 mod writeup;
@@ -161,6 +161,7 @@ pub struct UpdateChallengeInstanceRequest {
     request_body = UpdateChallengeInstanceRequest,
     responses(
         (status = 200, description = "Updated", body = EmptyJson),
+        (status = 400, description = "Invalid instance config", body = crate::traits::ErrorResponse),
         (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
     )
 )]
@@ -171,6 +172,9 @@ pub async fn update_challenge_instance(
     VJson(body): VJson<UpdateChallengeInstanceRequest>,
 ) -> Result<Json<EmptyJson>, WebError> {
     let _ = crate::util::loader::prepare_challenge(&s.db.conn, challenge_id).await?;
+    if let Some(instance) = &body.instance {
+        crate::util::cluster::validate_instance(&s.env, instance)?;
+    }
 
     let _ = cds_db::challenge::update::<ChallengeDetail>(
         &s.db.conn,
@@ -104,6 +104,8 @@ pub struct CreateChallengeRequest {
     pub has_attachment: Option<bool>,
     pub instance: Option<cds_db::challenge::Instance>,
     pub checker: Option<String>,
+    pub allowed_hosts: Option<Vec<String>>,
+    pub checker_config: Option<cds_db::challenge::CheckerConfig>,
 }
 
 #[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
@@ -119,6 +121,7 @@ pub struct AdminChallengeResponse {
     request_body = CreateChallengeRequest,
     responses(
         (status = 201, description = "Created", body = AdminChallengeResponse),
+        (status = 400, description = "Invalid checker or instance config", body = crate::traits::ErrorResponse),
         (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
     )
 )]
@@ -127,6 +130,12 @@ pub async fn create_challenge(
     State(s): State<Arc<AppState>>,
     ReqJson(body): ReqJson<CreateChallengeRequest>,
 ) -> Result<(StatusCode, Json<AdminChallengeResponse>), WebError> {
+    if let Some(config) = &body.checker_config {
+        challenge_id::checker::validate_config(config)?;
+    }
+    if let Some(instance) = &body.instance {
+        crate::util::cluster::validate_instance(&s.env, instance)?;
+    }
     let challenge = create_challenge_with_key(
         &s.db.conn,
         &s.media,
@@ -141,6 +150,8 @@ pub async fn create_challenge(
             has_writeup: Set(false),
             instance: Set(body.instance),
             checker: Set(body.checker),
+            allowed_hosts: Set(body.allowed_hosts.unwrap_or_default()),
+            checker_config: Set(body.checker_config.unwrap_or_default()),
             ..Default::default()
         },
     )
@@ -0,0 +1,89 @@
+//! Admin view of the Lua engine's compiled script cache.
+//!
+//! Checker and IdP scripts are compiled once per key (`challenge/{id}`,
+//! `idp/{id}`, ...) and kept with a pool of Lua states. Evicting a key makes
+//! its next call recompile it from the database.
+
+use std::sync::Arc;
+
+use axum::{Json, Router, extract::State};
+use cds_engine::traits::CachedScript;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+use crate::{
+    extract::Query,
+    traits::{AppState, WebError},
+};
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(get_cached_scripts).with_state(state.clone()))
+        .routes(routes!(evict_cached_scripts).with_state(state.clone()))
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct CachedScriptsResponse {
+    pub scripts: Vec<CachedScript>,
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EvictCachedScriptsRequest {
+    /// Script to evict; every cached script when omitted.
+    pub key: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct EvictCachedScriptsResponse {
+    pub evicted: usize,
+}
+
+/// Lists compiled scripts with their Lua state pools.
+#[utoipa::path(
+    get,
+    path = "/scripts",
+    tag = "admin-engine",
+    responses(
+        (status = 200, description = "Cached scripts", body = CachedScriptsResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_get_cached_scripts"))]
+pub async fn get_cached_scripts(
+    State(_s): State<Arc<AppState>>,
+) -> Result<Json<CachedScriptsResponse>, WebError> {
+    Ok(Json(CachedScriptsResponse {
+        scripts: cds_engine::cached_scripts(),
+    }))
+}
+
+/// Evicts one cached script, or all of them.
+#[utoipa::path(
+    delete,
+    path = "/scripts",
+    tag = "admin-engine",
+    params(EvictCachedScriptsRequest),
+    responses(
+        (status = 200, description = "Scripts evicted", body = EvictCachedScriptsResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_evict_cached_scripts"))]
+pub async fn evict_cached_scripts(
+    State(_s): State<Arc<AppState>>,
+    Query(params): Query<EvictCachedScriptsRequest>,
+) -> Result<Json<EvictCachedScriptsResponse>, WebError> {
+    let evicted = match params.key.as_deref() {
+        Some(key) => usize::from(cds_engine::evict(key)),
+        None => cds_engine::clear_cache(),
+    };
+    info!(key = ?params.key, evicted, "admin evicted cached lua scripts");
+
+    Ok(Json(EvictCachedScriptsResponse { evicted }))
+}
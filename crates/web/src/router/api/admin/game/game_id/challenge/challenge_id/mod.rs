@@ -1,6 +1,9 @@
 //! HTTP routing for `challenge_id` — Axum router wiring and OpenAPI route
 //! registration.
 
+/// Defines the `rejudge` submodule (see sibling `*.rs` files).
+mod rejudge;
+
 use std::sync::Arc;
 
 use axum::{Json, Router, extract::State};
@@ -29,6 +32,7 @@ pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
     OpenApiRouter::from(Router::new().with_state(state.clone()))
         .routes(routes!(update_game_challenge).with_state(state.clone()))
         .routes(routes!(delete_game_challenge).with_state(state.clone()))
+        .nest("/rejudges", rejudge::router(state.clone()))
 }
 
 #[serde_as]
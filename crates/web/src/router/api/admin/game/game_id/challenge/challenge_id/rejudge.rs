@@ -0,0 +1,197 @@
+//! HTTP handlers for re-judging a game challenge's submissions with the
+//! challenge's current checker.
+
+use std::sync::Arc;
+
+use axum::{Extension, Json, Router, extract::State, http::StatusCode};
+use cds_db::{
+    RejudgeEntryView, RejudgeJobView, rejudge::NewRejudge, sea_orm::TransactionTrait,
+    submission::Status,
+};
+use cds_worker::checker::SUBJECT;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+use crate::{
+    extract::{Json as ReqJson, Path, Query},
+    traits::{AppState, AuthPrincipal, WebError},
+};
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(get_rejudges).with_state(state.clone()))
+        .routes(routes!(create_rejudge).with_state(state.clone()))
+        .routes(routes!(get_rejudge).with_state(state.clone()))
+}
+
+#[derive(Clone, Debug, Deserialize, utoipa::ToSchema)]
+pub struct CreateRejudgeRequest {
+    /// Only re-judge submissions that currently have this status.
+    pub status: Option<Status>,
+    /// Inclusive bounds on the submissions' `created_at`.
+    pub created_from: Option<i64>,
+    pub created_until: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct RejudgeJobResponse {
+    pub job: RejudgeJobView,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct RejudgeJobsResponse {
+    pub jobs: Vec<RejudgeJobView>,
+}
+
+#[derive(Clone, Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetRejudgeRequest {
+    /// Only list submissions whose verdict changed.
+    #[serde(default)]
+    pub changed_only: bool,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct RejudgeReportResponse {
+    pub job: RejudgeJobView,
+    pub entries: Vec<RejudgeEntryView>,
+}
+
+/// Lists re-judge jobs of a game challenge, newest first.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "admin-game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+        ("challenge_id" = i64, Path, description = "Challenge id"),
+    ),
+    responses(
+        (status = 200, description = "Re-judge jobs", body = RejudgeJobsResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_rejudges"))]
+pub async fn get_rejudges(
+    State(s): State<Arc<AppState>>,
+    Path((game_id, challenge_id)): Path<(i64, i64)>,
+) -> Result<Json<RejudgeJobsResponse>, WebError> {
+    let jobs = cds_db::rejudge::find_jobs(&s.db.conn, game_id, challenge_id).await?;
+
+    Ok(Json(RejudgeJobsResponse { jobs }))
+}
+
+/// Returns matching submissions to the checker queue to be judged again by
+/// the current checker script. Scores are recalculated once all are done.
+///
+/// A re-judge cannot lift the bans a `Cheat` verdict applied, so it is refused
+/// with `cheat_teams_still_banned` until the teams of the matching `Cheat`
+/// submissions, and the peers they were caught with, are unbanned by hand.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "admin-game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+        ("challenge_id" = i64, Path, description = "Challenge id"),
+    ),
+    request_body = CreateRejudgeRequest,
+    responses(
+        (status = 201, description = "Re-judge job started", body = RejudgeJobResponse),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "create_rejudge"))]
+pub async fn create_rejudge(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+    Path((game_id, challenge_id)): Path<(i64, i64)>,
+    ReqJson(body): ReqJson<CreateRejudgeRequest>,
+) -> Result<(StatusCode, Json<RejudgeJobResponse>), WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+    let _ = crate::util::loader::prepare_game_challenge(&s.db.conn, game_id, challenge_id).await?;
+    if let (Some(from), Some(until)) = (body.created_from, body.created_until)
+        && from > until
+    {
+        return Err(WebError::BadRequest(json!("invalid_time_range")));
+    }
+
+    let transaction = s.db.conn.begin().await.map_err(cds_db::DbError::from)?;
+    let (job, submission_ids) = cds_db::rejudge::create(
+        &transaction,
+        NewRejudge {
+            game_id,
+            challenge_id,
+            status: body.status,
+            created_from: body.created_from,
+            created_until: body.created_until,
+            author_id: Some(operator.id),
+        },
+    )
+    .await
+    .map_err(|err| match err {
+        cds_db::DbError::BadRequest(reason) => WebError::BadRequest(json!(reason)),
+        err => err.into(),
+    })?;
+    transaction.commit().await.map_err(cds_db::DbError::from)?;
+
+    // Unpublished ids stay queued and are picked up by checker recovery.
+    for submission_id in &submission_ids {
+        s.queue.publish(SUBJECT, submission_id).await?;
+    }
+    info!(
+        rejudge_job_id = job.id,
+        game_id,
+        challenge_id,
+        user_id = operator.id,
+        total = job.total,
+        "rejudge started"
+    );
+
+    Ok((StatusCode::CREATED, Json(RejudgeJobResponse { job })))
+}
+
+/// Returns a re-judge job with each submission's verdict before and after.
+#[utoipa::path(
+    get,
+    path = "/{job_id}",
+    tag = "admin-game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+        ("challenge_id" = i64, Path, description = "Challenge id"),
+        ("job_id" = i64, Path, description = "Re-judge job id"),
+        GetRejudgeRequest,
+    ),
+    responses(
+        (status = 200, description = "Re-judge report", body = RejudgeReportResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_rejudge"))]
+pub async fn get_rejudge(
+    State(s): State<Arc<AppState>>,
+    Path((game_id, challenge_id, job_id)): Path<(i64, i64, i64)>,
+    Query(params): Query<GetRejudgeRequest>,
+) -> Result<Json<RejudgeReportResponse>, WebError> {
+    let job = cds_db::rejudge::find_job_by_id(&s.db.conn, job_id)
+        .await?
+        .filter(|job| job.game_id == game_id && job.challenge_id == challenge_id)
+        .ok_or(WebError::NotFound(json!("rejudge_not_found")))?;
+
+    let mut entries = cds_db::rejudge::find_entries(&s.db.conn, job_id).await?;
+    if params.changed_only {
+        entries.retain(RejudgeEntryView::changed);
+    }
+
+    Ok(Json(RejudgeReportResponse { job, entries }))
+}
@@ -0,0 +1,87 @@
+//! HTTP routing for `export` — downloads a game as a portable bundle.
+
+use std::sync::Arc;
+
+use axum::{
+    Router,
+    body::Body,
+    extract::State,
+    http::{Response, StatusCode, header},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+use crate::{
+    extract::{Path, Query},
+    traits::{AppState, WebError},
+    util::bundle::{self, CollectOptions},
+};
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(export_game).with_state(state.clone()))
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, utoipa::ToSchema, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportGameRequest {
+    /// Includes teams, their members' usernames, avatars and writeups.
+    #[serde(default)]
+    pub teams: bool,
+    /// Includes submissions. Implies `teams`.
+    #[serde(default)]
+    pub submissions: bool,
+    #[serde(default)]
+    pub notices: bool,
+}
+
+/// Exports a game with its challenges and media as a bundle.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "admin-game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+        ExportGameRequest,
+    ),
+    responses(
+        (status = 200, description = "Game bundle", body = bundle::GameBundle),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "export_game"))]
+pub async fn export_game(
+    State(s): State<Arc<AppState>>,
+    Path(game_id): Path<i64>,
+    Query(params): Query<ExportGameRequest>,
+) -> Result<impl IntoResponse, WebError> {
+    let game = crate::util::loader::prepare_game(&s.db.conn, game_id).await?;
+    let bundle = bundle::collect(
+        &s.db.conn,
+        &s.media,
+        game,
+        CollectOptions {
+            teams: params.teams || params.submissions,
+            submissions: params.submissions,
+            notices: params.notices,
+        },
+    )
+    .await?;
+
+    let body = serde_json::to_vec(&bundle).map_err(anyhow::Error::from)?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"game-{game_id}.bundle.json\""),
+        )
+        .body(Body::from(body))?)
+}
@@ -4,6 +4,9 @@
 /// Defines the `challenge` submodule (see sibling `*.rs` files).
 mod challenge;
 
+/// Defines the `export` submodule (see sibling `*.rs` files).
+mod export;
+
 /// Defines the `icon` submodule (see sibling `*.rs` files).
 mod icon;
 
@@ -16,6 +19,9 @@ mod poster;
 /// Defines the `team` submodule (see sibling `*.rs` files).
 mod team;
 
+/// Defines the `warmup` submodule (see sibling `*.rs` files).
+mod warmup;
+
 use std::sync::Arc;
 
 use axum::{Json, Router, extract::State};
@@ -50,6 +56,8 @@ pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
         .nest("/notices", notice::router(state.clone()))
         .nest("/icon", icon::router(state.clone()))
         .nest("/poster", poster::router(state.clone()))
+        .nest("/export", export::router(state.clone()))
+        .nest("/warmup", warmup::router(state.clone()))
 }
 
 /// Returns game.
@@ -0,0 +1,154 @@
+//! HTTP routing for `export` — team rosters of a game as CSV or JSON.
+
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::State,
+    http::{Response, StatusCode, header},
+    response::IntoResponse,
+};
+use cds_db::TeamRosterEntry;
+use serde::{Deserialize, Serialize};
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+use crate::{
+    extract::{Path, Query},
+    traits::{AppState, WebError},
+    util,
+};
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(export_teams).with_state(state.clone()))
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportTeamsRequest {
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct ExportTeamsResponse {
+    pub members: Vec<TeamRosterEntry>,
+    pub total: u64,
+}
+
+/// Exports every team member of a game with the team's standing.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "admin-game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+        ExportTeamsRequest,
+    ),
+    responses(
+        (status = 200, description = "Team roster", body = ExportTeamsResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "export_teams"))]
+pub async fn export_teams(
+    State(s): State<Arc<AppState>>,
+    Path(game_id): Path<i64>,
+    Query(params): Query<ExportTeamsRequest>,
+) -> Result<impl IntoResponse, WebError> {
+    let game = util::loader::prepare_game(&s.db.conn, game_id).await?;
+    let members = cds_db::team_user::find_roster(&s.db.conn, game.id).await?;
+
+    match params.format.unwrap_or_default() {
+        ExportFormat::Json => {
+            let total = members.len() as u64;
+            Ok(Json(ExportTeamsResponse { members, total }).into_response())
+        }
+        ExportFormat::Csv => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+            .header(
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"game-{}-teams.csv\"", game.id),
+            )
+            .body(Body::from(to_csv(&members)))?
+            .into_response()),
+    }
+}
+
+/// Renders roster entries as CSV with a header row.
+fn to_csv(members: &[TeamRosterEntry]) -> String {
+    let mut out = String::new();
+    util::csv::write_record(
+        &mut out,
+        [
+            "team_id", "team", "division", "state", "rank", "pts", "user_id", "username", "name",
+            "email",
+        ],
+    );
+    for member in members {
+        util::csv::write_record(
+            &mut out,
+            [
+                member.team_id.to_string(),
+                member.team_name.clone(),
+                member.division.clone().unwrap_or_default(),
+                format!("{:?}", member.state).to_lowercase(),
+                member.rank.to_string(),
+                member.pts.to_string(),
+                member.user_id.to_string(),
+                member.username.clone(),
+                member.user_name.clone(),
+                member.email.clone().unwrap_or_default(),
+            ],
+        );
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use cds_db::team::State as TState;
+
+    use super::*;
+
+    #[test]
+    fn csv_export_has_header_and_one_line_per_member() {
+        let csv = to_csv(&[TeamRosterEntry {
+            team_id: 1,
+            team_name: "Red, Inc".to_owned(),
+            division: Some("student".to_owned()),
+            state: TState::Passed,
+            pts: 900,
+            rank: 1,
+            user_id: 7,
+            username: "alice".to_owned(),
+            user_name: "Alice".to_owned(),
+            email: None,
+        }]);
+
+        let records = util::csv::parse(&csv).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0][0], "team_id");
+        assert_eq!(
+            records[1],
+            vec![
+                "1", "Red, Inc", "student", "passed", "1", "900", "7", "alice", "Alice", ""
+            ]
+        );
+    }
+}
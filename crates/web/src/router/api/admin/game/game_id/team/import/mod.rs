@@ -0,0 +1,552 @@
+//! HTTP routing for `import` — bulk creation of users and `Passed` teams for a
+//! game from CSV or JSON rows.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use axum::{Json, Router, extract::State};
+use cds_db::{
+    EmailView, GameDetail, TeamUserView, TeamView, UserAccountView,
+    sea_orm::{ActiveValue::Set, TransactionTrait},
+    team::State as TState,
+    team_user::FindTeamUserOptions,
+    user::Group,
+};
+use cds_media::config::email::EmailType;
+use cds_worker::mailbox::SUBJECT;
+use nanoid::nanoid;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+use validator::ValidateEmail;
+
+use crate::{
+    extract::{Json as ReqJson, Path},
+    traits::{AppState, WebError},
+    util,
+};
+
+/// Upper bound on rows accepted by one import request.
+const MAX_ROWS: usize = 2000;
+
+/// Used when no `credential` email template has been uploaded.
+const DEFAULT_CREDENTIAL_EMAIL: &str = "<html><head><title>Your account for %GAME%</title></head>\
+<body><p>Hello %USER%,</p><p>An account has been created for you in team <b>%TEAM%</b>.</p>\
+<p>Username: <code>%USERNAME%</code><br/>Password: <code>%PASSWORD%</code></p>\
+<p>Please change your password after signing in.</p></body></html>";
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(import_teams).with_state(state.clone()))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct TeamImportRow {
+    pub username: String,
+    pub email: String,
+    pub team: String,
+    /// Display name for newly created users; defaults to the username.
+    pub name: Option<String>,
+    pub division: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ImportTeamsRequest {
+    /// Rows as JSON objects. Exactly one of `rows` and `csv` must be set.
+    pub rows: Option<Vec<TeamImportRow>>,
+    /// CSV text whose header names `username`, `email` and `team`, and
+    /// optionally `name` and `division`.
+    pub csv: Option<String>,
+    /// Emails generated credentials to newly created users.
+    #[serde(default)]
+    pub send_credentials: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, utoipa::ToSchema)]
+pub struct TeamImportResult {
+    /// One-based position of the row in the request.
+    pub row: usize,
+    pub username: String,
+    pub team: String,
+    pub user_id: Option<i64>,
+    pub team_id: Option<i64>,
+    pub user_created: bool,
+    pub team_created: bool,
+    /// Whether the user was added to the team by this row.
+    pub linked: bool,
+    /// Generated password of a newly created user. Returned only once.
+    pub password: Option<String>,
+    pub credentials_sent: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct ImportTeamsResponse {
+    pub results: Vec<TeamImportResult>,
+    pub total: u64,
+    pub failed: u64,
+}
+
+/// Imports users and teams in bulk.
+///
+/// Rows are applied one by one, each in its own transaction, so a bad row is
+/// reported without undoing the rows around it. Missing users are created
+/// with a random password and a verified email; missing teams are created in
+/// the `Passed` state. A row naming an existing user under another email, or
+/// an existing team under another division, fails with `email_mismatch` or
+/// `division_mismatch` instead of silently dropping the field. Teams skip the
+/// player flow's member limit check, so every row of a team whose final size
+/// would fall outside the game's limits fails with
+/// `member_limit_not_satisfied`.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "admin-game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+    ),
+    request_body = ImportTeamsRequest,
+    responses(
+        (status = 200, description = "Per-row report", body = ImportTeamsResponse),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "import_teams"))]
+pub async fn import_teams(
+    State(s): State<Arc<AppState>>,
+    Path(game_id): Path<i64>,
+    ReqJson(body): ReqJson<ImportTeamsRequest>,
+) -> Result<Json<ImportTeamsResponse>, WebError> {
+    let game = util::loader::prepare_game(&s.db.conn, game_id).await?;
+
+    let rows = match (body.rows, body.csv) {
+        (Some(rows), None) => rows,
+        (None, Some(csv)) => rows_from_csv(&csv).map_err(|err| WebError::BadRequest(json!(err)))?,
+        _ => return Err(WebError::BadRequest(json!("import_source_invalid"))),
+    };
+    if rows.len() > MAX_ROWS {
+        return Err(WebError::BadRequest(json!("import_too_many_rows")));
+    }
+
+    let template = if body.send_credentials {
+        if !cds_db::get_config(&s.db.conn).await.email.enabled {
+            return Err(WebError::BadRequest(json!("email_disabled")));
+        }
+        Some(
+            s.media
+                .config()
+                .email()
+                .get_email(EmailType::Credential)
+                .await
+                .ok()
+                .filter(|body| !body.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_CREDENTIAL_EMAIL.to_owned()),
+        )
+    } else {
+        None
+    };
+
+    let unfit = unfit_teams(&s, &game, &rows).await?;
+
+    let mut results = Vec::with_capacity(rows.len());
+    for (i, row) in rows.into_iter().enumerate() {
+        let mut result = TeamImportResult {
+            row: i + 1,
+            username: row.username.clone(),
+            team: row.team.clone(),
+            ..Default::default()
+        };
+
+        if unfit.contains(row.team.trim()) {
+            result.error = Some("member_limit_not_satisfied".to_owned());
+            results.push(result);
+            continue;
+        }
+
+        if let Err(err) = import_row(&s, &game, row.clone(), &mut result).await {
+            result.error = Some(reason(err));
+            results.push(result);
+            continue;
+        }
+
+        if let (Some(template), Some(password)) = (&template, &result.password) {
+            let body = template
+                .replace("%USER%", row.name.as_deref().unwrap_or(&row.username))
+                .replace("%USERNAME%", &result.username)
+                .replace("%PASSWORD%", password)
+                .replace("%TEAM%", &row.team)
+                .replace("%GAME%", &game.title);
+            let sent = s
+                .queue
+                .publish(
+                    SUBJECT,
+                    cds_mailbox::Payload {
+                        name: row.name.clone().unwrap_or(row.username.clone()),
+                        email: row.email.trim().to_lowercase(),
+                        subject: util::email::extract_title(&body)
+                            .unwrap_or("Your Account".to_owned()),
+                        body,
+                    },
+                )
+                .await;
+            match sent {
+                Ok(_) => result.credentials_sent = true,
+                Err(err) => warn!(user_id = ?result.user_id, %err, "credential email not queued"),
+            }
+        }
+
+        results.push(result);
+    }
+
+    let total = results.len() as u64;
+    let failed = results.iter().filter(|r| r.error.is_some()).count() as u64;
+    info!(game_id = game.id, total, failed, "admin imported teams");
+
+    Ok(Json(ImportTeamsResponse {
+        results,
+        total,
+        failed,
+    }))
+}
+
+/// Names of the teams the rows would leave with fewer members than the game
+/// requires or more than it allows, counting their current members.
+async fn unfit_teams(
+    s: &AppState,
+    game: &GameDetail,
+    rows: &[TeamImportRow],
+) -> Result<HashSet<String>, WebError> {
+    let mut incoming = HashMap::<&str, HashSet<String>>::new();
+    for row in rows {
+        incoming
+            .entry(row.team.trim())
+            .or_default()
+            .insert(row.username.trim().to_lowercase());
+    }
+
+    let mut unfit = HashSet::new();
+    for (team_name, usernames) in incoming {
+        let members =
+            match cds_db::team::find_by_name_in_game::<TeamView>(&s.db.conn, game.id, team_name)
+                .await?
+            {
+                Some(team) => cds_db::team_user::find_users::<UserAccountView>(&s.db.conn, team.id)
+                    .await?
+                    .into_iter()
+                    .map(|user| user.username)
+                    .collect(),
+                None => Vec::new(),
+            };
+        let size = final_size(&members, &usernames);
+        if size < game.member_limit_min as usize || size > game.member_limit_max as usize {
+            unfit.insert(team_name.to_owned());
+        }
+    }
+
+    Ok(unfit)
+}
+
+/// Number of members a team ends up with once every username is in it.
+fn final_size(members: &[String], usernames: &HashSet<String>) -> usize {
+    usernames.len()
+        + members
+            .iter()
+            .filter(|member| !usernames.contains(member.as_str()))
+            .count()
+}
+
+/// Applies one row inside its own transaction, filling `result` as it goes.
+async fn import_row(
+    s: &AppState,
+    game: &GameDetail,
+    row: TeamImportRow,
+    result: &mut TeamImportResult,
+) -> Result<(), WebError> {
+    let game_id = game.id;
+    let username = row.username.trim().to_lowercase();
+    let email = row.email.trim().to_lowercase();
+    let team_name = row.team.trim().to_owned();
+    let division = row
+        .division
+        .map(|division| division.trim().to_owned())
+        .filter(|division| !division.is_empty());
+
+    if !(3..=20).contains(&username.chars().count()) {
+        return Err(WebError::BadRequest(json!("username_invalid")));
+    }
+    if !email.validate_email() {
+        return Err(WebError::BadRequest(json!("email_invalid")));
+    }
+    if team_name.is_empty() {
+        return Err(WebError::BadRequest(json!("team_name_invalid")));
+    }
+    result.username = username.clone();
+    result.team = team_name.clone();
+
+    let transaction = s.db.conn.begin().await.map_err(cds_db::DbError::from)?;
+    // Only reported once the transaction commits, so a rolled-back row never
+    // hands out a password for an account that does not exist.
+    let mut password_created = None;
+    let mut team_created = false;
+
+    let user =
+        match cds_db::user::find_by_account::<UserAccountView>(&transaction, username.clone())
+            .await?
+        {
+            Some(user) if user.username != username => {
+                return Err(WebError::Conflict(json!("username_mismatch")));
+            }
+            Some(user) if user.group == Group::Banned => {
+                return Err(WebError::Forbidden(json!("user_banned")));
+            }
+            Some(user) => {
+                let emails =
+                    cds_db::email::find_by_user_id::<EmailView>(&transaction, user.id).await?;
+                if !email_matches(&emails, &email) {
+                    return Err(WebError::Conflict(json!("email_mismatch")));
+                }
+                user
+            }
+            None => {
+                if !cds_db::user::is_email_unique(&transaction, &email).await? {
+                    return Err(WebError::Conflict(json!("email_already_exists")));
+                }
+
+                let password = nanoid!(16);
+                let user = cds_db::user::create::<UserAccountView>(
+                    &transaction,
+                    cds_db::user::ActiveModel {
+                        name: Set(row
+                            .name
+                            .map(|name| name.trim().to_owned())
+                            .unwrap_or(username.clone())),
+                        username: Set(username),
+                        hashed_password: Set(util::crypto::hash_password(password.clone())),
+                        group: Set(Group::User),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                let _ = cds_db::email::create::<EmailView>(
+                    &transaction,
+                    cds_db::email::ActiveModel {
+                        user_id: Set(user.id),
+                        email: Set(email),
+                        verified: Set(true),
+                    },
+                )
+                .await?;
+
+                password_created = Some(password);
+                user
+            }
+        };
+
+    let team =
+        match cds_db::team::find_by_name_in_game::<TeamView>(&transaction, game_id, &team_name)
+            .await?
+        {
+            Some(team) if !division_matches(team.division.as_deref(), division.as_deref()) => {
+                return Err(WebError::Conflict(json!("division_mismatch")));
+            }
+            Some(team) => team,
+            None => {
+                team_created = true;
+                cds_db::team::create::<TeamView>(
+                    &transaction,
+                    cds_db::team::ActiveModel {
+                        name: Set(team_name),
+                        game_id: Set(game_id),
+                        division: Set(division),
+                        state: Set(TState::Passed),
+                        ..Default::default()
+                    },
+                )
+                .await?
+            }
+        };
+
+    let mut linked = false;
+    if cds_db::team_user::contains_user(&transaction, team.id, user.id).await? {
+        // Already a member: the row is satisfied as-is.
+    } else if cds_db::team::contains_user_in_game(&transaction, game_id, user.id, None).await? {
+        return Err(WebError::Conflict(json!("user_already_in_game")));
+    } else {
+        // Members may have joined since the sizes were planned.
+        let (_, members) = cds_db::team_user::find::<TeamUserView>(
+            &transaction,
+            FindTeamUserOptions {
+                team_id: Some(team.id),
+                ..Default::default()
+            },
+        )
+        .await?;
+        if members >= game.member_limit_max as u64 {
+            return Err(WebError::Conflict(json!("member_limit_not_satisfied")));
+        }
+
+        let _ = cds_db::team_user::create::<TeamUserView>(
+            &transaction,
+            cds_db::team_user::ActiveModel {
+                team_id: Set(team.id),
+                user_id: Set(user.id),
+            },
+        )
+        .await?;
+        linked = true;
+    }
+
+    transaction.commit().await.map_err(cds_db::DbError::from)?;
+    result.user_id = Some(user.id);
+    result.team_id = Some(team.id);
+    result.user_created = password_created.is_some();
+    result.password = password_created;
+    result.team_created = team_created;
+    result.linked = linked;
+
+    Ok(())
+}
+
+/// Returns whether an existing user owns `email`, so a row cannot silently
+/// drop the address it names.
+fn email_matches(emails: &[EmailView], email: &str) -> bool {
+    emails
+        .iter()
+        .any(|existing| existing.email.eq_ignore_ascii_case(email))
+}
+
+/// Returns whether an existing team can take a row naming `division`. Rows
+/// without a division accept the team as-is.
+fn division_matches(existing: Option<&str>, division: Option<&str>) -> bool {
+    division.is_none_or(|division| existing == Some(division))
+}
+
+/// Reduces an error to the short reason reported for a failed row.
+fn reason(err: WebError) -> String {
+    match err {
+        WebError::BadRequest(value)
+        | WebError::Conflict(value)
+        | WebError::Forbidden(value)
+        | WebError::NotFound(value) => value.as_str().unwrap_or_default().to_owned(),
+        err => {
+            warn!(%err, "team import row failed");
+            "internal_error".to_owned()
+        }
+    }
+}
+
+/// Converts CSV text with a header row into import rows.
+fn rows_from_csv(input: &str) -> Result<Vec<TeamImportRow>, String> {
+    let mut records = util::csv::parse(input)?.into_iter();
+    let header = records.next().ok_or("csv_empty")?;
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|field| field.trim().eq_ignore_ascii_case(name))
+    };
+    let (Some(username), Some(email), Some(team)) =
+        (column("username"), column("email"), column("team"))
+    else {
+        return Err("csv_header_invalid".to_owned());
+    };
+    let (name, division) = (column("name"), column("division"));
+
+    let field = |record: &[String], index: Option<usize>| {
+        index
+            .and_then(|index| record.get(index))
+            .map(|value| value.trim().to_owned())
+            .filter(|value| !value.is_empty())
+    };
+
+    Ok(records
+        .map(|record| TeamImportRow {
+            username: field(&record, Some(username)).unwrap_or_default(),
+            email: field(&record, Some(email)).unwrap_or_default(),
+            team: field(&record, Some(team)).unwrap_or_default(),
+            name: field(&record, name),
+            division: field(&record, division),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_follow_header_order() {
+        let rows = rows_from_csv(
+            "Team,Username,Email,Division\n\"Red, Inc\",alice,alice@example.com,\nBlue,bob,bob@example.com,student\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                TeamImportRow {
+                    username: "alice".to_owned(),
+                    email: "alice@example.com".to_owned(),
+                    team: "Red, Inc".to_owned(),
+                    name: None,
+                    division: None,
+                },
+                TeamImportRow {
+                    username: "bob".to_owned(),
+                    email: "bob@example.com".to_owned(),
+                    team: "Blue".to_owned(),
+                    name: None,
+                    division: Some("student".to_owned()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn existing_user_with_another_email_is_a_mismatch() {
+        let emails = vec![EmailView {
+            email: "alice@example.com".to_owned(),
+            verified: true,
+        }];
+
+        assert!(email_matches(&emails, "alice@example.com"));
+        assert!(!email_matches(&emails, "alice@other.example"));
+        assert!(!email_matches(&[], "alice@example.com"));
+    }
+
+    #[test]
+    fn existing_team_with_another_division_is_a_mismatch() {
+        assert!(division_matches(Some("student"), Some("student")));
+        assert!(division_matches(Some("student"), None));
+        assert!(division_matches(None, None));
+        assert!(!division_matches(Some("student"), Some("open")));
+        assert!(!division_matches(None, Some("open")));
+    }
+
+    #[test]
+    fn final_size_counts_current_and_incoming_members_once() {
+        let members = vec!["alice".to_owned(), "bob".to_owned()];
+        let usernames = HashSet::from(["bob".to_owned(), "carol".to_owned()]);
+
+        assert_eq!(final_size(&members, &usernames), 3);
+        assert_eq!(final_size(&[], &usernames), 2);
+        assert_eq!(final_size(&members, &HashSet::new()), 2);
+    }
+
+    #[test]
+    fn csv_without_required_columns_is_rejected() {
+        assert_eq!(
+            rows_from_csv("username,team\nalice,red\n").unwrap_err(),
+            "csv_header_invalid"
+        );
+        assert_eq!(rows_from_csv("").unwrap_err(), "csv_empty");
+    }
+}
@@ -1,5 +1,11 @@
 //! HTTP routing for `team` — Axum router wiring and OpenAPI route registration.
 
+/// Defines the `export` submodule (see sibling `*.rs` files).
+mod export;
+
+/// Defines the `import` submodule (see sibling `*.rs` files).
+mod import;
+
 /// Defines the `team_id` submodule (see sibling `*.rs` files).
 mod team_id;
 
@@ -28,6 +34,8 @@ pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
     OpenApiRouter::from(Router::new().with_state(state.clone()))
         .routes(routes!(get_team).with_state(state.clone()))
         .routes(routes!(create_team).with_state(state.clone()))
+        .nest("/import", import::router(state.clone()))
+        .nest("/export", export::router(state.clone()))
         .nest("/{team_id}", team_id::router(state.clone()))
 }
 
@@ -0,0 +1,150 @@
+//! HTTP routing for `warmup` — pre-pulls a game's challenge images on every
+//! cluster node.
+
+use std::sync::Arc;
+
+use axum::{Json, Router, extract::State};
+use cds_cluster::prepull::PrepullStatus;
+use cds_db::{ChallengeDetail, GameChallengeView, game_challenge::FindGameChallengeOptions};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+use crate::{
+    extract::Path,
+    traits::{AppState, EmptyJson, WebError},
+};
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(get_warmup).with_state(state.clone()))
+        .routes(routes!(warmup_game).with_state(state.clone()))
+        .routes(routes!(delete_warmup).with_state(state.clone()))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct WarmupResponse {
+    /// `None` when no pre-pull is running for the game.
+    pub status: Option<PrepullStatus>,
+}
+
+/// Returns the progress of the game's image pre-pull.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "admin-game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+    ),
+    responses(
+        (status = 200, description = "Pre-pull progress", body = WarmupResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_warmup"))]
+pub async fn get_warmup(
+    State(s): State<Arc<AppState>>,
+    Path(game_id): Path<i64>,
+) -> Result<Json<WarmupResponse>, WebError> {
+    let game = crate::util::loader::prepare_game(&s.db.conn, game_id).await?;
+    let status = s.cluster.prepull_status(&game.id.to_string()).await?;
+
+    Ok(Json(WarmupResponse { status }))
+}
+
+/// Pre-pulls the images of the game's enabled challenges on every node,
+/// replacing any earlier pre-pull of the game.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "admin-game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+    ),
+    responses(
+        (status = 200, description = "Pre-pull started", body = WarmupResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "warmup_game"))]
+pub async fn warmup_game(
+    State(s): State<Arc<AppState>>,
+    Path(game_id): Path<i64>,
+) -> Result<Json<WarmupResponse>, WebError> {
+    let game = crate::util::loader::prepare_game(&s.db.conn, game_id).await?;
+
+    let (game_challenges, _) = cds_db::game_challenge::find::<GameChallengeView>(
+        &s.db.conn,
+        FindGameChallengeOptions {
+            game_id: Some(game.id),
+            enabled: Some(true),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let mut images = Vec::new();
+    for game_challenge in &game_challenges {
+        let challenge = cds_db::challenge::find_by_id::<ChallengeDetail>(
+            &s.db.conn,
+            game_challenge.challenge_id,
+        )
+        .await?;
+        if let Some(instance) = challenge
+            .filter(|challenge| challenge.has_instance)
+            .and_then(|challenge| challenge.instance)
+        {
+            images.extend(
+                instance
+                    .containers
+                    .into_iter()
+                    .map(|container| container.image),
+            );
+        }
+    }
+
+    let status = s
+        .cluster
+        .prepull_images(&game.id.to_string(), images)
+        .await?;
+    info!(
+        game_id = game.id,
+        images = status.images.len(),
+        "admin started image pre-pull"
+    );
+
+    Ok(Json(WarmupResponse {
+        status: Some(status),
+    }))
+}
+
+/// Removes the game's pre-pull pods; pulled images stay cached on the nodes.
+#[utoipa::path(
+    delete,
+    path = "/",
+    tag = "admin-game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+    ),
+    responses(
+        (status = 200, description = "Deleted", body = EmptyJson),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "delete_warmup"))]
+pub async fn delete_warmup(
+    State(s): State<Arc<AppState>>,
+    Path(game_id): Path<i64>,
+) -> Result<Json<EmptyJson>, WebError> {
+    let game = crate::util::loader::prepare_game(&s.db.conn, game_id).await?;
+    s.cluster.delete_prepull(&game.id.to_string()).await?;
+
+    Ok(Json(EmptyJson::default()))
+}
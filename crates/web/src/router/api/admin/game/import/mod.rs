@@ -0,0 +1,68 @@
+//! HTTP routing for `import` — creates a game from a portable bundle.
+
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{DefaultBodyLimit, State},
+};
+use cds_worker::calculator;
+use tracing::info;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+use crate::{
+    extract::Json as ReqJson,
+    traits::{AppState, WebError},
+    util::{
+        bundle::{self, GameBundle, RestoreReport},
+        cluster::validate_instance,
+    },
+};
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone())).routes(
+        routes!(import_game)
+            .with_state(state.clone())
+            .layer(DefaultBodyLimit::max(512 * 1024 * 1024 /* MB */)),
+    )
+}
+
+/// Imports a game bundle as a new, disabled game.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "admin-game",
+    request_body = GameBundle,
+    responses(
+        (status = 200, description = "Import report", body = RestoreReport),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 422, description = "Unsupported bundle", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "import_game"))]
+pub async fn import_game(
+    State(s): State<Arc<AppState>>,
+    ReqJson(body): ReqJson<GameBundle>,
+) -> Result<Json<RestoreReport>, WebError> {
+    for instance in body.challenges.iter().filter_map(|c| c.instance.as_ref()) {
+        validate_instance(&s.env, instance)?;
+    }
+    let report = bundle::restore(&s.db.conn, &s.media, body).await?;
+    calculator::notify(&s.queue, report.game_id).await;
+
+    info!(
+        game_id = report.game_id,
+        challenges = report.challenges.len(),
+        teams = report.teams.len(),
+        submissions = report.submissions,
+        media = report.media,
+        "admin imported game bundle"
+    );
+
+    Ok(Json(report))
+}
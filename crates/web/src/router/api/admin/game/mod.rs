@@ -3,6 +3,9 @@
 /// Defines the `game_id` submodule (see sibling `*.rs` files).
 mod game_id;
 
+/// Defines the `import` submodule (see sibling `*.rs` files).
+mod import;
+
 use std::sync::Arc;
 
 use axum::{Json, Router, extract::State, http::StatusCode};
@@ -30,6 +33,7 @@ pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
     OpenApiRouter::from(Router::new().with_state(state.clone()))
         .routes(routes!(get_games).with_state(state.clone()))
         .routes(routes!(create_game).with_state(state.clone()))
+        .nest("/import", import::router(state.clone()))
         .nest("/{game_id}", game_id::router(state.clone()))
 }
 
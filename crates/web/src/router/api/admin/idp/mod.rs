@@ -3,18 +3,22 @@
 /// Defines the `avatar` submodule (see sibling `*.rs` files).
 mod avatar;
 
+/// Defines the `revision` submodule (see sibling `*.rs` files).
+mod revision;
+
 use std::sync::Arc;
 
 use axum::{
-    Json, Router,
+    Extension, Json, Router,
     extract::{Path, State},
     http::StatusCode,
 };
 use cds_db::{
     IdpView,
+    script_revision::{NewRevision, Owner},
     sea_orm::ActiveValue::{NotSet, Set, Unchanged},
 };
-use cds_engine::traits::EngineError;
+use cds_engine::traits::{DiagnosticMarker, EngineError};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::error;
@@ -26,12 +30,14 @@ use validator::Validate;
 
 use crate::{
     extract::Json as ReqJson,
-    traits::{AppState, EmptyJson, WebError},
+    traits::{AppState, AuthPrincipal, EmptyJson, WebError},
 };
 
 #[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct IdpLintResponse {
     pub markers: Vec<cds_engine::traits::DiagnosticMarker>,
+    /// Lua API available to IdP scripts, for editor completion.
+    pub api: Vec<cds_engine::api::ApiEntry>,
 }
 
 pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
@@ -43,6 +49,7 @@ pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
         .routes(routes!(delete_idp).with_state(state.clone()))
         .routes(routes!(lint_idp_script).with_state(state.clone()))
         .nest("/{idp_id}/avatar", avatar::router(state.clone()))
+        .nest("/{idp_id}/revisions", revision::router(state.clone()))
 }
 
 #[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
@@ -65,6 +72,9 @@ pub struct AdminIdpRequest {
     pub registration_enabled: bool,
     pub portal: Option<String>,
     pub script: String,
+    /// Hosts the script may reach over HTTP; others must be public.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, utoipa::ToSchema)]
@@ -103,18 +113,13 @@ pub async fn list_idps(
 #[tracing::instrument(skip_all, fields(handler = "admin_create_idp"))]
 pub async fn create_idp(
     State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
     ReqJson(body): ReqJson<AdminIdpRequest>,
 ) -> Result<(StatusCode, Json<AdminIdpResponse>), WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
     body.validate()
         .map_err(|err| WebError::BadRequest(json!(err.to_string())))?;
-    cds_idp::Idp::lint(&body.script)
-        .await
-        .map_err(|err| match err {
-            EngineError::DiagnosticsError(markers) => {
-                WebError::BadRequest(json!({ "markers": markers }))
-            }
-            _ => WebError::BadRequest(json!(err.to_string())),
-        })?;
+    let warnings = lint_script(&body.script).await?;
 
     let idp = cds_db::idp::create_idp::<IdpView>(
         &s.db.conn,
@@ -125,10 +130,12 @@ pub async fn create_idp(
             registration_enabled: Set(body.registration_enabled),
             portal: Set(body.portal),
             script: Set(body.script),
+            allowed_hosts: Set(body.allowed_hosts),
             ..Default::default()
         },
     )
     .await?;
+    record_revision(&s, &idp, operator.id, None, warnings).await?;
     Ok((StatusCode::CREATED, Json(AdminIdpResponse { idp })))
 }
 
@@ -161,22 +168,17 @@ pub async fn get_idp(
 #[tracing::instrument(skip_all, fields(handler = "admin_update_idp"))]
 pub async fn update_idp(
     State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
     Path(idp_id): Path<i64>,
     ReqJson(body): ReqJson<AdminIdpRequest>,
 ) -> Result<Json<AdminIdpResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
     body.validate()
         .map_err(|err| WebError::BadRequest(json!(err.to_string())))?;
     let _ = cds_db::idp::find_idp_by_id::<IdpView>(&s.db.conn, idp_id)
         .await?
         .ok_or(WebError::NotFound(json!("idp_not_found")))?;
-    cds_idp::Idp::lint(&body.script)
-        .await
-        .map_err(|err| match err {
-            EngineError::DiagnosticsError(markers) => {
-                WebError::BadRequest(json!({ "markers": markers }))
-            }
-            _ => WebError::BadRequest(json!(err.to_string())),
-        })?;
+    let warnings = lint_script(&body.script).await?;
 
     let idp = cds_db::idp::update_idp::<IdpView>(
         &s.db.conn,
@@ -187,13 +189,47 @@ pub async fn update_idp(
             registration_enabled: Set(body.registration_enabled),
             portal: Set(body.portal),
             script: Set(body.script),
+            allowed_hosts: Set(body.allowed_hosts),
             ..Default::default()
         },
     )
     .await?;
+    record_revision(&s, &idp, operator.id, None, warnings).await?;
     Ok(Json(AdminIdpResponse { idp }))
 }
 
+/// Rejects scripts with lint errors, returning their warnings.
+async fn lint_script(script: &str) -> Result<Vec<DiagnosticMarker>, WebError> {
+    cds_idp::Idp::lint(script).await.map_err(|err| match err {
+        EngineError::DiagnosticsError(markers) => {
+            WebError::BadRequest(json!({ "markers": markers }))
+        }
+        _ => WebError::BadRequest(json!(err.to_string())),
+    })
+}
+
+/// Stores the IdP's saved script as its next revision.
+async fn record_revision(
+    s: &AppState,
+    idp: &IdpView,
+    author_id: i64,
+    restored_from: Option<i32>,
+    markers: Vec<DiagnosticMarker>,
+) -> Result<cds_db::ScriptRevisionSummary, WebError> {
+    Ok(cds_db::script_revision::create(
+        &s.db.conn,
+        Owner::Idp(idp.id),
+        NewRevision {
+            script: idp.script.clone(),
+            allowed_hosts: idp.allowed_hosts.clone(),
+            author_id: Some(author_id),
+            restored_from,
+            markers: json!(markers),
+        },
+    )
+    .await?)
+}
+
 #[utoipa::path(
     delete,
     path = "/{idp_id}",
@@ -224,20 +260,18 @@ pub async fn delete_idp(
 pub async fn lint_idp_script(
     ReqJson(body): ReqJson<LintIdpScriptRequest>,
 ) -> Result<Json<IdpLintResponse>, WebError> {
-    let lint = cds_idp::Idp::lint(&body.script).await;
-    let diagnostics = if let Err(lint) = lint {
-        match lint {
-            EngineError::DiagnosticsError(diagnostics) => Some(diagnostics),
-            _ => {
-                error!("{:?}", lint);
-                None
-            }
+    let markers = match cds_idp::Idp::lint(&body.script).await {
+        Ok(warnings) => warnings,
+        Err(EngineError::DiagnosticsError(markers)) => markers,
+        Err(err) => {
+            error!("{:?}", err);
+            Vec::new()
         }
-    } else {
-        None
     };
+    let api = cds_idp::Idp::api().map_err(|err| {
+        error!("failed to describe idp lua api: {err:?}");
+        WebError::InternalServerError(json!("lua_api_unavailable"))
+    })?;
 
-    Ok(Json(IdpLintResponse {
-        markers: diagnostics.unwrap_or_default(),
-    }))
+    Ok(Json(IdpLintResponse { markers, api }))
 }
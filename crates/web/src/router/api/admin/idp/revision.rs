@@ -0,0 +1,149 @@
+//! Admin history of IdP scripts: saved revisions, diffs and rollback.
+
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+};
+use cds_db::{
+    IdpView, ScriptRevisionSummary, ScriptRevisionView,
+    script_revision::Owner,
+    sea_orm::ActiveValue::{Set, Unchanged},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+use crate::{
+    extract::Query,
+    traits::{AppState, AuthPrincipal, WebError},
+    util::{diff::RevisionDiff, loader::prepare_script_revision},
+};
+
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(list_idp_revisions).with_state(state.clone()))
+        .routes(routes!(get_idp_revision).with_state(state.clone()))
+        .routes(routes!(diff_idp_revision).with_state(state.clone()))
+        .routes(routes!(rollback_idp_revision).with_state(state.clone()))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct AdminIdpRevisionsResponse {
+    pub revisions: Vec<ScriptRevisionSummary>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct AdminIdpRevisionResponse {
+    pub revision: ScriptRevisionView,
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DiffIdpRevisionRequest {
+    /// Revision to compare against; defaults to the previous one.
+    pub against: Option<i32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "admin-idp",
+    params(("idp_id" = i64, Path, description = "IdP id")),
+    responses((status = 200, description = "IdP script revisions, newest first", body = AdminIdpRevisionsResponse))
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_list_idp_revisions"))]
+pub async fn list_idp_revisions(
+    State(s): State<Arc<AppState>>,
+    Path(idp_id): Path<i64>,
+) -> Result<Json<AdminIdpRevisionsResponse>, WebError> {
+    let revisions = cds_db::script_revision::find_by_owner(&s.db.conn, Owner::Idp(idp_id)).await?;
+    Ok(Json(AdminIdpRevisionsResponse { revisions }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{number}",
+    tag = "admin-idp",
+    params(
+        ("idp_id" = i64, Path, description = "IdP id"),
+        ("number" = i32, Path, description = "Revision number"),
+    ),
+    responses((status = 200, description = "IdP script revision", body = AdminIdpRevisionResponse))
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_get_idp_revision"))]
+pub async fn get_idp_revision(
+    State(s): State<Arc<AppState>>,
+    Path((idp_id, number)): Path<(i64, i32)>,
+) -> Result<Json<AdminIdpRevisionResponse>, WebError> {
+    let revision = prepare_script_revision(&s.db.conn, Owner::Idp(idp_id), number).await?;
+    Ok(Json(AdminIdpRevisionResponse { revision }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{number}/diff",
+    tag = "admin-idp",
+    params(
+        ("idp_id" = i64, Path, description = "IdP id"),
+        ("number" = i32, Path, description = "Revision number"),
+        DiffIdpRevisionRequest,
+    ),
+    responses((status = 200, description = "Line diff", body = RevisionDiff))
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_diff_idp_revision"))]
+pub async fn diff_idp_revision(
+    State(s): State<Arc<AppState>>,
+    Path((idp_id, number)): Path<(i64, i32)>,
+    Query(params): Query<DiffIdpRevisionRequest>,
+) -> Result<Json<RevisionDiff>, WebError> {
+    let owner = Owner::Idp(idp_id);
+    let revision = prepare_script_revision(&s.db.conn, owner, number).await?;
+    let base = match params.against.or((number > 1).then_some(number - 1)) {
+        Some(against) => Some(prepare_script_revision(&s.db.conn, owner, against).await?),
+        None => None,
+    };
+    Ok(Json(RevisionDiff::between(base.as_ref(), &revision)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{number}/rollback",
+    tag = "admin-idp",
+    params(
+        ("idp_id" = i64, Path, description = "IdP id"),
+        ("number" = i32, Path, description = "Revision number"),
+    ),
+    responses(
+        (status = 200, description = "IdP with the restored script", body = super::AdminIdpResponse),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse)
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_rollback_idp_revision"))]
+pub async fn rollback_idp_revision(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+    Path((idp_id, number)): Path<(i64, i32)>,
+) -> Result<Json<super::AdminIdpResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+    let revision = prepare_script_revision(&s.db.conn, Owner::Idp(idp_id), number).await?;
+    // Libraries the revision requires may have changed since it was saved.
+    let warnings = super::lint_script(&revision.script).await?;
+
+    let idp = cds_db::idp::update_idp::<IdpView>(
+        &s.db.conn,
+        cds_db::idp::IdpActiveModel {
+            id: Unchanged(idp_id),
+            script: Set(revision.script),
+            allowed_hosts: Set(revision.allowed_hosts),
+            ..Default::default()
+        },
+    )
+    .await?;
+    super::record_revision(&s, &idp, operator.id, Some(number), warnings).await?;
+    Ok(Json(super::AdminIdpResponse { idp }))
+}
@@ -4,26 +4,52 @@
 use std::sync::Arc;
 
 use axum::{
+    Router,
     extract::{State, WebSocketUpgrade},
     response::IntoResponse,
 };
 use serde::{Deserialize, Serialize};
+use tracing::debug;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
 
 use crate::{
     extract::{Path, Query},
     traits::{AppState, WebError},
 };
 
-#[allow(dead_code)]
-#[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Paths are relative to
+/// `/admin/instances/{instance_id}/containers/{container_id}`.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(get_shell).with_state(state.clone()))
+        .routes(routes!(get_logs).with_state(state.clone()))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct GetShellRequest {
     pub command: String,
 }
 
-/// Returns shell.
-
-#[allow(dead_code)]
+/// Opens an interactive shell in a container over WebSocket.
+#[utoipa::path(
+    get,
+    path = "/shell",
+    tag = "admin-instance",
+    params(
+        ("instance_id" = String, Path, description = "Instance / pod identifier"),
+        ("container_id" = String, Path, description = "Container identifier"),
+        GetShellRequest,
+    ),
+    responses(
+        (status = 101, description = "WebSocket upgrade"),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_shell"))]
 pub async fn get_shell(
     State(s): State<Arc<AppState>>,
 
@@ -38,3 +64,46 @@ pub async fn get_shell(
             .await;
     }))
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetLogsRequest {
+    /// Lines of history to send before following; defaults to 200.
+    pub tail: Option<i64>,
+}
+
+/// Streams a container's log over WebSocket, one text frame per line.
+#[utoipa::path(
+    get,
+    path = "/logs",
+    tag = "admin-instance",
+    params(
+        ("instance_id" = String, Path, description = "Instance / pod identifier"),
+        ("container_id" = String, Path, description = "Container identifier"),
+        GetLogsRequest,
+    ),
+    responses(
+        (status = 101, description = "WebSocket upgrade"),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_logs"))]
+pub async fn get_logs(
+    State(s): State<Arc<AppState>>,
+
+    Path((instance_id, container_id)): Path<(String, String)>,
+    Query(params): Query<GetLogsRequest>,
+    ws: WebSocketUpgrade,
+) -> Result<impl IntoResponse, WebError> {
+    let tail = params.tail.unwrap_or(200).clamp(0, 5000);
+
+    Ok(ws.on_upgrade(move |socket| async move {
+        if let Err(err) = s
+            .cluster
+            .logs(&instance_id, &container_id, tail, socket)
+            .await
+        {
+            debug!("log stream of {} ended: {:?}", instance_id, err);
+        }
+    }))
+}
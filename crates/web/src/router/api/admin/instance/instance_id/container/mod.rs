@@ -19,10 +19,7 @@ use crate::traits::{AppState, EmptyJson, WebError};
 pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
     OpenApiRouter::from(Router::new().with_state(state.clone()))
         .routes(routes!(get_container).with_state(state.clone()))
-        .nest(
-            "/{container_id}",
-            OpenApiRouter::from(Router::new().with_state(state.clone())),
-        )
+        .nest("/{container_id}", container_id::router(state.clone()))
 }
 
 /// Returns container.
@@ -7,6 +7,8 @@ mod container;
 use std::sync::Arc;
 
 use axum::{Json, Router, extract::State};
+use cds_cluster::inspect::{InstanceEvent, InstanceStatus};
+use serde::Serialize;
 use utoipa_axum::{
     router::{OpenApiRouter, UtoipaMethodRouterExt},
     routes,
@@ -22,6 +24,8 @@ use crate::{
 pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
     OpenApiRouter::from(Router::new().with_state(state.clone()))
         .routes(routes!(stop_instance).with_state(state.clone()))
+        .routes(routes!(get_instance_status).with_state(state.clone()))
+        .routes(routes!(get_instance_events).with_state(state.clone()))
         .nest("/containers", container::router(state.clone()))
 }
 
@@ -53,7 +57,61 @@ pub async fn stop_instance(
         .map(|s| s.to_string())
         .unwrap_or_default();
 
-    s.cluster.delete_challenge_instance(&id).await?;
+    s.cluster
+        .delete_challenge_instance(&id, cds_db::instance::TerminationReason::AdminStopped)
+        .await?;
 
     Ok(Json(EmptyJson::default()))
 }
+
+/// Returns an instance's container states, restart counts and resource usage.
+#[utoipa::path(
+    get,
+    path = "/status",
+    tag = "admin-instance",
+    params(
+        ("instance_id" = String, Path, description = "Instance / pod identifier"),
+    ),
+    responses(
+        (status = 200, description = "Instance status", body = InstanceStatus),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_instance_status"))]
+pub async fn get_instance_status(
+    State(s): State<Arc<AppState>>,
+
+    Path(instance_id): Path<String>,
+) -> Result<Json<InstanceStatus>, WebError> {
+    Ok(Json(s.cluster.instance_status(&instance_id).await?))
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct InstanceEventsResponse {
+    pub events: Vec<InstanceEvent>,
+}
+
+/// Returns the Kubernetes events of an instance's pod, oldest first.
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "admin-instance",
+    params(
+        ("instance_id" = String, Path, description = "Instance / pod identifier"),
+    ),
+    responses(
+        (status = 200, description = "Pod events", body = InstanceEventsResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_instance_events"))]
+pub async fn get_instance_events(
+    State(s): State<Arc<AppState>>,
+
+    Path(instance_id): Path<String>,
+) -> Result<Json<InstanceEventsResponse>, WebError> {
+    let events = s.cluster.instance_events(&instance_id).await?;
+
+    Ok(Json(InstanceEventsResponse { events }))
+}
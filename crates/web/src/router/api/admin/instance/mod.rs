@@ -7,8 +7,13 @@ mod instance_id;
 use std::{collections::BTreeMap, sync::Arc};
 
 use axum::{Json, Router, extract::State, http::StatusCode};
+use cds_db::{
+    InstanceView,
+    instance::{FindInstanceOptions, State as InstanceState},
+};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use tracing::warn;
 use utoipa_axum::{
     router::{OpenApiRouter, UtoipaMethodRouterExt},
     routes,
@@ -25,6 +30,8 @@ pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
     OpenApiRouter::from(Router::new().with_state(state.clone()))
         .routes(routes!(list_instances).with_state(state.clone()))
         .routes(routes!(create_debug_instance).with_state(state.clone()))
+        .routes(routes!(get_instance_records).with_state(state.clone()))
+        .routes(routes!(stop_instances).with_state(state.clone()))
         .nest("/{instance_id}", instance_id::router(state.clone()))
 }
 
@@ -99,6 +106,62 @@ pub async fn list_instances(
     Ok(Json(ListInstancesResponse { instances: envs }))
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetInstanceRecordsRequest {
+    pub id: Option<String>,
+    pub user_id: Option<i64>,
+    pub team_id: Option<i64>,
+    pub game_id: Option<i64>,
+    pub challenge_id: Option<i64>,
+    pub state: Option<InstanceState>,
+    pub page: Option<u64>,
+    pub size: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct InstanceRecordsResponse {
+    pub instances: Vec<InstanceView>,
+    pub total: u64,
+}
+
+/// Returns recorded instances, including terminated ones, newest first.
+#[utoipa::path(
+    get,
+    path = "/records",
+    tag = "admin-instance",
+    params(GetInstanceRecordsRequest),
+    responses(
+        (status = 200, description = "Instance records", body = InstanceRecordsResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_instance_records"))]
+pub async fn get_instance_records(
+    State(s): State<Arc<AppState>>,
+    Query(params): Query<GetInstanceRecordsRequest>,
+) -> Result<Json<InstanceRecordsResponse>, WebError> {
+    let page = params.page.unwrap_or(1);
+    let size = params.size.unwrap_or(10).min(100);
+
+    let (instances, total) = cds_db::instance::find(
+        &s.db.conn,
+        FindInstanceOptions {
+            id: params.id,
+            user_id: params.user_id,
+            team_id: params.team_id,
+            game_id: params.game_id,
+            challenge_id: params.challenge_id,
+            state: params.state,
+            page: Some(page),
+            size: Some(size),
+        },
+    )
+    .await?;
+
+    Ok(Json(InstanceRecordsResponse { instances, total }))
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct CreateDebugInstanceRequest {
     pub challenge_id: i64,
@@ -164,3 +227,86 @@ pub async fn create_debug_instance(
         Json(CreateDebugInstanceResponse { instance_id }),
     ))
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct StopInstancesRequest {
+    pub challenge_id: Option<i64>,
+    pub team_id: Option<i64>,
+    pub game_id: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct StopInstancesResponse {
+    pub stopped: u64,
+    /// Instances that could not be stopped.
+    pub failed: Vec<String>,
+}
+
+/// Stops every instance of a challenge, a team or a game (any combination of
+/// filters narrows the set), for incident response. An instance that fails
+/// to stop is logged and reported without holding up the others.
+#[utoipa::path(
+    post,
+    path = "/stop",
+    tag = "admin-instance",
+    request_body = StopInstancesRequest,
+    responses(
+        (status = 200, description = "Instances stopped", body = StopInstancesResponse),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "stop_instances"))]
+pub async fn stop_instances(
+    State(s): State<Arc<AppState>>,
+
+    ReqJson(body): ReqJson<StopInstancesRequest>,
+) -> Result<Json<StopInstancesResponse>, WebError> {
+    // Warm pool pods belong to no one yet; the pool worker manages them.
+    let mut selectors = vec!["cds/instance_id".to_owned(), "!cds/pool".to_owned()];
+
+    if let Some(challenge_id) = body.challenge_id {
+        selectors.push(format!("cds/challenge_id={}", challenge_id));
+    }
+
+    if let Some(team_id) = body.team_id {
+        selectors.push(format!("cds/team_id={}", team_id));
+    }
+
+    if let Some(game_id) = body.game_id {
+        selectors.push(format!("cds/game_id={}", game_id));
+    }
+
+    if selectors.len() == 2 {
+        return Err(WebError::BadRequest(json!("stop_filter_required")));
+    }
+
+    let pods = s.cluster.get_pods_by_label(&selectors.join(",")).await?;
+
+    let mut stopped = 0;
+    let mut failed = Vec::new();
+    for pod in pods {
+        let Some(id) = pod
+            .metadata
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get("cds/instance_id"))
+        else {
+            continue;
+        };
+
+        match s
+            .cluster
+            .delete_challenge_instance(id, cds_db::instance::TerminationReason::AdminStopped)
+            .await
+        {
+            Ok(()) => stopped += 1,
+            Err(err) => {
+                warn!(instance_id = %id, error = %err, "failed to stop instance");
+                failed.push(id.clone());
+            }
+        }
+    }
+
+    Ok(Json(StopInstancesResponse { stopped, failed }))
+}
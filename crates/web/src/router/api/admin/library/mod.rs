@@ -0,0 +1,272 @@
+//! Admin management of shared Lua libraries.
+//!
+//! Libraries are loaded by checker and IdP scripts with
+//! `require("lib/<name>")`. Every saved library is registered with the script
+//! engine right away; changing a script bumps its version, which rebuilds the
+//! cached scripts that depend on it.
+
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use cds_db::{
+    LuaLibraryView,
+    sea_orm::ActiveValue::{NotSet, Set, Unchanged},
+};
+use cds_engine::{
+    api::ApiEntry,
+    traits::{DiagnosticMarker, EngineError},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::error;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+use crate::{
+    extract::Json as ReqJson,
+    traits::{AppState, EmptyJson, WebError},
+};
+
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(list_libraries).with_state(state.clone()))
+        .routes(routes!(create_library).with_state(state.clone()))
+        .routes(routes!(get_library).with_state(state.clone()))
+        .routes(routes!(update_library).with_state(state.clone()))
+        .routes(routes!(delete_library).with_state(state.clone()))
+        .routes(routes!(lint_library).with_state(state.clone()))
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct AdminLibrariesResponse {
+    pub libraries: Vec<LuaLibraryView>,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct AdminLibraryResponse {
+    pub library: LuaLibraryView,
+}
+
+#[derive(Clone, Debug, Deserialize, utoipa::ToSchema)]
+pub struct CreateLibraryRequest {
+    /// Module name without the `lib/` prefix, e.g. `flag/format`.
+    pub name: String,
+    pub description: Option<String>,
+    pub script: String,
+}
+
+#[derive(Clone, Debug, Deserialize, utoipa::ToSchema)]
+pub struct UpdateLibraryRequest {
+    pub description: Option<String>,
+    pub script: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, utoipa::ToSchema)]
+pub struct LintLibraryRequest {
+    pub name: String,
+    pub script: String,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct LibraryLintResponse {
+    pub markers: Vec<DiagnosticMarker>,
+    /// Lua API available to libraries, for editor completion.
+    pub api: Vec<ApiEntry>,
+}
+
+/// Lints a library, mapping diagnostics to a bad request.
+async fn ensure_valid(name: &str, script: &str) -> Result<(), WebError> {
+    cds_engine::library::lint(name, script)
+        .await
+        .map_err(|err| match err {
+            EngineError::DiagnosticsError(markers) => {
+                WebError::BadRequest(json!({ "markers": markers }))
+            }
+            _ => WebError::BadRequest(json!(err.to_string())),
+        })?;
+    Ok(())
+}
+
+/// Makes a saved library available to the script engine.
+fn register(library: &LuaLibraryView) -> Result<(), WebError> {
+    cds_engine::library::register(&library.name, &library.script, library.version).map_err(|err| {
+        error!(library = %library.name, "failed to register lua library: {err:?}");
+        WebError::InternalServerError(json!("library_register_failed"))
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "admin-library",
+    responses((status = 200, description = "Libraries", body = AdminLibrariesResponse))
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_list_libraries"))]
+pub async fn list_libraries(
+    State(s): State<Arc<AppState>>,
+) -> Result<Json<AdminLibrariesResponse>, WebError> {
+    let libraries = cds_db::lua_library::find_all::<LuaLibraryView>(&s.db.conn).await?;
+    Ok(Json(AdminLibrariesResponse { libraries }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "admin-library",
+    request_body = CreateLibraryRequest,
+    responses(
+        (status = 201, description = "Created library", body = AdminLibraryResponse),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 409, description = "Name already taken", body = crate::traits::ErrorResponse)
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_create_library"))]
+pub async fn create_library(
+    State(s): State<Arc<AppState>>,
+    ReqJson(body): ReqJson<CreateLibraryRequest>,
+) -> Result<(StatusCode, Json<AdminLibraryResponse>), WebError> {
+    if !cds_engine::library::is_valid_name(&body.name) {
+        return Err(WebError::BadRequest(json!("invalid_library_name")));
+    }
+    if cds_db::lua_library::find_by_name::<LuaLibraryView>(&s.db.conn, &body.name)
+        .await?
+        .is_some()
+    {
+        return Err(WebError::Conflict(json!("library_already_exists")));
+    }
+    ensure_valid(&body.name, &body.script).await?;
+
+    let library = cds_db::lua_library::create::<LuaLibraryView>(
+        &s.db.conn,
+        cds_db::lua_library::ActiveModel {
+            id: NotSet,
+            name: Set(body.name),
+            description: Set(body.description),
+            script: Set(body.script),
+            version: Set(1),
+            ..Default::default()
+        },
+    )
+    .await?;
+    register(&library)?;
+    Ok((StatusCode::CREATED, Json(AdminLibraryResponse { library })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{library_id}",
+    tag = "admin-library",
+    params(("library_id" = i64, Path, description = "Library id")),
+    responses((status = 200, description = "Library", body = AdminLibraryResponse))
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_get_library"))]
+pub async fn get_library(
+    State(s): State<Arc<AppState>>,
+    Path(library_id): Path<i64>,
+) -> Result<Json<AdminLibraryResponse>, WebError> {
+    let library = cds_db::lua_library::find_by_id::<LuaLibraryView>(&s.db.conn, library_id)
+        .await?
+        .ok_or(WebError::NotFound(json!("library_not_found")))?;
+    Ok(Json(AdminLibraryResponse { library }))
+}
+
+/// Updates a library. The name is fixed once created because scripts refer
+/// to it; a script change bumps the version.
+#[utoipa::path(
+    put,
+    path = "/{library_id}",
+    tag = "admin-library",
+    params(("library_id" = i64, Path, description = "Library id")),
+    request_body = UpdateLibraryRequest,
+    responses(
+        (status = 200, description = "Updated library", body = AdminLibraryResponse),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse)
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_update_library"))]
+pub async fn update_library(
+    State(s): State<Arc<AppState>>,
+    Path(library_id): Path<i64>,
+    ReqJson(body): ReqJson<UpdateLibraryRequest>,
+) -> Result<Json<AdminLibraryResponse>, WebError> {
+    let current = cds_db::lua_library::find_by_id::<LuaLibraryView>(&s.db.conn, library_id)
+        .await?
+        .ok_or(WebError::NotFound(json!("library_not_found")))?;
+    let script = body.script.filter(|script| *script != current.script);
+    if let Some(script) = &script {
+        ensure_valid(&current.name, script).await?;
+    }
+
+    let library = cds_db::lua_library::update::<LuaLibraryView>(
+        &s.db.conn,
+        cds_db::lua_library::ActiveModel {
+            id: Unchanged(library_id),
+            description: body.description.map_or(NotSet, |v| Set(Some(v))),
+            version: if script.is_some() {
+                Set(current.version + 1)
+            } else {
+                NotSet
+            },
+            script: script.map_or(NotSet, Set),
+            ..Default::default()
+        },
+    )
+    .await?;
+    register(&library)?;
+    Ok(Json(AdminLibraryResponse { library }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{library_id}",
+    tag = "admin-library",
+    params(("library_id" = i64, Path, description = "Library id")),
+    responses((status = 200, description = "Deleted", body = EmptyJson))
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_delete_library"))]
+pub async fn delete_library(
+    State(s): State<Arc<AppState>>,
+    Path(library_id): Path<i64>,
+) -> Result<Json<EmptyJson>, WebError> {
+    let library = cds_db::lua_library::find_by_id::<LuaLibraryView>(&s.db.conn, library_id)
+        .await?
+        .ok_or(WebError::NotFound(json!("library_not_found")))?;
+    cds_db::lua_library::delete(&s.db.conn, library_id).await?;
+    cds_engine::library::unregister(&library.name);
+    Ok(Json(EmptyJson::default()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/lint",
+    tag = "admin-library",
+    request_body = LintLibraryRequest,
+    responses(
+        (status = 200, description = "Lint markers (empty if clean)", body = LibraryLintResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "admin_lint_library"))]
+pub async fn lint_library(
+    ReqJson(body): ReqJson<LintLibraryRequest>,
+) -> Result<Json<LibraryLintResponse>, WebError> {
+    let markers = match cds_engine::library::lint(&body.name, &body.script).await {
+        Ok(warnings) => warnings,
+        Err(EngineError::DiagnosticsError(markers)) => markers,
+        Err(err) => {
+            error!("{:?}", err);
+            Vec::new()
+        }
+    };
+    let api = cds_engine::api::environment(&|_| Ok(())).map_err(|err| {
+        error!("failed to describe library lua api: {err:?}");
+        WebError::InternalServerError(json!("lua_api_unavailable"))
+    })?;
+    Ok(Json(LibraryLintResponse { markers, api }))
+}
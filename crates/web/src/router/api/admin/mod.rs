@@ -7,6 +7,9 @@ mod challenge;
 /// Defines the `config` submodule (see sibling `*.rs` files).
 mod config;
 
+/// Defines the `engine` submodule (see sibling `*.rs` files).
+mod engine;
+
 /// Defines the `game` submodule (see sibling `*.rs` files).
 mod game;
 
@@ -16,6 +19,9 @@ mod instance;
 /// Defines the `idp` submodule (see sibling `*.rs` files).
 mod idp;
 
+/// Defines the `library` submodule (see sibling `*.rs` files).
+mod library;
+
 /// Defines the `submission` submodule (see sibling `*.rs` files).
 mod submission;
 
@@ -39,5 +45,7 @@ pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
         .nest("/challenges", challenge::router(state.clone()))
         .nest("/games", game::router(state.clone()))
         .nest("/idps", idp::router(state.clone()))
+        .nest("/libraries", library::router(state.clone()))
         .nest("/configs", config::router(state.clone()))
+        .nest("/engine", engine::router(state.clone()))
 }
@@ -102,11 +102,16 @@ pub struct CreateDebugSubmissionRequest {
 #[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
 pub struct CreateDebugSubmissionResponse {
     pub status: Status,
+    pub feedback: Option<String>,
+    pub flag_id: Option<String>,
+    pub credit: Option<i32>,
+    pub trace: cds_checker::Trace,
 }
 
 /// Debug-submits a flag for immediate feedback without recording a submission.
 ///
-/// Runs the checker script synchronously and returns the result directly.
+/// Runs the checker script synchronously and returns the result directly,
+/// together with the script's execution trace.
 /// Does **not** create a submission record, affect counts, or trigger scoring.
 /// Intended for admin challenge preview use.
 #[utoipa::path(
@@ -135,12 +140,12 @@ pub async fn create_debug_submission(
     let challenge = crate::util::loader::prepare_challenge(&s.db.conn, body.challenge_id).await?;
 
     // Run the Lua checker synchronously (no queue, no DB record).
-    let result = s
+    let (result, trace) = s
         .checker
-        .check(&challenge, operator.id, &body.content)
+        .check_traced(&challenge, operator.id, &body.content)
         .await;
 
-    let status = match result {
+    let status = match result.as_ref().map(|judgement| &judgement.status) {
         Ok(cds_checker::Status::Correct) => Status::Correct,
         Ok(cds_checker::Status::Cheat(peer_id)) => {
             warn!(
@@ -152,6 +157,7 @@ pub async fn create_debug_submission(
         }
         _ => Status::Incorrect,
     };
+    let judgement = result.ok();
 
     info!(
         user_id = operator.id,
@@ -160,5 +166,15 @@ pub async fn create_debug_submission(
         "debug submit result"
     );
 
-    Ok(Json(CreateDebugSubmissionResponse { status }))
+    Ok(Json(CreateDebugSubmissionResponse {
+        status,
+        feedback: judgement
+            .as_ref()
+            .and_then(|judgement| judgement.feedback.clone()),
+        flag_id: judgement
+            .as_ref()
+            .and_then(|judgement| judgement.flag_id.clone()),
+        credit: judgement.as_ref().map(|judgement| judgement.credit),
+        trace,
+    }))
 }
@@ -4,7 +4,7 @@ use std::sync::Arc;
 
 use axum::{Json, Router, extract::State};
 use cds_db::{
-    SubmissionView,
+    CheckerLogView, SubmissionView,
     sea_orm::{
         AccessMode,
         ActiveValue::{self, NotSet, Set, Unchanged},
@@ -30,10 +30,52 @@ use crate::{
 
 pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
     OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(get_submission).with_state(state.clone()))
         .routes(routes!(update_submission_status).with_state(state.clone()))
         .routes(routes!(delete_submission).with_state(state.clone()))
 }
 
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct AdminSubmissionDetail {
+    #[serde(flatten)]
+    pub submission: SubmissionView,
+    /// Checker runs for this submission, newest first. Older runs of the
+    /// challenge are pruned, so this can be empty.
+    pub checker_logs: Vec<CheckerLogView>,
+}
+
+/// Returns a submission with its recorded checker runs.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "admin-submission",
+    params(
+        ("submission_id" = i64, Path, description = "Submission id"),
+    ),
+    responses(
+        (status = 200, description = "Submission detail", body = AdminSubmissionDetail),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_submission"))]
+pub async fn get_submission(
+    State(s): State<Arc<AppState>>,
+
+    Path(submission_id): Path<i64>,
+) -> Result<Json<AdminSubmissionDetail>, WebError> {
+    let submission = cds_db::submission::find_by_id(&s.db.conn, submission_id)
+        .await?
+        .ok_or_else(|| WebError::NotFound(json!("")))?;
+    let checker_logs =
+        cds_db::checker_log::find_by_submission_id(&s.db.conn, submission_id).await?;
+
+    Ok(Json(AdminSubmissionDetail {
+        submission,
+        checker_logs,
+    }))
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct UpdateSubmissionStatusRequest {
     pub status: cds_db::submission::Status,
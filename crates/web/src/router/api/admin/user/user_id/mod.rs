@@ -4,6 +4,9 @@
 /// Defines the `email` submodule (see sibling `*.rs` files).
 mod email;
 
+/// Defines the `role` submodule (see sibling `*.rs` files).
+mod role;
+
 use std::sync::Arc;
 
 use argon2::{
@@ -29,6 +32,7 @@ use crate::{
     extract::{Path, VJson},
     router::api::user::UserResponse,
     traits::{AppState, EmptyJson, WebError},
+    util,
 };
 
 /// Builds the Axum router fragment for this module.
@@ -38,7 +42,9 @@ pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
         .routes(routes!(get_user).with_state(state.clone()))
         .routes(routes!(update_user).with_state(state.clone()))
         .routes(routes!(delete_user).with_state(state.clone()))
+        .routes(routes!(revoke_user_sessions).with_state(state.clone()))
         .nest("/emails", email::router(state.clone()))
+        .nest("/roles", role::router(state.clone()))
 }
 
 /// Returns user.
@@ -72,7 +78,8 @@ pub struct UpdateUserRequest {
     pub description: Option<String>,
 }
 
-/// Updates user.
+/// Updates user. Banning the user or resetting the password signs out all of
+/// the user's sessions.
 #[utoipa::path(
     put,
     path = "/",
@@ -94,6 +101,8 @@ pub async fn update_user(
 ) -> Result<Json<UserResponse>, WebError> {
     let user = crate::util::loader::prepare_user(&s.db.conn, user_id).await?;
 
+    let revoke_sessions = body.password.is_some() || body.group == Some(Group::Banned);
+
     if let Some(password) = body.password {
         let hashed_password = Argon2::default()
             .hash_password(password.as_bytes(), &SaltString::generate(&mut OsRng))
@@ -115,6 +124,10 @@ pub async fn update_user(
     )
     .await?;
 
+    if revoke_sessions {
+        util::session::revoke_all(&s, user_id, None).await?;
+    }
+
     Ok(Json(UserResponse { user }))
 }
 
@@ -139,5 +152,36 @@ pub async fn delete_user(
     let transaction = s.db.conn.begin().await.map_err(cds_db::DbError::from)?;
     cds_db::user::delete(&transaction, user_id).await?;
     transaction.commit().await.map_err(cds_db::DbError::from)?;
+    util::session::revoke_all(&s, user_id, None).await?;
     Ok(Json(EmptyJson::default()))
 }
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct AdminRevokeSessionsResponse {
+    pub revoked: u64,
+}
+
+/// Signs the user out of every session.
+#[utoipa::path(
+    delete,
+    path = "/sessions",
+    tag = "admin-user",
+    params(
+        ("user_id" = i64, Path, description = "User id"),
+    ),
+    responses(
+        (status = 200, description = "Revoked sessions", body = AdminRevokeSessionsResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "revoke_user_sessions"))]
+pub async fn revoke_user_sessions(
+    State(s): State<Arc<AppState>>,
+    Path(user_id): Path<i64>,
+) -> Result<Json<AdminRevokeSessionsResponse>, WebError> {
+    let user = crate::util::loader::prepare_user(&s.db.conn, user_id).await?;
+    let revoked = util::session::revoke_all(&s, user.id, None).await?;
+
+    Ok(Json(AdminRevokeSessionsResponse { revoked }))
+}
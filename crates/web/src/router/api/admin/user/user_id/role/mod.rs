@@ -0,0 +1,166 @@
+//! HTTP routing for `role` — scoped role assignments of a user.
+
+use std::sync::Arc;
+
+use axum::{Json, Router, extract::State};
+use cds_db::{
+    RoleAssignmentView,
+    role_assignment::{ActiveModel, Role},
+    sea_orm::{Set, SqlErr},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+use crate::{
+    extract::{Json as ReqJson, Path},
+    traits::{AppState, EmptyJson, WebError},
+};
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(get_roles).with_state(state.clone()))
+        .routes(routes!(assign_role).with_state(state.clone()))
+        .routes(routes!(unassign_role).with_state(state.clone()))
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct AdminRolesListResponse {
+    pub roles: Vec<RoleAssignmentView>,
+    pub total: u64,
+}
+
+/// Returns the role assignments of a user.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "admin-user",
+    params(
+        ("user_id" = i64, Path, description = "User id"),
+    ),
+    responses(
+        (status = 200, description = "Role assignments", body = AdminRolesListResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_roles"))]
+pub async fn get_roles(
+    State(s): State<Arc<AppState>>,
+    Path(user_id): Path<i64>,
+) -> Result<Json<AdminRolesListResponse>, WebError> {
+    let roles = cds_db::role_assignment::find_by_user_id(&s.db.conn, user_id).await?;
+    let total = roles.len() as u64;
+
+    Ok(Json(AdminRolesListResponse { roles, total }))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AssignRoleRequest {
+    pub role: Role,
+    /// Required for [`Role::GameOperator`], optional for [`Role::Observer`].
+    pub game_id: Option<i64>,
+    /// Required for [`Role::ChallengeAuthor`].
+    pub challenge_id: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct AdminRoleResponse {
+    pub role: RoleAssignmentView,
+}
+
+/// Grants a scoped role to a user.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "admin-user",
+    params(
+        ("user_id" = i64, Path, description = "User id"),
+    ),
+    request_body = AssignRoleRequest,
+    responses(
+        (status = 200, description = "Assigned role", body = AdminRoleResponse),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 409, description = "Conflict", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "assign_role"))]
+pub async fn assign_role(
+    State(s): State<Arc<AppState>>,
+    Path(user_id): Path<i64>,
+    ReqJson(body): ReqJson<AssignRoleRequest>,
+) -> Result<Json<AdminRoleResponse>, WebError> {
+    let user = crate::util::loader::prepare_user(&s.db.conn, user_id).await?;
+
+    let scoped = RoleAssignmentView {
+        id: 0,
+        user_id: user.id,
+        role: body.role,
+        game_id: body.game_id,
+        challenge_id: body.challenge_id,
+        created_at: 0,
+        updated_at: 0,
+    };
+    if !scoped.is_well_scoped() {
+        return Err(WebError::BadRequest(json!("role_scope_invalid")));
+    }
+
+    if let Some(game_id) = body.game_id {
+        let _ = crate::util::loader::prepare_game(&s.db.conn, game_id).await?;
+    }
+    if let Some(challenge_id) = body.challenge_id {
+        let _ = crate::util::loader::prepare_challenge(&s.db.conn, challenge_id).await?;
+    }
+
+    let role = cds_db::role_assignment::create(
+        &s.db.conn,
+        ActiveModel {
+            user_id: Set(user.id),
+            role: Set(body.role),
+            game_id: Set(body.game_id),
+            challenge_id: Set(body.challenge_id),
+            ..Default::default()
+        },
+    )
+    .await
+    .map_err(|error| match &error {
+        cds_db::DbError::SeaORM(err)
+            if matches!(err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) =>
+        {
+            WebError::Conflict(json!("role_already_assigned"))
+        }
+        _ => error.into(),
+    })?;
+
+    Ok(Json(AdminRoleResponse { role }))
+}
+
+/// Revokes a scoped role from a user.
+#[utoipa::path(
+    delete,
+    path = "/{role_assignment_id}",
+    tag = "admin-user",
+    params(
+        ("user_id" = i64, Path, description = "User id"),
+        ("role_assignment_id" = i64, Path, description = "Role assignment id"),
+    ),
+    responses(
+        (status = 200, description = "Unassigned", body = EmptyJson),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "unassign_role"))]
+pub async fn unassign_role(
+    State(s): State<Arc<AppState>>,
+    Path((user_id, role_assignment_id)): Path<(i64, i64)>,
+) -> Result<Json<EmptyJson>, WebError> {
+    cds_db::role_assignment::delete(&s.db.conn, user_id, role_assignment_id).await?;
+
+    Ok(Json(EmptyJson::default()))
+}
@@ -178,6 +178,9 @@ pub async fn query_challenge_status(
         );
     }
 
+    // Challenges with several flags hold one correct submission per flag, so
+    // solves and bloods count each team or standalone user once.
+    let mut solvers = HashSet::new();
     for submission in &submissions {
         if let Some(status_response) = result.get_mut(&submission.challenge_id) {
             if Some(submission.user_id) == body.user_id
@@ -188,6 +191,10 @@ pub async fn query_challenge_status(
                 status_response.solved = true;
             }
 
+            let solver = submission.team_id.ok_or(submission.user_id);
+            if !solvers.insert((submission.challenge_id, solver)) {
+                continue;
+            }
             status_response.solved_times += 1;
 
             if status_response.bloods.len() < 3 {
@@ -9,6 +9,9 @@ pub mod token;
 /// Defines the `user` submodule (see sibling `*.rs` files).
 mod user;
 
+/// Defines the `wireguard` submodule (see sibling `*.rs` files).
+mod wireguard;
+
 /// Defines the `writeup` submodule (see sibling `*.rs` files).
 mod writeup;
 
@@ -49,6 +52,7 @@ pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
         .nest("/avatar", avatar::router(state.clone()))
         .nest("/users", user::router(state.clone()))
         .nest("/token", token::router(state.clone()))
+        .nest("/wireguard", wireguard::router(state.clone()))
         .nest("/writeup", writeup::router(state.clone()))
 }
 
@@ -0,0 +1,173 @@
+//! HTTP routing for `wireguard` — the caller's team's WireGuard peer, when
+//! the WireGuard access mode is enabled.
+
+use std::sync::Arc;
+
+use axum::{Json, Router, extract::State};
+use cds_db::{WireguardPeerView, team::State as TState};
+use serde::Serialize;
+use serde_json::json;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+use crate::{
+    extract::{Extension, Path},
+    traits::{AppState, AuthPrincipal, EmptyJson, WebError},
+};
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(get_wireguard).with_state(state.clone()))
+        .routes(routes!(create_wireguard).with_state(state.clone()))
+        .routes(routes!(delete_wireguard).with_state(state.clone()))
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct WireguardPeer {
+    /// Tunnel address of the team's peer.
+    pub address: String,
+    pub public_key: String,
+    pub created_at: i64,
+    /// `wg-quick` config to import, carrying the peer's private key.
+    pub config: String,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct WireguardResponse {
+    pub peer: Option<WireguardPeer>,
+}
+
+fn ensure_enabled(s: &AppState) -> Result<(), WebError> {
+    if !s.env.cluster.wireguard.enabled {
+        return Err(WebError::BadRequest(json!("wireguard_disabled")));
+    }
+
+    Ok(())
+}
+
+async fn response(
+    s: &AppState,
+    peer: Option<WireguardPeerView>,
+) -> Result<WireguardResponse, WebError> {
+    let Some(peer) = peer else {
+        return Ok(WireguardResponse { peer: None });
+    };
+    let config = s.cluster.wireguard_config(&peer).await?;
+
+    Ok(WireguardResponse {
+        peer: Some(WireguardPeer {
+            address: peer.address,
+            public_key: peer.public_key,
+            created_at: peer.created_at,
+            config,
+        }),
+    })
+}
+
+/// Returns the team's WireGuard peer.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+    ),
+    responses(
+        (status = 200, description = "Current peer if any", body = WireguardResponse),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_wireguard"))]
+pub async fn get_wireguard(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+    Path(game_id): Path<i64>,
+) -> Result<Json<WireguardResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+    ensure_enabled(&s)?;
+    let team = crate::util::loader::prepare_self_team(&s.db.conn, game_id, operator.id).await?;
+
+    let peer = s.cluster.wireguard_peer(game_id, team.id).await?;
+
+    Ok(Json(response(&s, peer).await?))
+}
+
+/// Issues the team a WireGuard peer, replacing and revoking the one it had.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+    ),
+    responses(
+        (status = 200, description = "New peer", body = WireguardResponse),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 403, description = "Forbidden", body = crate::traits::ErrorResponse),
+        (status = 423, description = "Game paused", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "create_wireguard"))]
+pub async fn create_wireguard(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+    Path(game_id): Path<i64>,
+) -> Result<Json<WireguardResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+    ensure_enabled(&s)?;
+    let game = crate::util::loader::prepare_game(&s.db.conn, game_id).await?;
+    crate::util::loader::ensure_game_not_paused(&game)?;
+    crate::util::loader::ensure_game_ongoing(
+        &game,
+        time::OffsetDateTime::now_utc().unix_timestamp(),
+    )?;
+    let team = crate::util::loader::prepare_self_team(&s.db.conn, game_id, operator.id).await?;
+
+    if team.state != TState::Passed {
+        return Err(WebError::Forbidden(json!("team_not_passed")));
+    }
+
+    let peer = s.cluster.issue_wireguard_peer(game_id, team.id).await?;
+
+    Ok(Json(response(&s, Some(peer)).await?))
+}
+
+/// Revokes the team's WireGuard peer.
+#[utoipa::path(
+    delete,
+    path = "/",
+    tag = "game",
+    params(
+        ("game_id" = i64, Path, description = "Game id"),
+    ),
+    responses(
+        (status = 200, description = "Peer revoked", body = EmptyJson),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "delete_wireguard"))]
+pub async fn delete_wireguard(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+    Path(game_id): Path<i64>,
+) -> Result<Json<EmptyJson>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+    ensure_enabled(&s)?;
+    let team = crate::util::loader::prepare_self_team(&s.db.conn, game_id, operator.id).await?;
+
+    if !s.cluster.revoke_wireguard_peer(game_id, team.id).await? {
+        return Err(WebError::NotFound(json!("wireguard_peer_not_found")));
+    }
+
+    Ok(Json(EmptyJson::default()))
+}
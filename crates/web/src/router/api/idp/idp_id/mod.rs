@@ -8,7 +8,7 @@ use std::{collections::HashMap, sync::Arc};
 use axum::{
     Json, Router,
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
 };
 use cds_db::{
     EmailView, IdpSummary, IdpView, UserAccountView, UserIdpSource, UserIdpSummary, UserIdpView,
@@ -96,7 +96,7 @@ pub async fn bind(
 ) -> Result<(StatusCode, Json<IdpBindResponse>), WebError> {
     let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
     let idp = enabled_idp(&s, idp_id).await?;
-    cds_idp::Idp::preload(idp.id, &idp.script)
+    cds_idp::Idp::preload(idp.id, &idp.script, &idp.allowed_hosts)
         .await
         .map_err(|err| WebError::BadRequest(json!(err.to_string())))?;
 
@@ -192,11 +192,13 @@ pub struct IdpRegisterRequest {
 pub async fn login(
     State(s): State<Arc<AppState>>,
     session: Session,
+    headers: HeaderMap,
+    Extension(ext): Extension<AuthPrincipal>,
     Path(idp_id): Path<i64>,
     ReqJson(body): ReqJson<IdpAuthRequest>,
 ) -> Result<Json<IdpLoginResponse>, WebError> {
     let idp = enabled_idp(&s, idp_id).await?;
-    cds_idp::Idp::preload(idp.id, &idp.script)
+    cds_idp::Idp::preload(idp.id, &idp.script, &idp.allowed_hosts)
         .await
         .map_err(|err| WebError::BadRequest(json!(err.to_string())))?;
     let payload = cds_idp::Idp::login(idp.id, body.params).await?;
@@ -211,7 +213,7 @@ pub async fn login(
         let user = cds_db::user::find_by_id::<UserAccountView>(&s.db.conn, identity.user_id)
             .await?
             .ok_or(WebError::NotFound(json!("user_not_found")))?;
-        session.insert("user_id", user.id).await?;
+        util::session::sign_in(&session, user.id, &ext.client_ip, &headers).await?;
         Span::current().record("username", user.username.as_str());
         info!(
             user_id = user.id,
@@ -271,6 +273,8 @@ pub async fn login(
 pub async fn register(
     State(s): State<Arc<AppState>>,
     session: Session,
+    headers: HeaderMap,
+    Extension(ext): Extension<AuthPrincipal>,
     Path(idp_id): Path<i64>,
     ReqJson(mut body): ReqJson<IdpRegisterRequest>,
 ) -> Result<(StatusCode, Json<crate::router::api::user::UserResponse>), WebError> {
@@ -351,7 +355,7 @@ pub async fn register(
         .map_err(cds_db::DbError::from)
         .map_err(registration_db_error)?;
 
-    session.insert("user_id", user.id).await?;
+    util::session::sign_in(&session, user.id, &ext.client_ip, &headers).await?;
     Span::current().record("username", user.username.as_str());
 
     info!(
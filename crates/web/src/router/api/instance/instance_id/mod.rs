@@ -8,6 +8,8 @@ use axum::{
     extract::{State, WebSocketUpgrade},
     response::IntoResponse,
 };
+use cds_cluster::{k8s_openapi::api::core::v1::Pod, reset, tcp::TcpAllocation};
+use cds_db::{InstanceView, instance::Checkpoint};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::debug;
@@ -17,7 +19,7 @@ use utoipa_axum::{
 };
 
 use crate::{
-    extract::{Extension, Path, Query},
+    extract::{Extension, Json as ReqJson, Path, Query},
     traits::{AppState, AuthPrincipal, EmptyJson, WebError},
 };
 
@@ -26,7 +28,13 @@ pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
     OpenApiRouter::from(Router::new().with_state(state.clone()))
         .routes(routes!(renew_instance).with_state(state.clone()))
         .routes(routes!(stop_instance).with_state(state.clone()))
+        .routes(routes!(reset_instance).with_state(state.clone()))
+        .routes(routes!(get_checkpoints).with_state(state.clone()))
+        .routes(routes!(save_checkpoint).with_state(state.clone()))
+        .routes(routes!(restore_checkpoint).with_state(state.clone()))
         .routes(routes!(wsrx).with_state(state.clone()))
+        .routes(routes!(get_tcp_allocations).with_state(state.clone()))
+        .routes(routes!(allocate_tcp_port).with_state(state.clone()))
 }
 
 /// Extends or refreshes a player instance from the API.
@@ -93,31 +101,23 @@ pub async fn renew_instance(
         crate::util::loader::ensure_game_not_paused(&game)?;
     }
 
-    // SAFETY: the creation_timestamp could be safely unwrapped.
-    let started_at = pod.metadata.creation_timestamp.unwrap().0.as_second();
+    let record = cds_db::instance::find_by_id::<cds_db::InstanceView>(&s.db.conn, &id)
+        .await?
+        .ok_or(WebError::NotFound(json!("instance_not_found")))?;
 
-    let annotations = pod.metadata.annotations.unwrap_or_default();
-
-    let renew = annotations
-        .get("cds/renew")
-        .map(|s| s.to_owned())
-        .unwrap_or_default()
-        .parse::<i64>()
-        .unwrap_or(3);
-    let duration = annotations
-        .get("cds/duration")
-        .map(|s| s.to_owned())
-        .unwrap_or_default()
-        .parse::<i64>()
-        .unwrap_or_default();
-
-    if renew == 3 {
+    if record.renew >= record.max_renewals {
         return Err(WebError::BadRequest(json!("no_more_renewal")));
     }
 
+    if record
+        .max_expires_at
+        .is_some_and(|max_expires_at| record.expires_at >= max_expires_at)
+    {
+        return Err(WebError::BadRequest(json!("max_lifetime_reached")));
+    }
+
     let now = time::OffsetDateTime::now_utc().unix_timestamp();
-    let next_start = started_at + (renew + 1) * duration;
-    if next_start - now > time::Duration::minutes(10).whole_seconds() {
+    if record.expires_at - now > time::Duration::minutes(10).whole_seconds() {
         return Err(WebError::BadRequest(json!("renewal_within_10_minutes")));
     }
 
@@ -177,7 +177,206 @@ pub async fn stop_instance(
         return Err(WebError::Forbidden(json!("")));
     }
 
-    s.cluster.delete_challenge_instance(&id).await?;
+    s.cluster
+        .delete_challenge_instance(&id, cds_db::instance::TerminationReason::Stopped)
+        .await?;
+
+    Ok(Json(EmptyJson::default()))
+}
+
+/// Loads the record of a running instance.
+async fn running_record(s: &AppState, id: &str) -> Result<InstanceView, WebError> {
+    cds_db::instance::find_by_id::<InstanceView>(&s.db.conn, id)
+        .await?
+        .filter(|record| record.state == cds_db::instance::State::Running)
+        .ok_or(WebError::NotFound(json!("instance_not_found")))
+}
+
+/// Recreates the instance's pod from the same spec, keeping its id and
+/// ports.
+#[utoipa::path(
+    post,
+    path = "/reset",
+    tag = "instance",
+    params(
+        ("instance_id" = String, Path, description = "Instance / pod identifier"),
+    ),
+    responses(
+        (status = 200, description = "Reset", body = EmptyJson),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 403, description = "Forbidden", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 423, description = "Game paused", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "reset_instance"))]
+pub async fn reset_instance(
+    State(s): State<Arc<AppState>>,
+
+    Extension(ext): Extension<AuthPrincipal>,
+    Path(instance_id): Path<String>,
+) -> Result<Json<EmptyJson>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    let pod = s.cluster.get_pod(&instance_id).await?;
+    ensure_instance_access(&s, operator.id, &pod).await?;
+
+    let record = running_record(&s, &instance_id).await?;
+    if record.resets >= record.max_resets {
+        return Err(WebError::BadRequest(json!("no_more_reset")));
+    }
+
+    s.cluster.reset_challenge_instance(&instance_id).await?;
+
+    Ok(Json(EmptyJson::default()))
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct CheckpointsResponse {
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+/// Lists the checkpoints saved in an instance.
+#[utoipa::path(
+    get,
+    path = "/checkpoints",
+    tag = "instance",
+    params(
+        ("instance_id" = String, Path, description = "Instance / pod identifier"),
+    ),
+    responses(
+        (status = 200, description = "Checkpoints", body = CheckpointsResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 403, description = "Forbidden", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_checkpoints"))]
+pub async fn get_checkpoints(
+    State(s): State<Arc<AppState>>,
+
+    Extension(ext): Extension<AuthPrincipal>,
+    Path(instance_id): Path<String>,
+) -> Result<Json<CheckpointsResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    let pod = s.cluster.get_pod(&instance_id).await?;
+    ensure_instance_access(&s, operator.id, &pod).await?;
+
+    Ok(Json(CheckpointsResponse {
+        checkpoints: running_record(&s, &instance_id).await?.checkpoints,
+    }))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SaveCheckpointRequest {
+    /// 1 to 32 lowercase letters, digits, `-` and `_`.
+    pub name: String,
+}
+
+/// Saves a named checkpoint in the instance's containers, replacing one of
+/// the same name.
+#[utoipa::path(
+    post,
+    path = "/checkpoints",
+    tag = "instance",
+    params(
+        ("instance_id" = String, Path, description = "Instance / pod identifier"),
+    ),
+    request_body = SaveCheckpointRequest,
+    responses(
+        (status = 200, description = "Checkpoints", body = CheckpointsResponse),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 403, description = "Forbidden", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 423, description = "Game paused", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "save_checkpoint"))]
+pub async fn save_checkpoint(
+    State(s): State<Arc<AppState>>,
+
+    Extension(ext): Extension<AuthPrincipal>,
+    Path(instance_id): Path<String>,
+    ReqJson(body): ReqJson<SaveCheckpointRequest>,
+) -> Result<Json<CheckpointsResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    if !reset::is_valid_checkpoint_name(&body.name) {
+        return Err(WebError::BadRequest(json!("checkpoint_name_invalid")));
+    }
+
+    let pod = s.cluster.get_pod(&instance_id).await?;
+    ensure_instance_access(&s, operator.id, &pod).await?;
+    if !reset::supports_checkpoints(&pod) {
+        return Err(WebError::BadRequest(json!("checkpoint_unsupported")));
+    }
+
+    let record = running_record(&s, &instance_id).await?;
+    if record.checkpoints.len() >= s.env.cluster.reset.max_checkpoints
+        && !record
+            .checkpoints
+            .iter()
+            .any(|checkpoint| checkpoint.name == body.name)
+    {
+        return Err(WebError::BadRequest(json!("too_many_checkpoints")));
+    }
+
+    let checkpoints = s.cluster.save_checkpoint(&instance_id, &body.name).await?;
+
+    Ok(Json(CheckpointsResponse { checkpoints }))
+}
+
+/// Restores a named checkpoint in the instance's containers; counts as a
+/// reset.
+#[utoipa::path(
+    post,
+    path = "/checkpoints/{name}/restore",
+    tag = "instance",
+    params(
+        ("instance_id" = String, Path, description = "Instance / pod identifier"),
+        ("name" = String, Path, description = "Checkpoint name"),
+    ),
+    responses(
+        (status = 200, description = "Restored", body = EmptyJson),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 403, description = "Forbidden", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 423, description = "Game paused", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "restore_checkpoint"))]
+pub async fn restore_checkpoint(
+    State(s): State<Arc<AppState>>,
+
+    Extension(ext): Extension<AuthPrincipal>,
+    Path((instance_id, name)): Path<(String, String)>,
+) -> Result<Json<EmptyJson>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    let pod = s.cluster.get_pod(&instance_id).await?;
+    ensure_instance_access(&s, operator.id, &pod).await?;
+
+    let record = running_record(&s, &instance_id).await?;
+    if !record
+        .checkpoints
+        .iter()
+        .any(|checkpoint| checkpoint.name == name)
+    {
+        return Err(WebError::NotFound(json!("checkpoint_not_found")));
+    }
+    if record.resets >= record.max_resets {
+        return Err(WebError::BadRequest(json!("no_more_reset")));
+    }
+
+    s.cluster.restore_checkpoint(&instance_id, &name).await?;
 
     Ok(Json(EmptyJson::default()))
 }
@@ -234,3 +433,133 @@ pub async fn wsrx(
         }
     }))
 }
+
+/// Checks that the operator owns the instance, directly or through its team,
+/// and that its game is not paused.
+async fn ensure_instance_access(s: &AppState, operator_id: i64, pod: &Pod) -> Result<(), WebError> {
+    let labels = pod.metadata.labels.clone().unwrap_or_default();
+    let label = |key: &str| {
+        labels
+            .get(key)
+            .and_then(|value| value.parse::<i64>().ok())
+            .unwrap_or_default()
+    };
+
+    if !(operator_id == label("cds/user_id")
+        || cds_db::team_user::contains_user(&s.db.conn, label("cds/team_id"), operator_id).await?)
+    {
+        return Err(WebError::Forbidden(json!("")));
+    }
+
+    let game_id = label("cds/game_id");
+    if game_id != 0 {
+        let game = crate::util::loader::prepare_game(&s.db.conn, game_id).await?;
+        crate::util::loader::ensure_game_not_paused(&game)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct TcpAllocationsResponse {
+    pub allocations: Vec<TcpAllocation>,
+}
+
+/// Lists the TCP proxy ports handed out for an instance.
+#[utoipa::path(
+    get,
+    path = "/tcp",
+    tag = "instance",
+    params(
+        ("instance_id" = String, Path, description = "Instance / pod identifier"),
+    ),
+    responses(
+        (status = 200, description = "TCP proxy ports", body = TcpAllocationsResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 403, description = "Forbidden", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_tcp_allocations"))]
+pub async fn get_tcp_allocations(
+    State(s): State<Arc<AppState>>,
+
+    Extension(ext): Extension<AuthPrincipal>,
+    Path(instance_id): Path<String>,
+) -> Result<Json<TcpAllocationsResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    let pod = s.cluster.get_pod(&instance_id).await?;
+    ensure_instance_access(&s, operator.id, &pod).await?;
+
+    Ok(Json(TcpAllocationsResponse {
+        allocations: s.cluster.tcp_allocations(&instance_id),
+    }))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AllocateTcpPortRequest {
+    /// Instance port to reach.
+    pub port: i32,
+}
+
+/// Hands out a TCP proxy port for one of the instance's ports.
+///
+/// Calling it again for the same port keeps the proxy port and renews its
+/// credentials: a fresh one-time token, or the caller's address.
+#[utoipa::path(
+    post,
+    path = "/tcp",
+    tag = "instance",
+    params(
+        ("instance_id" = String, Path, description = "Instance / pod identifier"),
+    ),
+    request_body = AllocateTcpPortRequest,
+    responses(
+        (status = 200, description = "TCP proxy port", body = TcpAllocation),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 403, description = "Forbidden", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 423, description = "Game paused", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "allocate_tcp_port"))]
+pub async fn allocate_tcp_port(
+    State(s): State<Arc<AppState>>,
+
+    Extension(ext): Extension<AuthPrincipal>,
+    Path(instance_id): Path<String>,
+    ReqJson(body): ReqJson<AllocateTcpPortRequest>,
+) -> Result<Json<TcpAllocation>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    if !s.env.cluster.tcp_proxy.enabled {
+        return Err(WebError::NotFound(json!("tcp_proxy_disabled")));
+    }
+
+    let pod = s.cluster.get_pod(&instance_id).await?;
+    ensure_instance_access(&s, operator.id, &pod).await?;
+
+    let ports = pod
+        .metadata
+        .annotations
+        .unwrap_or_default()
+        .get("cds/ports")
+        .and_then(|ports| serde_json::from_str::<Vec<cds_db::challenge::Port>>(ports).ok())
+        .unwrap_or_default();
+    let port = ports
+        .iter()
+        .find(|port| port.port == body.port && port.protocol.eq_ignore_ascii_case("tcp"))
+        .and_then(|port| u16::try_from(port.port).ok())
+        .ok_or_else(|| WebError::BadRequest(json!("port_not_found")))?;
+
+    let allocation = s
+        .cluster
+        .allocate_tcp_port(&instance_id, port, ext.client_ip.parse().ok())
+        .await?;
+
+    Ok(Json(allocation))
+}
@@ -1,5 +1,5 @@
 //! Public HTTP API under `/api`: nests feature routers and attaches admin auth
-//! middleware.
+//! middleware (Admin group or a matching scoped role).
 //!
 //! [`openapi_documented_under_api`] is the **single** OpenAPI-aware tree merged
 //! into the top-level [`crate::docs::ApiDoc`] in [`crate::router::router`].
@@ -61,8 +61,9 @@ pub fn openapi_documented_under_api(state: Arc<AppState>) -> OpenApiRouter<Arc<A
         .nest("/submissions", submission::router(state.clone()))
         .nest(
             "/admin",
-            admin::router(state.clone()).route_layer(axum::middleware::from_fn(
-                crate::middleware::auth::admin_only,
+            admin::router(state.clone()).route_layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                crate::middleware::auth::admin_or_role,
             )),
         )
 }
@@ -81,7 +81,7 @@ pub async fn list_submissions(
     Extension(ext): Extension<AuthPrincipal>,
     Query(params): Query<ListSubmissionsRequest>,
 ) -> Result<Json<ListSubmissionsResponse>, WebError> {
-    let _ = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
 
     let page = params.page.unwrap_or(1);
     let size = params.size.unwrap_or(10).min(100);
@@ -104,7 +104,13 @@ pub async fn list_submissions(
 
     let submissions = submissions
         .iter()
-        .map(SubmissionSummary::from)
+        .map(|submission| {
+            if submission.user_id == operator.id {
+                SubmissionSummary::for_submitter(submission)
+            } else {
+                SubmissionSummary::from(submission)
+            }
+        })
         .collect::<Vec<_>>();
     debug!(
         page,
@@ -12,6 +12,12 @@ mod idp;
 /// Defines the `note` submodule (see sibling `*.rs` files).
 mod note;
 
+/// Defines the `session` submodule (see sibling `*.rs` files).
+mod session;
+
+/// Defines the `token` submodule (see sibling `*.rs` files).
+mod token;
+
 use std::sync::Arc;
 
 use axum::{Json, Router, extract::State};
@@ -21,6 +27,7 @@ use cds_db::sea_orm::{
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use tower_sessions::Session;
 use utoipa_axum::{
     router::{OpenApiRouter, UtoipaMethodRouterExt},
     routes,
@@ -42,10 +49,13 @@ pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
         .routes(routes!(update_user_profile).with_state(state.clone()))
         .routes(routes!(delete_user_profile).with_state(state.clone()))
         .routes(routes!(update_user_profile_password).with_state(state.clone()))
+        .routes(routes!(get_user_roles).with_state(state.clone()))
         .nest("/emails", email::router(state.clone()))
         .nest("/idps", idp::router(state.clone()))
         .nest("/avatar", avatar::router(state.clone()))
         .nest("/notes", note::router(state.clone()))
+        .nest("/sessions", session::router(state.clone()))
+        .nest("/tokens", token::router(state.clone()))
 }
 
 /// Returns user profile.
@@ -158,6 +168,7 @@ pub async fn delete_user_profile(
     let transaction = s.db.conn.begin().await.map_err(cds_db::DbError::from)?;
     cds_db::user::delete(&transaction, operator.id).await?;
     transaction.commit().await.map_err(cds_db::DbError::from)?;
+    util::session::revoke_all(&s, operator.id, None).await?;
 
     Ok(Json(EmptyJson::default()))
 }
@@ -168,7 +179,7 @@ pub struct UpdateUserProfilePasswordRequest {
     pub new_password: String,
 }
 
-/// Updates user profile password.
+/// Updates user profile password and signs out the user's other sessions.
 #[utoipa::path(
     put,
     path = "/password",
@@ -184,6 +195,7 @@ pub struct UpdateUserProfilePasswordRequest {
 #[tracing::instrument(skip_all, fields(handler = "update_user_profile_password"))]
 pub async fn update_user_profile_password(
     State(s): State<Arc<AppState>>,
+    session: Session,
     Extension(ext): Extension<AuthPrincipal>,
     ReqJson(body): ReqJson<UpdateUserProfilePasswordRequest>,
 ) -> Result<Json<EmptyJson>, WebError> {
@@ -198,6 +210,37 @@ pub async fn update_user_profile_password(
     let hashed_password = util::crypto::hash_password(body.new_password);
 
     cds_db::user::update_password(&s.db.conn, operator.id, hashed_password).await?;
+    util::session::revoke_all(&s, operator.id, Some(&session)).await?;
 
     Ok(Json(EmptyJson::default()))
 }
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct UserRolesResponse {
+    pub roles: Vec<cds_db::RoleAssignmentView>,
+    pub total: u64,
+}
+
+/// Returns the scoped admin roles held by the current user.
+#[utoipa::path(
+    get,
+    path = "/roles",
+    tag = "user",
+    responses(
+        (status = 200, description = "Role assignments", body = UserRolesResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_user_roles"))]
+pub async fn get_user_roles(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+) -> Result<Json<UserRolesResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized("".into()))?;
+
+    let roles = cds_db::role_assignment::find_by_user_id(&s.db.conn, operator.id).await?;
+    let total = roles.len() as u64;
+
+    Ok(Json(UserRolesResponse { roles, total }))
+}
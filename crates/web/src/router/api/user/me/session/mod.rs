@@ -0,0 +1,199 @@
+//! HTTP routing for `session` — lists and revokes the signed-in user's
+//! sessions.
+
+use std::{cmp::Reverse, str::FromStr, sync::Arc};
+
+use axum::{Json, Router, extract::State};
+use cds_cache::session::RedisStore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tower_sessions::{
+    Session,
+    session::{Id, Record},
+};
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+use crate::{
+    extract::{Extension, Path},
+    traits::{AppState, AuthPrincipal, EmptyJson, WebError},
+    util,
+};
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(get_sessions).with_state(state.clone()))
+        .routes(routes!(revoke_other_sessions).with_state(state.clone()))
+        .routes(routes!(revoke_session).with_state(state.clone()))
+}
+
+/// One active sign-in of the current user.
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SessionView {
+    pub id: String,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub signed_in_at: Option<i64>,
+    pub last_seen_at: Option<i64>,
+    pub expires_at: i64,
+    /// Whether this is the session the request was made with.
+    pub current: bool,
+}
+
+impl SessionView {
+    fn from_record(record: &Record, current: Option<&Id>) -> Self {
+        let get = |key: &str| record.data.get(key).cloned().unwrap_or_default();
+
+        Self {
+            id: record.id.to_string(),
+            client_ip: serde_json::from_value(get("client_ip")).ok(),
+            user_agent: serde_json::from_value(get("user_agent")).ok().flatten(),
+            signed_in_at: serde_json::from_value(get("signed_in_at")).ok(),
+            last_seen_at: serde_json::from_value(get("last_seen_at")).ok(),
+            expires_at: record.expiry_date.unix_timestamp(),
+            current: Some(&record.id) == current,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct SessionsListResponse {
+    pub sessions: Vec<SessionView>,
+    pub total: u64,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct RevokeSessionsResponse {
+    pub revoked: u64,
+}
+
+/// Returns the active sessions of the current user, most recently used first.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "user",
+    responses(
+        (status = 200, description = "Active sessions", body = SessionsListResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_sessions"))]
+pub async fn get_sessions(
+    State(s): State<Arc<AppState>>,
+    session: Session,
+    Extension(ext): Extension<AuthPrincipal>,
+) -> Result<Json<SessionsListResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    let current = session.id();
+    let mut sessions = RedisStore::new(s.cache.clone())
+        .list_by_user(operator.id)
+        .await
+        .map_err(|err| WebError::OtherError(err.into()))?
+        .iter()
+        .map(|record| SessionView::from_record(record, current.as_ref()))
+        .collect::<Vec<_>>();
+    sessions.sort_by_key(|view| Reverse(view.last_seen_at));
+    let total = sessions.len() as u64;
+
+    Ok(Json(SessionsListResponse { sessions, total }))
+}
+
+/// Signs out every session of the current user except the calling one.
+#[utoipa::path(
+    delete,
+    path = "/",
+    tag = "user",
+    responses(
+        (status = 200, description = "Revoked sessions", body = RevokeSessionsResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "revoke_other_sessions"))]
+pub async fn revoke_other_sessions(
+    State(s): State<Arc<AppState>>,
+    session: Session,
+    Extension(ext): Extension<AuthPrincipal>,
+) -> Result<Json<RevokeSessionsResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    let revoked = util::session::revoke_all(&s, operator.id, Some(&session)).await?;
+
+    Ok(Json(RevokeSessionsResponse { revoked }))
+}
+
+/// Signs out one session of the current user.
+#[utoipa::path(
+    delete,
+    path = "/{session_id}",
+    tag = "user",
+    params(
+        ("session_id" = String, Path, description = "Session id"),
+    ),
+    responses(
+        (status = 200, description = "Revoked", body = EmptyJson),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "revoke_session"))]
+pub async fn revoke_session(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+    Path(session_id): Path<String>,
+) -> Result<Json<EmptyJson>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    let session_id =
+        Id::from_str(&session_id).map_err(|_| WebError::BadRequest(json!("session_id_invalid")))?;
+
+    if !RedisStore::new(s.cache.clone())
+        .revoke(operator.id, &session_id)
+        .await
+        .map_err(|err| WebError::OtherError(err.into()))?
+    {
+        return Err(WebError::NotFound(json!("session_not_found")));
+    }
+
+    Ok(Json(EmptyJson::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tower_sessions::session::{Id, Record};
+
+    use super::SessionView;
+
+    #[test]
+    fn view_reads_sign_in_metadata_and_marks_current() {
+        let record = Record {
+            id: Id::default(),
+            data: HashMap::from([
+                ("user_id".to_owned(), serde_json::json!(1)),
+                ("client_ip".to_owned(), serde_json::json!("10.0.0.1")),
+                ("user_agent".to_owned(), serde_json::Value::Null),
+                ("signed_in_at".to_owned(), serde_json::json!(100)),
+            ]),
+            expiry_date: time::OffsetDateTime::from_unix_timestamp(200).unwrap(),
+        };
+
+        let view = SessionView::from_record(&record, Some(&record.id));
+        assert!(view.current);
+        assert_eq!(view.client_ip.as_deref(), Some("10.0.0.1"));
+        assert_eq!(view.user_agent, None);
+        assert_eq!(view.signed_in_at, Some(100));
+        assert_eq!(view.last_seen_at, None);
+        assert_eq!(view.expires_at, 200);
+
+        assert!(!SessionView::from_record(&record, None).current);
+    }
+}
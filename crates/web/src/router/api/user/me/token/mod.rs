@@ -0,0 +1,173 @@
+//! HTTP routing for `token` — personal API tokens of the signed-in user.
+
+use std::sync::Arc;
+
+use axum::{Json, Router, extract::State};
+use cds_db::{
+    ApiTokenView,
+    api_token::{ActiveModel, Scope},
+    sea_orm::Set,
+    user::Group,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+use validator::Validate;
+
+use crate::{
+    extract::{Extension, Path, VJson},
+    traits::{AppState, AuthPrincipal, EmptyJson, WebError},
+    util,
+};
+
+/// Builds the Axum router fragment for this module.
+pub fn router(state: Arc<AppState>) -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::from(Router::new().with_state(state.clone()))
+        .routes(routes!(get_tokens).with_state(state.clone()))
+        .routes(routes!(create_token).with_state(state.clone()))
+        .routes(routes!(delete_token).with_state(state.clone()))
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct ApiTokensListResponse {
+    pub tokens: Vec<ApiTokenView>,
+    pub total: u64,
+}
+
+/// Returns the personal API tokens of the current user.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "user",
+    responses(
+        (status = 200, description = "API tokens", body = ApiTokensListResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "get_tokens"))]
+pub async fn get_tokens(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+) -> Result<Json<ApiTokensListResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    let tokens = cds_db::api_token::find_by_user_id(&s.db.conn, operator.id).await?;
+    let total = tokens.len() as u64;
+
+    Ok(Json(ApiTokensListResponse { tokens, total }))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Validate, utoipa::ToSchema)]
+pub struct CreateApiTokenRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    #[validate(length(min = 1))]
+    pub scopes: Vec<Scope>,
+    /// Unix timestamp after which the token is rejected; `None` never
+    /// expires.
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct CreateApiTokenResponse {
+    pub token: ApiTokenView,
+    /// Plaintext secret; only returned once, at creation.
+    pub secret: String,
+}
+
+/// Creates a personal API token and returns its secret once.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "user",
+    request_body = CreateApiTokenRequest,
+    responses(
+        (status = 200, description = "Created token", body = CreateApiTokenResponse),
+        (status = 400, description = "Bad request", body = crate::traits::ErrorResponse),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 403, description = "Forbidden", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "create_token"))]
+pub async fn create_token(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+    VJson(body): VJson<CreateApiTokenRequest>,
+) -> Result<Json<CreateApiTokenResponse>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    if body.scopes.contains(&Scope::Admin) && operator.group < Group::Admin {
+        return Err(WebError::Forbidden(json!("token_scope_forbidden")));
+    }
+
+    if body
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= time::OffsetDateTime::now_utc().unix_timestamp())
+    {
+        return Err(WebError::BadRequest(json!("token_expiry_in_past")));
+    }
+
+    let mut scopes = body
+        .scopes
+        .iter()
+        .map(|scope| scope.as_str().to_owned())
+        .collect::<Vec<_>>();
+    scopes.sort();
+    scopes.dedup();
+
+    let secret = util::crypto::generate_api_token();
+    let prefix = secret
+        .chars()
+        .skip(util::crypto::API_TOKEN_PREFIX.len())
+        .take(8)
+        .collect::<String>();
+
+    let token = cds_db::api_token::create(
+        &s.db.conn,
+        ActiveModel {
+            user_id: Set(operator.id),
+            name: Set(body.name),
+            prefix: Set(prefix),
+            token_hash: Set(util::crypto::hash_api_token(&secret)),
+            scopes: Set(scopes),
+            expires_at: Set(body.expires_at),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    Ok(Json(CreateApiTokenResponse { token, secret }))
+}
+
+/// Revokes one of the current user's API tokens.
+#[utoipa::path(
+    delete,
+    path = "/{token_id}",
+    tag = "user",
+    params(
+        ("token_id" = i64, Path, description = "API token id"),
+    ),
+    responses(
+        (status = 200, description = "Revoked", body = EmptyJson),
+        (status = 401, description = "Unauthorized", body = crate::traits::ErrorResponse),
+        (status = 404, description = "Not found", body = crate::traits::ErrorResponse),
+        (status = 500, description = "Server error", body = crate::traits::ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip_all, fields(handler = "delete_token"))]
+pub async fn delete_token(
+    State(s): State<Arc<AppState>>,
+    Extension(ext): Extension<AuthPrincipal>,
+    Path(token_id): Path<i64>,
+) -> Result<Json<EmptyJson>, WebError> {
+    let operator = ext.operator.ok_or(WebError::Unauthorized(json!("")))?;
+
+    cds_db::api_token::delete(&s.db.conn, operator.id, token_id).await?;
+
+    Ok(Json(EmptyJson::default()))
+}
@@ -11,7 +11,11 @@ mod user_id;
 
 use std::sync::Arc;
 
-use axum::{Json, Router, extract::State, http::StatusCode};
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
 use cds_db::{
     EmailView, UserAccountView, UserProfile,
     sea_orm::ActiveValue::Set,
@@ -78,13 +82,14 @@ pub struct UserLoginRequest {
 pub async fn user_login(
     State(s): State<Arc<AppState>>,
     session: Session,
+    headers: HeaderMap,
     Extension(ext): Extension<AuthPrincipal>,
     ReqJson(mut body): ReqJson<UserLoginRequest>,
 ) -> Result<Json<UserResponse>, WebError> {
     if !s
         .captcha
         .check(&cds_captcha::Answer {
-            client_ip: Some(ext.client_ip),
+            client_ip: Some(ext.client_ip.clone()),
             ..body.captcha.unwrap_or_default()
         })
         .await?
@@ -104,7 +109,7 @@ pub async fn user_login(
         return Err(WebError::BadRequest(json!("invalid")));
     }
 
-    session.insert("user_id", user.id).await?;
+    util::session::sign_in(&session, user.id, &ext.client_ip, &headers).await?;
     Span::current().record("username", user.username.as_str());
 
     info!(
@@ -236,7 +241,9 @@ pub async fn user_logout(
     Extension(ext): Extension<AuthPrincipal>,
 ) -> Result<Json<EmptyJson>, WebError> {
     let operator = ext.operator.ok_or(WebError::Unauthorized("".into()))?;
-    let _ = session.remove::<Option<i64>>("user_id").await?;
+    let _ = session
+        .remove::<Option<i64>>(cds_cache::session::USER_ID_KEY)
+        .await?;
     info!(
         user_id = operator.id,
         username = %operator.username,
@@ -129,5 +129,12 @@ pub async fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
         base = base.layer(from_fn(middleware::telemetry::track_metrics));
     }
 
-    base.merge(proxy::router(state))
+    let mut app = base.merge(proxy::router(state.clone()));
+
+    // Instance hosts bypass sessions, auth and rate limits entirely.
+    if state.env.cluster.traffic == cds_env::cluster::Traffic::Ingress {
+        app = app.layer(from_fn_with_state(state, middleware::ingress::dispatch));
+    }
+
+    app
 }
@@ -8,7 +8,7 @@ use axum::{
     http::{Response, StatusCode},
     response::IntoResponse,
 };
-use cds_db::UserAccountView;
+use cds_db::{ApiTokenView, UserAccountView};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{error, warn};
@@ -43,6 +43,9 @@ pub struct AppState {
 #[derive(Clone, Debug, Default)]
 pub struct AuthPrincipal {
     pub operator: Option<UserAccountView>,
+    /// Personal API token the operator authenticated with, if the request
+    /// carried `Authorization: Bearer` instead of a session cookie.
+    pub token: Option<ApiTokenView>,
     pub client_ip: String,
 }
 
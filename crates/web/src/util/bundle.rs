@@ -0,0 +1,799 @@
+//! Web utility — `bundle` (versioned game archives for moving a game between
+//! deployments).
+//!
+//! A bundle is one JSON document: a manifest of the game, its challenges and
+//! their game settings, optionally teams, submissions and notices, plus the
+//! media objects they reference (attachments, checker `.key`s, icons,
+//! posters, avatars and writeups) inlined as base64. Identifiers in a bundle
+//! are those of the source deployment; [`restore`] creates fresh rows and
+//! rewrites every reference, including media paths.
+
+use std::collections::HashMap;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use cds_db::{
+    ChallengeDetail, GameChallengeView, GameDetail, GameNoticeView, TeamView, UserAccountView,
+    challenge::{CheckerConfig, Instance},
+    game::Timeslot,
+    game_challenge::FindGameChallengeOptions,
+    sea_orm::{ActiveValue::Set, DatabaseConnection, TransactionTrait},
+    submission::{FindSubmissionsOptions, Status},
+    team::{FindTeamOptions, State as TState},
+};
+use cds_media::{Media, SaveIfAbsent, traits::MediaError};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::traits::WebError;
+
+/// Identifies the document type of a bundle.
+pub const FORMAT: &str = "cdsctf.game";
+
+/// Current bundle layout version. Bump on incompatible changes.
+pub const VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct GameBundle {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: i64,
+    pub game: BundleGame,
+    pub challenges: Vec<BundleChallenge>,
+    pub game_challenges: Vec<BundleGameChallenge>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notices: Option<Vec<BundleNotice>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub teams: Option<Vec<BundleTeam>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submissions: Option<Vec<BundleSubmission>>,
+    #[serde(default)]
+    pub media: Vec<BundleMedia>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BundleGame {
+    pub id: i64,
+    pub title: String,
+    pub sketch: Option<String>,
+    pub description: Option<String>,
+    pub public: bool,
+    pub writeup_required: bool,
+    pub member_limit_min: i64,
+    pub member_limit_max: i64,
+    pub timeslots: Vec<Timeslot>,
+    pub started_at: i64,
+    pub frozen_at: i64,
+    pub ended_at: i64,
+    pub icon_hash: Option<String>,
+    pub poster_hash: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BundleChallenge {
+    pub id: i64,
+    pub title: String,
+    pub description: String,
+    pub category: i32,
+    pub tags: Vec<String>,
+    pub has_instance: bool,
+    pub has_attachment: bool,
+    pub has_writeup: bool,
+    pub public: bool,
+    pub instance: Option<Instance>,
+    pub checker: Option<String>,
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    #[serde(default)]
+    pub checker_config: CheckerConfig,
+    pub writeup: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BundleGameChallenge {
+    pub challenge_id: i64,
+    pub difficulty: i64,
+    pub max_pts: i64,
+    pub min_pts: i64,
+    pub bonus_ratios: Vec<i64>,
+    pub enabled: bool,
+    pub frozen_at: Option<i64>,
+    pub pts: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BundleNotice {
+    pub title: String,
+    pub content: String,
+    pub created_at: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BundleTeam {
+    pub id: i64,
+    pub name: String,
+    pub email: Option<String>,
+    pub slogan: Option<String>,
+    pub avatar_hash: Option<String>,
+    pub has_writeup: bool,
+    pub division: Option<String>,
+    pub state: TState,
+    pub pts: i64,
+    pub rank: i64,
+    /// Usernames of the members; linked to existing users on import.
+    pub members: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BundleSubmission {
+    pub content: String,
+    pub status: Status,
+    pub challenge_id: i64,
+    pub username: String,
+    pub team_id: Option<i64>,
+    pub created_at: i64,
+    pub checked_at: Option<i64>,
+    pub pts: i64,
+    pub rank: i64,
+}
+
+/// One media object, addressed as in [`Media::get`].
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BundleMedia {
+    pub path: String,
+    pub filename: String,
+    /// Base64 (standard alphabet, padded) object bytes.
+    pub data: String,
+}
+
+/// Which optional sections [`collect`] includes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CollectOptions {
+    pub teams: bool,
+    pub submissions: bool,
+    pub notices: bool,
+}
+
+/// Outcome of [`restore`].
+#[derive(Clone, Debug, Default, Serialize, utoipa::ToSchema)]
+pub struct RestoreReport {
+    pub game_id: i64,
+    /// Source challenge id to new challenge id.
+    pub challenges: HashMap<i64, i64>,
+    /// Source team id to new team id.
+    pub teams: HashMap<i64, i64>,
+    pub notices: u64,
+    pub submissions: u64,
+    pub media: u64,
+    /// Usernames referenced by the bundle that do not exist here. Their
+    /// memberships and submissions are skipped.
+    pub missing_users: Vec<String>,
+}
+
+/// Builds a bundle for one game.
+pub async fn collect(
+    conn: &DatabaseConnection,
+    media: &Media,
+    game: GameDetail,
+    options: CollectOptions,
+) -> Result<GameBundle, WebError> {
+    let mut objects = Vec::new();
+
+    for hash in [&game.icon_hash, &game.poster_hash].into_iter().flatten() {
+        push_object(media, &mut objects, "media", hash).await?;
+    }
+
+    let (game_challenges, _) = cds_db::game_challenge::find::<GameChallengeView>(
+        conn,
+        FindGameChallengeOptions {
+            game_id: Some(game.id),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let mut challenges = Vec::with_capacity(game_challenges.len());
+    for game_challenge in &game_challenges {
+        let Some(challenge) =
+            cds_db::challenge::find_by_id::<ChallengeDetail>(conn, game_challenge.challenge_id)
+                .await?
+        else {
+            continue;
+        };
+
+        let path = format!("challenges/{}", challenge.id);
+        push_object(media, &mut objects, &path, ".key").await?;
+        let attachments = crate::util::media::build_challenge_attachment_path(challenge.id);
+        for (filename, _) in media.scan_dir(attachments.clone()).await? {
+            push_object(media, &mut objects, &attachments, &filename).await?;
+        }
+
+        challenges.push(BundleChallenge {
+            id: challenge.id,
+            title: challenge.title,
+            description: challenge.description,
+            category: challenge.category,
+            tags: challenge.tags,
+            has_instance: challenge.has_instance,
+            has_attachment: challenge.has_attachment,
+            has_writeup: challenge.has_writeup,
+            public: challenge.public,
+            instance: challenge.instance,
+            checker: challenge.checker,
+            allowed_hosts: challenge.allowed_hosts,
+            checker_config: challenge.checker_config,
+            writeup: challenge.writeup,
+        });
+    }
+
+    let game_challenges = game_challenges
+        .into_iter()
+        .filter(|gc| challenges.iter().any(|c| c.id == gc.challenge_id))
+        .map(|gc| BundleGameChallenge {
+            challenge_id: gc.challenge_id,
+            difficulty: gc.difficulty,
+            max_pts: gc.max_pts,
+            min_pts: gc.min_pts,
+            bonus_ratios: gc.bonus_ratios,
+            enabled: gc.enabled,
+            frozen_at: gc.frozen_at,
+            pts: gc.pts,
+        })
+        .collect();
+
+    let notices = if options.notices {
+        Some(
+            cds_db::game_notice::find_by_game_id::<GameNoticeView>(conn, game.id)
+                .await?
+                .into_iter()
+                .map(|notice| BundleNotice {
+                    title: notice.title,
+                    content: notice.content,
+                    created_at: notice.created_at,
+                })
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    let mut usernames = HashMap::new();
+    let teams = if options.teams || options.submissions {
+        let (teams, _) = cds_db::team::find::<TeamView>(
+            conn,
+            FindTeamOptions {
+                game_id: Some(game.id),
+                ..Default::default()
+            },
+        )
+        .await?;
+        let roster = cds_db::team_user::find_roster(conn, game.id).await?;
+
+        let mut bundled = Vec::with_capacity(teams.len());
+        for team in teams {
+            if let Some(hash) = &team.avatar_hash {
+                push_object(media, &mut objects, "media", hash).await?;
+            }
+            let writeup = format!("games/{}/teams/{}/writeup", game.id, team.id);
+            for (filename, _) in media.scan_dir(writeup.clone()).await? {
+                push_object(media, &mut objects, &writeup, &filename).await?;
+            }
+
+            let members = roster
+                .iter()
+                .filter(|entry| entry.team_id == team.id)
+                .map(|entry| {
+                    usernames.insert(entry.user_id, entry.username.clone());
+                    entry.username.clone()
+                })
+                .collect();
+            bundled.push(BundleTeam {
+                id: team.id,
+                name: team.name,
+                email: team.email,
+                slogan: team.slogan,
+                avatar_hash: team.avatar_hash,
+                has_writeup: team.has_writeup,
+                division: team.division,
+                state: team.state,
+                pts: team.pts,
+                rank: team.rank,
+                members,
+            });
+        }
+        Some(bundled)
+    } else {
+        None
+    };
+
+    let submissions = if options.submissions {
+        let (submissions, _) = cds_db::submission::find(
+            conn,
+            FindSubmissionsOptions {
+                game_id: Some(Some(game.id)),
+                sorts: Some("id".to_owned()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let mut bundled = Vec::with_capacity(submissions.len());
+        for submission in submissions {
+            let username = match usernames.get(&submission.user_id) {
+                Some(username) => username.clone(),
+                None => {
+                    let Some(user) =
+                        cds_db::user::find_by_id::<UserAccountView>(conn, submission.user_id)
+                            .await?
+                    else {
+                        continue;
+                    };
+                    usernames.insert(user.id, user.username.clone());
+                    user.username
+                }
+            };
+            bundled.push(BundleSubmission {
+                content: submission.content,
+                status: submission.status,
+                challenge_id: submission.challenge_id,
+                username,
+                team_id: submission.team_id,
+                created_at: submission.created_at,
+                checked_at: submission.checked_at,
+                pts: submission.pts,
+                rank: submission.rank,
+            });
+        }
+        Some(bundled)
+    } else {
+        None
+    };
+
+    Ok(GameBundle {
+        format: FORMAT.to_owned(),
+        version: VERSION,
+        exported_at: time::OffsetDateTime::now_utc().unix_timestamp(),
+        game: BundleGame {
+            id: game.id,
+            title: game.title,
+            sketch: game.sketch,
+            description: game.description,
+            public: game.public,
+            writeup_required: game.writeup_required,
+            member_limit_min: game.member_limit_min,
+            member_limit_max: game.member_limit_max,
+            timeslots: game.timeslots,
+            started_at: game.started_at,
+            frozen_at: game.frozen_at,
+            ended_at: game.ended_at,
+            icon_hash: game.icon_hash,
+            poster_hash: game.poster_hash,
+        },
+        challenges,
+        game_challenges,
+        notices,
+        teams: options.teams.then_some(teams).flatten(),
+        submissions,
+        media: objects,
+    })
+}
+
+/// Appends one media object, skipping objects that do not exist.
+async fn push_object(
+    media: &Media,
+    objects: &mut Vec<BundleMedia>,
+    path: &str,
+    filename: &str,
+) -> Result<(), WebError> {
+    match media.get(path.to_owned(), filename.to_owned()).await {
+        Ok(data) => {
+            objects.push(BundleMedia {
+                path: path.to_owned(),
+                filename: filename.to_owned(),
+                data: STANDARD.encode(data),
+            });
+            Ok(())
+        }
+        Err(MediaError::NotFound(_)) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Imports a bundle as a new, disabled game.
+///
+/// All rows are written in one transaction. Media objects are written before
+/// the commit and removed again if anything fails, so a rejected bundle
+/// leaves nothing behind but shared media, which is only ever added under
+/// its content hash and never replaced.
+pub async fn restore(
+    conn: &DatabaseConnection,
+    media: &Media,
+    bundle: GameBundle,
+) -> Result<RestoreReport, WebError> {
+    if bundle.format != FORMAT {
+        return Err(WebError::BadRequest(json!("bundle_format_invalid")));
+    }
+    if bundle.version == 0 || bundle.version > VERSION {
+        return Err(WebError::UnprocessableEntity(json!(
+            "bundle_version_unsupported"
+        )));
+    }
+
+    let mut report = RestoreReport::default();
+    let transaction = conn.begin().await.map_err(cds_db::DbError::from)?;
+
+    let source = bundle.game;
+    let game = cds_db::game::create::<GameDetail>(
+        &transaction,
+        cds_db::game::ActiveModel {
+            title: Set(source.title),
+            sketch: Set(source.sketch),
+            description: Set(source.description),
+            enabled: Set(false),
+            public: Set(source.public),
+            writeup_required: Set(source.writeup_required),
+            member_limit_min: Set(source.member_limit_min),
+            member_limit_max: Set(source.member_limit_max),
+            timeslots: Set(source.timeslots),
+            started_at: Set(source.started_at),
+            frozen_at: Set(source.frozen_at),
+            ended_at: Set(source.ended_at),
+            icon_hash: Set(source.icon_hash),
+            poster_hash: Set(source.poster_hash),
+            ..Default::default()
+        },
+    )
+    .await?;
+    report.game_id = game.id;
+
+    for challenge in bundle.challenges {
+        let created = cds_db::challenge::create::<ChallengeDetail>(
+            &transaction,
+            cds_db::challenge::ActiveModel {
+                title: Set(challenge.title),
+                description: Set(challenge.description),
+                category: Set(challenge.category),
+                tags: Set(challenge.tags),
+                has_instance: Set(challenge.has_instance),
+                has_attachment: Set(challenge.has_attachment),
+                has_writeup: Set(challenge.has_writeup),
+                public: Set(challenge.public),
+                instance: Set(challenge.instance),
+                checker: Set(challenge.checker),
+                allowed_hosts: Set(challenge.allowed_hosts),
+                checker_config: Set(challenge.checker_config),
+                writeup: Set(challenge.writeup),
+                ..Default::default()
+            },
+        )
+        .await?;
+        report.challenges.insert(challenge.id, created.id);
+    }
+
+    for game_challenge in bundle.game_challenges {
+        let challenge_id = *report
+            .challenges
+            .get(&game_challenge.challenge_id)
+            .ok_or_else(|| WebError::BadRequest(json!("bundle_challenge_missing")))?;
+        let _ = cds_db::game_challenge::create(
+            &transaction,
+            cds_db::game_challenge::ActiveModel {
+                game_id: Set(game.id),
+                challenge_id: Set(challenge_id),
+                difficulty: Set(game_challenge.difficulty),
+                max_pts: Set(game_challenge.max_pts),
+                min_pts: Set(game_challenge.min_pts),
+                bonus_ratios: Set(game_challenge.bonus_ratios),
+                enabled: Set(game_challenge.enabled),
+                frozen_at: Set(game_challenge.frozen_at),
+                pts: Set(game_challenge.pts),
+            },
+        )
+        .await?;
+    }
+
+    for notice in bundle.notices.unwrap_or_default() {
+        cds_db::game_notice::create::<GameNoticeView>(
+            &transaction,
+            cds_db::game_notice::ActiveModel {
+                game_id: Set(game.id),
+                title: Set(notice.title),
+                content: Set(notice.content),
+                created_at: Set(notice.created_at),
+                ..Default::default()
+            },
+        )
+        .await?;
+        report.notices += 1;
+    }
+
+    let mut users = HashMap::<String, Option<i64>>::new();
+    for team in bundle.teams.unwrap_or_default() {
+        let created = cds_db::team::create::<TeamView>(
+            &transaction,
+            cds_db::team::ActiveModel {
+                game_id: Set(game.id),
+                name: Set(team.name),
+                email: Set(team.email),
+                slogan: Set(team.slogan),
+                avatar_hash: Set(team.avatar_hash),
+                has_writeup: Set(team.has_writeup),
+                division: Set(team.division),
+                state: Set(team.state),
+                pts: Set(team.pts),
+                rank: Set(team.rank),
+                ..Default::default()
+            },
+        )
+        .await?;
+        report.teams.insert(team.id, created.id);
+
+        for username in team.members {
+            let Some(user_id) = resolve_user(&transaction, &mut users, &username).await? else {
+                continue;
+            };
+            let _ = cds_db::team_user::create::<cds_db::TeamUserView>(
+                &transaction,
+                cds_db::team_user::ActiveModel {
+                    team_id: Set(created.id),
+                    user_id: Set(user_id),
+                },
+            )
+            .await?;
+        }
+    }
+
+    for submission in bundle.submissions.unwrap_or_default() {
+        let Some(&challenge_id) = report.challenges.get(&submission.challenge_id) else {
+            continue;
+        };
+        let team_id = match submission.team_id {
+            Some(team_id) => match report.teams.get(&team_id) {
+                Some(&team_id) => Some(team_id),
+                None => continue,
+            },
+            None => None,
+        };
+        let Some(user_id) = resolve_user(&transaction, &mut users, &submission.username).await?
+        else {
+            continue;
+        };
+        let _ = cds_db::submission::create(
+            &transaction,
+            cds_db::submission::ActiveModel {
+                content: Set(submission.content),
+                status: Set(submission.status),
+                challenge_id: Set(challenge_id),
+                user_id: Set(user_id),
+                team_id: Set(team_id),
+                game_id: Set(Some(game.id)),
+                created_at: Set(submission.created_at),
+                checked_at: Set(submission.checked_at),
+                pts: Set(submission.pts),
+                rank: Set(submission.rank),
+                ..Default::default()
+            },
+        )
+        .await?;
+        report.submissions += 1;
+    }
+
+    report.missing_users = users
+        .into_iter()
+        .filter_map(|(username, id)| id.is_none().then_some(username))
+        .collect();
+    report.missing_users.sort();
+
+    let mut objects = Vec::with_capacity(bundle.media.len());
+    for object in bundle.media {
+        let Some(path) = remap_path(&object.path, source.id, &report) else {
+            continue;
+        };
+        let data = STANDARD
+            .decode(object.data.as_bytes())
+            .map_err(|_| WebError::BadRequest(json!("bundle_media_invalid")))?;
+        if !is_restorable(&path, &object.filename, &data) {
+            continue;
+        }
+        objects.push((path, object.filename, data));
+    }
+
+    // Challenges exported without a checker key get a fresh one, exactly as
+    // if they had been created through the admin API.
+    for challenge_id in report.challenges.values() {
+        let path = format!("challenges/{challenge_id}");
+        if !objects.iter().any(|(p, f, _)| *p == path && f == ".key") {
+            objects.push((path, ".key".to_owned(), generate_checker_key()?));
+        }
+    }
+
+    // Media under fresh ids is unreachable if the import fails, but shared
+    // `media/` objects may be in use as soon as they exist, so they are
+    // never rolled back.
+    let mut written: Vec<(String, String)> = Vec::with_capacity(objects.len());
+    for (path, filename, data) in objects {
+        let saved = if path == "media" {
+            media
+                .save_if_absent(path.clone(), filename.clone(), data)
+                .await
+                .map(|saved| saved == SaveIfAbsent::Created)
+        } else {
+            media
+                .save(path.clone(), filename.clone(), data)
+                .await
+                .map(|_| true)
+        };
+        match saved {
+            Ok(true) => written.push((path, filename)),
+            Ok(false) => {}
+            Err(err) => {
+                discard(media, written).await;
+                return Err(err.into());
+            }
+        }
+    }
+    report.media = written.len() as u64;
+
+    if let Err(err) = transaction.commit().await {
+        discard(media, written).await;
+        return Err(cds_db::DbError::from(err).into());
+    }
+
+    Ok(report)
+}
+
+/// Deletes the objects an import wrote, except shared `media/` ones.
+async fn discard(media: &Media, written: Vec<(String, String)>) {
+    for (path, filename) in written {
+        if path != "media" {
+            let _ = media.delete(path, filename).await;
+        }
+    }
+}
+
+/// Looks up a user id by username, memoizing misses.
+async fn resolve_user(
+    conn: &impl cds_db::sea_orm::ConnectionTrait,
+    users: &mut HashMap<String, Option<i64>>,
+    username: &str,
+) -> Result<Option<i64>, WebError> {
+    if let Some(user_id) = users.get(username) {
+        return Ok(*user_id);
+    }
+
+    let user_id = cds_db::user::find_by_account::<UserAccountView>(conn, username.to_owned())
+        .await?
+        .filter(|user| user.username == username)
+        .map(|user| user.id);
+    users.insert(username.to_owned(), user_id);
+
+    Ok(user_id)
+}
+
+fn generate_checker_key() -> Result<Vec<u8>, WebError> {
+    let mut key = [0_u8; 64];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| WebError::InternalServerError(json!("checker_key_generation_failed")))?;
+
+    Ok(hex::encode(key).into_bytes())
+}
+
+/// Rewrites a source media directory to this deployment's ids.
+///
+/// Only the layouts produced by [`collect`] are accepted; anything else maps
+/// to `None` and is dropped.
+fn remap_path(path: &str, source_game_id: i64, report: &RestoreReport) -> Option<String> {
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    let id = |segment: &str| segment.parse::<i64>().ok();
+
+    match segments.as_slice() {
+        ["media"] => Some("media".to_owned()),
+        ["challenges", challenge_id] => {
+            let challenge_id = report.challenges.get(&id(challenge_id)?)?;
+            Some(format!("challenges/{challenge_id}"))
+        }
+        ["challenges", challenge_id, "attachments"] => {
+            let challenge_id = report.challenges.get(&id(challenge_id)?)?;
+            Some(crate::util::media::build_challenge_attachment_path(
+                *challenge_id,
+            ))
+        }
+        ["games", game_id, "teams", team_id, "writeup"] if id(game_id)? == source_game_id => {
+            let team_id = report.teams.get(&id(team_id)?)?;
+            Some(format!(
+                "games/{}/teams/{}/writeup",
+                report.game_id, team_id
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Whether an object may be written under `path`. Shared media is
+/// addressed by content hash and referenced by other games, so it is only
+/// taken under its own hash.
+fn is_restorable(path: &str, filename: &str, data: &[u8]) -> bool {
+    if filename.is_empty() || filename.contains(['/', '\\']) {
+        return false;
+    }
+    path != "media" || cds_media::util::hash(data.to_vec()) == filename
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> RestoreReport {
+        RestoreReport {
+            game_id: 50,
+            challenges: HashMap::from([(1, 101), (2, 102)]),
+            teams: HashMap::from([(7, 207)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn media_paths_follow_new_ids() {
+        let report = report();
+
+        assert_eq!(remap_path("media", 5, &report).as_deref(), Some("media"));
+        assert_eq!(
+            remap_path("challenges/1", 5, &report).as_deref(),
+            Some("challenges/101")
+        );
+        assert_eq!(
+            remap_path("challenges/2/attachments", 5, &report).as_deref(),
+            Some("challenges/102/attachments")
+        );
+        assert_eq!(
+            remap_path("games/5/teams/7/writeup", 5, &report).as_deref(),
+            Some("games/50/teams/207/writeup")
+        );
+    }
+
+    #[test]
+    fn unknown_or_foreign_media_paths_are_dropped() {
+        let report = report();
+
+        assert_eq!(remap_path("challenges/3", 5, &report), None);
+        assert_eq!(remap_path("games/6/teams/7/writeup", 5, &report), None);
+        assert_eq!(remap_path("configs/emails", 5, &report), None);
+        assert_eq!(remap_path("challenges/../media", 5, &report), None);
+    }
+
+    #[test]
+    fn shared_media_must_be_named_by_its_hash() {
+        let hash = cds_media::util::hash(b"icon".to_vec());
+        assert!(is_restorable("media", &hash, b"icon"));
+        assert!(!is_restorable("media", &hash, b"other"));
+        assert!(!is_restorable("media", "logo.webp", b"icon"));
+        assert!(is_restorable("challenges/1", "logo.webp", b"icon"));
+        assert!(!is_restorable("challenges/1", "../logo.webp", b"icon"));
+        assert!(!is_restorable("challenges/1", "", b"icon"));
+    }
+
+    #[test]
+    fn optional_sections_default_when_absent() {
+        let bundle: GameBundle = serde_json::from_value(json!({
+            "format": FORMAT,
+            "version": VERSION,
+            "exported_at": 0,
+            "game": {
+                "id": 1, "title": "t", "sketch": null, "description": null,
+                "public": true, "writeup_required": false,
+                "member_limit_min": 1, "member_limit_max": 3, "timeslots": [],
+                "started_at": 0, "frozen_at": 0, "ended_at": 0,
+                "icon_hash": null, "poster_hash": null
+            },
+            "challenges": [],
+            "game_challenges": []
+        }))
+        .unwrap();
+
+        assert!(bundle.teams.is_none());
+        assert!(bundle.submissions.is_none());
+        assert!(bundle.notices.is_none());
+        assert!(bundle.media.is_empty());
+    }
+}
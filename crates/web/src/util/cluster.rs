@@ -2,8 +2,11 @@
 
 use cds_cluster::{k8s_openapi::api::core::v1::Pod, traits::Nat};
 use cds_db::challenge::Port;
-use cds_env::Env;
+use cds_env::{Env, cluster::Traffic};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{middleware::ingress::TOKEN_QUERY, traits::WebError};
 
 #[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct Instance {
@@ -15,20 +18,50 @@ pub struct Instance {
 
     pub ports: Vec<Port>,
     pub public_entry: Option<String>,
+    /// Browser link to the instance under `ingress` traffic, carrying its
+    /// access token when it has one.
+    pub public_url: Option<String>,
+    /// Whether raw TCP ports can be requested for this instance.
+    pub tcp_proxy: bool,
     pub nats: Vec<Nat>,
 
     pub status: String,
     pub reason: String,
 
     pub renew: i64,
+    pub max_renewals: i64,
     pub duration: i64,
     pub started_at: i64,
+    /// When the instance stops unless renewed; it may stop earlier when
+    /// idle.
+    pub expires_at: i64,
+    /// Resets and checkpoint restores done so far.
+    pub resets: i64,
+    pub max_resets: i64,
+    /// Whether checkpoints can be saved in this instance.
+    pub checkpoint: bool,
+
+    #[serde(skip)]
+    access_token: Option<String>,
 }
 
 impl Instance {
     /// Applies checker-generated environment variables to a pod spec.
     pub fn with_env(mut self, env: &Env) -> Self {
         self.public_entry = Some(env.cluster.public_entry.clone());
+        self.tcp_proxy = env.cluster.tcp_proxy.enabled;
+        if env.cluster.traffic == Traffic::Ingress {
+            let scheme = if env.cluster.ingress.tls {
+                "https"
+            } else {
+                "http"
+            };
+            let mut url = format!("{scheme}://{}.{}/", self.id, env.cluster.public_entry);
+            if let Some(token) = &self.access_token {
+                url.push_str(&format!("?{TOKEN_QUERY}={token}"));
+            }
+            self.public_url = Some(url);
+        }
         self
     }
 }
@@ -36,6 +69,7 @@ impl Instance {
 impl From<Pod> for Instance {
     /// Converts from the input into `Self`.
     fn from(pod: Pod) -> Self {
+        let checkpoint = cds_cluster::reset::supports_checkpoints(&pod);
         let labels = pod.metadata.labels.unwrap_or_default();
 
         let id = labels
@@ -95,6 +129,7 @@ impl From<Pod> for Instance {
             .to_owned()
             .parse::<i64>()
             .unwrap_or(0);
+        let access_token = annotations.get("cds/access_token").cloned();
         let renew = annotations
             .get("cds/renew")
             .map(|s| s.to_owned())
@@ -102,6 +137,26 @@ impl From<Pod> for Instance {
             .to_owned()
             .parse::<i64>()
             .unwrap_or(0);
+        let max_renewals = annotations
+            .get("cds/max_renewals")
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(3);
+        let max_lifetime = annotations
+            .get("cds/max_lifetime")
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(0);
+
+        let resets = annotations
+            .get("cds/resets")
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(0);
+        let max_resets = annotations
+            .get("cds/max_resets")
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(0);
+        let started_at = annotations
+            .get(cds_cluster::reset::STARTED_AT_ANNOTATION)
+            .and_then(|s| s.parse::<i64>().ok());
 
         let mut status = "".to_owned();
         let mut reason = "".to_owned();
@@ -130,8 +185,14 @@ impl From<Pod> for Instance {
                 }
             });
 
+        // A reset pod keeps the start of the instance it replaced.
         // SAFETY: the creation_timestamp could be safely unwrapped.
-        let started_at = pod.metadata.creation_timestamp.unwrap().0.as_second();
+        let started_at =
+            started_at.unwrap_or_else(|| pod.metadata.creation_timestamp.unwrap().0.as_second());
+        let mut expires_at = started_at + (renew + 1) * duration;
+        if max_lifetime > 0 {
+            expires_at = expires_at.min(started_at + max_lifetime);
+        }
 
         Instance {
             id,
@@ -144,9 +205,65 @@ impl From<Pod> for Instance {
             status,
             reason,
             renew,
+            max_renewals,
             duration,
             started_at,
+            expires_at,
+            resets,
+            max_resets,
+            checkpoint,
             public_entry: None,
+            public_url: None,
+            tcp_proxy: false,
+            access_token,
         }
     }
 }
+
+/// Rejects instance settings that are malformed, would loosen the
+/// platform's `cluster.security` policy, ask for a warm pool larger than
+/// `cluster.warmup.max_pool_size`, set lifetime or reset limits that make no
+/// sense or leave a checkpoint command empty.
+pub fn validate_instance(
+    env: &Env,
+    instance: &cds_db::challenge::Instance,
+) -> Result<(), WebError> {
+    cds_cluster::security::validate(&env.cluster.security, instance)
+        .map_err(|code| WebError::BadRequest(json!(code)))?;
+
+    if !(0..=env.cluster.warmup.max_pool_size).contains(&instance.warm_pool) {
+        return Err(WebError::BadRequest(json!("warm_pool_out_of_range")));
+    }
+
+    if instance.max_renewals.is_some_and(|max| max < 0) {
+        return Err(WebError::BadRequest(json!("max_renewals_invalid")));
+    }
+
+    if instance
+        .max_lifetime
+        .is_some_and(|max| max < 0 || (max > 0 && max < instance.duration))
+    {
+        return Err(WebError::BadRequest(json!(
+            "max_lifetime_shorter_than_duration"
+        )));
+    }
+
+    if instance.idle_timeout.is_some_and(|timeout| timeout < 0) {
+        return Err(WebError::BadRequest(json!("idle_timeout_invalid")));
+    }
+
+    if instance.max_resets.is_some_and(|max| max < 0) {
+        return Err(WebError::BadRequest(json!("max_resets_invalid")));
+    }
+
+    if instance.containers.iter().any(|container| {
+        container
+            .checkpoint
+            .as_ref()
+            .is_some_and(|checkpoint| checkpoint.save.is_empty() || checkpoint.restore.is_empty())
+    }) {
+        return Err(WebError::BadRequest(json!("checkpoint_command_required")));
+    }
+
+    Ok(())
+}
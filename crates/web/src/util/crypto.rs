@@ -4,6 +4,12 @@ use argon2::{
     PasswordHasher, PasswordVerifier,
     password_hash::{SaltString, rand_core::OsRng},
 };
+use nanoid::nanoid;
+use ring::digest::{SHA256, digest};
+
+/// Prefix of every personal API token secret, so leaked tokens are easy to
+/// recognise in logs and secret scanners.
+pub const API_TOKEN_PREFIX: &str = "cds_";
 
 /// Hashes a password with Argon2 for storage.
 pub fn hash_password(password: String) -> String {
@@ -24,3 +30,31 @@ pub fn verify_password(password: String, hash: String) -> bool {
         )
         .is_ok()
 }
+
+/// Generates a fresh personal API token secret (shown to the user once).
+pub fn generate_api_token() -> String {
+    format!("{API_TOKEN_PREFIX}{}", nanoid!(40))
+}
+
+/// Hex-encoded SHA-256 digest under which an API token secret is stored.
+pub fn hash_api_token(token: &str) -> String {
+    hex::encode(digest(&SHA256, token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{API_TOKEN_PREFIX, generate_api_token, hash_api_token};
+
+    #[test]
+    fn api_tokens_are_prefixed_and_hashed_deterministically() {
+        let token = generate_api_token();
+        assert!(token.starts_with(API_TOKEN_PREFIX));
+        assert_eq!(token.len(), API_TOKEN_PREFIX.len() + 40);
+        assert_eq!(hash_api_token(&token), hash_api_token(&token));
+        assert_eq!(hash_api_token(&token).len(), 64);
+        assert_ne!(
+            hash_api_token(&token),
+            hash_api_token(&generate_api_token())
+        );
+    }
+}
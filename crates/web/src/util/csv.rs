@@ -0,0 +1,113 @@
+//! Web utility — `csv` (minimal RFC 4180 reader and writer for admin
+//! import/export).
+
+/// Splits CSV text into records of fields.
+///
+/// Quoted fields may contain separators, doubled quotes and line breaks.
+/// Blank lines are skipped and `\r\n` is accepted as a record terminator.
+pub fn parse(input: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if !(record.len() == 1 && record[0].is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err("unterminated quoted field".to_owned());
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// Appends one record to `out`, quoting fields only when needed.
+pub fn write_record<I, S>(out: &mut String, fields: I)
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>, {
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let field = field.as_ref();
+        if field.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quoted_fields_and_skips_blank_lines() {
+        let records = parse("a,b\r\n\n\"x, y\",\"say \"\"hi\"\"\"\n\"multi\nline\",").unwrap();
+
+        assert_eq!(
+            records,
+            vec![
+                vec!["a".to_owned(), "b".to_owned()],
+                vec!["x, y".to_owned(), "say \"hi\"".to_owned()],
+                vec!["multi\nline".to_owned(), String::new()],
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        assert!(parse("\"open,field\n").is_err());
+    }
+
+    #[test]
+    fn written_records_parse_back() {
+        let mut out = String::new();
+        write_record(&mut out, ["plain", "comma,here", "quote\"here"]);
+        write_record(&mut out, ["", "line\nbreak", "x"]);
+
+        assert_eq!(
+            parse(&out).unwrap(),
+            vec![
+                vec!["plain", "comma,here", "quote\"here"],
+                vec!["", "line\nbreak", "x"],
+            ]
+        );
+    }
+}
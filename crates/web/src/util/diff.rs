@@ -0,0 +1,177 @@
+//! Web utility — `diff` (line diff between two script revisions).
+
+use cds_db::ScriptRevisionView;
+use serde::Serialize;
+
+/// Above this many line pairs the diff degrades to "replace everything"
+/// rather than filling a quadratic table.
+const MAX_CELLS: usize = 4_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    /// 1-based line number in the old text, absent for insertions.
+    pub old_line: Option<usize>,
+    /// 1-based line number in the new text, absent for deletions.
+    pub new_line: Option<usize>,
+    pub text: String,
+}
+
+/// Changes between two revisions of the same script.
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct RevisionDiff {
+    /// Revision compared against, absent when diffing the first revision.
+    pub from: Option<i32>,
+    pub to: i32,
+    pub from_allowed_hosts: Vec<String>,
+    pub to_allowed_hosts: Vec<String>,
+    pub lines: Vec<DiffLine>,
+}
+
+impl RevisionDiff {
+    pub fn between(from: Option<&ScriptRevisionView>, to: &ScriptRevisionView) -> Self {
+        Self {
+            from: from.map(|revision| revision.number),
+            to: to.number,
+            from_allowed_hosts: from
+                .map(|revision| revision.allowed_hosts.clone())
+                .unwrap_or_default(),
+            to_allowed_hosts: to.allowed_hosts.clone(),
+            lines: lines(
+                from.map_or("", |revision| revision.script.as_str()),
+                &to.script,
+            ),
+        }
+    }
+}
+
+/// Computes a longest-common-subsequence line diff from `old` to `new`.
+pub fn lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    // Common prefix and suffix are trimmed so typical small edits stay cheap.
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take_while(|(left, right)| left == right)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(left, right)| left == right)
+        .count();
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut result = (0..prefix).map(|i| equal(i, i, old[i])).collect::<Vec<_>>();
+
+    if a.len().saturating_mul(b.len()) > MAX_CELLS {
+        result.extend(a.iter().enumerate().map(|(i, text)| DiffLine {
+            kind: DiffKind::Delete,
+            old_line: Some(prefix + i + 1),
+            new_line: None,
+            text: (*text).to_owned(),
+        }));
+        result.extend(b.iter().enumerate().map(|(j, text)| DiffLine {
+            kind: DiffKind::Insert,
+            old_line: None,
+            new_line: Some(prefix + j + 1),
+            text: (*text).to_owned(),
+        }));
+    } else {
+        // table[i][j]: LCS length of a[i..] and b[j..].
+        let width = b.len() + 1;
+        let mut table = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                table[i * width + j] = if a[i] == b[j] {
+                    table[(i + 1) * width + j + 1] + 1
+                } else {
+                    table[(i + 1) * width + j].max(table[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                result.push(equal(prefix + i, prefix + j, a[i]));
+                i += 1;
+                j += 1;
+            } else if j < b.len()
+                && (i == a.len() || table[i * width + j + 1] >= table[(i + 1) * width + j])
+            {
+                result.push(DiffLine {
+                    kind: DiffKind::Insert,
+                    old_line: None,
+                    new_line: Some(prefix + j + 1),
+                    text: b[j].to_owned(),
+                });
+                j += 1;
+            } else {
+                result.push(DiffLine {
+                    kind: DiffKind::Delete,
+                    old_line: Some(prefix + i + 1),
+                    new_line: None,
+                    text: a[i].to_owned(),
+                });
+                i += 1;
+            }
+        }
+    }
+
+    let (old_tail, new_tail) = (old.len() - suffix, new.len() - suffix);
+    result.extend((0..suffix).map(|k| equal(old_tail + k, new_tail + k, old[old_tail + k])));
+    result
+}
+
+fn equal(old_index: usize, new_index: usize, text: &str) -> DiffLine {
+    DiffLine {
+        kind: DiffKind::Equal,
+        old_line: Some(old_index + 1),
+        new_line: Some(new_index + 1),
+        text: text.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiffKind, lines};
+
+    #[test]
+    fn reports_inserted_and_deleted_lines_with_numbers() {
+        let diff = lines("a\nb\nc\nd", "a\nc\nx\nd");
+        let summary = diff
+            .iter()
+            .map(|line| (line.kind, line.old_line, line.new_line, line.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (DiffKind::Equal, Some(1), Some(1), "a"),
+                (DiffKind::Delete, Some(2), None, "b"),
+                (DiffKind::Equal, Some(3), Some(2), "c"),
+                (DiffKind::Insert, None, Some(3), "x"),
+                (DiffKind::Equal, Some(4), Some(4), "d"),
+            ]
+        );
+        assert!(
+            lines("same\n", "same\n")
+                .iter()
+                .all(|line| line.kind == DiffKind::Equal)
+        );
+        assert_eq!(lines("", "new").len(), 1);
+    }
+}
@@ -1,7 +1,8 @@
 //! Web utility — `loader` (shared HTTP helpers).
 
 use cds_db::{
-    ChallengeDetail, GameChallengeView, GameDetail, UserAccountView,
+    ChallengeDetail, GameChallengeView, GameDetail, ScriptRevisionView, UserAccountView,
+    script_revision::Owner,
     sea_orm::DatabaseConnection,
     team::{FindTeamOptions, TeamView},
 };
@@ -27,6 +28,19 @@ pub fn ensure_game_ongoing(game: &GameDetail, now: i64) -> Result<(), WebError>
     Ok(())
 }
 
+/// Loads one saved revision of a checker or IdP script.
+pub async fn prepare_script_revision(
+    db: &DatabaseConnection,
+    owner: Owner,
+    number: i32,
+) -> Result<ScriptRevisionView, WebError> {
+    let revision = cds_db::script_revision::find_by_number(db, owner, number)
+        .await?
+        .ok_or(WebError::NotFound(json!("script_revision_not_found")))?;
+
+    Ok(revision)
+}
+
 /// Loads a challenge model for downstream handlers.
 pub async fn prepare_challenge(
     db: &DatabaseConnection,
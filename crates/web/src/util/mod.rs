@@ -1,11 +1,20 @@
 //! Utility module group for `web`.
 
+/// Defines the `bundle` submodule (see sibling `*.rs` files).
+pub mod bundle;
+
 /// Defines the `cluster` submodule (see sibling `*.rs` files).
 pub mod cluster;
 
 /// Defines the `crypto` submodule (see sibling `*.rs` files).
 pub mod crypto;
 
+/// Defines the `csv` submodule (see sibling `*.rs` files).
+pub mod csv;
+
+/// Defines the `diff` submodule (see sibling `*.rs` files).
+pub mod diff;
+
 /// Defines the `email` submodule (see sibling `*.rs` files).
 pub mod email;
 
@@ -17,3 +26,6 @@ pub mod media;
 
 /// Defines the `network` submodule (see sibling `*.rs` files).
 pub mod network;
+
+/// Defines the `session` submodule (see sibling `*.rs` files).
+pub mod session;
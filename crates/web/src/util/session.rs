@@ -0,0 +1,47 @@
+//! Web utility — `session` (sign-in metadata and per-user session revocation).
+
+use axum::http::{HeaderMap, header::USER_AGENT};
+use cds_cache::session::{RedisStore, USER_ID_KEY};
+use tower_sessions::Session;
+use tracing::info;
+
+use crate::traits::{AppState, WebError};
+
+/// Marks `session` as signed in for `user_id`, recording where the sign-in
+/// came from so it can be shown in the user's session list.
+pub async fn sign_in(
+    session: &Session,
+    user_id: i64,
+    client_ip: &str,
+    headers: &HeaderMap,
+) -> Result<(), WebError> {
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let user_agent = headers
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.chars().take(512).collect::<String>());
+
+    session.insert(USER_ID_KEY, user_id).await?;
+    session.insert("client_ip", client_ip).await?;
+    session.insert("user_agent", user_agent).await?;
+    session.insert("signed_in_at", now).await?;
+    session.insert("last_seen_at", now).await?;
+
+    Ok(())
+}
+
+/// Revokes every session of `user_id` except `keep`.
+pub async fn revoke_all(
+    s: &AppState,
+    user_id: i64,
+    keep: Option<&Session>,
+) -> Result<u64, WebError> {
+    let keep = keep.and_then(|session| session.id());
+    let revoked = RedisStore::new(s.cache.clone())
+        .revoke_all(user_id, keep.as_ref())
+        .await
+        .map_err(|err| WebError::OtherError(err.into()))?;
+    info!(user_id, revoked, "user sessions revoked");
+
+    Ok(revoked)
+}
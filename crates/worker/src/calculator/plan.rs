@@ -1,6 +1,6 @@
 //! Pure score planning from a narrow, immutable game snapshot.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use anyhow::{Result, bail};
 use cds_db::{
@@ -19,25 +19,28 @@ pub(super) struct ScorePlan {
 }
 
 /// Computes every persisted score from one database snapshot.
+///
+/// Each flag of a challenge is scored as an independent solve stream: its
+/// solve count drives the decay curve and its order drives rank and bonus.
+/// Submissions without a flag id form the challenge's single default stream.
+/// A submission's points are then scaled by its credit percent. The
+/// challenge's displayed value decays with the number of distinct solvers.
 pub(super) fn build(
     mut submissions: Vec<SubmissionScoreInput>,
     mut challenges: Vec<ChallengeScoreInput>,
     teams: Vec<TeamScoreInput>,
 ) -> Result<ScorePlan> {
-    submissions.sort_by_key(|submission| {
-        (
-            submission.challenge_id,
-            submission.created_at,
-            submission.id,
-        )
-    });
+    submissions.sort_by_key(|submission| (submission.created_at, submission.id));
     challenges.sort_by_key(|challenge| challenge.challenge_id);
 
-    let mut by_challenge: HashMap<i64, Vec<SubmissionScoreInput>> = HashMap::new();
+    let mut by_challenge: HashMap<i64, BTreeMap<Option<String>, Vec<SubmissionScoreInput>>> =
+        HashMap::new();
     for submission in submissions {
         by_challenge
             .entry(submission.challenge_id)
             .or_default()
+            .entry(submission.flag_id.clone())
+            .or_default()
             .push(submission);
     }
 
@@ -45,47 +48,55 @@ pub(super) fn build(
     let mut team_totals: HashMap<i64, (i64, Option<i64>)> = HashMap::new();
 
     for challenge in challenges {
-        let challenge_submissions = by_challenge
+        let flags = by_challenge
             .remove(&challenge.challenge_id)
             .unwrap_or_default();
-        let solve_count = challenge_submissions.len();
+        let solvers = flags
+            .values()
+            .flatten()
+            .map(|submission| submission.team_id.ok_or(submission.id))
+            .collect::<HashSet<_>>()
+            .len();
+
+        for flag_submissions in flags.into_values() {
+            let base_pts = math::curve(
+                challenge.max_pts,
+                challenge.min_pts,
+                challenge.difficulty,
+                flag_submissions.len() as i64,
+            );
+
+            for (index, submission) in flag_submissions.into_iter().enumerate() {
+                let bonus = challenge.bonus_ratios.get(index).copied().unwrap_or(0);
+                let pts = base_pts * (100 + bonus) / 100 * i64::from(submission.credit) / 100;
+                let rank = index as i64 + 1;
+
+                if submission.pts != pts || submission.rank != rank {
+                    plan.submissions.push(SubmissionScoreUpdate {
+                        id: submission.id,
+                        pts,
+                        rank,
+                    });
+                }
+
+                if let Some(team_id) = submission.team_id {
+                    let total = team_totals.entry(team_id).or_insert((0, None));
+                    total.0 += pts;
+                    total.1 = Some(total.1.map_or(submission.created_at, |last| {
+                        last.max(submission.created_at)
+                    }));
+                }
+            }
+        }
+
         let base_pts = math::curve(
             challenge.max_pts,
             challenge.min_pts,
             challenge.difficulty,
-            solve_count as i64,
+            solvers as i64,
         );
-
-        for (index, submission) in challenge_submissions.into_iter().enumerate() {
-            let bonus = challenge.bonus_ratios.get(index).copied().unwrap_or(0);
-            let pts = base_pts * (100 + bonus) / 100;
-            let rank = index as i64 + 1;
-
-            if submission.pts != pts || submission.rank != rank {
-                plan.submissions.push(SubmissionScoreUpdate {
-                    id: submission.id,
-                    pts,
-                    rank,
-                });
-            }
-
-            if let Some(team_id) = submission.team_id {
-                let total = team_totals.entry(team_id).or_insert((0, None));
-                total.0 += pts;
-                total.1 = Some(total.1.map_or(submission.created_at, |last| {
-                    last.max(submission.created_at)
-                }));
-            }
-        }
-
-        let challenge_pts = base_pts
-            * (100
-                + challenge
-                    .bonus_ratios
-                    .get(solve_count)
-                    .copied()
-                    .unwrap_or(0))
-            / 100;
+        let challenge_pts =
+            base_pts * (100 + challenge.bonus_ratios.get(solvers).copied().unwrap_or(0)) / 100;
         if challenge.pts != challenge_pts {
             plan.challenges.push(ChallengeScoreUpdate {
                 challenge_id: challenge.challenge_id,
@@ -151,12 +162,28 @@ mod tests {
             id,
             challenge_id,
             team_id: Some(team_id),
+            flag_id: None,
+            credit: 100,
             created_at,
             pts: 0,
             rank: 0,
         }
     }
 
+    fn flag_submission(
+        id: i64,
+        team_id: i64,
+        created_at: i64,
+        flag_id: &str,
+        credit: i32,
+    ) -> SubmissionScoreInput {
+        SubmissionScoreInput {
+            flag_id: Some(flag_id.to_owned()),
+            credit,
+            ..submission(id, 10, team_id, created_at)
+        }
+    }
+
     #[test]
     fn builds_submission_challenge_and_team_scores_in_one_pass() {
         let plan = build(
@@ -231,6 +258,8 @@ mod tests {
                 id: 1,
                 challenge_id: 10,
                 team_id: Some(1),
+                flag_id: None,
+                credit: 100,
                 created_at: 100,
                 pts: base * 110 / 100,
                 rank: 1,
@@ -277,6 +306,60 @@ mod tests {
         assert_eq!(plan.teams[1].rank, 2);
     }
 
+    #[test]
+    fn flags_are_ranked_independently_and_scaled_by_credit() {
+        let plan = build(
+            vec![
+                flag_submission(1, 1, 100, "a", 50),
+                flag_submission(2, 2, 200, "a", 50),
+                flag_submission(3, 2, 150, "b", 50),
+            ],
+            vec![challenge(10, 0)],
+            vec![
+                TeamScoreInput {
+                    id: 1,
+                    pts: 0,
+                    rank: 0,
+                },
+                TeamScoreInput {
+                    id: 2,
+                    pts: 0,
+                    rank: 0,
+                },
+            ],
+        )
+        .unwrap();
+
+        let two_solves = math::curve(1_000, 100, 10, 2);
+        let one_solve = math::curve(1_000, 100, 10, 1);
+        assert_eq!(
+            plan.submissions,
+            vec![
+                SubmissionScoreUpdate {
+                    id: 1,
+                    pts: two_solves * 110 / 100 * 50 / 100,
+                    rank: 1,
+                },
+                SubmissionScoreUpdate {
+                    id: 2,
+                    pts: two_solves * 105 / 100 * 50 / 100,
+                    rank: 2,
+                },
+                SubmissionScoreUpdate {
+                    id: 3,
+                    pts: one_solve * 110 / 100 * 50 / 100,
+                    rank: 1,
+                },
+            ]
+        );
+        assert_eq!(plan.challenges[0].pts, two_solves);
+        assert_eq!(plan.teams[0].id, 2);
+        assert_eq!(
+            plan.teams[0].pts,
+            two_solves * 105 / 100 * 50 / 100 + one_solve * 110 / 100 * 50 / 100
+        );
+    }
+
     #[test]
     fn rejects_submission_without_game_challenge_configuration() {
         let error = build(vec![submission(1, 99, 1, 100)], Vec::new(), Vec::new()).unwrap_err();
@@ -7,12 +7,15 @@
 //! solves. A valid in-game cheat additionally takes the same per-game advisory
 //! lock used by the calculator before changing team state and the score
 //! revision.
+//!
+//! [`refinalize`] applies the same rules to a submission re-queued by an admin
+//! re-judge job, judged as of the moment it was submitted.
 
 use anyhow::{Context as _, anyhow};
 use cds_db::{
-    DB, GameDetail, SubmissionView,
+    DB, GameDetail, RejudgeEntryView, SubmissionView,
     sea_orm::{AccessMode, ConnectionTrait, IsolationLevel, TransactionTrait},
-    submission::Status,
+    submission::{Judgement, Status},
     team::State,
 };
 
@@ -44,12 +47,14 @@ struct CheatPolicy {
     peer_team_id: i64,
 }
 
-/// Commits a checker verdict and its associated durable effects.
+/// Commits a checker verdict, the checker's feedback, credit and flag id, and
+/// the associated durable effects.
 pub(crate) async fn finalize(
     db: &DB,
     submission: &SubmissionView,
     processing_at: i64,
     verdict: Verdict,
+    judgement: Judgement,
 ) -> Result<FinalizeOutcome, anyhow::Error> {
     let transaction = db
         .conn
@@ -59,7 +64,14 @@ pub(crate) async fn finalize(
         )
         .await?;
 
-    let (status, cheat) = resolve_status(&transaction, submission, verdict).await?;
+    // Duplicate detection is per flag and credit, so judge the row as it will
+    // be stored.
+    let judged = SubmissionView {
+        flag_id: judgement.flag_id.clone(),
+        credit: judgement.credit,
+        ..submission.clone()
+    };
+    let (status, cheat) = resolve_status(&transaction, &judged, verdict, false).await?;
 
     // The calculator takes this lock before reading or writing any score
     // inputs. Taking it before checking the lease gives cheat policy and score
@@ -75,6 +87,8 @@ pub(crate) async fn finalize(
         submission.id,
         processing_at,
         status.clone(),
+        judgement,
+        time::OffsetDateTime::now_utc().unix_timestamp(),
     )
     .await?
     else {
@@ -102,13 +116,90 @@ pub(crate) async fn finalize(
     })
 }
 
+/// Commits the verdict of a submission re-queued by a re-judge job.
+///
+/// Unlike [`finalize`], game windows are evaluated at the submission's
+/// `created_at`, the highest-credit and then earliest correct submission in
+/// scope wins whichever is checked first, and the original `checked_at` is
+/// kept, so rankings are unaffected by when the re-judge ran. Score
+/// recalculation is requested once, by the entry that completes the job.
+pub(crate) async fn refinalize(
+    db: &DB,
+    submission: &SubmissionView,
+    processing_at: i64,
+    verdict: Verdict,
+    judgement: Judgement,
+    entry: &RejudgeEntryView,
+) -> Result<FinalizeOutcome, anyhow::Error> {
+    let transaction = db
+        .conn
+        .begin_with_config(
+            Some(IsolationLevel::ReadCommitted),
+            Some(AccessMode::ReadWrite),
+        )
+        .await?;
+
+    let judged = SubmissionView {
+        flag_id: judgement.flag_id.clone(),
+        credit: judgement.credit,
+        ..submission.clone()
+    };
+    let (status, cheat) = resolve_status(&transaction, &judged, verdict, true).await?;
+    if let Some(cheat) = cheat {
+        cds_db::game::lock_score_recalculation(&transaction, cheat.game_id).await?;
+    }
+
+    let (flag_id, credit) = (judgement.flag_id.clone(), judgement.credit);
+    let checked_at = entry
+        .previous_checked_at
+        .unwrap_or_else(|| time::OffsetDateTime::now_utc().unix_timestamp());
+    if cds_db::submission::finish_processing(
+        &transaction,
+        submission.id,
+        processing_at,
+        status.clone(),
+        judgement,
+        checked_at,
+    )
+    .await?
+    .is_none()
+    {
+        transaction.rollback().await?;
+        return Ok(FinalizeOutcome::LeaseLost);
+    }
+
+    if let Some(cheat) = cheat {
+        apply_cheat_policy(&transaction, cheat).await?;
+    }
+
+    let completed =
+        cds_db::rejudge::finish_entry(&transaction, entry, status.clone(), flag_id, credit).await?;
+    let score_game_id = match completed {
+        Some(job) => {
+            cds_db::game::request_score_recalculation(&transaction, job.game_id).await?;
+            Some(job.game_id)
+        }
+        None => None,
+    };
+
+    transaction.commit().await?;
+    Ok(FinalizeOutcome::Committed {
+        status,
+        score_game_id,
+    })
+}
+
 async fn resolve_status(
     transaction: &impl ConnectionTrait,
     submission: &SubmissionView,
     verdict: Verdict,
+    rejudged: bool,
 ) -> Result<(Status, Option<CheatPolicy>), anyhow::Error> {
     match verdict {
-        Verdict::Correct => Ok((resolve_correct_status(transaction, submission).await?, None)),
+        Verdict::Correct => Ok((
+            resolve_correct_status(transaction, submission, rejudged).await?,
+            None,
+        )),
         Verdict::Incorrect => Ok((Status::Incorrect, None)),
         Verdict::Cheat { peer_team_id } => match (submission.game_id, submission.team_id) {
             (Some(game_id), Some(team_id)) => Ok((
@@ -129,6 +220,7 @@ async fn resolve_status(
 async fn resolve_correct_status(
     transaction: &impl ConnectionTrait,
     submission: &SubmissionView,
+    rejudged: bool,
 ) -> Result<Status, anyhow::Error> {
     if let (Some(game_id), Some(_team_id)) = (submission.game_id, submission.team_id) {
         let game = cds_db::game::find_by_id::<GameDetail>(transaction, game_id)
@@ -138,7 +230,11 @@ async fn resolve_correct_status(
             cds_db::game_challenge::find_by_id(transaction, game_id, submission.challenge_id)
                 .await?
                 .context("game_challenge_not_found")?;
-        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        let now = if rejudged {
+            submission.created_at
+        } else {
+            time::OffsetDateTime::now_utc().unix_timestamp()
+        };
         if now > game.frozen_at
             || now > game.ended_at
             || game_challenge
@@ -150,11 +246,29 @@ async fn resolve_correct_status(
     }
 
     cds_db::submission::lock_solve_owner(transaction, submission).await?;
+    if rejudged {
+        if cds_db::submission::has_preferred_correct_in_scope(transaction, submission).await? {
+            return Ok(Status::Duplicate);
+        }
+        cds_db::submission::demote_other_correct_in_scope(transaction, submission).await?;
+        return Ok(Status::Correct);
+    }
+    // A higher-credit submission supersedes a partial solve of the same flag
+    // instead of being judged a duplicate of it.
+    if cds_db::submission::has_equal_or_higher_credit_correct_in_scope(transaction, submission)
+        .await?
+    {
+        return Ok(Status::Duplicate);
+    }
     if cds_db::submission::has_other_correct_in_scope(transaction, submission).await? {
-        Ok(Status::Duplicate)
-    } else {
-        Ok(Status::Correct)
+        // The calculator may already have read the superseded solve; order the
+        // demotion after any running score plan like admin status changes do.
+        if let Some(game_id) = submission.game_id {
+            cds_db::game::lock_score_recalculation(transaction, game_id).await?;
+        }
+        cds_db::submission::demote_lower_credit_correct_in_scope(transaction, submission).await?;
     }
+    Ok(Status::Correct)
 }
 
 async fn apply_cheat_policy(
@@ -267,7 +381,8 @@ mod tests {
                     (101, 7, 'Peer', NULL, NULL, NULL, FALSE, 3, 0, 0),
                     (102, 8, 'Other game peer', NULL, NULL, NULL, FALSE, 3, 0, 0),
                     (104, 7, 'Rollback submitter', NULL, NULL, NULL, FALSE, 3, 0, 0),
-                    (105, 7, 'Rollback peer', NULL, NULL, NULL, FALSE, 3, 0, 0);
+                    (105, 7, 'Rollback peer', NULL, NULL, NULL, FALSE, 3, 0, 0),
+                    (106, 7, 'Partial solver', NULL, NULL, NULL, FALSE, 3, 0, 0);
                 INSERT INTO game_challenges (
                     game_id, challenge_id, difficulty, max_pts, min_pts,
                     bonus_ratios, enabled, frozen_at, pts
@@ -283,7 +398,10 @@ mod tests {
                     (4, 'flag', 'processing', 10, 1, 104, 7, 0, 1700000000, NULL, 0, 0),
                     (5, 'flag', 'processing', 10, 1, 100, 7, 0, 1700000000, NULL, 0, 0),
                     (6, 'flag', 'processing', 10, 1, 100, 7, 0, 1700000000, NULL, 0, 0),
-                    (7, 'flag', 'processing', 10, 1, NULL, NULL, 0, 1700000000, NULL, 0, 0);
+                    (7, 'flag', 'processing', 10, 1, NULL, NULL, 0, 1700000000, NULL, 0, 0),
+                    (8, 'flag', 'processing', 10, 1, 106, 7, 0, 1700000000, NULL, 0, 0),
+                    (9, 'flag', 'processing', 10, 1, 106, 7, 1, 1700000000, NULL, 0, 0),
+                    (10, 'flag', 'processing', 10, 1, 106, 7, 2, 1700000000, NULL, 0, 0);
             "#,
         )
         .await
@@ -408,6 +526,9 @@ mod tests {
             checked_at: None,
             pts: 0,
             rank: 0,
+            feedback: None,
+            flag_id: None,
+            credit: 100,
         }
     }
 
@@ -482,6 +603,7 @@ mod tests {
             &game_submission(1, 100),
             PROCESSING_AT,
             Verdict::Incorrect,
+            Judgement::default(),
         )
         .await
         .unwrap();
@@ -511,6 +633,7 @@ mod tests {
             &game_submission(2, 100),
             PROCESSING_AT + 1,
             Verdict::Cheat { peer_team_id: 101 },
+            Judgement::default(),
         )
         .await
         .unwrap();
@@ -526,6 +649,7 @@ mod tests {
             &game_submission(2, 100),
             PROCESSING_AT,
             Verdict::Cheat { peer_team_id: 102 },
+            Judgement::default(),
         )
         .await
         .unwrap();
@@ -547,6 +671,7 @@ mod tests {
             &submission(7, None, None),
             PROCESSING_AT,
             Verdict::Cheat { peer_team_id: 101 },
+            Judgement::default(),
         )
         .await
         .unwrap();
@@ -564,6 +689,7 @@ mod tests {
             &game_submission(5, 100),
             PROCESSING_AT,
             Verdict::Correct,
+            Judgement::default(),
         )
         .await
         .unwrap();
@@ -589,6 +715,7 @@ mod tests {
             &game_submission(6, 100),
             PROCESSING_AT,
             Verdict::Correct,
+            Judgement::default(),
         )
         .await
         .unwrap();
@@ -614,6 +741,7 @@ mod tests {
             &game_submission(3, 100),
             PROCESSING_AT,
             Verdict::Cheat { peer_team_id: 101 },
+            Judgement::default(),
         )
         .await
         .unwrap();
@@ -655,6 +783,7 @@ mod tests {
             &game_submission(4, 104),
             PROCESSING_AT,
             Verdict::Cheat { peer_team_id: 105 },
+            Judgement::default(),
         )
         .await;
         assert!(error.is_err());
@@ -677,6 +806,78 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    #[ignore = "requires CDS_TEST_DATABASE_URL pointing to PostgreSQL"]
+    async fn full_credit_supersedes_a_partial_solve_of_the_same_flag() {
+        let db = test_db().await;
+        let judgement = |credit| Judgement {
+            flag_id: Some("a".to_owned()),
+            credit,
+            ..Judgement::default()
+        };
+
+        let partial = finalize(
+            &db,
+            &game_submission(8, 106),
+            PROCESSING_AT,
+            Verdict::Correct,
+            judgement(50),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            partial,
+            FinalizeOutcome::Committed {
+                status: Status::Correct,
+                score_game_id: Some(GAME_ID),
+            }
+        );
+
+        let full = finalize(
+            &db,
+            &game_submission(9, 106),
+            PROCESSING_AT,
+            Verdict::Correct,
+            judgement(100),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            full,
+            FinalizeOutcome::Committed {
+                status: Status::Correct,
+                score_game_id: Some(GAME_ID),
+            }
+        );
+        assert_eq!(status(&db, 8).await, "duplicate");
+        assert_eq!(status(&db, 9).await, "correct");
+
+        let partial_again = finalize(
+            &db,
+            &game_submission(10, 106),
+            PROCESSING_AT,
+            Verdict::Correct,
+            judgement(50),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            partial_again,
+            FinalizeOutcome::Committed {
+                status: Status::Duplicate,
+                score_game_id: None,
+            }
+        );
+        assert_eq!(
+            scalar_i64(
+                &db,
+                "SELECT COUNT(*) AS value FROM submissions WHERE team_id = 106 AND status = 'correct'"
+            )
+            .await,
+            1
+        );
+    }
+
     #[tokio::test]
     #[ignore = "requires CDS_TEST_DATABASE_URL pointing to PostgreSQL"]
     async fn concurrent_correct_finalization_is_unique_across_connections() {
@@ -698,6 +899,7 @@ mod tests {
                     &game_submission(1000 + index, 100),
                     PROCESSING_AT,
                     Verdict::Correct,
+                    Judgement::default(),
                 )
                 .await
             }));
@@ -713,7 +915,14 @@ mod tests {
                 let mut standalone = submission(2000 + index, None, None);
                 standalone.user_id = 2;
                 barrier.wait().await;
-                finalize(&db, &standalone, PROCESSING_AT, Verdict::Correct).await
+                finalize(
+                    &db,
+                    &standalone,
+                    PROCESSING_AT,
+                    Verdict::Correct,
+                    Judgement::default(),
+                )
+                .await
             }));
         }
 
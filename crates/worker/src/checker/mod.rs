@@ -1,5 +1,6 @@
 //! JetStream consumer for subject **`cds.submission.check`**: resolves
-//! **queued** flag submissions with the Lua [`cds_checker::Checker`], applies
+//! **queued** flag submissions with [`cds_checker::Checker`] (the challenge's
+//! Lua script or its built-in checker kind), applies
 //! game rules (duplicate, freeze, cheat), and may enqueue [`crate::calculator`]
 //! work when a submission becomes correct.
 //!
@@ -11,6 +12,9 @@
 //!
 //! [`spawn`] returns expired `Processing` rows to `Queued` and
 //! re-publishes every queued submission so no job is lost after a restart.
+//!
+//! Submissions returned to the queue by an admin re-judge job (see
+//! [`cds_db::rejudge`]) are finalized through [`finalizer::refinalize`].
 
 mod finalizer;
 
@@ -20,7 +24,7 @@ use anyhow::anyhow;
 use cds_checker::Checker;
 use cds_db::{
     DB, SubmissionView, UserAccountView,
-    submission::{FindSubmissionsOptions, PROCESSING_LEASE_SECONDS, Status},
+    submission::{FindSubmissionsOptions, Judgement, PROCESSING_LEASE_SECONDS, Status},
 };
 use cds_queue::{Queue, async_nats::jetstream::AckKind};
 use futures_util::StreamExt as _;
@@ -121,18 +125,36 @@ async fn check(
         _ => submission.user_id,
     };
 
-    let checker_result = enforce_check_timeout(ctx.checker.check(
+    let checker_result = enforce_check_timeout(ctx.checker.check_traced(
         &challenge,
         operator_id,
         &submission.content,
     ))
     .await;
-    let verdict = match checker_result {
-        Ok(Ok(c_status)) => match c_status {
-            cds_checker::Status::Correct => Verdict::Correct,
-            cds_checker::Status::Incorrect => Verdict::Incorrect,
-            cds_checker::Status::Cheat(peer_team_id) => Verdict::Cheat { peer_team_id },
-        },
+    let (checker_result, trace) = match checker_result {
+        Ok((result, trace)) => (Ok(result), trace),
+        Err(elapsed) => (
+            Err(elapsed),
+            cds_checker::Trace::failed("checker invocation timed out"),
+        ),
+    };
+    record_trace(ctx, &submission, &trace).await;
+    let (verdict, judgement) = match checker_result {
+        Ok(Ok(judgement)) => {
+            let verdict = match judgement.status {
+                cds_checker::Status::Correct => Verdict::Correct,
+                cds_checker::Status::Incorrect => Verdict::Incorrect,
+                cds_checker::Status::Cheat(peer_team_id) => Verdict::Cheat { peer_team_id },
+            };
+            (
+                verdict,
+                Judgement {
+                    feedback: judgement.feedback,
+                    flag_id: judgement.flag_id,
+                    credit: judgement.credit,
+                },
+            )
+        }
         Ok(Err(err)) => {
             warn!(
                 submission_id = submission.id,
@@ -140,7 +162,7 @@ async fn check(
                 error = ?err,
                 "checker script failed"
             );
-            Verdict::Incorrect
+            (Verdict::Incorrect, Judgement::default())
         }
         Err(_) => {
             warn!(
@@ -149,14 +171,33 @@ async fn check(
                 timeout_seconds = CHECK_TIMEOUT.as_secs(),
                 "checker invocation timed out"
             );
-            Verdict::Incorrect
+            (Verdict::Incorrect, Judgement::default())
         }
     };
 
+    // Submissions re-queued by an admin re-judge job keep their original
+    // timing and share one score recalculation per job.
+    let rejudge = cds_db::rejudge::find_pending_entry(&ctx.db.conn, submission.id).await?;
+    let outcome = match &rejudge {
+        Some(entry) => {
+            finalizer::refinalize(
+                &ctx.db,
+                &submission,
+                processing_at,
+                verdict,
+                judgement,
+                entry,
+            )
+            .await?
+        }
+        None => {
+            finalizer::finalize(&ctx.db, &submission, processing_at, verdict, judgement).await?
+        }
+    };
     let FinalizeOutcome::Committed {
         status,
         score_game_id,
-    } = finalizer::finalize(&ctx.db, &submission, processing_at, verdict).await?
+    } = outcome
     else {
         warn!(
             submission_id = submission.id,
@@ -173,6 +214,7 @@ async fn check(
         challenge_id = challenge.id,
         game_id = submission.game_id,
         team_id = submission.team_id,
+        rejudge_job_id = rejudge.as_ref().map(|entry| entry.job_id),
         "submission checked"
     );
 
@@ -202,6 +244,25 @@ async fn check(
     Ok(CheckOutcome::Committed)
 }
 
+/// Stores the checker trace for admins. Failures are logged and never affect
+/// the verdict.
+async fn record_trace(ctx: &Context, submission: &SubmissionView, trace: &cds_checker::Trace) {
+    let result = match serde_json::to_value(trace) {
+        Ok(trace) => {
+            cds_db::checker_log::create(&ctx.db.conn, submission.id, submission.challenge_id, trace)
+                .await
+        }
+        Err(err) => Err(cds_db::DbError::Other(err.into())),
+    };
+    if let Err(err) = result {
+        warn!(
+            submission_id = submission.id,
+            error = ?err,
+            "checker log could not be recorded"
+        );
+    }
+}
+
 /// Re-publishes historical `Queued` rows so they are checked after deploys /
 /// crashes.
 #[tracing::instrument(skip_all)]
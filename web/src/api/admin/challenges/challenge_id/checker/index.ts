@@ -1,8 +1,11 @@
+import type { CheckerConfig } from "@/models/challenge";
 import { api } from "@/utils/query";
 
 export type UpdateCheckerRequest = {
   id?: number;
   checker?: string;
+  allowed_hosts?: Array<string>;
+  checker_config?: CheckerConfig;
 };
 
 export async function updateChallengeChecker(request: UpdateCheckerRequest) {
@@ -25,8 +28,16 @@ export type DiagnosticMarker = {
   message: string;
 };
 
+export type LuaApiEntry = {
+  path: string;
+  kind: "namespace" | "function" | "value";
+  signature: string | null;
+  params: Array<{ name: string; optional: boolean; variadic: boolean }>;
+  doc: string | null;
+};
+
 export async function lintChallengeChecker(request: LintCheckerRequest) {
   return api
     .post(`admin/challenges/${request?.id}/checker/lint`, { json: request })
-    .json<{ markers: DiagnosticMarker[] }>();
+    .json<{ markers: DiagnosticMarker[]; api: LuaApiEntry[] }>();
 }
@@ -0,0 +1,75 @@
+import type {
+  RevisionDiff,
+  ScriptRevisionSummary,
+  ScriptRevisionView,
+} from "@/models/script_revision";
+import type { Status } from "@/models/submission";
+import { api } from "@/utils/query";
+
+export async function getCheckerRevisions(challengeId: number) {
+  return api
+    .get(`admin/challenges/${challengeId}/checker/revisions`)
+    .json<{ revisions: ScriptRevisionSummary[] }>();
+}
+
+export async function getCheckerRevision(challengeId: number, number: number) {
+  return api
+    .get(`admin/challenges/${challengeId}/checker/revisions/${number}`)
+    .json<{ revision: ScriptRevisionView }>();
+}
+
+export async function diffCheckerRevision(
+  challengeId: number,
+  number: number,
+  against?: number
+) {
+  return api
+    .get(`admin/challenges/${challengeId}/checker/revisions/${number}/diff`, {
+      searchParams: against === undefined ? undefined : { against },
+    })
+    .json<RevisionDiff>();
+}
+
+export async function rollbackCheckerRevision(
+  challengeId: number,
+  number: number
+) {
+  return api
+    .post(`admin/challenges/${challengeId}/checker/revisions/${number}/rollback`)
+    .json<{ revision: ScriptRevisionSummary }>();
+}
+
+export type JudgeCheckerRevisionRequest = {
+  game_id: number;
+  status?: Status;
+};
+
+export type RevisionJudgement = {
+  submission_id: number;
+  user_id: number;
+  team_id: number | null;
+  created_at: number;
+  status: Status;
+  verdict: Status | null;
+  credit: number | null;
+  flag_id: string | null;
+  feedback: string | null;
+  error: string | null;
+};
+
+export async function judgeCheckerRevision(
+  challengeId: number,
+  number: number,
+  request: JudgeCheckerRevisionRequest
+) {
+  return api
+    .post(`admin/challenges/${challengeId}/checker/revisions/${number}/judge`, {
+      json: request,
+    })
+    .json<{
+      revision: number;
+      total: number;
+      changed: number;
+      judgements: RevisionJudgement[];
+    }>();
+}
@@ -0,0 +1,23 @@
+import { api, toSearchParams } from "@/utils/query";
+
+export type CachedScript = {
+  key: string;
+  created_at: number;
+  script_size: number;
+  allowed_hosts: Array<string>;
+  idle_states: number;
+  busy_states: number;
+  capacity: number;
+};
+
+export async function getCachedScripts() {
+  return api
+    .get("admin/engine/scripts")
+    .json<{ scripts: Array<CachedScript> }>();
+}
+
+export async function evictCachedScripts(key?: string) {
+  return api
+    .delete("admin/engine/scripts", { searchParams: toSearchParams({ key }) })
+    .json<{ evicted: number }>();
+}
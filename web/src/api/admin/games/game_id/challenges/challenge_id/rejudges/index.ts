@@ -0,0 +1,71 @@
+import type { Status } from "@/models/submission";
+import { api } from "@/utils/query";
+
+export type RejudgeJob = {
+  id: number;
+  game_id: number;
+  challenge_id: number;
+  status_filter: Status | null;
+  created_from: number | null;
+  created_until: number | null;
+  state: "running" | "completed";
+  total: number;
+  processed: number;
+  changed: number;
+  author_id: number | null;
+  created_at: number;
+  finished_at: number | null;
+};
+
+export type RejudgeEntry = {
+  id: number;
+  job_id: number;
+  submission_id: number;
+  user_id: number;
+  team_id: number | null;
+  created_at: number;
+  previous_status: Status;
+  previous_flag_id: string | null;
+  previous_credit: number;
+  previous_checked_at: number | null;
+  status: Status | null;
+  flag_id: string | null;
+  credit: number | null;
+  finished_at: number | null;
+};
+
+export type CreateRejudgeRequest = {
+  game_id: number;
+  challenge_id: number;
+  status?: Status;
+  created_from?: number;
+  created_until?: number;
+};
+
+export async function getRejudges(gameId: number, challengeId: number) {
+  return api
+    .get(`admin/games/${gameId}/challenges/${challengeId}/rejudges`)
+    .json<{ jobs: RejudgeJob[] }>();
+}
+
+export async function createRejudge(request: CreateRejudgeRequest) {
+  return api
+    .post(
+      `admin/games/${request.game_id}/challenges/${request.challenge_id}/rejudges`,
+      { json: request }
+    )
+    .json<{ job: RejudgeJob }>();
+}
+
+export async function getRejudge(
+  gameId: number,
+  challengeId: number,
+  jobId: number,
+  changedOnly = false
+) {
+  return api
+    .get(`admin/games/${gameId}/challenges/${challengeId}/rejudges/${jobId}`, {
+      searchParams: { changed_only: changedOnly },
+    })
+    .json<{ job: RejudgeJob; entries: RejudgeEntry[] }>();
+}
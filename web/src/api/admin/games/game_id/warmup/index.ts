@@ -0,0 +1,33 @@
+import { api } from "@/utils/query";
+
+export type PrepullStatus = {
+  images: Array<string>;
+  desired: number;
+  ready: number;
+};
+
+export type WarmupRequest = {
+  game_id: number;
+};
+
+export type WarmupResponse = {
+  status: PrepullStatus | null;
+};
+
+export async function getWarmup(request: WarmupRequest) {
+  return api
+    .get(`admin/games/${request.game_id}/warmup`)
+    .json<WarmupResponse>();
+}
+
+export async function warmupGame(request: WarmupRequest) {
+  return api
+    .post(`admin/games/${request.game_id}/warmup`)
+    .json<WarmupResponse>();
+}
+
+export async function deleteWarmup(request: WarmupRequest) {
+  return api
+    .delete(`admin/games/${request.game_id}/warmup`)
+    .json<Record<string, never>>();
+}
@@ -1,4 +1,9 @@
 import type { IdpView } from "@/models/idp";
+import type {
+  RevisionDiff,
+  ScriptRevisionSummary,
+  ScriptRevisionView,
+} from "@/models/script_revision";
 import { api } from "@/utils/query";
 
 export type IdpRequest = {
@@ -18,6 +23,14 @@ export type DiagnosticMarker = {
   message: string;
 };
 
+export type LuaApiEntry = {
+  path: string;
+  kind: "namespace" | "function" | "value";
+  signature: string | null;
+  params: Array<{ name: string; optional: boolean; variadic: boolean }>;
+  doc: string | null;
+};
+
 export async function getAdminIdps() {
   return api.get("admin/idps").json<{ idps: IdpView[] }>();
 }
@@ -47,5 +60,35 @@ export async function deleteAdminIdpAvatar(idpId: number) {
 export async function lintIdpScript(script: string) {
   return api
     .post("admin/idps/lint", { json: { script } })
-    .json<{ markers: DiagnosticMarker[] }>();
+    .json<{ markers: DiagnosticMarker[]; api: LuaApiEntry[] }>();
+}
+
+export async function getAdminIdpRevisions(idpId: number) {
+  return api
+    .get(`admin/idps/${idpId}/revisions`)
+    .json<{ revisions: ScriptRevisionSummary[] }>();
+}
+
+export async function getAdminIdpRevision(idpId: number, number: number) {
+  return api
+    .get(`admin/idps/${idpId}/revisions/${number}`)
+    .json<{ revision: ScriptRevisionView }>();
+}
+
+export async function diffAdminIdpRevision(
+  idpId: number,
+  number: number,
+  against?: number
+) {
+  return api
+    .get(`admin/idps/${idpId}/revisions/${number}/diff`, {
+      searchParams: against === undefined ? undefined : { against },
+    })
+    .json<RevisionDiff>();
+}
+
+export async function rollbackAdminIdpRevision(idpId: number, number: number) {
+  return api
+    .post(`admin/idps/${idpId}/revisions/${number}/rollback`)
+    .json<{ idp: IdpView }>();
 }
@@ -1,4 +1,9 @@
-import { api } from "@/utils/query";
+import type {
+  Instance,
+  InstanceRecord,
+  InstanceState,
+} from "@/models/instance";
+import { api, toSearchParams } from "@/utils/query";
 
 export type CreateDebugInstanceRequest = {
   challenge_id?: number;
@@ -9,3 +14,50 @@ export async function createDebugInstance(request: CreateDebugInstanceRequest) {
     .post("admin/instances", { json: request })
     .json<{ instance_id: string }>();
 }
+
+export type GetInstanceRecordsRequest = {
+  id?: string;
+  user_id?: number;
+  team_id?: number;
+  game_id?: number;
+  challenge_id?: number;
+  state?: InstanceState;
+  page?: number;
+  size?: number;
+};
+
+export async function getInstanceRecords(request: GetInstanceRecordsRequest) {
+  return api
+    .get("admin/instances/records", {
+      searchParams: toSearchParams(request),
+    })
+    .json<{ instances: InstanceRecord[]; total: number }>();
+}
+
+export type GetInstancesRequest = {
+  id?: string;
+  user_id?: number;
+  team_id?: number;
+  game_id?: number;
+  challenge_id?: number;
+};
+
+export async function getInstances(request: GetInstancesRequest) {
+  return api
+    .get("admin/instances", {
+      searchParams: toSearchParams(request),
+    })
+    .json<{ instances: Instance[] }>();
+}
+
+export type StopInstancesRequest = {
+  challenge_id?: number;
+  team_id?: number;
+  game_id?: number;
+};
+
+export async function stopInstances(request: StopInstancesRequest) {
+  return api
+    .post("admin/instances/stop", { json: request })
+    .json<{ stopped: number; failed: Array<string> }>();
+}
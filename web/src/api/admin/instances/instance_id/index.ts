@@ -0,0 +1,33 @@
+import type { InstanceEvent, InstanceStatus } from "@/models/instance";
+import { api } from "@/utils/query";
+
+export type InstanceRequest = {
+  instance_id: string;
+};
+
+export async function getInstanceStatus(request: InstanceRequest) {
+  return api
+    .get(`admin/instances/${request.instance_id}/status`)
+    .json<InstanceStatus>();
+}
+
+export async function getInstanceEvents(request: InstanceRequest) {
+  return api
+    .get(`admin/instances/${request.instance_id}/events`)
+    .json<{ events: InstanceEvent[] }>();
+}
+
+export async function stopInstance(request: InstanceRequest) {
+  return api
+    .post(`admin/instances/${request.instance_id}/stop`)
+    .json<Record<string, never>>();
+}
+
+export function instanceLogsUrl(
+  request: InstanceRequest & { container_id: string; tail?: number }
+) {
+  const protocol = window.location.protocol.replace("http", "ws");
+  const tail = request.tail ?? 200;
+
+  return `${protocol}//${window.location.host}/api/admin/instances/${request.instance_id}/containers/${request.container_id}/logs?tail=${tail}`;
+}
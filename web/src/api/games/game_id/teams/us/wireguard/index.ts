@@ -0,0 +1,30 @@
+import { api } from "@/utils/query";
+
+export type WireguardPeer = {
+  address: string;
+  public_key: string;
+  created_at: number;
+  config: string;
+};
+
+export type WireguardRequest = {
+  game_id?: number;
+};
+
+export async function getWireguard(request: WireguardRequest) {
+  return api
+    .get(`games/${request.game_id}/teams/us/wireguard`)
+    .json<{ peer: WireguardPeer | null }>();
+}
+
+export async function createWireguard(request: WireguardRequest) {
+  return api
+    .post(`games/${request.game_id}/teams/us/wireguard`)
+    .json<{ peer: WireguardPeer | null }>();
+}
+
+export async function deleteWireguard(request: WireguardRequest) {
+  return api
+    .delete(`games/${request.game_id}/teams/us/wireguard`)
+    .json<Record<string, never>>();
+}
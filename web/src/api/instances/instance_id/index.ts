@@ -1,4 +1,5 @@
 import type { Instance } from "@/models/challenge";
+import type { Checkpoint, TcpAllocation } from "@/models/instance";
 import { api } from "@/utils/query";
 
 export type StopInstanceRequest = {
@@ -22,3 +23,54 @@ export async function renewInstance(request: RenewInstanceRequest) {
     .post(`instances/${request.id}/renew`, { json: request })
     .json<Instance>();
 }
+
+export async function resetInstance(id: string) {
+  return api.post(`instances/${id}/reset`).json<unknown>();
+}
+
+export async function getCheckpoints(id: string) {
+  return api
+    .get(`instances/${id}/checkpoints`)
+    .json<{ checkpoints: Array<Checkpoint> }>();
+}
+
+export type SaveCheckpointRequest = {
+  id: string;
+  name: string;
+};
+
+export async function saveCheckpoint(request: SaveCheckpointRequest) {
+  return api
+    .post(`instances/${request.id}/checkpoints`, {
+      json: { name: request.name },
+    })
+    .json<{ checkpoints: Array<Checkpoint> }>();
+}
+
+export type RestoreCheckpointRequest = {
+  id: string;
+  name: string;
+};
+
+export async function restoreCheckpoint(request: RestoreCheckpointRequest) {
+  return api
+    .post(`instances/${request.id}/checkpoints/${request.name}/restore`)
+    .json<unknown>();
+}
+
+export async function getTcpAllocations(id: string) {
+  return api
+    .get(`instances/${id}/tcp`)
+    .json<{ allocations: Array<TcpAllocation> }>();
+}
+
+export type AllocateTcpPortRequest = {
+  id: string;
+  port: number;
+};
+
+export async function allocateTcpPort(request: AllocateTcpPortRequest) {
+  return api
+    .post(`instances/${request.id}/tcp`, { json: { port: request.port } })
+    .json<TcpAllocation>();
+}
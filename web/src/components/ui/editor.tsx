@@ -46,10 +46,22 @@ const luaCompletions: Record<string, Completion[]> = {
     { label: "suid", type: "namespace", info: "SUID encoding helpers" },
   ],
   "checker.audit": [
-    { label: "cheat", type: "function", info: "cheat(operator_id)" },
-    { label: "correct", type: "function", info: "correct()" },
+    {
+      label: "cheat",
+      type: "function",
+      info: "cheat(operator_id, options?: { feedback?: string })",
+    },
+    {
+      label: "correct",
+      type: "function",
+      info: "correct(options?: { feedback?: string, credit?: number, flag?: string })",
+    },
     { label: "format", type: "function", info: "format(flag)" },
-    { label: "incorrect", type: "function", info: "incorrect()" },
+    {
+      label: "incorrect",
+      type: "function",
+      info: "incorrect(options?: { feedback?: string })",
+    },
     { label: "new", type: "function", info: "new()" },
     { label: "parse", type: "function", info: "parse(content)" },
   ],
@@ -151,26 +163,50 @@ const luaCompletions: Record<string, Completion[]> = {
   ],
 };
 
-function luaCompletionSource(
-  context: CompletionContext
-): CompletionResult | null {
-  const word = context.matchBefore(/[A-Za-z_][A-Za-z0-9_]*$/);
-  const from = word?.from ?? context.pos;
-  const before = context.state.sliceDoc(0, from);
-  const pathMatch = before.match(
-    /(?:[A-Za-z_][A-Za-z0-9_]*\.)+[A-Za-z_][A-Za-z0-9_]*\.$/
-  );
-  const namespace =
-    pathMatch?.[0].slice(0, -1) ??
-    before.match(/(?:[A-Za-z_][A-Za-z0-9_]*\.)+$/)?.[0].slice(0, -1) ??
-    "";
-  const options = luaCompletions[namespace];
+type LuaApiEntry = {
+  path: string;
+  kind: "namespace" | "function" | "value";
+  signature?: string | null;
+  doc?: string | null;
+};
+
+function luaCompletionsFromApi(
+  api: Array<LuaApiEntry>
+): Record<string, Completion[]> {
+  const completions: Record<string, Completion[]> = { "": [] };
+  for (const entry of api) {
+    const index = entry.path.lastIndexOf(".");
+    const namespace = index === -1 ? "" : entry.path.slice(0, index);
+    const info = [entry.signature, entry.doc].filter(Boolean).join("\n");
+    (completions[namespace] ??= []).push({
+      label: entry.path.slice(index + 1),
+      type: entry.kind === "value" ? "constant" : entry.kind,
+      info: info || undefined,
+    });
+  }
+  return completions;
+}
+
+function luaCompletionSource(completions: Record<string, Completion[]>) {
+  return (context: CompletionContext): CompletionResult | null => {
+    const word = context.matchBefore(/[A-Za-z_][A-Za-z0-9_]*$/);
+    const from = word?.from ?? context.pos;
+    const before = context.state.sliceDoc(0, from);
+    const pathMatch = before.match(
+      /(?:[A-Za-z_][A-Za-z0-9_]*\.)+[A-Za-z_][A-Za-z0-9_]*\.$/
+    );
+    const namespace =
+      pathMatch?.[0].slice(0, -1) ??
+      before.match(/(?:[A-Za-z_][A-Za-z0-9_]*\.)+$/)?.[0].slice(0, -1) ??
+      "";
+    const options = completions[namespace];
 
-  if (!options && !context.explicit) return null;
-  return {
-    from,
-    options: options ?? luaCompletions[""],
-    validFor: /^[A-Za-z_][A-Za-z0-9_]*$/,
+    if (!options && !context.explicit) return null;
+    return {
+      from,
+      options: options ?? completions[""],
+      validFor: /^[A-Za-z_][A-Za-z0-9_]*$/,
+    };
   };
 }
 
@@ -193,6 +229,8 @@ type EditorProps = Omit<React.ComponentProps<"div">, "onChange"> & {
     kind: "error" | "warning";
     message: string;
   }>;
+  /** Lua API returned by a lint endpoint; replaces the built-in completions. */
+  luaApi?: Array<LuaApiEntry>;
   className?: string;
 };
 
@@ -206,6 +244,7 @@ function Editor(props: EditorProps) {
     tabSize = 2,
     showLineNumbers = false,
     diagnostics = [],
+    luaApi,
     className,
     ...rest
   } = props;
@@ -313,7 +352,17 @@ function Editor(props: EditorProps) {
           getLanguage(),
           getDiagnosticsExtension(),
           ...(lang === "lua"
-            ? [autocompletion({ override: [luaCompletionSource] })]
+            ? [
+                autocompletion({
+                  override: [
+                    luaCompletionSource(
+                      luaApi?.length
+                        ? luaCompletionsFromApi(luaApi)
+                        : luaCompletions
+                    ),
+                  ],
+                }),
+              ]
             : []),
         ]}
         className={cn([
@@ -330,4 +379,4 @@ function Editor(props: EditorProps) {
   );
 }
 
-export { Editor, type EditorProps };
+export { Editor, type EditorProps, type LuaApiEntry };
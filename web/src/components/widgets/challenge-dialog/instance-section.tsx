@@ -5,7 +5,13 @@ import {
   ClipboardIcon,
   ClockIcon,
   EthernetPortIcon,
+  ExternalLinkIcon,
+  GlobeIcon,
+  HistoryIcon,
   PlayIcon,
+  RotateCcwIcon,
+  SaveIcon,
+  TerminalIcon,
   TrashIcon,
 } from "lucide-react";
 import { useCallback, useContext, useEffect, useMemo, useState } from "react";
@@ -13,22 +19,55 @@ import { useTranslation } from "react-i18next";
 import { toast } from "sonner";
 import { createDebugInstance } from "@/api/admin/instances";
 import { createInstance, getInstances } from "@/api/instances";
-import { renewInstance, stopInstance } from "@/api/instances/instance_id";
+import {
+  allocateTcpPort,
+  getCheckpoints,
+  renewInstance,
+  resetInstance,
+  restoreCheckpoint,
+  saveCheckpoint,
+  stopInstance,
+} from "@/api/instances/instance_id";
 import { Button } from "@/components/ui/button";
 import { Field, FieldButton, FieldIcon } from "@/components/ui/field";
 import { TextField } from "@/components/ui/text-field";
 import { useClipboard } from "@/hooks/use-clipboard";
 import { useInterval } from "@/hooks/use-interval";
 import type { Port } from "@/models/challenge";
-import type { Instance, Nat } from "@/models/instance";
+import type {
+  Checkpoint,
+  Instance,
+  Nat,
+  TcpAllocation,
+} from "@/models/instance";
 import { useAuthStore } from "@/storages/auth";
 import { cn } from "@/utils";
 import { formatApiMsg, parseErrorResponse } from "@/utils/query";
 import { Context } from "./context";
 
 function PortInfo({ instance, port }: { instance: Instance; port: Port }) {
+  const { t } = useTranslation();
   const { isCopied, copyToClipboard } = useClipboard();
-  const url = `${window.location.protocol.replace("http", "ws")}//${window.location.host}/api/instances/${instance?.id}/wsrx?port=${port.port}`;
+  const [allocation, setAllocation] = useState<TcpAllocation>();
+  const url = allocation
+    ? `nc ${allocation.host} ${allocation.port}`
+    : `${window.location.protocol.replace("http", "ws")}//${window.location.host}/api/instances/${instance?.id}/wsrx?port=${port.port}`;
+
+  async function handleAllocateTcpPort() {
+    try {
+      const res = await allocateTcpPort({ id: instance.id, port: port.port });
+      setAllocation(res);
+      if (res.token) {
+        toast.info(t("instance:actions.tcp.token", { token: res.token }), {
+          id: "instance-tcp",
+          duration: Infinity,
+          closeButton: true,
+        });
+      }
+    } catch {
+      toast.error(t("instance:actions.tcp.error"), { id: "instance-tcp" });
+    }
+  }
 
   return (
     <div className={cn(["flex"])}>
@@ -40,6 +79,13 @@ function PortInfo({ instance, port }: { instance: Instance; port: Port }) {
           >{`${port.protocol} | ${port.port}`}</span>
         </FieldIcon>
         <TextField readOnly value={url} />
+        {instance.tcp_proxy && port.protocol === "TCP" && (
+          <FieldButton
+            icon={<TerminalIcon />}
+            title={t("instance:actions.tcp._")}
+            onClick={handleAllocateTcpPort}
+          />
+        )}
         <FieldButton
           icon={isCopied ? <ClipboardCheckIcon /> : <ClipboardIcon />}
           onClick={() => copyToClipboard(url)}
@@ -72,6 +118,115 @@ function NatInfo({ instance, nat }: { instance: Instance; nat: Nat }) {
   );
 }
 
+function UrlInfo({ url }: { url: string }) {
+  const { isCopied, copyToClipboard } = useClipboard();
+
+  return (
+    <div className={cn(["flex"])}>
+      <Field size={"sm"} className={cn(["flex-1"])}>
+        <FieldIcon className={cn(["w-fit", "px-4"])}>
+          <GlobeIcon />
+        </FieldIcon>
+        <TextField readOnly value={url} />
+        <FieldButton
+          icon={<ExternalLinkIcon />}
+          onClick={() => window.open(url, "_blank", "noopener")}
+        />
+        <FieldButton
+          icon={isCopied ? <ClipboardCheckIcon /> : <ClipboardIcon />}
+          onClick={() => copyToClipboard(url)}
+        />
+      </Field>
+    </div>
+  );
+}
+
+function CheckpointInfo({ instance }: { instance: Instance }) {
+  const { t } = useTranslation();
+  const [name, setName] = useState("");
+  const [checkpoints, setCheckpoints] = useState<Array<Checkpoint>>([]);
+  const [loading, setLoading] = useState(false);
+
+  useEffect(() => {
+    getCheckpoints(instance.id)
+      .then((res) => setCheckpoints(res.checkpoints))
+      .catch(() => setCheckpoints([]));
+  }, [instance.id, instance.resets]);
+
+  async function run(action: () => Promise<unknown>, success: string) {
+    setLoading(true);
+    try {
+      await action();
+      toast.success(success, { id: "instance-checkpoint" });
+    } catch (error) {
+      if (!(error instanceof HTTPError)) return;
+      const body = await parseErrorResponse(error);
+
+      toast.error(t("instance:actions.checkpoint.error"), {
+        id: "instance-checkpoint",
+        description: formatApiMsg(body.msg),
+      });
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  function handleSave() {
+    run(async () => {
+      const res = await saveCheckpoint({ id: instance.id, name });
+      setCheckpoints(res.checkpoints);
+      setName("");
+    }, t("instance:actions.checkpoint.saved", { name }));
+  }
+
+  function handleRestore(checkpoint: Checkpoint) {
+    run(
+      () => restoreCheckpoint({ id: instance.id, name: checkpoint.name }),
+      t("instance:actions.checkpoint.restored", { name: checkpoint.name })
+    );
+  }
+
+  return (
+    <div className={cn(["flex", "flex-col", "gap-2"])}>
+      <Field size={"sm"}>
+        <FieldIcon className={cn(["w-fit", "px-4"])}>
+          <HistoryIcon />
+        </FieldIcon>
+        <TextField
+          value={name}
+          onChange={(e) => setName(e.target.value)}
+          placeholder={t("instance:actions.checkpoint.placeholder")}
+        />
+        <FieldButton
+          icon={<SaveIcon />}
+          title={t("instance:actions.checkpoint.save")}
+          disabled={!name || loading}
+          onClick={handleSave}
+        />
+      </Field>
+      {checkpoints.length > 0 && (
+        <div className={cn(["flex", "flex-wrap", "gap-2"])}>
+          {checkpoints.map((checkpoint) => (
+            <Button
+              key={checkpoint.name}
+              size={"sm"}
+              variant={"tonal"}
+              icon={<RotateCcwIcon />}
+              disabled={
+                loading ||
+                Number(instance.resets) >= Number(instance.max_resets)
+              }
+              onClick={() => handleRestore(checkpoint)}
+            >
+              {checkpoint.name}
+            </Button>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
+
 function InstanceSection() {
   const { t } = useTranslation();
 
@@ -91,6 +246,8 @@ function InstanceSection() {
     useState<boolean>(false);
   const [instanceCreateLoading, setInstanceCreateLoading] =
     useState<boolean>(false);
+  const [instanceResetLoading, setInstanceResetLoading] =
+    useState<boolean>(false);
   const [timeLeft, setTimeLeft] = useState(0);
 
   useEffect(() => {
@@ -148,8 +305,9 @@ function InstanceSection() {
         setInstance(p);
         setTimeLeft(
           Math.ceil(
-            Number(p?.started_at) +
-              (Number(p?.renew) + 1) * Number(p?.duration) -
+            (p?.expires_at ??
+              Number(p?.started_at) +
+                (Number(p?.renew) + 1) * Number(p?.duration)) -
               Date.now() / 1000
           )
         );
@@ -197,6 +355,29 @@ function InstanceSection() {
     }
   }
 
+  async function handleInstanceReset() {
+    if (!instance) return;
+
+    setInstanceResetLoading(true);
+    try {
+      await resetInstance(instance.id);
+
+      toast.success(t("instance:actions.reset.success"), {
+        id: "reset",
+      });
+    } catch (error) {
+      if (!(error instanceof HTTPError)) return;
+      const body = await parseErrorResponse(error);
+
+      toast.error(t("instance:actions.reset.error"), {
+        id: "reset",
+        description: formatApiMsg(body.msg),
+      });
+    } finally {
+      setInstanceResetLoading(false);
+    }
+  }
+
   const handleInstanceStop = useCallback(async () => {
     if (!instance) return;
 
@@ -279,13 +460,18 @@ function InstanceSection() {
       {instance?.id ? (
         <>
           <div className={cn(["flex-1", "flex", "flex-col", "gap-3"])}>
-            {instance?.nats?.length
-              ? instance?.nats.map((nat) => (
-                  <NatInfo nat={nat} instance={instance} key={nat.node_port} />
-                ))
-              : instance?.ports?.map((port) => (
-                  <PortInfo instance={instance} port={port} key={port.port} />
-                ))}
+            {instance?.public_url ? (
+              <UrlInfo url={instance.public_url} />
+            ) : instance?.nats?.length ? (
+              instance?.nats.map((nat) => (
+                <NatInfo nat={nat} instance={instance} key={nat.node_port} />
+              ))
+            ) : (
+              instance?.ports?.map((port) => (
+                <PortInfo instance={instance} port={port} key={port.port} />
+              ))
+            )}
+            {instance?.checkpoint && <CheckpointInfo instance={instance} />}
           </div>
           <div className={cn(["flex", "flex-col", "gap-2", "items-center"])}>
             <span
@@ -310,11 +496,25 @@ function InstanceSection() {
                 level={"info"}
                 variant={"solid"}
                 onClick={() => handleInstanceRenew()}
-                disabled={Number(instance.renew) === 3}
+                disabled={
+                  Number(instance.renew) >= (instance.max_renewals ?? 3)
+                }
                 className={cn(["items-center"])}
               >
                 {t("instance:actions.renew._")}
               </Button>
+              <Button
+                icon={<RotateCcwIcon />}
+                level={"warning"}
+                variant={"solid"}
+                onClick={() => handleInstanceReset()}
+                loading={instanceResetLoading}
+                disabled={
+                  Number(instance.resets) >= Number(instance.max_resets)
+                }
+              >
+                {t("instance:actions.reset._")}
+              </Button>
               <Button
                 icon={<TrashIcon />}
                 variant={"solid"}
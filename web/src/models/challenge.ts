@@ -10,6 +10,7 @@ export type ChallengeDetail = {
   has_instance: boolean;
   instance: Instance | null;
   checker: string | null;
+  checker_config: CheckerConfig;
   writeup: string | null;
   deleted_at: number | null;
   updated_at: number;
@@ -29,10 +30,25 @@ export type ChallengeView = Pick<
   | "writeup"
 >;
 
+export type CheckerConfig =
+  | { kind: "lua" }
+  | { kind: "static"; flags: Array<string> }
+  | { kind: "regex"; pattern: string }
+  | { kind: "case_insensitive"; flags: Array<string> }
+  | { kind: "webhook"; url: string; secret: string; timeout_ms: number };
+
 export type Instance = {
   duration?: number;
   internet?: boolean;
   containers?: Array<Container>;
+  runtime_class_name?: string | null;
+  node_selector?: Record<string, string>;
+  tolerations?: Array<Toleration>;
+  warm_pool?: number;
+  max_renewals?: number | null;
+  max_lifetime?: number | null;
+  idle_timeout?: number | null;
+  max_resets?: number | null;
 };
 
 export type Container = {
@@ -42,6 +58,24 @@ export type Container = {
   ports: Array<Port>;
   envs: Array<EnvVar>;
   image_pull_policy: string;
+  ephemeral_storage_limit?: number | null;
+  read_only_root_filesystem?: boolean;
+  run_as_non_root?: boolean;
+  drop_capabilities?: Array<string>;
+  seccomp_profile?: string | null;
+  checkpoint?: CheckpointCommands | null;
+};
+
+export type CheckpointCommands = {
+  save: Array<string>;
+  restore: Array<string>;
+};
+
+export type Toleration = {
+  key: string;
+  operator: string;
+  value: string;
+  effect: string;
 };
 
 export type Port = {
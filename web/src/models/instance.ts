@@ -9,13 +9,20 @@ export type Instance = {
   nats?: Array<Nat>;
   ports?: Array<Port>;
   public_entry?: string;
+  public_url?: string;
+  tcp_proxy?: boolean;
 
   status?: string;
   reason?: string;
 
   renew?: number;
+  max_renewals?: number;
   duration?: number;
   started_at?: number;
+  expires_at?: number;
+  resets?: number;
+  max_resets?: number;
+  checkpoint?: boolean;
 };
 
 export type Nat = {
@@ -23,3 +30,83 @@ export type Nat = {
   node_port: number;
   protocol: "TCP" | "UDP";
 };
+
+export type InstanceState = "running" | "terminated";
+
+export type TerminationReason =
+  | "stopped"
+  | "admin_stopped"
+  | "expired"
+  | "create_failed"
+  | "evicted"
+  | "pod_lost"
+  | "idle";
+
+export type InstanceRecord = {
+  id: string;
+  user_id: number;
+  team_id?: number;
+  game_id?: number;
+  challenge_id: number;
+  ports: Array<Port>;
+  nats: Array<Nat>;
+  state: InstanceState;
+  renew: number;
+  duration: number;
+  created_at: number;
+  renewed_at?: number;
+  expires_at: number;
+  terminated_at?: number;
+  termination_reason?: TerminationReason;
+  access_token?: string;
+  max_renewals: number;
+  max_expires_at?: number;
+  idle_timeout: number;
+  last_active_at?: number;
+  warned_at?: number;
+  resets: number;
+  max_resets: number;
+  reset_at?: number;
+  checkpoints: Array<Checkpoint>;
+};
+
+export type Checkpoint = {
+  name: string;
+  created_at: number;
+};
+
+export type TcpAllocation = {
+  instance_id: string;
+  target_port: number;
+  host: string;
+  port: number;
+  token?: string;
+  bytes_in: number;
+  bytes_out: number;
+  connections: number;
+};
+
+export type ContainerStatus = {
+  id: string;
+  image: string;
+  ready: boolean;
+  restart_count: number;
+  state: "waiting" | "running" | "terminated";
+  reason?: string;
+  cpu_millicores?: number;
+  memory_bytes?: number;
+};
+
+export type InstanceStatus = {
+  phase: string;
+  node?: string;
+  containers: Array<ContainerStatus>;
+};
+
+export type InstanceEvent = {
+  type_: string;
+  reason: string;
+  message: string;
+  count: number;
+  last_seen?: number;
+};
@@ -0,0 +1,39 @@
+export type ScriptRevisionMarker = {
+  start_line: number;
+  start_column: number;
+  end_line: number;
+  end_column: number;
+  kind: "error" | "warning";
+  message: string;
+};
+
+export type ScriptRevisionSummary = {
+  id: number;
+  number: number;
+  author_id: number | null;
+  restored_from: number | null;
+  markers: ScriptRevisionMarker[];
+  created_at: number;
+};
+
+export type ScriptRevisionView = ScriptRevisionSummary & {
+  challenge_id: number | null;
+  idp_id: number | null;
+  script: string;
+  allowed_hosts: string[];
+};
+
+export type RevisionDiffLine = {
+  kind: "equal" | "insert" | "delete";
+  old_line: number | null;
+  new_line: number | null;
+  text: string;
+};
+
+export type RevisionDiff = {
+  from: number | null;
+  to: number;
+  from_allowed_hosts: string[];
+  to_allowed_hosts: string[];
+  lines: RevisionDiffLine[];
+};
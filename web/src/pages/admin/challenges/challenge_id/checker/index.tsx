@@ -14,6 +14,7 @@ import { toast } from "sonner";
 import { z } from "zod";
 import {
   type DiagnosticMarker,
+  type LuaApiEntry,
   lintChallengeChecker,
   updateChallengeChecker,
 } from "@/api/admin/challenges/challenge_id/checker";
@@ -53,6 +54,7 @@ export default function Index() {
   const sharedStore = useSharedStore();
   const [_loading, setLoading] = useState<boolean>(false);
   const [lint, setLint] = useState<Array<DiagnosticMarker>>();
+  const [luaApi, setLuaApi] = useState<Array<LuaApiEntry>>();
   const [lintState, setLintState] = useState<
     "idle" | "checking" | "valid" | "invalid" | "error"
   >("idle");
@@ -116,7 +118,12 @@ export default function Index() {
       .then((res) => {
         if (active) {
           setLint(res.markers);
-          setLintState(res.markers.length === 0 ? "valid" : "invalid");
+          setLuaApi(res.api);
+          setLintState(
+            res.markers.some((marker) => marker.kind === "error")
+              ? "invalid"
+              : "valid"
+          );
         }
       })
       .catch(() => {
@@ -138,7 +145,7 @@ export default function Index() {
     checking: t("challenge:checker.lint.checking"),
     valid: t("challenge:checker.lint.valid"),
     invalid: t("challenge:checker.lint.invalid", {
-      count: lint?.length ?? 0,
+      count: lint?.filter((marker) => marker.kind === "error").length ?? 0,
     }),
     error: t("challenge:checker.lint.error"),
   }[displayedLintState];
@@ -277,6 +284,7 @@ export default function Index() {
                   showLineNumbers
                   className={cn(["h-full", "min-h-120"])}
                   diagnostics={isLintPending ? [] : lint}
+                  luaApi={luaApi}
                 />
               </FormControl>
             </FormItem>
@@ -1,16 +1,23 @@
 import { zodResolver } from "@hookform/resolvers/zod";
 import {
   ClockIcon,
+  FlameIcon,
   ContainerIcon,
+  HourglassIcon,
+  RefreshCwIcon,
+  RotateCcwIcon,
+  TimerOffIcon,
   CpuIcon,
   DownloadIcon,
   HandshakeIcon,
+  HardDriveIcon,
   KeyIcon,
   MemoryStickIcon,
   MinusIcon,
   NetworkIcon,
   PlusIcon,
   SaveIcon,
+  ShieldIcon,
   TextIcon,
   TrashIcon,
 } from "lucide-react";
@@ -53,6 +60,23 @@ export default function Index() {
     internet: z.boolean({
       message: t("challenge:form.instance.internet.message"),
     }),
+    runtime_class_name: z.string().nullish(),
+    node_selector: z.record(z.string(), z.string()).optional(),
+    tolerations: z
+      .array(
+        z.object({
+          key: z.string(),
+          operator: z.string(),
+          value: z.string(),
+          effect: z.string(),
+        })
+      )
+      .optional(),
+    warm_pool: z.number().min(0).optional(),
+    max_renewals: z.number().min(0).nullish(),
+    max_lifetime: z.number().min(0).nullish(),
+    idle_timeout: z.number().min(0).nullish(),
+    max_resets: z.number().min(0).nullish(),
     containers: z.array(
       z.object({
         image: z.string({
@@ -69,6 +93,17 @@ export default function Index() {
             "challenge:form.instance.containers.image_pull_policy.message"
           ),
         }),
+        ephemeral_storage_limit: z.number().positive().nullish(),
+        read_only_root_filesystem: z.boolean().optional(),
+        run_as_non_root: z.boolean().optional(),
+        drop_capabilities: z.array(z.string()).optional(),
+        seccomp_profile: z.string().nullish(),
+        checkpoint: z
+          .object({
+            save: z.array(z.string()),
+            restore: z.array(z.string()),
+          })
+          .nullish(),
         envs: z.array(
           z.object({
             key: z.string().min(1, {
@@ -263,6 +298,139 @@ export default function Index() {
               </FormItem>
             )}
           />
+          <FormField
+            control={form.control}
+            name={"warm_pool"}
+            render={({ field }) => (
+              <FormItem className={cn(["w-full"])}>
+                <FormLabel>{t("challenge:form.instance.warm_pool._")}</FormLabel>
+                <FormControl>
+                  <Field>
+                    <FieldIcon>
+                      <FlameIcon />
+                    </FieldIcon>
+                    <NumberField
+                      placeholder={t(
+                        "challenge:form.instance.warm_pool.placeholder"
+                      )}
+                      min={0}
+                      value={field.value ?? 0}
+                      onValueChange={(value) => field.onChange(value ?? 0)}
+                    />
+                  </Field>
+                </FormControl>
+                <FormMessage />
+              </FormItem>
+            )}
+          />
+          <FormField
+            control={form.control}
+            name={"max_renewals"}
+            render={({ field }) => (
+              <FormItem className={cn(["w-full"])}>
+                <FormLabel>
+                  {t("challenge:form.instance.max_renewals._")}
+                </FormLabel>
+                <FormControl>
+                  <Field>
+                    <FieldIcon>
+                      <RefreshCwIcon />
+                    </FieldIcon>
+                    <NumberField
+                      placeholder={t(
+                        "challenge:form.instance.max_renewals.placeholder"
+                      )}
+                      min={0}
+                      value={field.value ?? undefined}
+                      onValueChange={(value) => field.onChange(value ?? null)}
+                    />
+                  </Field>
+                </FormControl>
+                <FormMessage />
+              </FormItem>
+            )}
+          />
+          <FormField
+            control={form.control}
+            name={"max_lifetime"}
+            render={({ field }) => (
+              <FormItem className={cn(["w-full"])}>
+                <FormLabel>
+                  {t("challenge:form.instance.max_lifetime._")}
+                </FormLabel>
+                <FormControl>
+                  <Field>
+                    <FieldIcon>
+                      <HourglassIcon />
+                    </FieldIcon>
+                    <NumberField
+                      placeholder={t(
+                        "challenge:form.instance.max_lifetime.placeholder"
+                      )}
+                      min={0}
+                      value={field.value ?? undefined}
+                      onValueChange={(value) => field.onChange(value ?? null)}
+                    />
+                  </Field>
+                </FormControl>
+                <FormMessage />
+              </FormItem>
+            )}
+          />
+          <FormField
+            control={form.control}
+            name={"idle_timeout"}
+            render={({ field }) => (
+              <FormItem className={cn(["w-full"])}>
+                <FormLabel>
+                  {t("challenge:form.instance.idle_timeout._")}
+                </FormLabel>
+                <FormControl>
+                  <Field>
+                    <FieldIcon>
+                      <TimerOffIcon />
+                    </FieldIcon>
+                    <NumberField
+                      placeholder={t(
+                        "challenge:form.instance.idle_timeout.placeholder"
+                      )}
+                      min={0}
+                      value={field.value ?? undefined}
+                      onValueChange={(value) => field.onChange(value ?? null)}
+                    />
+                  </Field>
+                </FormControl>
+                <FormMessage />
+              </FormItem>
+            )}
+          />
+          <FormField
+            control={form.control}
+            name={"max_resets"}
+            render={({ field }) => (
+              <FormItem className={cn(["w-full"])}>
+                <FormLabel>
+                  {t("challenge:form.instance.max_resets._")}
+                </FormLabel>
+                <FormControl>
+                  <Field>
+                    <FieldIcon>
+                      <RotateCcwIcon />
+                    </FieldIcon>
+                    <NumberField
+                      placeholder={t(
+                        "challenge:form.instance.max_resets.placeholder"
+                      )}
+                      min={0}
+                      value={field.value ?? undefined}
+                      onValueChange={(value) => field.onChange(value ?? null)}
+                    />
+                  </Field>
+                </FormControl>
+                <FormMessage />
+              </FormItem>
+            )}
+          />
         </div>
         {form.watch("containers")?.map((container, containerIndex) => (
           <div
@@ -397,6 +565,120 @@ export default function Index() {
                 )}
               />
             </div>
+            <div className={cn(["flex", "flex-col", "gap-5", "sm:flex-row"])}>
+              <FormField
+                control={form.control}
+                name={`containers.${containerIndex}.ephemeral_storage_limit`}
+                render={({ field }) => (
+                  <FormItem className={cn(["w-full", "sm:w-1/3"])}>
+                    <FormLabel>
+                      {t(
+                        "challenge:form.instance.containers.ephemeral_storage_limit._"
+                      )}
+                    </FormLabel>
+                    <FormControl>
+                      <Field>
+                        <FieldIcon>
+                          <HardDriveIcon />
+                        </FieldIcon>
+                        <NumberField
+                          placeholder={t(
+                            "challenge:form.instance.containers.ephemeral_storage_limit.placeholder"
+                          )}
+                          value={field.value ?? undefined}
+                          onValueChange={(value) =>
+                            field.onChange(value ?? null)
+                          }
+                        />
+                      </Field>
+                    </FormControl>
+                    <FormMessage />
+                  </FormItem>
+                )}
+              />
+              <FormField
+                control={form.control}
+                name={`containers.${containerIndex}.read_only_root_filesystem`}
+                render={({ field }) => (
+                  <FormItem className={cn(["w-full", "sm:w-1/3"])}>
+                    <FormLabel>
+                      {t(
+                        "challenge:form.instance.containers.read_only_root_filesystem._"
+                      )}
+                    </FormLabel>
+                    <FormControl>
+                      <Field>
+                        <FieldIcon>
+                          <ShieldIcon />
+                        </FieldIcon>
+                        <Select
+                          {...field}
+                          options={[
+                            {
+                              value: String(true),
+                              content: t(
+                                "challenge:form.instance.containers.read_only_root_filesystem.true"
+                              ),
+                            },
+                            {
+                              value: String(false),
+                              content: t(
+                                "challenge:form.instance.containers.read_only_root_filesystem.false"
+                              ),
+                            },
+                          ]}
+                          onValueChange={(value) => {
+                            field.onChange(value === "true");
+                          }}
+                          value={String(field.value ?? false)}
+                        />
+                      </Field>
+                    </FormControl>
+                    <FormMessage />
+                  </FormItem>
+                )}
+              />
+              <FormField
+                control={form.control}
+                name={`containers.${containerIndex}.run_as_non_root`}
+                render={({ field }) => (
+                  <FormItem className={cn(["w-full", "sm:w-1/3"])}>
+                    <FormLabel>
+                      {t("challenge:form.instance.containers.run_as_non_root._")}
+                    </FormLabel>
+                    <FormControl>
+                      <Field>
+                        <FieldIcon>
+                          <ShieldIcon />
+                        </FieldIcon>
+                        <Select
+                          {...field}
+                          options={[
+                            {
+                              value: String(true),
+                              content: t(
+                                "challenge:form.instance.containers.run_as_non_root.true"
+                              ),
+                            },
+                            {
+                              value: String(false),
+                              content: t(
+                                "challenge:form.instance.containers.run_as_non_root.false"
+                              ),
+                            },
+                          ]}
+                          onValueChange={(value) => {
+                            field.onChange(value === "true");
+                          }}
+                          value={String(field.value ?? false)}
+                        />
+                      </Field>
+                    </FormControl>
+                    <FormMessage />
+                  </FormItem>
+                )}
+              />
+            </div>
             <Label>{t("challenge:form.instance.containers.ports._")}</Label>
             <div
               className={cn([
@@ -1,6 +1,7 @@
 import { keepPreviousData, useQuery } from "@tanstack/react-query";
 import {
   CirclePauseIcon,
+  CloudDownloadIcon,
   FlagIcon,
   InfoIcon,
   LibraryIcon,
@@ -15,6 +16,7 @@ import { Link, Outlet, useLocation, useParams } from "react-router";
 import { toast } from "sonner";
 import { getGame, updateGame } from "@/api/admin/games/game_id";
 import { calculateGame } from "@/api/admin/games/game_id/calculate";
+import { getWarmup, warmupGame } from "@/api/admin/games/game_id/warmup";
 import { Button, buttonVariants } from "@/components/ui/button";
 import { Card } from "@/components/ui/card";
 import { ScrollableNav } from "@/components/ui/scrollable-nav";
@@ -76,6 +78,20 @@ export default function Layout() {
   }, [game_id, t]);
 
   const [loading, setLoading] = useState<boolean>(false);
+  const [warming, setWarming] = useState<boolean>(false);
+
+  const { data: warmup, refetch: refetchWarmup } = useQuery({
+    queryKey: ["admin", "game", gameId, "warmup"],
+    queryFn: async () => {
+      const res = await getWarmup({ game_id: gameId! });
+      return res.status;
+    },
+    refetchInterval: (query) => {
+      const status = query.state.data;
+      return status && status.ready < status.desired ? 5000 : false;
+    },
+    enabled: gameId != null,
+  });
   const [updatingState, setUpdatingState] = useState<
     "paused" | "blacked_out" | null
   >(null);
@@ -132,6 +148,22 @@ export default function Layout() {
       });
   }
 
+  function handleWarmup() {
+    setWarming(true);
+    warmupGame({ game_id: gameId! })
+      .then((res) => {
+        toast.success(
+          t("game:edit.warmup.success", {
+            count: res.status?.images.length ?? 0,
+          })
+        );
+        refetchWarmup();
+      })
+      .finally(() => {
+        setWarming(false);
+      });
+  }
+
   return (
     <>
       <title>{`${game?.title} - ${configStore?.config?.meta?.title}`}</title>
@@ -265,6 +297,26 @@ export default function Layout() {
               >
                 {t("game:edit.recalculate")}
               </Button>
+              <Button
+                icon={<CloudDownloadIcon className="size-4" />}
+                variant="ghost"
+                className={cn([
+                  "justify-start",
+                  "w-full",
+                  "text-muted-foreground",
+                ])}
+                loading={warming}
+                onClick={handleWarmup}
+              >
+                <span className="flex-1 text-left">
+                  {t("game:edit.warmup._")}
+                </span>
+                {warmup && (
+                  <span className="text-xs tabular-nums">
+                    {`${warmup.ready}/${warmup.desired}`}
+                  </span>
+                )}
+              </Button>
             </div>
           </aside>
           <Card
@@ -16,6 +16,7 @@ import { toast } from "sonner";
 import { z } from "zod";
 import {
   type DiagnosticMarker,
+  type LuaApiEntry,
   deleteAdminIdpAvatar,
   getAdminIdp,
   lintIdpScript,
@@ -74,6 +75,7 @@ export default function Index() {
   const [_deleting, _setDeleting] = useState(false);
   const [hasAvatar, setHasAvatar] = useState(false);
   const [lint, setLint] = useState<Array<DiagnosticMarker>>();
+  const [luaApi, setLuaApi] = useState<Array<LuaApiEntry>>();
   const avatarInput = useRef<HTMLInputElement>(null);
   const { data: idp, isLoading } = useQuery({
     queryKey: ["admin", "idp", idpId, sharedStore.refresh],
@@ -119,7 +121,10 @@ export default function Index() {
 
     lintIdpScript(debouncedScript)
       .then((res) => {
-        if (active) setLint(res.markers);
+        if (active) {
+          setLint(res.markers);
+          setLuaApi(res.api);
+        }
       })
       .catch(() => {
         if (active) setLint([]);
@@ -405,6 +410,7 @@ export default function Index() {
                       value={field.value}
                       onChange={field.onChange}
                       diagnostics={lint}
+                      luaApi={luaApi}
                     />
                   </FormControl>
                   <FormMessage />
@@ -0,0 +1,188 @@
+import { useQuery } from "@tanstack/react-query";
+import { SquareIcon } from "lucide-react";
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+import { toast } from "sonner";
+import {
+  getInstanceEvents,
+  getInstanceStatus,
+  stopInstance,
+} from "@/api/admin/instances/instance_id";
+import { Badge } from "@/components/ui/badge";
+import { Button } from "@/components/ui/button";
+import { Card } from "@/components/ui/card";
+import {
+  Table,
+  TableBody,
+  TableCell,
+  TableHead,
+  TableHeader,
+  TableRow,
+} from "@/components/ui/table";
+import type { ContainerStatus } from "@/models/instance";
+import { useSharedStore } from "@/storages/shared";
+import { cn } from "@/utils";
+import { Logs } from "./logs";
+
+function formatCpu(container: ContainerStatus) {
+  return container.cpu_millicores != null
+    ? `${container.cpu_millicores}m`
+    : "-";
+}
+
+function formatMemory(container: ContainerStatus) {
+  return container.memory_bytes != null
+    ? `${(container.memory_bytes / 1024 / 1024).toFixed(1)} MiB`
+    : "-";
+}
+
+type DetailProps = {
+  instanceId: string;
+  onStopped: () => void;
+};
+
+function Detail(props: DetailProps) {
+  const { instanceId, onStopped } = props;
+  const { t } = useTranslation();
+  const sharedStore = useSharedStore();
+
+  const [logContainer, setLogContainer] = useState<string>();
+  const [stopping, setStopping] = useState(false);
+
+  const { data: status } = useQuery({
+    queryKey: ["admin", "instance", instanceId, "status"],
+    queryFn: () => getInstanceStatus({ instance_id: instanceId }),
+    refetchInterval: 5000,
+  });
+
+  const { data: events } = useQuery({
+    queryKey: ["admin", "instance", instanceId, "events"],
+    queryFn: async () => {
+      const res = await getInstanceEvents({ instance_id: instanceId });
+      return res.events;
+    },
+    refetchInterval: 10000,
+  });
+
+  function handleStop() {
+    setStopping(true);
+    stopInstance({ instance_id: instanceId })
+      .then(() => {
+        toast.success(t("admin:instance.actions.stop.success"));
+        sharedStore.setRefresh();
+        onStopped();
+      })
+      .finally(() => setStopping(false));
+  }
+
+  return (
+    <Card className={cn(["flex", "flex-col", "gap-4", "p-4"])}>
+      <div className={cn(["flex", "items-center", "justify-between", "gap-3"])}>
+        <div className={cn(["flex", "flex-col", "min-w-0"])}>
+          <span className="font-mono text-sm truncate">{instanceId}</span>
+          <span className="text-xs text-muted-foreground">
+            {[status?.phase, status?.node].filter(Boolean).join(" · ")}
+          </span>
+        </div>
+        <Button
+          icon={<SquareIcon />}
+          level="error"
+          size="sm"
+          loading={stopping}
+          onClick={handleStop}
+        >
+          {t("admin:instance.actions.stop._")}
+        </Button>
+      </div>
+
+      <Table>
+        <TableHeader>
+          <TableRow>
+            <TableHead>{t("admin:instance.container")}</TableHead>
+            <TableHead>{t("admin:instance.state")}</TableHead>
+            <TableHead>{t("admin:instance.restarts")}</TableHead>
+            <TableHead>{t("admin:instance.cpu")}</TableHead>
+            <TableHead>{t("admin:instance.memory")}</TableHead>
+            <TableHead />
+          </TableRow>
+        </TableHeader>
+        <TableBody>
+          {status?.containers.map((container) => (
+            <TableRow key={container.id}>
+              <TableCell>
+                <div className={cn(["flex", "flex-col"])}>
+                  <span className="font-mono text-xs">{container.id}</span>
+                  <span className="text-xs text-muted-foreground truncate">
+                    {container.image}
+                  </span>
+                </div>
+              </TableCell>
+              <TableCell>
+                <Badge variant={container.ready ? "solid" : "tonal"}>
+                  {container.reason ?? container.state}
+                </Badge>
+              </TableCell>
+              <TableCell className="tabular-nums">
+                {container.restart_count}
+              </TableCell>
+              <TableCell className="tabular-nums">
+                {formatCpu(container)}
+              </TableCell>
+              <TableCell className="tabular-nums">
+                {formatMemory(container)}
+              </TableCell>
+              <TableCell>
+                <Button
+                  size="sm"
+                  variant={logContainer === container.id ? "tonal" : "ghost"}
+                  onClick={() =>
+                    setLogContainer(
+                      logContainer === container.id ? undefined : container.id
+                    )
+                  }
+                >
+                  {t("admin:instance.logs._")}
+                </Button>
+              </TableCell>
+            </TableRow>
+          ))}
+        </TableBody>
+      </Table>
+
+      {logContainer && (
+        <Logs instanceId={instanceId} containerId={logContainer} />
+      )}
+
+      <div className={cn(["flex", "flex-col", "gap-2"])}>
+        <h4 className="text-sm font-semibold">
+          {t("admin:instance.events._")}
+        </h4>
+        {events?.length ? (
+          <ul className={cn(["flex", "flex-col", "gap-1", "text-xs"])}>
+            {events.map((event, index) => (
+              <li key={index} className={cn(["flex", "gap-2"])}>
+                <span
+                  className={cn([
+                    "shrink-0",
+                    "font-semibold",
+                    event.type_ === "Warning" && "text-error",
+                  ])}
+                >
+                  {event.reason}
+                  {event.count > 1 && ` ×${event.count}`}
+                </span>
+                <span className="text-muted-foreground">{event.message}</span>
+              </li>
+            ))}
+          </ul>
+        ) : (
+          <p className="text-xs text-muted-foreground">
+            {t("admin:instance.events.empty")}
+          </p>
+        )}
+      </div>
+    </Card>
+  );
+}
+
+export { Detail };
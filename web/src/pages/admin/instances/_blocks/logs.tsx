@@ -0,0 +1,66 @@
+import { useEffect, useRef, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { instanceLogsUrl } from "@/api/admin/instances/instance_id";
+import { cn } from "@/utils";
+
+const MAX_LINES = 2000;
+
+type LogsProps = {
+  instanceId: string;
+  containerId: string;
+};
+
+function Logs(props: LogsProps) {
+  const { instanceId, containerId } = props;
+  const { t } = useTranslation();
+
+  const [lines, setLines] = useState<Array<string>>([]);
+  const [closed, setClosed] = useState(false);
+  const bottomRef = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    setLines([]);
+    setClosed(false);
+
+    const socket = new WebSocket(
+      instanceLogsUrl({ instance_id: instanceId, container_id: containerId })
+    );
+    socket.onmessage = (event) => {
+      setLines((prev) => [...prev, String(event.data)].slice(-MAX_LINES));
+    };
+    socket.onclose = () => setClosed(true);
+
+    return () => socket.close();
+  }, [instanceId, containerId]);
+
+  useEffect(() => {
+    bottomRef.current?.scrollIntoView({ block: "end" });
+  }, [lines]);
+
+  return (
+    <pre
+      className={cn([
+        "h-72",
+        "overflow-auto",
+        "rounded-md",
+        "border",
+        "bg-muted/40",
+        "p-3",
+        "text-xs",
+        "font-mono",
+        "whitespace-pre-wrap",
+        "break-all",
+      ])}
+    >
+      {lines.join("\n")}
+      {closed && (
+        <span className="block text-muted-foreground">
+          {t("admin:instance.logs.closed")}
+        </span>
+      )}
+      <div ref={bottomRef} />
+    </pre>
+  );
+}
+
+export { Logs };
@@ -0,0 +1,107 @@
+import { OctagonXIcon } from "lucide-react";
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+import { toast } from "sonner";
+import {
+  type StopInstancesRequest,
+  stopInstances,
+} from "@/api/admin/instances";
+import { Button } from "@/components/ui/button";
+import { Card } from "@/components/ui/card";
+import { useSharedStore } from "@/storages/shared";
+import { cn } from "@/utils";
+
+type StopDialogProps = {
+  filters: StopInstancesRequest;
+  onClose: () => void;
+};
+
+function StopDialog(props: StopDialogProps) {
+  const { filters, onClose } = props;
+  const { t } = useTranslation();
+  const sharedStore = useSharedStore();
+
+  const [loading, setLoading] = useState(false);
+
+  const scope = Object.entries(filters)
+    .filter(([, value]) => value !== undefined)
+    .map(([key, value]) => `${t(`admin:instance.filters.${key}`)} #${value}`)
+    .join(", ");
+
+  function handleStop() {
+    setLoading(true);
+    stopInstances(filters)
+      .then((res) => {
+        if (res.failed.length > 0) {
+          toast.warning(
+            t("admin:instance.actions.stop_all.partial", {
+              count: res.stopped,
+              failed: res.failed.length,
+            }),
+            { description: res.failed.join(", ") }
+          );
+        } else {
+          toast.success(
+            t("admin:instance.actions.stop_all.success", {
+              count: res.stopped,
+            })
+          );
+        }
+        sharedStore.setRefresh();
+        onClose();
+      })
+      .finally(() => setLoading(false));
+  }
+
+  return (
+    <Card
+      className={cn([
+        "flex",
+        "w-full",
+        "max-w-xl",
+        "flex-col",
+        "overflow-hidden",
+        "rounded-elevated",
+        "shadow-lg",
+      ])}
+    >
+      <div className={cn(["flex", "flex-col", "gap-5", "p-5"])}>
+        <div className={cn(["flex", "items-center", "gap-3"])}>
+          <div
+            className={cn([
+              "flex",
+              "size-10",
+              "shrink-0",
+              "items-center",
+              "justify-center",
+              "rounded-badge",
+              "bg-error/10",
+              "text-error",
+            ])}
+          >
+            <OctagonXIcon className="size-5" />
+          </div>
+          <h3 className="text-base font-semibold">
+            {t("admin:instance.actions.stop_all._")}
+          </h3>
+        </div>
+        <p className="text-sm">
+          {t("admin:instance.actions.stop_all.message", { scope })}
+        </p>
+        <div className="flex justify-end">
+          <Button
+            level="error"
+            variant="solid"
+            size="sm"
+            loading={loading}
+            onClick={handleStop}
+          >
+            {t("common:actions.confirm")}
+          </Button>
+        </div>
+      </div>
+    </Card>
+  );
+}
+
+export { StopDialog };
@@ -1,3 +1,207 @@
+import { keepPreviousData, useQuery } from "@tanstack/react-query";
+import {
+  FlagIcon,
+  LibraryIcon,
+  OctagonXIcon,
+  UsersRoundIcon,
+} from "lucide-react";
+import { useMemo, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { getInstances, type StopInstancesRequest } from "@/api/admin/instances";
+import { Button } from "@/components/ui/button";
+import { Dialog, DialogContent } from "@/components/ui/dialog";
+import { Field, FieldIcon } from "@/components/ui/field";
+import { LoadingOverlay } from "@/components/ui/loading-overlay";
+import { ScrollArea } from "@/components/ui/scroll-area";
+import {
+  Table,
+  TableBody,
+  TableCell,
+  TableHead,
+  TableHeader,
+  TableRow,
+} from "@/components/ui/table";
+import { TextField } from "@/components/ui/text-field";
+import { useDebounce } from "@/hooks/use-debounce";
+import { useConfigStore } from "@/storages/config";
+import { useSharedStore } from "@/storages/shared";
+import { cn } from "@/utils";
+import { Detail } from "./_blocks/detail";
+import { StopDialog } from "./_blocks/stop-dialog";
+
+function parseId(value: string) {
+  return Number(value) || undefined;
+}
+
 export default function Index() {
-  return <div>envs</div>;
+  const { t } = useTranslation();
+  const configStore = useConfigStore();
+  const { refresh } = useSharedStore();
+
+  const [gameId, setGameId] = useState("");
+  const [teamId, setTeamId] = useState("");
+  const [challengeId, setChallengeId] = useState("");
+  const [selected, setSelected] = useState<string>();
+  const [stopDialogOpen, setStopDialogOpen] = useState(false);
+
+  const input = useMemo<StopInstancesRequest>(
+    () => ({
+      game_id: parseId(gameId),
+      team_id: parseId(teamId),
+      challenge_id: parseId(challengeId),
+    }),
+    [gameId, teamId, challengeId]
+  );
+  const filters = useDebounce(input, 300);
+  const filtered = Object.values(filters).some((v) => v !== undefined);
+
+  const { data: instances, isLoading: loading } = useQuery({
+    queryKey: ["admin", "instances", filters, refresh],
+    queryFn: async () => {
+      const res = await getInstances(filters);
+      return res.instances;
+    },
+    placeholderData: keepPreviousData,
+    refetchInterval: 10000,
+  });
+
+  const fields = [
+    {
+      key: "game_id",
+      icon: <FlagIcon />,
+      value: gameId,
+      onChange: setGameId,
+    },
+    {
+      key: "team_id",
+      icon: <UsersRoundIcon />,
+      value: teamId,
+      onChange: setTeamId,
+    },
+    {
+      key: "challenge_id",
+      icon: <LibraryIcon />,
+      value: challengeId,
+      onChange: setChallengeId,
+    },
+  ];
+
+  return (
+    <>
+      <title>
+        {`${t("admin:instance._")} - ${configStore?.config?.meta?.title}`}
+      </title>
+      <Dialog open={stopDialogOpen} onOpenChange={setStopDialogOpen}>
+        <DialogContent>
+          <StopDialog
+            filters={filters}
+            onClose={() => setStopDialogOpen(false)}
+          />
+        </DialogContent>
+      </Dialog>
+      <div
+        className={cn([
+          "flex",
+          "flex-col",
+          "gap-4",
+          "px-4",
+          "py-4",
+          "sm:px-6",
+          "sm:py-6",
+          "lg:px-8",
+          "lg:py-8",
+        ])}
+      >
+        <div className={cn(["flex", "flex-wrap", "items-center", "gap-3"])}>
+          {fields.map((field) => (
+            <Field key={field.key} size="sm" className={cn(["w-44"])}>
+              <FieldIcon>{field.icon}</FieldIcon>
+              <TextField
+                type="number"
+                placeholder={t(`admin:instance.filters.${field.key}`)}
+                value={field.value}
+                onChange={(e) => field.onChange(e.target.value)}
+              />
+            </Field>
+          ))}
+          <Button
+            icon={<OctagonXIcon />}
+            level="error"
+            size="sm"
+            disabled={!filtered || !instances?.length}
+            onClick={() => setStopDialogOpen(true)}
+          >
+            {t("admin:instance.actions.stop_all._")}
+          </Button>
+        </div>
+        <ScrollArea
+          className={cn([
+            "max-h-96",
+            "rounded-lg",
+            "border",
+            "ring-1",
+            "ring-border/50",
+            "shadow-sm",
+          ])}
+        >
+          <LoadingOverlay loading={loading} />
+          <Table>
+            <TableHeader className={cn(["bg-muted/80"])}>
+              <TableRow>
+                <TableHead>{t("admin:instance.id")}</TableHead>
+                <TableHead>{t("admin:instance.filters.game_id")}</TableHead>
+                <TableHead>{t("admin:instance.filters.team_id")}</TableHead>
+                <TableHead>
+                  {t("admin:instance.filters.challenge_id")}
+                </TableHead>
+                <TableHead>{t("admin:instance.state")}</TableHead>
+              </TableRow>
+            </TableHeader>
+            <TableBody>
+              {instances?.length ? (
+                instances.map((instance) => (
+                  <TableRow
+                    key={instance.id}
+                    data-state={
+                      selected === instance.id ? "selected" : undefined
+                    }
+                    className="cursor-pointer"
+                    onClick={() => setSelected(instance.id)}
+                  >
+                    <TableCell className="font-mono text-xs">
+                      {instance.id}
+                    </TableCell>
+                    <TableCell>{instance.game_id || "-"}</TableCell>
+                    <TableCell>{instance.team_id || "-"}</TableCell>
+                    <TableCell>{instance.challenge_id}</TableCell>
+                    <TableCell>{instance.status}</TableCell>
+                  </TableRow>
+                ))
+              ) : !loading ? (
+                <TableRow>
+                  <TableCell
+                    colSpan={5}
+                    className={cn([
+                      "h-24",
+                      "text-center",
+                      "text-muted-foreground",
+                    ])}
+                  >
+                    {t("admin:instance.empty")}
+                  </TableCell>
+                </TableRow>
+              ) : null}
+            </TableBody>
+          </Table>
+        </ScrollArea>
+        {selected && (
+          <Detail
+            key={selected}
+            instanceId={selected}
+            onStopped={() => setSelected(undefined)}
+          />
+        )}
+      </div>
+    </>
+  );
 }
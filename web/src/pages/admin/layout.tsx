@@ -1,5 +1,6 @@
 import {
   BotIcon,
+  ContainerIcon,
   FlagIcon,
   GaugeIcon,
   IdCardIcon,
@@ -47,6 +48,11 @@ export default function Layout() {
       name: t("game:_"),
       icon: <FlagIcon />,
     },
+    {
+      link: "/admin/instances",
+      name: t("admin:instance._"),
+      icon: <ContainerIcon />,
+    },
     {
       link: "/admin/users",
       name: t("user:_"),
@@ -6,6 +6,7 @@ import {
   FilePenIcon,
   InfoIcon,
   LockIcon,
+  NetworkIcon,
   TriangleAlertIcon,
   UserRoundMinusIcon,
   UserRoundXIcon,
@@ -58,6 +59,12 @@ export default function Layout() {
       icon: <FilePenIcon />,
       disabled: !currentGame?.writeup_required || !isGameOngoing,
     },
+    {
+      link: `/games/${currentGame?.id}/team/wireguard`,
+      name: t("team:wireguard._"),
+      icon: <NetworkIcon />,
+      disabled: selfTeam?.state !== State.Passed,
+    },
   ];
 
   const [confirmDialogOpen, setConfirmDialogOpen] = useState<boolean>(false);
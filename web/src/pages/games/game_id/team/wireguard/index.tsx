@@ -0,0 +1,224 @@
+import { useQuery } from "@tanstack/react-query";
+import { StatusCodes } from "http-status-codes";
+import { HTTPError } from "ky";
+import {
+  DownloadIcon,
+  NetworkIcon,
+  RefreshCcwIcon,
+  TrashIcon,
+} from "lucide-react";
+import { useTranslation } from "react-i18next";
+import { toast } from "sonner";
+import {
+  createWireguard,
+  deleteWireguard,
+  getWireguard,
+} from "@/api/games/game_id/teams/us/wireguard";
+import { Button } from "@/components/ui/button";
+import { Card } from "@/components/ui/card";
+import { Separator } from "@/components/ui/separator";
+import { useRefresh } from "@/hooks/use-refresh";
+import { State } from "@/models/team";
+import { useGameStore } from "@/storages/game";
+import { cn } from "@/utils";
+import { formatApiMsg, parseErrorResponse } from "@/utils/query";
+
+export default function Index() {
+  const { t } = useTranslation();
+  const { currentGame, selfTeam } = useGameStore();
+  const { tick, bump } = useRefresh();
+
+  const isGameOngoing =
+    Number(currentGame?.started_at) * 1000 < Date.now() &&
+    Number(currentGame?.ended_at) * 1000 > Date.now();
+  const disabled = !isGameOngoing || selfTeam?.state !== State.Passed;
+
+  const { data: peer, isError } = useQuery({
+    queryKey: ["game_wireguard", currentGame?.id, selfTeam?.id, tick],
+    queryFn: () => getWireguard({ game_id: currentGame?.id }),
+    enabled: !!currentGame?.id && !!selfTeam?.id,
+    select: (res) => res.peer,
+    retry: false,
+  });
+
+  async function handleError(error: unknown) {
+    if (!(error instanceof HTTPError)) throw error;
+    const body = await parseErrorResponse(error);
+
+    if (
+      error.response.status === StatusCodes.BAD_REQUEST ||
+      error.response.status === StatusCodes.FORBIDDEN
+    ) {
+      toast.error(t("common:errors.default"), {
+        description: formatApiMsg(body.msg),
+      });
+    }
+  }
+
+  async function handleIssue() {
+    try {
+      await createWireguard({ game_id: currentGame?.id });
+      toast.success(t("team:wireguard.actions.issue.success"));
+    } catch (error) {
+      await handleError(error);
+    }
+    bump();
+  }
+
+  async function handleRevoke() {
+    try {
+      await deleteWireguard({ game_id: currentGame?.id });
+      toast.success(t("team:wireguard.actions.revoke.success"));
+    } catch (error) {
+      await handleError(error);
+    }
+    bump();
+  }
+
+  function handleDownload() {
+    if (!peer) return;
+    const url = URL.createObjectURL(
+      new Blob([peer.config], { type: "text/plain" })
+    );
+    const link = document.createElement("a");
+    link.href = url;
+    link.download = `cds-${currentGame?.id}.conf`;
+    link.click();
+    URL.revokeObjectURL(url);
+  }
+
+  return (
+    <>
+      <title>{`${t("team:wireguard._")} - ${currentGame?.title}`}</title>
+      <div
+        className={cn([
+          "flex",
+          "flex-col",
+          "flex-1",
+          "p-4",
+          "sm:p-6",
+          "lg:p-10",
+          "min-h-0",
+          "xl:mx-50",
+          "lg:mx-30",
+          "gap-5",
+        ])}
+      >
+        <div className={cn(["flex", "items-start", "gap-3.5"])}>
+          <div
+            className={cn([
+              "flex items-center justify-center",
+              "size-10 rounded-badge",
+              "bg-primary/10",
+              "shrink-0",
+            ])}
+          >
+            <NetworkIcon className={cn(["size-5"])} />
+          </div>
+          <div className={cn(["flex flex-col gap-1", "pt-0.5"])}>
+            <h2 className={cn(["text-sm", "font-semibold", "text-foreground"])}>
+              {t("team:wireguard._")}
+            </h2>
+            <p
+              className={cn([
+                "text-xs",
+                "text-muted-foreground/80",
+                "leading-relaxed",
+              ])}
+            >
+              {t("team:wireguard.hint")}
+            </p>
+          </div>
+        </div>
+        <Separator />
+
+        {isError ? (
+          <p className={cn(["text-sm", "text-muted-foreground"])}>
+            {t("team:wireguard.unavailable")}
+          </p>
+        ) : (
+          <Card
+            className={cn([
+              "p-5",
+              "rounded-elevated",
+              "shadow-md",
+              "flex",
+              "flex-col",
+              "gap-4",
+            ])}
+          >
+            <div
+              className={cn([
+                "flex",
+                "flex-col",
+                "gap-3",
+                "sm:flex-row",
+                "sm:items-center",
+                "sm:justify-between",
+              ])}
+            >
+              <div className={cn(["min-w-0"])}>
+                <p className={cn(["font-medium"])}>
+                  {peer
+                    ? t("team:wireguard.address", { address: peer.address })
+                    : t("team:wireguard.empty")}
+                </p>
+                {peer && (
+                  <p className={cn(["text-xs", "text-muted-foreground"])}>
+                    {t("team:wireguard.created_at", {
+                      time: new Date(peer.created_at * 1000).toLocaleString(),
+                    })}
+                  </p>
+                )}
+              </div>
+              <div className={cn(["flex", "gap-2", "flex-wrap"])}>
+                <Button
+                  variant={"solid"}
+                  icon={<RefreshCcwIcon />}
+                  disabled={disabled}
+                  onClick={handleIssue}
+                >
+                  {peer
+                    ? t("team:wireguard.actions.issue.rotate")
+                    : t("team:wireguard.actions.issue._")}
+                </Button>
+                <Button
+                  variant={"tonal"}
+                  icon={<DownloadIcon />}
+                  disabled={!peer}
+                  onClick={handleDownload}
+                >
+                  {t("team:wireguard.actions.download")}
+                </Button>
+                <Button
+                  variant={"tonal"}
+                  level={"error"}
+                  icon={<TrashIcon />}
+                  disabled={!peer}
+                  onClick={handleRevoke}
+                >
+                  {t("team:wireguard.actions.revoke._")}
+                </Button>
+              </div>
+            </div>
+            {peer && (
+              <pre
+                className={cn([
+                  "text-xs",
+                  "font-mono",
+                  "whitespace-pre-wrap",
+                  "break-all",
+                  "rounded-md",
+                  "bg-muted",
+                  "p-3",
+                ])}
+              >
+                {peer.config}
+              </pre>
+            )}
+          </Card>
+        )}
+      </div>
+    </>
+  );
+}
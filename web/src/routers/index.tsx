@@ -121,6 +121,14 @@ const router = createBrowserRouter([
                       ).default,
                     }),
                   },
+                  {
+                    path: "wireguard",
+                    lazy: async () => ({
+                      Component: (
+                        await import("@/pages/games/game_id/team/wireguard")
+                      ).default,
+                    }),
+                  },
                 ],
               },
               {